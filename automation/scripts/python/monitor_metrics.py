@@ -2,6 +2,9 @@
 """
 Server Metrics Monitor
 Collects system metrics and sends them to the Unified Panel API
+
+For production hosts prefer the native `unified-agent` binary, which also
+buffers samples while the panel is unreachable and registers itself.
 """
 
 import psutil
@@ -10,7 +13,7 @@ import time
 from datetime import datetime
 
 API_URL = "http://localhost:3000/api/servers"
-API_KEY = "your-api-key-here"  # Replace with actual API key
+API_KEY = "your-api-key-here"  # Personal API key with the metrics:write scope (POST /api/auth/api-keys)
 SERVER_ID = "your-server-id"  # Replace with actual server ID
 
 def get_system_metrics():
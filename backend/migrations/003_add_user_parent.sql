@@ -0,0 +1,5 @@
+-- Link customers to the reseller that manages them
+
+ALTER TABLE users ADD COLUMN IF NOT EXISTS parent_id UUID REFERENCES users(id) ON DELETE SET NULL;
+
+CREATE INDEX idx_users_parent_id ON users(parent_id);
@@ -0,0 +1,10 @@
+-- Per-customer resource quotas assigned by resellers (NULL = unlimited)
+
+CREATE TABLE IF NOT EXISTS user_quotas (
+    user_id UUID PRIMARY KEY REFERENCES users(id) ON DELETE CASCADE,
+    max_servers INTEGER,
+    max_vps INTEGER,
+    max_websites INTEGER,
+    updated_by UUID REFERENCES users(id) ON DELETE SET NULL,
+    updated_at TIMESTAMP WITH TIME ZONE NOT NULL DEFAULT NOW()
+);
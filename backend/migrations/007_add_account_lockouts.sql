@@ -0,0 +1,10 @@
+-- Consecutive failed logins per account and temporary lockouts
+
+CREATE TABLE IF NOT EXISTS account_lockouts (
+    email VARCHAR(255) PRIMARY KEY,
+    failed_attempts INTEGER NOT NULL DEFAULT 0,
+    locked_until TIMESTAMP WITH TIME ZONE,
+    updated_at TIMESTAMP WITH TIME ZONE NOT NULL DEFAULT NOW()
+);
+
+CREATE INDEX idx_account_lockouts_locked_until ON account_lockouts(locked_until);
@@ -0,0 +1,14 @@
+-- Last cumulative network counters reported by each server's agent, used to derive rates
+
+CREATE TABLE IF NOT EXISTS server_metric_counters (
+    server_id UUID PRIMARY KEY REFERENCES servers(id) ON DELETE CASCADE,
+    network_in BIGINT NOT NULL,
+    network_out BIGINT NOT NULL,
+    sampled_at TIMESTAMP WITH TIME ZONE NOT NULL
+);
+
+ALTER TABLE servers ADD COLUMN IF NOT EXISTS last_seen_at TIMESTAMP WITH TIME ZONE;
+
+-- Network columns now hold bytes per second rather than raw counters
+COMMENT ON COLUMN server_metrics.network_in IS 'Inbound bytes per second';
+COMMENT ON COLUMN server_metrics.network_out IS 'Outbound bytes per second';
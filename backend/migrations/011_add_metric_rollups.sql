@@ -0,0 +1,103 @@
+-- Downsampled server metrics at 1 minute, 1 hour and 1 day resolution.
+-- Minute rollups are built from raw samples, hour rollups from minutes and day rollups from hours;
+-- p95 in the coarser tables is the highest p95 of the finer buckets (an upper bound).
+
+ALTER TABLE server_metrics ADD COLUMN IF NOT EXISTS received_at TIMESTAMP WITH TIME ZONE NOT NULL DEFAULT NOW();
+CREATE INDEX idx_server_metrics_received_at ON server_metrics(received_at);
+CREATE INDEX idx_server_metrics_server_timestamp ON server_metrics(server_id, timestamp);
+
+CREATE TABLE IF NOT EXISTS server_metrics_1m (
+    server_id UUID NOT NULL REFERENCES servers(id) ON DELETE CASCADE,
+    bucket TIMESTAMP WITH TIME ZONE NOT NULL,
+    samples BIGINT NOT NULL,
+    cpu_avg DOUBLE PRECISION NOT NULL,
+    cpu_min DOUBLE PRECISION NOT NULL,
+    cpu_max DOUBLE PRECISION NOT NULL,
+    cpu_p95 DOUBLE PRECISION NOT NULL,
+    memory_avg DOUBLE PRECISION NOT NULL,
+    memory_min DOUBLE PRECISION NOT NULL,
+    memory_max DOUBLE PRECISION NOT NULL,
+    memory_p95 DOUBLE PRECISION NOT NULL,
+    disk_avg DOUBLE PRECISION NOT NULL,
+    disk_min DOUBLE PRECISION NOT NULL,
+    disk_max DOUBLE PRECISION NOT NULL,
+    disk_p95 DOUBLE PRECISION NOT NULL,
+    network_in_avg DOUBLE PRECISION NOT NULL,
+    network_in_min DOUBLE PRECISION NOT NULL,
+    network_in_max DOUBLE PRECISION NOT NULL,
+    network_in_p95 DOUBLE PRECISION NOT NULL,
+    network_out_avg DOUBLE PRECISION NOT NULL,
+    network_out_min DOUBLE PRECISION NOT NULL,
+    network_out_max DOUBLE PRECISION NOT NULL,
+    network_out_p95 DOUBLE PRECISION NOT NULL,
+    updated_at TIMESTAMP WITH TIME ZONE NOT NULL DEFAULT NOW(),
+    PRIMARY KEY (server_id, bucket)
+);
+
+CREATE INDEX idx_server_metrics_1m_updated_at ON server_metrics_1m(updated_at);
+
+CREATE TABLE IF NOT EXISTS server_metrics_1h (
+    server_id UUID NOT NULL REFERENCES servers(id) ON DELETE CASCADE,
+    bucket TIMESTAMP WITH TIME ZONE NOT NULL,
+    samples BIGINT NOT NULL,
+    cpu_avg DOUBLE PRECISION NOT NULL,
+    cpu_min DOUBLE PRECISION NOT NULL,
+    cpu_max DOUBLE PRECISION NOT NULL,
+    cpu_p95 DOUBLE PRECISION NOT NULL,
+    memory_avg DOUBLE PRECISION NOT NULL,
+    memory_min DOUBLE PRECISION NOT NULL,
+    memory_max DOUBLE PRECISION NOT NULL,
+    memory_p95 DOUBLE PRECISION NOT NULL,
+    disk_avg DOUBLE PRECISION NOT NULL,
+    disk_min DOUBLE PRECISION NOT NULL,
+    disk_max DOUBLE PRECISION NOT NULL,
+    disk_p95 DOUBLE PRECISION NOT NULL,
+    network_in_avg DOUBLE PRECISION NOT NULL,
+    network_in_min DOUBLE PRECISION NOT NULL,
+    network_in_max DOUBLE PRECISION NOT NULL,
+    network_in_p95 DOUBLE PRECISION NOT NULL,
+    network_out_avg DOUBLE PRECISION NOT NULL,
+    network_out_min DOUBLE PRECISION NOT NULL,
+    network_out_max DOUBLE PRECISION NOT NULL,
+    network_out_p95 DOUBLE PRECISION NOT NULL,
+    updated_at TIMESTAMP WITH TIME ZONE NOT NULL DEFAULT NOW(),
+    PRIMARY KEY (server_id, bucket)
+);
+
+CREATE INDEX idx_server_metrics_1h_updated_at ON server_metrics_1h(updated_at);
+
+CREATE TABLE IF NOT EXISTS server_metrics_1d (
+    server_id UUID NOT NULL REFERENCES servers(id) ON DELETE CASCADE,
+    bucket TIMESTAMP WITH TIME ZONE NOT NULL,
+    samples BIGINT NOT NULL,
+    cpu_avg DOUBLE PRECISION NOT NULL,
+    cpu_min DOUBLE PRECISION NOT NULL,
+    cpu_max DOUBLE PRECISION NOT NULL,
+    cpu_p95 DOUBLE PRECISION NOT NULL,
+    memory_avg DOUBLE PRECISION NOT NULL,
+    memory_min DOUBLE PRECISION NOT NULL,
+    memory_max DOUBLE PRECISION NOT NULL,
+    memory_p95 DOUBLE PRECISION NOT NULL,
+    disk_avg DOUBLE PRECISION NOT NULL,
+    disk_min DOUBLE PRECISION NOT NULL,
+    disk_max DOUBLE PRECISION NOT NULL,
+    disk_p95 DOUBLE PRECISION NOT NULL,
+    network_in_avg DOUBLE PRECISION NOT NULL,
+    network_in_min DOUBLE PRECISION NOT NULL,
+    network_in_max DOUBLE PRECISION NOT NULL,
+    network_in_p95 DOUBLE PRECISION NOT NULL,
+    network_out_avg DOUBLE PRECISION NOT NULL,
+    network_out_min DOUBLE PRECISION NOT NULL,
+    network_out_max DOUBLE PRECISION NOT NULL,
+    network_out_p95 DOUBLE PRECISION NOT NULL,
+    updated_at TIMESTAMP WITH TIME ZONE NOT NULL DEFAULT NOW(),
+    PRIMARY KEY (server_id, bucket)
+);
+
+CREATE INDEX idx_server_metrics_1d_updated_at ON server_metrics_1d(updated_at);
+
+-- How far each rollup has consumed its source (by received_at / updated_at)
+CREATE TABLE IF NOT EXISTS metric_rollup_state (
+    resolution VARCHAR(10) PRIMARY KEY,
+    processed_until TIMESTAMP WITH TIME ZONE NOT NULL
+);
@@ -0,0 +1,19 @@
+-- Load averages and per-mount disk usage reported by the native agent
+
+ALTER TABLE server_metrics ADD COLUMN IF NOT EXISTS load_1 REAL;
+ALTER TABLE server_metrics ADD COLUMN IF NOT EXISTS load_5 REAL;
+ALTER TABLE server_metrics ADD COLUMN IF NOT EXISTS load_15 REAL;
+
+-- Latest usage per mount point; replaced on every sample that carries mounts
+CREATE TABLE IF NOT EXISTS server_mounts (
+    server_id UUID NOT NULL REFERENCES servers(id) ON DELETE CASCADE,
+    mount_point VARCHAR(1024) NOT NULL,
+    filesystem VARCHAR(255) NOT NULL,
+    total_bytes BIGINT NOT NULL,
+    used_bytes BIGINT NOT NULL,
+    updated_at TIMESTAMP WITH TIME ZONE NOT NULL DEFAULT NOW(),
+    PRIMARY KEY (server_id, mount_point)
+);
+
+-- Agents register by hostname; keep that lookup cheap
+CREATE INDEX IF NOT EXISTS idx_servers_user_hostname ON servers(user_id, hostname);
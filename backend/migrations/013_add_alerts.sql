@@ -0,0 +1,44 @@
+-- User-defined alert rules and the alerts they (and the built-in checks) raise
+
+CREATE TABLE IF NOT EXISTS alert_rules (
+    id UUID PRIMARY KEY DEFAULT gen_random_uuid(),
+    user_id UUID NOT NULL REFERENCES users(id) ON DELETE CASCADE,
+    -- NULL applies the rule to every server visible to its owner
+    server_id UUID REFERENCES servers(id) ON DELETE CASCADE,
+    name VARCHAR(255) NOT NULL,
+    metric VARCHAR(50) NOT NULL,
+    comparison VARCHAR(10) NOT NULL,
+    threshold DOUBLE PRECISION NOT NULL,
+    duration_seconds INTEGER NOT NULL DEFAULT 0,
+    severity VARCHAR(20) NOT NULL DEFAULT 'warning',
+    enabled BOOLEAN NOT NULL DEFAULT TRUE,
+    created_at TIMESTAMP WITH TIME ZONE NOT NULL DEFAULT NOW(),
+    updated_at TIMESTAMP WITH TIME ZONE NOT NULL DEFAULT NOW()
+);
+
+CREATE INDEX idx_alert_rules_user_id ON alert_rules(user_id);
+
+CREATE TABLE IF NOT EXISTS alerts (
+    id UUID PRIMARY KEY DEFAULT gen_random_uuid(),
+    -- Identifies what the alert is about, e.g. rule:<rule>:<server> or no_metrics:<server>
+    fingerprint VARCHAR(255) NOT NULL,
+    kind VARCHAR(20) NOT NULL,
+    rule_id UUID REFERENCES alert_rules(id) ON DELETE SET NULL,
+    -- Owner of the affected resource, used for tenant scoping
+    user_id UUID NOT NULL REFERENCES users(id) ON DELETE CASCADE,
+    server_id UUID REFERENCES servers(id) ON DELETE CASCADE,
+    vps_id UUID REFERENCES vps(id) ON DELETE CASCADE,
+    severity VARCHAR(20) NOT NULL,
+    state VARCHAR(20) NOT NULL,
+    message TEXT NOT NULL,
+    value DOUBLE PRECISION,
+    started_at TIMESTAMP WITH TIME ZONE NOT NULL,
+    fired_at TIMESTAMP WITH TIME ZONE,
+    resolved_at TIMESTAMP WITH TIME ZONE,
+    updated_at TIMESTAMP WITH TIME ZONE NOT NULL DEFAULT NOW()
+);
+
+-- At most one pending or firing alert per fingerprint
+CREATE UNIQUE INDEX idx_alerts_open_fingerprint ON alerts(fingerprint) WHERE state <> 'resolved';
+CREATE INDEX idx_alerts_user_id ON alerts(user_id, started_at DESC);
+CREATE INDEX idx_alerts_server_id ON alerts(server_id);
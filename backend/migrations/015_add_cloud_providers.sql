@@ -0,0 +1,22 @@
+-- Provider-neutral cloud server ids on VPS rows and per-account provider API tokens
+
+ALTER TABLE vps ADD COLUMN IF NOT EXISTS provider VARCHAR(32) NOT NULL DEFAULT 'hetzner';
+ALTER TABLE vps ADD COLUMN IF NOT EXISTS external_id VARCHAR(64);
+
+UPDATE vps SET external_id = hetzner_id::text WHERE hetzner_id IS NOT NULL;
+
+DROP INDEX IF EXISTS idx_vps_hetzner_id;
+ALTER TABLE vps DROP COLUMN IF EXISTS hetzner_id;
+
+CREATE UNIQUE INDEX IF NOT EXISTS idx_vps_provider_external_id ON vps(provider, external_id);
+
+-- A reseller's token also serves their customers unless they set their own
+CREATE TABLE IF NOT EXISTS provider_credentials (
+    id UUID PRIMARY KEY DEFAULT gen_random_uuid(),
+    user_id UUID NOT NULL REFERENCES users(id) ON DELETE CASCADE,
+    provider VARCHAR(32) NOT NULL,
+    api_token TEXT NOT NULL,
+    created_at TIMESTAMP WITH TIME ZONE NOT NULL DEFAULT NOW(),
+    updated_at TIMESTAMP WITH TIME ZONE NOT NULL DEFAULT NOW(),
+    UNIQUE (user_id, provider)
+);
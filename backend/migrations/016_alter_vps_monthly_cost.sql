@@ -0,0 +1,3 @@
+-- Store the VPS monthly cost as a float to match the model (providers report fractional prices)
+
+ALTER TABLE vps ALTER COLUMN monthly_cost TYPE DOUBLE PRECISION;
@@ -0,0 +1,20 @@
+-- Provider actions (power on/off, reboot) on VPS, followed to completion by the action poller
+
+CREATE TABLE IF NOT EXISTS vps_actions (
+    id UUID PRIMARY KEY DEFAULT gen_random_uuid(),
+    vps_id UUID NOT NULL REFERENCES vps(id) ON DELETE CASCADE,
+    user_id UUID NOT NULL REFERENCES users(id) ON DELETE CASCADE,
+    -- The provider's action id
+    external_id VARCHAR(64) NOT NULL,
+    command VARCHAR(32) NOT NULL,
+    -- running, success or error
+    status VARCHAR(20) NOT NULL DEFAULT 'running',
+    progress INTEGER NOT NULL DEFAULT 0,
+    error TEXT,
+    finished_at TIMESTAMP WITH TIME ZONE,
+    created_at TIMESTAMP WITH TIME ZONE NOT NULL DEFAULT NOW(),
+    updated_at TIMESTAMP WITH TIME ZONE NOT NULL DEFAULT NOW()
+);
+
+CREATE INDEX idx_vps_actions_vps_id ON vps_actions(vps_id, created_at DESC);
+CREATE INDEX idx_vps_actions_running ON vps_actions(created_at) WHERE status = 'running';
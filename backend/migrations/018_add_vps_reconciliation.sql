@@ -0,0 +1,27 @@
+-- Reconciliation with cloud providers: VPS whose server vanished, and servers no VPS tracks
+
+ALTER TABLE vps ADD COLUMN IF NOT EXISTS missing_since TIMESTAMP WITH TIME ZONE;
+
+CREATE TABLE IF NOT EXISTS unmanaged_servers (
+    id UUID PRIMARY KEY DEFAULT gen_random_uuid(),
+    provider VARCHAR(32) NOT NULL,
+    external_id VARCHAR(64) NOT NULL,
+    -- Owner of the credentials the server was listed with; NULL for the platform-wide token
+    credential_user_id UUID REFERENCES users(id) ON DELETE CASCADE,
+    name VARCHAR(255) NOT NULL,
+    status VARCHAR(50) NOT NULL,
+    server_type VARCHAR(100) NOT NULL,
+    location VARCHAR(100) NOT NULL,
+    image VARCHAR(255) NOT NULL,
+    ipv4 VARCHAR(45),
+    ipv6 VARCHAR(100),
+    cpu_cores INTEGER NOT NULL,
+    ram_gb INTEGER NOT NULL,
+    disk_gb INTEGER NOT NULL,
+    monthly_cost DOUBLE PRECISION,
+    first_seen_at TIMESTAMP WITH TIME ZONE NOT NULL DEFAULT NOW(),
+    last_seen_at TIMESTAMP WITH TIME ZONE NOT NULL DEFAULT NOW(),
+    UNIQUE (provider, external_id)
+);
+
+CREATE INDEX idx_unmanaged_servers_credential_user_id ON unmanaged_servers(credential_user_id);
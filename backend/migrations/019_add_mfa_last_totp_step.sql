@@ -0,0 +1,3 @@
+-- Last TOTP time step accepted per user, so a code cannot be used twice
+
+ALTER TABLE users ADD COLUMN IF NOT EXISTS mfa_last_totp_step BIGINT;
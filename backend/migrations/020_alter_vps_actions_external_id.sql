@@ -0,0 +1,3 @@
+-- Actions are recorded before the provider is called, so the provider's id arrives later
+
+ALTER TABLE vps_actions ALTER COLUMN external_id DROP NOT NULL;
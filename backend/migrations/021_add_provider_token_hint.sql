@@ -0,0 +1,6 @@
+-- Provider API tokens are sealed with PROVIDER_TOKEN_KEY; keep their first characters
+-- in the clear so credentials can be listed without decrypting them
+
+ALTER TABLE provider_credentials ADD COLUMN IF NOT EXISTS token_hint VARCHAR(8) NOT NULL DEFAULT '';
+
+UPDATE provider_credentials SET token_hint = LEFT(api_token, 4) WHERE api_token NOT LIKE 'v1:%';
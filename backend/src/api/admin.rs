@@ -0,0 +1,59 @@
+use axum::{
+    extract::{Path, State},
+    Json,
+};
+use crate::{
+    middleware::{audit, auth::AuthUser, validation::ValidatedJson},
+    models::{auth::{AccountLockout, MfaPolicy}, user::UserRole, AppState},
+    services::{lockout_service, mfa_service},
+    utils::errors::AppError,
+};
+
+pub async fn get_mfa_policy(
+    State(state): State<AppState>,
+    auth: AuthUser,
+) -> Result<Json<MfaPolicy>, AppError> {
+    auth.require_role(&[UserRole::Admin])?;
+
+    let required_roles = mfa_service::required_roles(&state.db).await?;
+    Ok(Json(MfaPolicy { required_roles }))
+}
+
+pub async fn update_mfa_policy(
+    State(state): State<AppState>,
+    auth: AuthUser,
+    ValidatedJson(payload): ValidatedJson<MfaPolicy>,
+) -> Result<Json<MfaPolicy>, AppError> {
+    auth.require_role(&[UserRole::Admin])?;
+
+    let before = MfaPolicy { required_roles: mfa_service::required_roles(&state.db).await? };
+    let required_roles = mfa_service::set_required_roles(&state.db, &payload.required_roles).await?;
+    let policy = MfaPolicy { required_roles };
+    audit::changed("MFA policy", &before, &policy);
+    Ok(Json(policy))
+}
+
+pub async fn list_lockouts(
+    State(state): State<AppState>,
+    auth: AuthUser,
+) -> Result<Json<Vec<AccountLockout>>, AppError> {
+    auth.require_role(&[UserRole::Admin])?;
+
+    let lockouts = lockout_service::list_active(&state.db).await?;
+    Ok(Json(lockouts))
+}
+
+pub async fn clear_lockout(
+    State(state): State<AppState>,
+    auth: AuthUser,
+    Path(email): Path<String>,
+) -> Result<Json<()>, AppError> {
+    auth.require_role(&[UserRole::Admin])?;
+
+    if !lockout_service::clear(&state.db, &email).await? {
+        return Err(AppError::NotFound("Lockout not found".to_string()));
+    }
+
+    audit::describe(format!("Cleared lockout for {}", email));
+    Ok(Json(()))
+}
@@ -0,0 +1,91 @@
+use axum::{
+    extract::{Path, Query, State},
+    Json,
+};
+use uuid::Uuid;
+use crate::{
+    middleware::{audit, auth::AuthUser, validation::ValidatedJson},
+    models::{alert::*, api_key::ApiScope, AppState},
+    services::alert_service,
+    utils::errors::AppError,
+};
+
+pub async fn list_alerts(
+    State(state): State<AppState>,
+    auth: AuthUser,
+    Query(query): Query<AlertsQuery>,
+) -> Result<Json<Vec<Alert>>, AppError> {
+    auth.require_scope(ApiScope::AlertsRead)?;
+
+    let alerts = alert_service::list_alerts(&state.db, &auth.scope(), query).await?;
+    Ok(Json(alerts))
+}
+
+pub async fn list_rules(
+    State(state): State<AppState>,
+    auth: AuthUser,
+) -> Result<Json<Vec<AlertRule>>, AppError> {
+    auth.require_scope(ApiScope::AlertsRead)?;
+
+    let rules = alert_service::list_rules(&state.db, &auth.scope()).await?;
+    Ok(Json(rules))
+}
+
+pub async fn get_rule(
+    State(state): State<AppState>,
+    auth: AuthUser,
+    Path(id): Path<Uuid>,
+) -> Result<Json<AlertRule>, AppError> {
+    auth.require_scope(ApiScope::AlertsRead)?;
+
+    let rule = alert_service::get_rule(&state.db, &auth.scope(), id).await?;
+    Ok(Json(rule))
+}
+
+pub async fn create_rule(
+    State(state): State<AppState>,
+    auth: AuthUser,
+    ValidatedJson(payload): ValidatedJson<CreateAlertRule>,
+) -> Result<Json<AlertRule>, AppError> {
+    auth.require_scope(ApiScope::AlertsWrite)?;
+
+    let rule = alert_service::create_rule(&state.db, &auth.scope(), auth.id, payload).await?;
+    if let Some(server_id) = rule.server_id {
+        audit::server(server_id);
+    }
+    audit::created(&format!("alert rule {}", rule.name), &rule);
+    Ok(Json(rule))
+}
+
+pub async fn update_rule(
+    State(state): State<AppState>,
+    auth: AuthUser,
+    Path(id): Path<Uuid>,
+    ValidatedJson(payload): ValidatedJson<UpdateAlertRule>,
+) -> Result<Json<AlertRule>, AppError> {
+    auth.require_scope(ApiScope::AlertsWrite)?;
+
+    let before = alert_service::get_rule(&state.db, &auth.scope(), id).await?;
+    let rule = alert_service::update_rule(&state.db, &auth.scope(), id, payload).await?;
+    if let Some(server_id) = rule.server_id {
+        audit::server(server_id);
+    }
+    audit::changed(&format!("alert rule {}", rule.name), &before, &rule);
+    Ok(Json(rule))
+}
+
+pub async fn delete_rule(
+    State(state): State<AppState>,
+    auth: AuthUser,
+    Path(id): Path<Uuid>,
+) -> Result<Json<()>, AppError> {
+    auth.require_scope(ApiScope::AlertsWrite)?;
+
+    let before = alert_service::get_rule(&state.db, &auth.scope(), id).await?;
+    alert_service::delete_rule(&state.db, &auth.scope(), id).await?;
+    if let Some(server_id) = before.server_id {
+        audit::server(server_id);
+    }
+    audit::deleted(&format!("alert rule {}", before.name), &before);
+    Ok(Json(()))
+}
@@ -0,0 +1,41 @@
+use axum::{
+    extract::{Path, State},
+    http::StatusCode,
+    Json,
+};
+use uuid::Uuid;
+use crate::{
+    middleware::{audit, auth::AuthUser, validation::ValidatedJson},
+    models::{api_key::*, AppState},
+    services::api_key_service,
+    utils::errors::AppError,
+};
+
+pub async fn list_api_keys(
+    State(state): State<AppState>,
+    auth: AuthUser,
+) -> Result<Json<Vec<ApiKeyResponse>>, AppError> {
+    let keys = api_key_service::list_keys(&state.db, auth.id).await?;
+    Ok(Json(keys.into_iter().map(|k| k.into()).collect()))
+}
+
+pub async fn create_api_key(
+    State(state): State<AppState>,
+    auth: AuthUser,
+    ValidatedJson(payload): ValidatedJson<CreateApiKey>,
+) -> Result<(StatusCode, Json<CreatedApiKey>), AppError> {
+    let created = api_key_service::create_key(&state.db, auth.id, payload).await?;
+    // The response body carries the secret; only the key's metadata is logged
+    audit::created(&format!("API key {}", created.api_key.name), &created.api_key);
+    Ok((StatusCode::CREATED, Json(created)))
+}
+
+pub async fn revoke_api_key(
+    State(state): State<AppState>,
+    auth: AuthUser,
+    Path(id): Path<Uuid>,
+) -> Result<StatusCode, AppError> {
+    api_key_service::revoke_key(&state.db, auth.id, id).await?;
+    audit::describe(format!("Revoked API key {}", id));
+    Ok(StatusCode::NO_CONTENT)
+}
@@ -1,45 +1,228 @@
 use axum::{
     extract::State,
-    http::StatusCode,
-    response::{IntoResponse, Response},
+    http::{header, HeaderMap, StatusCode},
+    response::{AppendHeaders, IntoResponse},
     Json,
 };
 use crate::{
-    models::{auth::*, AppState, user::UserResponse},
-    services::auth_service,
+    config::Config,
+    middleware::{
+        audit,
+        auth::{cookie_value, AuthUser, REFRESH_COOKIE, SESSION_COOKIE},
+        validation::ValidatedJson,
+    },
+    models::{auth::*, AppState, user::{User, UserResponse}},
+    services::{auth_service, mfa_service, user_service, verification_service},
     utils::errors::AppError,
 };
 
+/// Browser clients authenticate with HTTP-only cookies, API clients with the returned tokens
+fn session_cookies(tokens: &SessionTokens, config: &Config) -> AppendHeaders<[(header::HeaderName, String); 2]> {
+    AppendHeaders([
+        (
+            header::SET_COOKIE,
+            format!(
+                "{}={}; HttpOnly; SameSite=Strict; Path=/; Max-Age={}",
+                SESSION_COOKIE, tokens.access_token, config.jwt_expiration
+            ),
+        ),
+        (
+            header::SET_COOKIE,
+            format!(
+                "{}={}; HttpOnly; SameSite=Strict; Path=/api/auth; Max-Age={}",
+                REFRESH_COOKIE, tokens.refresh_token, config.refresh_token_expiration
+            ),
+        ),
+    ])
+}
+
+fn clear_cookies() -> AppendHeaders<[(header::HeaderName, String); 2]> {
+    AppendHeaders([
+        (
+            header::SET_COOKIE,
+            format!("{}=; HttpOnly; SameSite=Strict; Path=/; Max-Age=0", SESSION_COOKIE),
+        ),
+        (
+            header::SET_COOKIE,
+            format!("{}=; HttpOnly; SameSite=Strict; Path=/api/auth; Max-Age=0", REFRESH_COOKIE),
+        ),
+    ])
+}
+
+fn session_response(tokens: SessionTokens, user: User, config: &Config) -> impl IntoResponse {
+    (
+        session_cookies(&tokens, config),
+        Json(LoginResponse {
+            token: tokens.access_token,
+            refresh_token: tokens.refresh_token,
+            expires_in: config.jwt_expiration,
+            user: user.into(),
+        }),
+    )
+}
+
 pub async fn login(
     State(state): State<AppState>,
-    Json(payload): Json<LoginRequest>,
-) -> Result<Json<LoginResponse>, AppError> {
-    let (token, user) = auth_service::login(&state.db, &state.config, payload).await?;
+    client: ClientInfo,
+    ValidatedJson(payload): ValidatedJson<LoginRequest>,
+) -> Result<impl IntoResponse, AppError> {
+    audit::note("email", &payload.email);
+    state.rate_limiters.check_login(&client.ip, &payload.email)?;
 
-    Ok(Json(LoginResponse {
-        token,
-        user: user.into(),
-    }))
+    let (tokens, user) = auth_service::login(&state.db, &state.config, payload, &client).await?;
+    audit::set_user(user.id);
+    audit::describe("Signed in");
+    Ok(session_response(tokens, user, &state.config))
 }
 
 pub async fn register(
     State(state): State<AppState>,
-    Json(payload): Json<RegisterRequest>,
+    client: ClientInfo,
+    ValidatedJson(payload): ValidatedJson<RegisterRequest>,
 ) -> Result<Json<UserResponse>, AppError> {
-    let user = auth_service::register(&state.db, payload).await?;
-    Ok(Json(user.into()))
+    state.rate_limiters.check_register(&client.ip)?;
+
+    let user = auth_service::register(&state.db, &state.config, payload).await?;
+    audit::set_user(user.id);
+    verification_service::send_verification_email(&state.db, &state.config, state.mailer.clone(), &user).await?;
+
+    let user: UserResponse = user.into();
+    audit::created(&format!("account {}", user.email), &user);
+    Ok(Json(user))
+}
+
+pub async fn request_password_reset(
+    State(state): State<AppState>,
+    client: ClientInfo,
+    ValidatedJson(payload): ValidatedJson<EmailRequest>,
+) -> Result<StatusCode, AppError> {
+    state.rate_limiters.check_email(&client.ip)?;
+
+    verification_service::request_password_reset(&state.db, &state.config, state.mailer.clone(), &payload.email).await?;
+    Ok(StatusCode::ACCEPTED)
+}
+
+pub async fn reset_password(
+    State(state): State<AppState>,
+    ValidatedJson(payload): ValidatedJson<ResetPasswordRequest>,
+) -> Result<StatusCode, AppError> {
+    verification_service::reset_password(&state.db, &state.config, &payload.token, &payload.new_password).await?;
+    Ok(StatusCode::OK)
+}
+
+pub async fn request_email_verification(
+    State(state): State<AppState>,
+    client: ClientInfo,
+    ValidatedJson(payload): ValidatedJson<EmailRequest>,
+) -> Result<StatusCode, AppError> {
+    state.rate_limiters.check_email(&client.ip)?;
+
+    verification_service::request_verification(&state.db, &state.config, state.mailer.clone(), &payload.email).await?;
+    Ok(StatusCode::ACCEPTED)
+}
+
+pub async fn verify_email(
+    State(state): State<AppState>,
+    ValidatedJson(payload): ValidatedJson<VerifyEmailRequest>,
+) -> Result<StatusCode, AppError> {
+    verification_service::verify_email(&state.db, &payload.token).await?;
+    Ok(StatusCode::OK)
 }
 
-pub async fn logout() -> impl IntoResponse {
-    // For JWT, logout is typically handled client-side
-    // We can implement token blacklisting if needed
-    StatusCode::OK
+/// Exchange a refresh token (JSON body or cookie) for a new token pair
+pub async fn refresh(
+    State(state): State<AppState>,
+    headers: HeaderMap,
+    payload: Option<ValidatedJson<RefreshRequest>>,
+) -> Result<impl IntoResponse, AppError> {
+    let refresh_token = payload
+        .map(|ValidatedJson(body)| body.refresh_token)
+        .or_else(|| cookie_value(&headers, REFRESH_COOKIE))
+        .ok_or(AppError::Unauthorized("Missing refresh token".to_string()))?;
+
+    let (tokens, user) = auth_service::refresh(&state.db, &state.config, &refresh_token).await?;
+    audit::set_user(user.id);
+    audit::describe("Refreshed session");
+    Ok(session_response(tokens, user, &state.config))
+}
+
+pub async fn logout(
+    State(state): State<AppState>,
+    auth: AuthUser,
+) -> Result<impl IntoResponse, AppError> {
+    auth_service::revoke_session(&state.db, auth.id, auth.session_id).await?;
+    audit::describe("Signed out");
+    Ok((StatusCode::OK, clear_cookies()))
+}
+
+pub async fn logout_all(
+    State(state): State<AppState>,
+    auth: AuthUser,
+) -> Result<impl IntoResponse, AppError> {
+    auth_service::revoke_all_sessions(&state.db, auth.id).await?;
+    audit::describe("Signed out of all sessions");
+    Ok((StatusCode::OK, clear_cookies()))
+}
+
+pub async fn change_password(
+    State(state): State<AppState>,
+    auth: AuthUser,
+    ValidatedJson(payload): ValidatedJson<ChangePasswordRequest>,
+) -> Result<impl IntoResponse, AppError> {
+    auth_service::change_password(&state.db, &state.config, auth.id, payload).await?;
+    audit::describe("Changed password");
+    Ok((StatusCode::OK, clear_cookies()))
 }
 
 pub async fn me(
     State(state): State<AppState>,
-    // Extract user from JWT (requires middleware)
+    auth: AuthUser,
 ) -> Result<Json<UserResponse>, AppError> {
-    // This will be implemented with auth middleware
-    todo!("Implement with auth middleware")
+    let user = user_service::get_user(&state.db, &auth.scope(), auth.id).await?;
+    Ok(Json(user.into()))
+}
+
+pub async fn mfa_enroll(
+    State(state): State<AppState>,
+    auth: AuthUser,
+) -> Result<Json<MfaEnrollResponse>, AppError> {
+    let user = user_service::get_user(&state.db, &auth.scope(), auth.id).await?;
+    let enrollment = mfa_service::enroll(&state.db, &user).await?;
+    audit::describe("Started MFA enrollment");
+    Ok(Json(enrollment))
+}
+
+pub async fn mfa_confirm(
+    State(state): State<AppState>,
+    auth: AuthUser,
+    ValidatedJson(payload): ValidatedJson<MfaConfirmRequest>,
+) -> Result<impl IntoResponse, AppError> {
+    let user = user_service::get_user(&state.db, &auth.scope(), auth.id).await?;
+    let recovery_codes = mfa_service::confirm(&state.db, &user, &payload.code).await?;
+
+    // Reload so the new session's token no longer claims enrollment is required
+    let user = user_service::get_user(&state.db, &auth.scope(), auth.id).await?;
+    let tokens = auth_service::reissue_session(&state.db, &state.config, &user, auth.session_id).await?;
+    audit::describe("Enabled MFA");
+
+    Ok((
+        session_cookies(&tokens, &state.config),
+        Json(MfaConfirmResponse {
+            recovery_codes,
+            token: tokens.access_token,
+            refresh_token: tokens.refresh_token,
+            expires_in: state.config.jwt_expiration,
+        }),
+    ))
+}
+
+pub async fn mfa_disable(
+    State(state): State<AppState>,
+    auth: AuthUser,
+    ValidatedJson(payload): ValidatedJson<MfaDisableRequest>,
+) -> Result<StatusCode, AppError> {
+    let user = user_service::get_user(&state.db, &auth.scope(), auth.id).await?;
+    mfa_service::disable(&state.db, &user, payload).await?;
+    audit::describe("Disabled MFA");
+    Ok(StatusCode::OK)
 }
@@ -0,0 +1,53 @@
+use axum::{
+    extract::{Query, State},
+    response::sse::{Event as SseEvent, KeepAlive, Sse},
+};
+use futures::stream::{self, Stream};
+use std::convert::Infallible;
+use crate::{
+    middleware::auth::AuthUser,
+    models::{event::*, AppState},
+    services::event_service::Delivery,
+    utils::errors::AppError,
+};
+
+/// Live VPS, metrics, alert and activity updates as Server-Sent Events, limited to the caller's tenants
+pub async fn stream_events(
+    State(state): State<AppState>,
+    auth: AuthUser,
+    Query(query): Query<EventsQuery>,
+) -> Result<Sse<impl Stream<Item = Result<SseEvent, Infallible>>>, AppError> {
+    let kinds = match query.types.as_deref() {
+        Some(types) => types
+            .split(',')
+            .map(|kind| kind.trim().parse::<EventKind>())
+            .collect::<Result<Vec<_>, _>>()
+            .map_err(AppError::BadRequest)?,
+        None => Vec::new(),
+    };
+
+    let subscription = state.events.subscribe(state.db.clone(), auth.scope(), auth.session_id, kinds);
+
+    let events = stream::unfold(subscription, |mut subscription| async move {
+        loop {
+            let event = match subscription.next().await? {
+                Delivery::Event(event) => match SseEvent::default()
+                    .id(event.id.to_string())
+                    .event(event.kind.as_str())
+                    .json_data(&event)
+                {
+                    Ok(event) => event,
+                    Err(e) => {
+                        tracing::warn!("Failed to encode {} event: {}", event.kind.as_str(), e);
+                        continue;
+                    }
+                },
+                Delivery::Lagged(missed) => SseEvent::default().event("resync").data(missed.to_string()),
+            };
+
+            return Some((Ok(event), subscription));
+        }
+    });
+
+    Ok(Sse::new(events).keep_alive(KeepAlive::default()))
+}
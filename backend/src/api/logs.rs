@@ -0,0 +1,53 @@
+use axum::{
+    extract::{Query, State},
+    http::header,
+    response::{IntoResponse, Response},
+};
+use crate::{
+    middleware::auth::AuthUser,
+    models::{audit::*, list::Page, user::UserRole, AppState},
+    services::audit_service,
+    utils::errors::AppError,
+};
+
+/// Audit entries visible to the caller, newest first; the unpaginated total is in `X-Total-Count`
+pub async fn list_logs(
+    State(state): State<AppState>,
+    auth: AuthUser,
+    Query(query): Query<LogsQuery>,
+) -> Result<Page<LogEntry>, AppError> {
+    let logs = audit_service::list_logs(&state.db, &auth.scope(), &query).await?;
+    Ok(logs)
+}
+
+/// Download every matching entry as JSON or CSV
+pub async fn export_logs(
+    State(state): State<AppState>,
+    auth: AuthUser,
+    Query(query): Query<LogsQuery>,
+) -> Result<Response, AppError> {
+    auth.require_role(&[UserRole::Admin])?;
+
+    let logs = audit_service::export_logs(&state.db, &query).await?;
+
+    let (content_type, extension, body) = match query.format {
+        ExportFormat::Json => (
+            "application/json",
+            "json",
+            serde_json::to_string(&logs).map_err(|e| AppError::InternalError(e.to_string()))?,
+        ),
+        ExportFormat::Csv => ("text/csv; charset=utf-8", "csv", audit_service::to_csv(&logs)),
+    };
+
+    let disposition = format!(
+        "attachment; filename=\"audit-log-{}.{}\"",
+        chrono::Utc::now().format("%Y%m%d%H%M%S"),
+        extension
+    );
+
+    Ok((
+        [(header::CONTENT_TYPE, content_type.to_string()), (header::CONTENT_DISPOSITION, disposition)],
+        body,
+    )
+        .into_response())
+}
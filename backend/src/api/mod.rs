@@ -1,39 +1,110 @@
+pub mod admin;
+pub mod alerts;
+pub mod api_keys;
 pub mod auth;
 pub mod dashboard;
+pub mod events;
+pub mod logs;
+pub mod notifications;
+pub mod providers;
 pub mod servers;
 pub mod users;
 pub mod vps;
+pub mod websites;
 
 use axum::{
-    routing::{get, post, put, delete},
+    middleware::from_fn_with_state,
+    routing::{delete, get, post, put},
     Router,
 };
-use crate::models::AppState;
+use crate::{
+    middleware::{
+        audit::audit_mutations,
+        auth::{require_auth, require_mfa_enrollment, require_session},
+    },
+    models::AppState,
+};
 
-pub fn router() -> Router<AppState> {
-    Router::new()
-        // Auth routes
+pub fn router(state: AppState) -> Router<AppState> {
+    // Public routes
+    let public = Router::new()
         .route("/auth/login", post(auth::login))
         .route("/auth/register", post(auth::register))
+        .route("/auth/refresh", post(auth::refresh))
+        .route("/auth/password-reset/request", post(auth::request_password_reset))
+        .route("/auth/password-reset/confirm", post(auth::reset_password))
+        .route("/auth/verify-email/request", post(auth::request_email_verification))
+        .route("/auth/verify-email/confirm", post(auth::verify_email));
+
+    // Session and MFA routes, reachable before a forced MFA enrollment is completed
+    let session = Router::new()
         .route("/auth/logout", post(auth::logout))
+        .route("/auth/logout-all", post(auth::logout_all))
         .route("/auth/me", get(auth::me))
+        .route("/auth/mfa/enroll", post(auth::mfa_enroll))
+        .route("/auth/mfa/confirm", post(auth::mfa_confirm))
+        .route("/auth/mfa/disable", post(auth::mfa_disable));
 
+    // Routes open to API keys; each handler checks the key's scopes
+    let automation = Router::new()
         // Server routes
         .route("/servers", get(servers::list_servers).post(servers::create_server))
+        .route("/servers/register", post(servers::register_server))
         .route("/servers/:id", get(servers::get_server).put(servers::update_server).delete(servers::delete_server))
-        .route("/servers/:id/metrics", get(servers::get_server_metrics))
+        .route("/servers/:id/metrics", get(servers::get_server_metrics).post(servers::ingest_server_metrics))
+        .route("/servers/:id/mounts", get(servers::get_server_mounts))
 
         // VPS routes
         .route("/vps", get(vps::list_vps).post(vps::create_vps))
+        .route("/vps/catalog/server-types", get(vps::list_server_types))
+        .route("/vps/catalog/locations", get(vps::list_locations))
+        .route("/vps/catalog/images", get(vps::list_images))
+        .route("/vps/unmanaged", get(vps::list_unmanaged))
+        .route("/vps/unmanaged/:id/adopt", post(vps::adopt_server))
         .route("/vps/:id", get(vps::get_vps).put(vps::update_vps).delete(vps::delete_vps))
         .route("/vps/:id/power-on", post(vps::power_on_vps))
         .route("/vps/:id/power-off", post(vps::power_off_vps))
         .route("/vps/:id/reboot", post(vps::reboot_vps))
+        .route("/vps/:id/actions", get(vps::list_actions))
+        .route("/vps/:id/actions/:action_id", get(vps::get_action))
         .route("/vps/:id/sync", post(vps::sync_vps))
+        .route("/vps/:id/snapshots", post(vps::create_snapshot))
+
+        // Alert routes
+        .route("/alerts", get(alerts::list_alerts))
+        .route("/alerts/rules", get(alerts::list_rules).post(alerts::create_rule))
+        .route("/alerts/rules/:id", get(alerts::get_rule).put(alerts::update_rule).delete(alerts::delete_rule))
+        .route_layer(from_fn_with_state(state.clone(), require_mfa_enrollment));
+
+    let protected = Router::new()
+        // Auth routes
+        .route("/auth/password", post(auth::change_password))
+        .route("/auth/api-keys", get(api_keys::list_api_keys).post(api_keys::create_api_key))
+        .route("/auth/api-keys/:id", delete(api_keys::revoke_api_key))
+
+        // Notification routes
+        .route("/notifications/channels", get(notifications::list_channels).post(notifications::create_channel))
+        .route(
+            "/notifications/channels/:id",
+            get(notifications::get_channel).put(notifications::update_channel).delete(notifications::delete_channel),
+        )
+        .route("/notifications/channels/:id/test", post(notifications::test_channel))
+        .route("/notifications/deliveries", get(notifications::list_deliveries))
+
+        // Website routes
+        .route("/websites", get(websites::list_websites).post(websites::create_website))
+
+        // Cloud provider credentials
+        .route("/providers/credentials", get(providers::list_credentials))
+        .route(
+            "/providers/credentials/:provider",
+            put(providers::set_credential).delete(providers::delete_credential),
+        )
 
         // User routes
         .route("/users", get(users::list_users).post(users::create_user))
         .route("/users/:id", get(users::get_user).put(users::update_user).delete(users::delete_user))
+        .route("/users/:id/quota", get(users::get_quota).put(users::update_quota))
 
         // Dashboard routes
         .route("/dashboard/stats", get(dashboard::get_stats))
@@ -42,4 +113,28 @@ pub fn router() -> Router<AppState> {
         .route("/dashboard/servers", get(dashboard::get_servers))
         .route("/dashboard/hosting/activity", get(dashboard::get_hosting_activity))
         .route("/dashboard/hosting/storage", get(dashboard::get_hosting_storage))
+
+        // Live updates
+        .route("/events", get(events::stream_events))
+
+        // Audit log routes
+        .route("/logs", get(logs::list_logs))
+        .route("/logs/export", get(logs::export_logs))
+
+        // Admin routes
+        .route("/admin/mfa-policy", get(admin::get_mfa_policy).put(admin::update_mfa_policy))
+        .route("/admin/lockouts", get(admin::list_lockouts))
+        .route("/admin/lockouts/:email", delete(admin::clear_lockout))
+        .route_layer(from_fn_with_state(state.clone(), require_mfa_enrollment));
+
+    let authenticated = session
+        .merge(protected)
+        .route_layer(from_fn_with_state(state.clone(), require_session))
+        .merge(automation)
+        .route_layer(from_fn_with_state(state.clone(), require_auth));
+
+    // Outermost, so failed authentication attempts are audited too
+    public
+        .merge(authenticated)
+        .layer(from_fn_with_state(state, audit_mutations))
 }
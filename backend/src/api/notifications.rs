@@ -0,0 +1,82 @@
+use axum::{
+    extract::{Path, Query, State},
+    http::StatusCode,
+    Json,
+};
+use uuid::Uuid;
+use crate::{
+    middleware::{audit, auth::AuthUser, validation::ValidatedJson},
+    models::{notification::*, AppState},
+    services::notification_service,
+    utils::errors::AppError,
+};
+
+pub async fn list_channels(
+    State(state): State<AppState>,
+    auth: AuthUser,
+) -> Result<Json<Vec<NotificationChannelResponse>>, AppError> {
+    let channels = notification_service::list_channels(&state.db, auth.id).await?;
+    Ok(Json(channels.into_iter().map(|c| c.into()).collect()))
+}
+
+pub async fn get_channel(
+    State(state): State<AppState>,
+    auth: AuthUser,
+    Path(id): Path<Uuid>,
+) -> Result<Json<NotificationChannelResponse>, AppError> {
+    let channel = notification_service::get_channel(&state.db, auth.id, id).await?;
+    Ok(Json(channel.into()))
+}
+
+pub async fn create_channel(
+    State(state): State<AppState>,
+    auth: AuthUser,
+    ValidatedJson(payload): ValidatedJson<CreateNotificationChannel>,
+) -> Result<(StatusCode, Json<NotificationChannelResponse>), AppError> {
+    let channel: NotificationChannelResponse = notification_service::create_channel(&state.db, &state.config, auth.id, payload).await?.into();
+    audit::created(&format!("notification channel {}", channel.name), &channel);
+    Ok((StatusCode::CREATED, Json(channel)))
+}
+
+pub async fn update_channel(
+    State(state): State<AppState>,
+    auth: AuthUser,
+    Path(id): Path<Uuid>,
+    ValidatedJson(payload): ValidatedJson<UpdateNotificationChannel>,
+) -> Result<Json<NotificationChannelResponse>, AppError> {
+    let before: NotificationChannelResponse = notification_service::get_channel(&state.db, auth.id, id).await?.into();
+    let channel: NotificationChannelResponse = notification_service::update_channel(&state.db, &state.config, auth.id, id, payload).await?.into();
+    audit::changed(&format!("notification channel {}", channel.name), &before, &channel);
+    Ok(Json(channel))
+}
+
+pub async fn delete_channel(
+    State(state): State<AppState>,
+    auth: AuthUser,
+    Path(id): Path<Uuid>,
+) -> Result<StatusCode, AppError> {
+    let before: NotificationChannelResponse = notification_service::get_channel(&state.db, auth.id, id).await?.into();
+    notification_service::delete_channel(&state.db, auth.id, id).await?;
+    audit::deleted(&format!("notification channel {}", before.name), &before);
+    Ok(StatusCode::NO_CONTENT)
+}
+
+/// Queue a test message; its outcome appears in the delivery log
+pub async fn test_channel(
+    State(state): State<AppState>,
+    auth: AuthUser,
+    Path(id): Path<Uuid>,
+) -> Result<(StatusCode, Json<NotificationDelivery>), AppError> {
+    let delivery = notification_service::send_test(&state.db, auth.id, id).await?;
+    audit::describe(format!("Queued test message for notification channel {}", id));
+    Ok((StatusCode::ACCEPTED, Json(delivery)))
+}
+
+pub async fn list_deliveries(
+    State(state): State<AppState>,
+    auth: AuthUser,
+    Query(query): Query<DeliveriesQuery>,
+) -> Result<Json<Vec<NotificationDelivery>>, AppError> {
+    let deliveries = notification_service::list_deliveries(&state.db, auth.id, query).await?;
+    Ok(Json(deliveries))
+}
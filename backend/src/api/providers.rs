@@ -0,0 +1,52 @@
+use axum::{
+    extract::{Path, State},
+    http::StatusCode,
+    Json,
+};
+use crate::{
+    middleware::{audit, auth::AuthUser, validation::ValidatedJson},
+    models::{provider::*, AppState},
+    services::cloud_service,
+    utils::errors::AppError,
+};
+
+pub async fn list_credentials(
+    State(state): State<AppState>,
+    auth: AuthUser,
+) -> Result<Json<Vec<ProviderCredentialResponse>>, AppError> {
+    let credentials = cloud_service::list_credentials(&state.db, auth.id).await?;
+    Ok(Json(credentials.into_iter().map(|c| c.into()).collect()))
+}
+
+pub async fn set_credential(
+    State(state): State<AppState>,
+    auth: AuthUser,
+    Path(provider): Path<ProviderKind>,
+    ValidatedJson(payload): ValidatedJson<SetProviderCredential>,
+) -> Result<Json<ProviderCredentialResponse>, AppError> {
+    let before: Option<ProviderCredentialResponse> = cloud_service::get_credential(&state.db, auth.id, provider)
+        .await?
+        .map(|c| c.into());
+    let credential: ProviderCredentialResponse = cloud_service::set_credential(&state.db, &state.providers, auth.id, provider, payload).await?.into();
+
+    let label = format!("{} credentials", provider.as_str());
+    match before {
+        Some(before) => audit::changed(&label, &before, &credential),
+        None => audit::created(&label, &credential),
+    }
+    Ok(Json(credential))
+}
+
+pub async fn delete_credential(
+    State(state): State<AppState>,
+    auth: AuthUser,
+    Path(provider): Path<ProviderKind>,
+) -> Result<StatusCode, AppError> {
+    let before: ProviderCredentialResponse = cloud_service::get_credential(&state.db, auth.id, provider)
+        .await?
+        .ok_or(AppError::NotFound("Provider credentials not found".to_string()))?
+        .into();
+    cloud_service::delete_credential(&state.db, auth.id, provider).await?;
+    audit::deleted(&format!("{} credentials", provider.as_str()), &before);
+    Ok(StatusCode::NO_CONTENT)
+}
@@ -1,62 +1,128 @@
 use axum::{
-    extract::{Path, State},
+    extract::{Path, Query, State},
     Json,
 };
 use uuid::Uuid;
 use crate::{
-    models::{server::*, AppState},
-    services::server_service,
+    middleware::{audit, auth::AuthUser, validation::{ValidatedJson, ValidatedQuery}},
+    models::{api_key::ApiScope, list::{ListQuery, Page}, server::*, AppState},
+    services::{metrics_service, server_service},
     utils::errors::AppError,
 };
 
 pub async fn list_servers(
     State(state): State<AppState>,
-    // user_id should come from JWT middleware
-) -> Result<Json<Vec<Server>>, AppError> {
-    // For now, list all servers (should be filtered by user role)
-    let servers = server_service::list_servers(&state.db).await?;
-    Ok(Json(servers))
+    auth: AuthUser,
+    ValidatedQuery(query): ValidatedQuery<ListQuery>,
+) -> Result<Page<Server>, AppError> {
+    auth.require_scope(ApiScope::ServersRead)?;
+
+    let servers = server_service::list_servers(&state.db, &auth.scope(), &query).await?;
+    Ok(servers)
 }
 
 pub async fn get_server(
     State(state): State<AppState>,
+    auth: AuthUser,
     Path(id): Path<Uuid>,
 ) -> Result<Json<Server>, AppError> {
-    let server = server_service::get_server(&state.db, id).await?;
+    auth.require_scope(ApiScope::ServersRead)?;
+
+    let server = server_service::get_server(&state.db, &auth.scope(), id).await?;
     Ok(Json(server))
 }
 
 pub async fn create_server(
     State(state): State<AppState>,
-    Json(payload): Json<CreateServer>,
+    auth: AuthUser,
+    ValidatedJson(payload): ValidatedJson<CreateServer>,
 ) -> Result<Json<Server>, AppError> {
-    // user_id should come from JWT middleware
-    let user_id = Uuid::new_v4(); // Placeholder
-    let server = server_service::create_server(&state.db, user_id, payload).await?;
+    auth.require_scope(ApiScope::ServersWrite)?;
+
+    let server = server_service::create_server(&state.db, auth.id, payload).await?;
+    audit::server(server.id);
+    audit::created(&format!("server {}", server.name), &server);
+    Ok(Json(server))
+}
+
+/// Called by `unified-agent` on first run; returns the existing server for a known hostname
+pub async fn register_server(
+    State(state): State<AppState>,
+    auth: AuthUser,
+    ValidatedJson(payload): ValidatedJson<CreateServer>,
+) -> Result<Json<Server>, AppError> {
+    auth.require_scope(ApiScope::ServersWrite)?;
+
+    let server = server_service::register_agent(&state.db, auth.id, payload).await?;
+    audit::server(server.id);
+    audit::describe(format!("Agent registered server {}", server.name));
     Ok(Json(server))
 }
 
 pub async fn update_server(
     State(state): State<AppState>,
+    auth: AuthUser,
     Path(id): Path<Uuid>,
-    Json(payload): Json<UpdateServer>,
+    ValidatedJson(payload): ValidatedJson<UpdateServer>,
 ) -> Result<Json<Server>, AppError> {
-    let server = server_service::update_server(&state.db, id, payload).await?;
+    auth.require_scope(ApiScope::ServersWrite)?;
+
+    let before = server_service::get_server(&state.db, &auth.scope(), id).await?;
+    let server = server_service::update_server(&state.db, &auth.scope(), id, payload).await?;
+    audit::server(server.id);
+    audit::changed(&format!("server {}", server.name), &before, &server);
     Ok(Json(server))
 }
 
 pub async fn delete_server(
     State(state): State<AppState>,
+    auth: AuthUser,
     Path(id): Path<Uuid>,
 ) -> Result<Json<()>, AppError> {
-    server_service::delete_server(&state.db, id).await?;
+    auth.require_scope(ApiScope::ServersWrite)?;
+
+    let before = server_service::get_server(&state.db, &auth.scope(), id).await?;
+    server_service::delete_server(&state.db, &auth.scope(), id).await?;
+    // No `server_id`: the row is gone and the foreign key would reject it
+    audit::deleted(&format!("server {}", before.name), &before);
     Ok(Json(()))
 }
 
 pub async fn get_server_metrics(
     State(state): State<AppState>,
+    auth: AuthUser,
     Path(id): Path<Uuid>,
-) -> Result<Json<Vec<ServerMetrics>>, AppError> {
-    let metrics = server_service::get_server_metrics(&state.db, id).await?;
+    Query(query): Query<MetricsQuery>,
+) -> Result<Json<MetricsResponse>, AppError> {
+    auth.require_scope(ApiScope::MetricsRead)?;
+
+    let metrics = metrics_service::query_metrics(&state.db, &state.config, &auth.scope(), id, query).await?;
     Ok(Json(metrics))
 }
+
+/// Agent ingestion: one sample, an array, or `{"samples": [...]}`
+pub async fn ingest_server_metrics(
+    State(state): State<AppState>,
+    auth: AuthUser,
+    Path(id): Path<Uuid>,
+    ValidatedJson(payload): ValidatedJson<IngestMetrics>,
+) -> Result<Json<IngestMetricsResponse>, AppError> {
+    // Agents post every few seconds; auditing samples would drown out everything else
+    audit::skip();
+    auth.require_scope(ApiScope::MetricsWrite)?;
+
+    let server = server_service::get_server(&state.db, &auth.scope(), id).await?;
+    let result = metrics_service::ingest_metrics(&state.db, &state.events, &server, payload.samples).await?;
+    Ok(Json(result))
+}
+
+pub async fn get_server_mounts(
+    State(state): State<AppState>,
+    auth: AuthUser,
+    Path(id): Path<Uuid>,
+) -> Result<Json<Vec<ServerMount>>, AppError> {
+    auth.require_scope(ApiScope::MetricsRead)?;
+
+    let mounts = metrics_service::list_mounts(&state.db, &auth.scope(), id).await?;
+    Ok(Json(mounts))
+}
@@ -4,48 +4,112 @@ use axum::{
 };
 use uuid::Uuid;
 use crate::{
-    models::{user::*, AppState},
-    services::user_service,
+    middleware::{audit, auth::AuthUser, validation::{ValidatedJson, ValidatedQuery}},
+    models::{list::{ListQuery, Page}, user::*, AppState},
+    services::{quota_service, user_service, verification_service},
     utils::errors::AppError,
 };
 
 pub async fn list_users(
     State(state): State<AppState>,
-) -> Result<Json<Vec<UserResponse>>, AppError> {
-    let users = user_service::list_users(&state.db).await?;
-    let response: Vec<UserResponse> = users.into_iter().map(|u| u.into()).collect();
-    Ok(Json(response))
+    auth: AuthUser,
+    ValidatedQuery(query): ValidatedQuery<ListQuery>,
+) -> Result<Page<UserResponse>, AppError> {
+    let users = user_service::list_users(&state.db, &auth.scope(), &query).await?;
+    Ok(users.map(|u| u.into()))
 }
 
 pub async fn get_user(
     State(state): State<AppState>,
+    auth: AuthUser,
     Path(id): Path<Uuid>,
 ) -> Result<Json<UserResponse>, AppError> {
-    let user = user_service::get_user(&state.db, id).await?;
+    let user = user_service::get_user(&state.db, &auth.scope(), id).await?;
     Ok(Json(user.into()))
 }
 
 pub async fn create_user(
     State(state): State<AppState>,
-    Json(payload): Json<CreateUser>,
+    auth: AuthUser,
+    ValidatedJson(mut payload): ValidatedJson<CreateUser>,
 ) -> Result<Json<UserResponse>, AppError> {
-    let user = user_service::create_user(&state.db, payload).await?;
-    Ok(Json(user.into()))
+    auth.require_role(&[UserRole::Admin, UserRole::Reseller])?;
+
+    // Resellers can only create customers, which are always attached to themselves
+    if auth.role == UserRole::Reseller {
+        if payload.role.as_ref().is_some_and(|role| *role != UserRole::User) {
+            return Err(AppError::Forbidden("Resellers can only create customer accounts".to_string()));
+        }
+        payload.parent_id = Some(auth.id);
+    }
+
+    let user: UserResponse = user_service::create_user(&state.db, &state.config, payload).await?.into();
+    audit::created(&format!("user {}", user.email), &user);
+    Ok(Json(user))
 }
 
 pub async fn update_user(
     State(state): State<AppState>,
+    auth: AuthUser,
     Path(id): Path<Uuid>,
-    Json(payload): Json<UpdateUser>,
+    ValidatedJson(payload): ValidatedJson<UpdateUser>,
 ) -> Result<Json<UserResponse>, AppError> {
-    let user = user_service::update_user(&state.db, id, payload).await?;
-    Ok(Json(user.into()))
+    let before: UserResponse = user_service::get_user(&state.db, &auth.scope(), id).await?.into();
+    let user = user_service::update_user(&state.db, &auth.scope(), id, payload).await?;
+
+    if user.email != before.email {
+        verification_service::send_verification_email(&state.db, &state.config, state.mailer.clone(), &user).await?;
+    }
+
+    let user: UserResponse = user.into();
+    audit::changed(&format!("user {}", user.email), &before, &user);
+    Ok(Json(user))
 }
 
 pub async fn delete_user(
     State(state): State<AppState>,
+    auth: AuthUser,
     Path(id): Path<Uuid>,
 ) -> Result<Json<()>, AppError> {
-    user_service::delete_user(&state.db, id).await?;
+    auth.require_role(&[UserRole::Admin, UserRole::Reseller])?;
+
+    if id == auth.id {
+        return Err(AppError::BadRequest("Cannot delete your own account".to_string()));
+    }
+
+    let before: UserResponse = user_service::get_user(&state.db, &auth.scope(), id).await?.into();
+    user_service::delete_user(&state.db, &auth.scope(), id).await?;
+    audit::deleted(&format!("user {}", before.email), &before);
     Ok(Json(()))
 }
+
+pub async fn get_quota(
+    State(state): State<AppState>,
+    auth: AuthUser,
+    Path(id): Path<Uuid>,
+) -> Result<Json<QuotaResponse>, AppError> {
+    user_service::get_user(&state.db, &auth.scope(), id).await?;
+
+    let quota = quota_service::get_quota(&state.db, id).await?;
+    Ok(Json(quota))
+}
+
+pub async fn update_quota(
+    State(state): State<AppState>,
+    auth: AuthUser,
+    Path(id): Path<Uuid>,
+    ValidatedJson(payload): ValidatedJson<UpdateQuota>,
+) -> Result<Json<QuotaResponse>, AppError> {
+    auth.require_role(&[UserRole::Admin, UserRole::Reseller])?;
+
+    if id == auth.id {
+        return Err(AppError::Forbidden("Cannot change your own quota".to_string()));
+    }
+
+    let user = user_service::get_user(&state.db, &auth.scope(), id).await?;
+
+    let before = quota_service::get_quota(&state.db, id).await?;
+    let quota = quota_service::set_quota(&state.db, id, auth.id, payload).await?;
+    audit::changed(&format!("quota for {}", user.email), &before, &quota);
+    Ok(Json(quota))
+}
@@ -1,114 +1,229 @@
 use axum::{
-    extract::{Path, State},
+    extract::{Path, Query, State},
+    http::StatusCode,
     Json,
 };
 use uuid::Uuid;
 use crate::{
-    models::{vps::*, AppState},
+    middleware::{audit, auth::AuthUser, validation::{ValidatedJson, ValidatedQuery}},
+    models::{
+        api_key::ApiScope,
+        list::{ListQuery, Page},
+        provider::{CatalogQuery, CloudSnapshot, CreateSnapshot, ImageOption, LocationOption, ServerTypeOption},
+        vps::*,
+        AppState,
+    },
     services::vps_service,
     utils::errors::AppError,
 };
 
 pub async fn list_vps(
     State(state): State<AppState>,
-    // TODO: Extract user from JWT middleware
-) -> Result<Json<Vec<Vps>>, AppError> {
-    let vps = vps_service::list_vps(&state.db, None).await?;
-    Ok(Json(vps))
+    auth: AuthUser,
+    ValidatedQuery(query): ValidatedQuery<ListQuery>,
+) -> Result<Page<Vps>, AppError> {
+    auth.require_scope(ApiScope::VpsRead)?;
+
+    let vps = vps_service::list_vps(&state.db, &auth.scope(), &query).await?;
+    Ok(vps)
 }
 
 pub async fn get_vps(
     State(state): State<AppState>,
+    auth: AuthUser,
     Path(id): Path<Uuid>,
 ) -> Result<Json<Vps>, AppError> {
-    let vps = vps_service::get_vps(&state.db, id).await?;
+    auth.require_scope(ApiScope::VpsRead)?;
+
+    let vps = vps_service::get_vps(&state.db, &auth.scope(), id).await?;
     Ok(Json(vps))
 }
 
 pub async fn create_vps(
     State(state): State<AppState>,
-    Json(payload): Json<CreateVps>,
+    auth: AuthUser,
+    ValidatedJson(payload): ValidatedJson<CreateVps>,
 ) -> Result<Json<Vps>, AppError> {
-    // TODO: Get user_id from JWT
-    let user_id = Uuid::new_v4(); // Placeholder
+    auth.require_scope(ApiScope::VpsWrite)?;
 
-    let vps = vps_service::create_vps(&state.db, &state.hetzner_client, user_id, payload).await?;
+    let vps = vps_service::create_vps(&state.db, &state.providers, &state.events, auth.id, payload).await?;
+    audit::created(&format!("VPS {}", vps.name), &vps);
     Ok(Json(vps))
 }
 
 pub async fn update_vps(
     State(state): State<AppState>,
+    auth: AuthUser,
     Path(id): Path<Uuid>,
-    Json(payload): Json<UpdateVps>,
+    ValidatedJson(payload): ValidatedJson<UpdateVps>,
 ) -> Result<Json<Vps>, AppError> {
-    let vps = vps_service::update_vps(&state.db, id, payload).await?;
+    auth.require_scope(ApiScope::VpsWrite)?;
+
+    let before = vps_service::get_vps(&state.db, &auth.scope(), id).await?;
+    let vps = vps_service::update_vps(&state.db, &state.events, &auth.scope(), id, payload).await?;
+    audit::changed(&format!("VPS {}", vps.name), &before, &vps);
     Ok(Json(vps))
 }
 
 pub async fn delete_vps(
     State(state): State<AppState>,
+    auth: AuthUser,
     Path(id): Path<Uuid>,
 ) -> Result<Json<()>, AppError> {
-    vps_service::delete_vps(&state.db, &state.hetzner_client, id).await?;
+    auth.require_scope(ApiScope::VpsWrite)?;
+
+    let before = vps_service::get_vps(&state.db, &auth.scope(), id).await?;
+    vps_service::delete_vps(&state.db, &state.providers, &state.events, &auth.scope(), id).await?;
+    audit::deleted(&format!("VPS {}", before.name), &before);
     Ok(Json(()))
 }
 
 pub async fn power_on_vps(
     State(state): State<AppState>,
+    auth: AuthUser,
     Path(id): Path<Uuid>,
-) -> Result<Json<Vps>, AppError> {
-    let vps = vps_service::get_vps(&state.db, id).await?;
-
-    if let Some(hetzner_id) = vps.hetzner_id {
-        state.hetzner_client.power_on(hetzner_id).await?;
-
-        // Wait a bit and sync status
-        tokio::time::sleep(tokio::time::Duration::from_secs(2)).await;
-        let vps = vps_service::sync_vps_status(&state.db, &state.hetzner_client, id).await?;
-        Ok(Json(vps))
-    } else {
-        Err(AppError::BadRequest("VPS not linked to Hetzner server".to_string()))
-    }
+) -> Result<(StatusCode, Json<VpsAction>), AppError> {
+    power(&state, &auth, id, PowerAction::PowerOn).await
 }
 
 pub async fn power_off_vps(
     State(state): State<AppState>,
+    auth: AuthUser,
     Path(id): Path<Uuid>,
-) -> Result<Json<Vps>, AppError> {
-    let vps = vps_service::get_vps(&state.db, id).await?;
+) -> Result<(StatusCode, Json<VpsAction>), AppError> {
+    power(&state, &auth, id, PowerAction::PowerOff).await
+}
 
-    if let Some(hetzner_id) = vps.hetzner_id {
-        state.hetzner_client.power_off(hetzner_id).await?;
+pub async fn reboot_vps(
+    State(state): State<AppState>,
+    auth: AuthUser,
+    Path(id): Path<Uuid>,
+) -> Result<(StatusCode, Json<VpsAction>), AppError> {
+    power(&state, &auth, id, PowerAction::Reboot).await
+}
 
-        tokio::time::sleep(tokio::time::Duration::from_secs(2)).await;
-        let vps = vps_service::sync_vps_status(&state.db, &state.hetzner_client, id).await?;
-        Ok(Json(vps))
-    } else {
-        Err(AppError::BadRequest("VPS not linked to Hetzner server".to_string()))
-    }
+/// Accepted once the provider has the action; watch it via `/vps/:id/actions/:action_id` or `vps.action` events
+async fn power(
+    state: &AppState,
+    auth: &AuthUser,
+    id: Uuid,
+    action: PowerAction,
+) -> Result<(StatusCode, Json<VpsAction>), AppError> {
+    auth.require_scope(ApiScope::VpsPower)?;
+
+    let vps = vps_service::get_vps(&state.db, &auth.scope(), id).await?;
+    let vps_action = vps_service::power_action(&state.db, &state.providers, &state.events, &auth.scope(), id, action).await?;
+    audit::describe(format!("{} VPS {}", action.describe(), vps.name));
+    Ok((StatusCode::ACCEPTED, Json(vps_action)))
 }
 
-pub async fn reboot_vps(
+pub async fn list_actions(
     State(state): State<AppState>,
+    auth: AuthUser,
     Path(id): Path<Uuid>,
-) -> Result<Json<Vps>, AppError> {
-    let vps = vps_service::get_vps(&state.db, id).await?;
+) -> Result<Json<Vec<VpsAction>>, AppError> {
+    auth.require_scope(ApiScope::VpsRead)?;
+
+    let actions = vps_service::list_actions(&state.db, &auth.scope(), id).await?;
+    Ok(Json(actions))
+}
+
+pub async fn get_action(
+    State(state): State<AppState>,
+    auth: AuthUser,
+    Path((id, action_id)): Path<(Uuid, Uuid)>,
+) -> Result<Json<VpsAction>, AppError> {
+    auth.require_scope(ApiScope::VpsRead)?;
+
+    let action = vps_service::get_action(&state.db, &auth.scope(), id, action_id).await?;
+    Ok(Json(action))
+}
 
-    if let Some(hetzner_id) = vps.hetzner_id {
-        state.hetzner_client.reboot(hetzner_id).await?;
+pub async fn create_snapshot(
+    State(state): State<AppState>,
+    auth: AuthUser,
+    Path(id): Path<Uuid>,
+    ValidatedJson(payload): ValidatedJson<CreateSnapshot>,
+) -> Result<(StatusCode, Json<CloudSnapshot>), AppError> {
+    auth.require_scope(ApiScope::VpsWrite)?;
 
-        tokio::time::sleep(tokio::time::Duration::from_secs(2)).await;
-        let vps = vps_service::sync_vps_status(&state.db, &state.hetzner_client, id).await?;
-        Ok(Json(vps))
-    } else {
-        Err(AppError::BadRequest("VPS not linked to Hetzner server".to_string()))
-    }
+    let snapshot = vps_service::create_snapshot(&state.db, &state.providers, &auth.scope(), id, payload.description).await?;
+    audit::created(&format!("snapshot {}", snapshot.description), &snapshot);
+    Ok((StatusCode::CREATED, Json(snapshot)))
 }
 
 pub async fn sync_vps(
     State(state): State<AppState>,
+    auth: AuthUser,
     Path(id): Path<Uuid>,
 ) -> Result<Json<Vps>, AppError> {
-    let vps = vps_service::sync_vps_status(&state.db, &state.hetzner_client, id).await?;
+    auth.require_scope(ApiScope::VpsWrite)?;
+
+    let before = vps_service::get_vps(&state.db, &auth.scope(), id).await?;
+    let vps = vps_service::sync_vps_status(&state.db, &state.providers, &state.events, &auth.scope(), id).await?;
+    audit::changed(&format!("VPS {} from {}", vps.name, vps.provider), &before, &vps);
     Ok(Json(vps))
 }
+
+/// Server types of a provider, optionally only those offered in `location`
+pub async fn list_server_types(
+    State(state): State<AppState>,
+    auth: AuthUser,
+    Query(query): Query<CatalogQuery>,
+) -> Result<Json<Vec<ServerTypeOption>>, AppError> {
+    auth.require_scope(ApiScope::VpsRead)?;
+
+    let catalog = state.providers.catalog(&state.db, auth.id, query.provider).await?;
+    let server_types = catalog.server_types
+        .iter()
+        .filter(|option| query.location.as_deref().is_none_or(|location| option.offered_in(location)))
+        .cloned()
+        .collect();
+    Ok(Json(server_types))
+}
+
+pub async fn list_locations(
+    State(state): State<AppState>,
+    auth: AuthUser,
+    Query(query): Query<CatalogQuery>,
+) -> Result<Json<Vec<LocationOption>>, AppError> {
+    auth.require_scope(ApiScope::VpsRead)?;
+
+    let catalog = state.providers.catalog(&state.db, auth.id, query.provider).await?;
+    Ok(Json(catalog.locations.clone()))
+}
+
+pub async fn list_images(
+    State(state): State<AppState>,
+    auth: AuthUser,
+    Query(query): Query<CatalogQuery>,
+) -> Result<Json<Vec<ImageOption>>, AppError> {
+    auth.require_scope(ApiScope::VpsRead)?;
+
+    let catalog = state.providers.catalog(&state.db, auth.id, query.provider).await?;
+    Ok(Json(catalog.images.clone()))
+}
+
+pub async fn list_unmanaged(
+    State(state): State<AppState>,
+    auth: AuthUser,
+) -> Result<Json<Vec<UnmanagedServer>>, AppError> {
+    auth.require_scope(ApiScope::VpsRead)?;
+
+    let servers = vps_service::list_unmanaged(&state.db, &auth.scope()).await?;
+    Ok(Json(servers))
+}
+
+pub async fn adopt_server(
+    State(state): State<AppState>,
+    auth: AuthUser,
+    Path(id): Path<Uuid>,
+    ValidatedJson(payload): ValidatedJson<AdoptServer>,
+) -> Result<(StatusCode, Json<Vps>), AppError> {
+    auth.require_scope(ApiScope::VpsWrite)?;
+
+    let user_id = payload.user_id.unwrap_or(auth.id);
+    let vps = vps_service::adopt_server(&state.db, &state.providers, &state.events, &auth.scope(), id, user_id).await?;
+    audit::created(&format!("VPS {} (adopted from {})", vps.name, vps.provider), &vps);
+    Ok((StatusCode::CREATED, Json(vps)))
+}
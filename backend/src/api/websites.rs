@@ -0,0 +1,29 @@
+use axum::{extract::State, Json};
+use crate::{
+    middleware::{audit, auth::AuthUser, validation::{ValidatedJson, ValidatedQuery}},
+    models::{list::{ListQuery, Page}, website::*, AppState},
+    services::website_service,
+    utils::errors::AppError,
+};
+
+pub async fn list_websites(
+    State(state): State<AppState>,
+    auth: AuthUser,
+    ValidatedQuery(query): ValidatedQuery<ListQuery>,
+) -> Result<Page<Website>, AppError> {
+    let websites = website_service::list_websites(&state.db, &auth.scope(), &query).await?;
+    Ok(websites)
+}
+
+pub async fn create_website(
+    State(state): State<AppState>,
+    auth: AuthUser,
+    ValidatedJson(payload): ValidatedJson<CreateWebsite>,
+) -> Result<Json<Website>, AppError> {
+    let website = website_service::create_website(&state.db, &auth.scope(), auth.id, payload).await?;
+    if let Some(server_id) = website.server_id {
+        audit::server(server_id);
+    }
+    audit::created(&format!("website {}", website.domain), &website);
+    Ok(Json(website))
+}
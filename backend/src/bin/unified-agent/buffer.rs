@@ -0,0 +1,151 @@
+use crate::collector::Sample;
+use anyhow::{Context, Result};
+use chrono::{Duration, Utc};
+use std::{
+    fs,
+    io::{BufRead, BufReader, Write},
+    path::PathBuf,
+};
+
+/// A week of samples at the default 60s interval
+pub const MAX_BUFFERED: usize = 10_080;
+
+/// The panel rejects samples older than 7 days; leave headroom for the flush itself
+const MAX_AGE_HOURS: i64 = 7 * 24 - 1;
+
+/// Samples the panel has not acknowledged yet, persisted as JSON lines so they survive restarts
+pub struct Buffer {
+    path: PathBuf,
+}
+
+impl Buffer {
+    pub fn new(path: PathBuf) -> Self {
+        Self { path }
+    }
+
+    pub fn load(&self) -> Result<Vec<Sample>> {
+        let file = match fs::File::open(&self.path) {
+            Ok(file) => file,
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => return Ok(Vec::new()),
+            Err(e) => return Err(e).with_context(|| format!("opening {}", self.path.display())),
+        };
+
+        // A torn final line from a crash mid-write is dropped, not fatal
+        let mut samples: Vec<Sample> = BufReader::new(file)
+            .lines()
+            .map_while(Result::ok)
+            .filter_map(|line| serde_json::from_str(&line).ok())
+            .collect();
+
+        discard_stale(&mut samples);
+        Ok(samples)
+    }
+
+    /// Replace the file contents with `samples`, removing it once everything is delivered
+    pub fn store(&self, samples: &[Sample]) -> Result<()> {
+        if samples.is_empty() {
+            return match fs::remove_file(&self.path) {
+                Err(e) if e.kind() != std::io::ErrorKind::NotFound => Err(e.into()),
+                _ => Ok(()),
+            };
+        }
+
+        let tmp = self.path.with_extension("tmp");
+        let mut file = fs::File::create(&tmp).with_context(|| format!("creating {}", tmp.display()))?;
+        for sample in samples {
+            serde_json::to_writer(&mut file, sample)?;
+            file.write_all(b"\n")?;
+        }
+        file.sync_all()?;
+        fs::rename(&tmp, &self.path)?;
+
+        Ok(())
+    }
+}
+
+/// Drop samples the panel would refuse and keep the newest `MAX_BUFFERED`
+pub fn discard_stale(samples: &mut Vec<Sample>) {
+    let cutoff = Utc::now() - Duration::hours(MAX_AGE_HOURS);
+    samples.retain(|sample| sample.timestamp >= cutoff);
+
+    if samples.len() > MAX_BUFFERED {
+        samples.drain(..samples.len() - MAX_BUFFERED);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_support::sample;
+
+    fn temp_buffer() -> (Buffer, PathBuf) {
+        let path = std::env::temp_dir().join(format!("unified-agent-buffer-{}.jsonl", uuid::Uuid::new_v4()));
+        (Buffer::new(path.clone()), path)
+    }
+
+    #[test]
+    fn samples_survive_a_round_trip() {
+        let (buffer, path) = temp_buffer();
+        let samples = vec![sample(Utc::now() - Duration::minutes(2)), sample(Utc::now())];
+
+        buffer.store(&samples).unwrap();
+        let loaded = buffer.load().unwrap();
+        fs::remove_file(&path).ok();
+
+        assert_eq!(loaded.len(), 2);
+        assert_eq!(loaded[0].timestamp, samples[0].timestamp);
+        assert_eq!(loaded[1].network_out, 2_000);
+    }
+
+    #[test]
+    fn missing_file_is_an_empty_buffer() {
+        let (buffer, _) = temp_buffer();
+
+        assert!(buffer.load().unwrap().is_empty());
+    }
+
+    #[test]
+    fn storing_nothing_removes_the_file() {
+        let (buffer, path) = temp_buffer();
+        buffer.store(&[sample(Utc::now())]).unwrap();
+
+        buffer.store(&[]).unwrap();
+        buffer.store(&[]).unwrap();
+
+        assert!(!path.exists());
+    }
+
+    #[test]
+    fn torn_lines_are_dropped() {
+        let (buffer, path) = temp_buffer();
+        buffer.store(&[sample(Utc::now())]).unwrap();
+        fs::OpenOptions::new().append(true).open(&path).unwrap().write_all(b"{\"cpu_usage\": 1").unwrap();
+
+        let loaded = buffer.load().unwrap();
+        fs::remove_file(&path).ok();
+
+        assert_eq!(loaded.len(), 1);
+    }
+
+    #[test]
+    fn stale_samples_are_discarded() {
+        let mut samples = vec![sample(Utc::now() - Duration::days(8)), sample(Utc::now())];
+
+        discard_stale(&mut samples);
+
+        assert_eq!(samples.len(), 1);
+    }
+
+    #[test]
+    fn only_the_newest_samples_are_kept() {
+        let start = Utc::now() - Duration::hours(1);
+        let mut samples: Vec<Sample> = (0..MAX_BUFFERED as i64 + 5)
+            .map(|i| sample(start + Duration::milliseconds(i)))
+            .collect();
+
+        discard_stale(&mut samples);
+
+        assert_eq!(samples.len(), MAX_BUFFERED);
+        assert_eq!(samples[0].timestamp, start + Duration::milliseconds(5));
+    }
+}
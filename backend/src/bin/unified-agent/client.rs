@@ -0,0 +1,249 @@
+use crate::collector::{HostInfo, Sample};
+use anyhow::{anyhow, Context, Result};
+use reqwest::StatusCode;
+use serde::{Deserialize, Serialize};
+use std::{net::UdpSocket, time::Duration};
+use uuid::Uuid;
+
+const REQUEST_TIMEOUT_SECONDS: u64 = 15;
+
+/// Why a delivery failed, which decides whether the samples are kept
+#[derive(Debug)]
+pub enum SendError {
+    /// Network errors, timeouts, 429, 5xx and anything unexpected; retry later
+    Unavailable(String),
+    /// The server row is gone or not visible to this key
+    UnknownServer,
+    /// 401 or 403: the key was revoked, expired or lacks `metrics:write`; keep the samples until it is fixed
+    Unauthorized(String),
+    /// 400, 413 or 422: the panel refused the batch for good; retrying would fail the same way
+    Rejected(String),
+}
+
+#[derive(Serialize)]
+struct RegisterRequest<'a> {
+    name: &'a str,
+    hostname: &'a str,
+    ip_address: String,
+    server_type: &'a str,
+    cpu_cores: i32,
+    ram_gb: i32,
+    disk_gb: i32,
+    os: Option<&'a str>,
+}
+
+#[derive(Deserialize)]
+struct RegisteredServer {
+    id: Uuid,
+}
+
+#[derive(Serialize)]
+struct IngestRequest<'a> {
+    samples: &'a [Sample],
+}
+
+pub struct PanelClient {
+    base_url: String,
+    api_key: String,
+    client: reqwest::Client,
+}
+
+impl PanelClient {
+    pub fn new(base_url: &str, api_key: String) -> Result<Self> {
+        Ok(Self {
+            base_url: base_url.trim_end_matches('/').to_string(),
+            api_key,
+            client: reqwest::Client::builder()
+                .timeout(Duration::from_secs(REQUEST_TIMEOUT_SECONDS))
+                .user_agent(concat!("unified-agent/", env!("CARGO_PKG_VERSION")))
+                .build()?,
+        })
+    }
+
+    /// Create or look up this host's `Server` row, matched by hostname
+    pub async fn register(
+        &self,
+        host: &HostInfo,
+        name: &str,
+        server_type: &str,
+        ip_address: Option<&str>,
+    ) -> Result<Uuid> {
+        let ip_address = match ip_address {
+            Some(ip) => ip.to_string(),
+            None => self.local_address()?,
+        };
+
+        let response = self.client
+            .post(format!("{}/api/servers/register", self.base_url))
+            .bearer_auth(&self.api_key)
+            .json(&RegisterRequest {
+                name,
+                hostname: &host.hostname,
+                ip_address,
+                server_type,
+                cpu_cores: host.cpu_cores,
+                ram_gb: host.ram_gb,
+                disk_gb: host.disk_gb,
+                os: host.os.as_deref(),
+            })
+            .send()
+            .await
+            .context("registering with the panel")?;
+
+        let status = response.status();
+        if !status.is_success() {
+            let body = response.text().await.unwrap_or_default();
+            return Err(anyhow!("registration failed with {}: {}", status, body));
+        }
+
+        Ok(response.json::<RegisteredServer>().await?.id)
+    }
+
+    pub async fn send(&self, server_id: Uuid, samples: &[Sample]) -> Result<(), SendError> {
+        let response = self.client
+            .post(format!("{}/api/servers/{}/metrics", self.base_url, server_id))
+            .bearer_auth(&self.api_key)
+            .json(&IngestRequest { samples })
+            .send()
+            .await
+            .map_err(|e| SendError::Unavailable(e.to_string()))?;
+
+        let status = response.status();
+        if status.is_success() {
+            return Ok(());
+        }
+
+        let body = response.text().await.unwrap_or_default();
+        Err(match status {
+            StatusCode::NOT_FOUND => SendError::UnknownServer,
+            StatusCode::UNAUTHORIZED | StatusCode::FORBIDDEN => {
+                SendError::Unauthorized(format!("{}: {}", status, body))
+            }
+            StatusCode::BAD_REQUEST | StatusCode::PAYLOAD_TOO_LARGE | StatusCode::UNPROCESSABLE_ENTITY => {
+                SendError::Rejected(format!("{}: {}", status, body))
+            }
+            StatusCode::TOO_MANY_REQUESTS | StatusCode::REQUEST_TIMEOUT => {
+                SendError::Unavailable(format!("{}: {}", status, body))
+            }
+            _ => SendError::Unavailable(format!("{}: {}", status, body)),
+        })
+    }
+
+    /// The source address used to reach the panel; connecting a UDP socket sends no packets
+    fn local_address(&self) -> Result<String> {
+        let url = reqwest::Url::parse(&self.base_url)?;
+        let remote = url
+            .socket_addrs(|| None)?
+            .into_iter()
+            .next()
+            .ok_or_else(|| anyhow!("could not resolve {}", self.base_url))?;
+
+        let socket = UdpSocket::bind(if remote.is_ipv4() { "0.0.0.0:0" } else { "[::]:0" })?;
+        socket.connect(remote)?;
+
+        Ok(socket.local_addr()?.ip().to_string())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_support::{sample, serve};
+    use axum::{
+        http::{HeaderMap, StatusCode as Status},
+        routing::post,
+        Json, Router,
+    };
+    use chrono::Utc;
+
+    async fn send_with_status(status: Status) -> Result<(), SendError> {
+        let url = serve(Router::new().route(
+            "/api/servers/:id/metrics",
+            post(move || async move { (status, "nope") }),
+        ))
+        .await;
+
+        PanelClient::new(&url, "key".to_string())
+            .unwrap()
+            .send(Uuid::new_v4(), &[sample(Utc::now())])
+            .await
+    }
+
+    #[tokio::test]
+    async fn statuses_decide_whether_samples_are_kept() {
+        assert!(send_with_status(Status::ACCEPTED).await.is_ok());
+        assert!(matches!(send_with_status(Status::NOT_FOUND).await, Err(SendError::UnknownServer)));
+        for status in [Status::TOO_MANY_REQUESTS, Status::REQUEST_TIMEOUT, Status::BAD_GATEWAY, Status::CONFLICT] {
+            assert!(matches!(send_with_status(status).await, Err(SendError::Unavailable(_))));
+        }
+        for status in [Status::UNAUTHORIZED, Status::FORBIDDEN] {
+            assert!(matches!(send_with_status(status).await, Err(SendError::Unauthorized(_))));
+        }
+        for status in [Status::BAD_REQUEST, Status::PAYLOAD_TOO_LARGE, Status::UNPROCESSABLE_ENTITY] {
+            assert!(matches!(send_with_status(status).await, Err(SendError::Rejected(reason)) if reason.contains("nope")));
+        }
+    }
+
+    #[tokio::test]
+    async fn unreachable_panel_is_unavailable() {
+        let listener = std::net::TcpListener::bind("127.0.0.1:0").unwrap();
+        let url = format!("http://{}", listener.local_addr().unwrap());
+        drop(listener);
+
+        let result = PanelClient::new(&url, "key".to_string())
+            .unwrap()
+            .send(Uuid::new_v4(), &[])
+            .await;
+
+        assert!(matches!(result, Err(SendError::Unavailable(_))));
+    }
+
+    #[tokio::test]
+    async fn registration_posts_host_details() {
+        let url = serve(Router::new().route(
+            "/api/servers/register",
+            post(|headers: HeaderMap, Json(body): Json<serde_json::Value>| async move {
+                assert_eq!(headers["authorization"], "Bearer key");
+                assert_eq!(body["hostname"], "web-1");
+                assert_eq!(body["name"], "Web");
+                assert_eq!(body["ip_address"], "192.0.2.10");
+                assert_eq!(body["cpu_cores"], 4);
+                Json(serde_json::json!({ "id": "6f1c2a9e-8d4b-4c1e-9a7f-3b2d1e0c5a6b" }))
+            }),
+        ))
+        .await;
+        let host = HostInfo {
+            hostname: "web-1".to_string(),
+            cpu_cores: 4,
+            ram_gb: 8,
+            disk_gb: 100,
+            os: Some("Debian GNU/Linux 12".to_string()),
+        };
+
+        let id = PanelClient::new(&format!("{}/", url), "key".to_string())
+            .unwrap()
+            .register(&host, "Web", "dedicated", Some("192.0.2.10"))
+            .await
+            .unwrap();
+
+        assert_eq!(id.to_string(), "6f1c2a9e-8d4b-4c1e-9a7f-3b2d1e0c5a6b");
+    }
+
+    #[tokio::test]
+    async fn failed_registration_is_an_error() {
+        let url = serve(Router::new().route(
+            "/api/servers/register",
+            post(|| async { (Status::FORBIDDEN, "missing scope") }),
+        ))
+        .await;
+        let host = HostInfo { hostname: "web-1".to_string(), cpu_cores: 1, ram_gb: 1, disk_gb: 1, os: None };
+
+        let error = PanelClient::new(&url, "key".to_string())
+            .unwrap()
+            .register(&host, "Web", "dedicated", Some("192.0.2.10"))
+            .await
+            .unwrap_err();
+
+        assert!(error.to_string().contains("missing scope"));
+    }
+}
@@ -0,0 +1,395 @@
+use anyhow::{anyhow, Context, Result};
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use std::{collections::HashSet, ffi::CString, fs};
+
+/// Pseudo and virtual filesystems that never count as disk usage
+const IGNORED_FILESYSTEMS: &[&str] = &[
+    "autofs", "binfmt_misc", "bpf", "cgroup", "cgroup2", "configfs", "debugfs", "devpts",
+    "devtmpfs", "efivarfs", "fusectl", "hugetlbfs", "mqueue", "nsfs", "overlay", "proc",
+    "pstore", "ramfs", "rpc_pipefs", "securityfs", "squashfs", "sysfs", "tmpfs", "tracefs",
+];
+
+/// The panel accepts at most this many mounts per sample
+const MAX_MOUNTS: usize = 256;
+
+/// One sample in the shape accepted by `POST /api/servers/:id/metrics`
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Sample {
+    pub cpu_usage: f32,
+    pub memory_usage: f32,
+    pub disk_usage: f32,
+    /// Cumulative byte counters; the panel turns them into rates
+    pub network_in: i64,
+    pub network_out: i64,
+    pub load_1: f32,
+    pub load_5: f32,
+    pub load_15: f32,
+    pub mounts: Vec<Mount>,
+    pub timestamp: DateTime<Utc>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Mount {
+    pub mount_point: String,
+    pub filesystem: String,
+    pub total_bytes: i64,
+    pub used_bytes: i64,
+}
+
+/// Static facts reported when registering with the panel
+#[derive(Debug)]
+pub struct HostInfo {
+    pub hostname: String,
+    pub cpu_cores: i32,
+    pub ram_gb: i32,
+    pub disk_gb: i32,
+    pub os: Option<String>,
+}
+
+/// Jiffies from the aggregate `cpu` line of `/proc/stat`
+#[derive(Debug, Clone, Copy)]
+struct CpuTimes {
+    busy: u64,
+    total: u64,
+}
+
+/// Turns `/proc` readings into samples; CPU usage is measured between consecutive calls
+pub struct Collector {
+    last_cpu: CpuTimes,
+}
+
+impl Collector {
+    pub fn new() -> Result<Self> {
+        Ok(Self {
+            last_cpu: read_cpu_times()?,
+        })
+    }
+
+    pub fn collect(&mut self) -> Result<Sample> {
+        let cpu = read_cpu_times()?;
+        let cpu_usage = percent(
+            cpu.busy.saturating_sub(self.last_cpu.busy),
+            cpu.total.saturating_sub(self.last_cpu.total),
+        );
+        self.last_cpu = cpu;
+
+        let (mem_total, mem_available) = read_memory()?;
+        let (load_1, load_5, load_15) = read_loadavg()?;
+        let (network_in, network_out) = read_network()?;
+        let mounts = read_mounts()?;
+
+        let root = usage("/")?;
+        let disk_usage = percent(root.used_bytes as u64, (root.used_bytes + root.available_bytes) as u64);
+
+        Ok(Sample {
+            cpu_usage,
+            memory_usage: percent(mem_total.saturating_sub(mem_available), mem_total),
+            disk_usage,
+            network_in: network_in as i64,
+            network_out: network_out as i64,
+            load_1,
+            load_5,
+            load_15,
+            mounts,
+            timestamp: Utc::now(),
+        })
+    }
+
+    pub fn host_info(&self) -> Result<HostInfo> {
+        let hostname = fs::read_to_string("/proc/sys/kernel/hostname")
+            .context("reading hostname")?
+            .trim()
+            .to_string();
+
+        let stat = fs::read_to_string("/proc/stat")?;
+        let cpu_cores = stat
+            .lines()
+            .filter(|line| line.starts_with("cpu") && line.as_bytes().get(3).is_some_and(u8::is_ascii_digit))
+            .count() as i32;
+
+        let (mem_total, _) = read_memory()?;
+        let root = usage("/")?;
+
+        Ok(HostInfo {
+            hostname,
+            cpu_cores: cpu_cores.max(1),
+            ram_gb: gibibytes(mem_total),
+            disk_gb: gibibytes(root.total_bytes as u64),
+            os: read_os_name(),
+        })
+    }
+}
+
+fn percent(part: u64, whole: u64) -> f32 {
+    if whole == 0 {
+        return 0.0;
+    }
+    ((part as f64 / whole as f64) * 100.0).clamp(0.0, 100.0) as f32
+}
+
+/// Whole GiB, rounded, but never zero for a machine that exists
+fn gibibytes(bytes: u64) -> i32 {
+    ((bytes as f64 / (1u64 << 30) as f64).round() as i32).max(1)
+}
+
+fn read_cpu_times() -> Result<CpuTimes> {
+    parse_cpu_times(&fs::read_to_string("/proc/stat").context("reading /proc/stat")?)
+}
+
+fn parse_cpu_times(stat: &str) -> Result<CpuTimes> {
+    let line = stat
+        .lines()
+        .find(|line| line.starts_with("cpu "))
+        .ok_or_else(|| anyhow!("no cpu line in /proc/stat"))?;
+
+    // user nice system idle iowait irq softirq steal; guest time is already part of user
+    let fields: Vec<u64> = line
+        .split_whitespace()
+        .skip(1)
+        .take(8)
+        .map(|v| v.parse().unwrap_or(0))
+        .collect();
+    let total: u64 = fields.iter().sum();
+    let idle = fields.get(3).copied().unwrap_or(0) + fields.get(4).copied().unwrap_or(0);
+
+    Ok(CpuTimes { busy: total - idle, total })
+}
+
+fn read_memory() -> Result<(u64, u64)> {
+    parse_memory(&fs::read_to_string("/proc/meminfo").context("reading /proc/meminfo")?)
+}
+
+/// `(MemTotal, MemAvailable)` in bytes
+fn parse_memory(meminfo: &str) -> Result<(u64, u64)> {
+    let field = |name: &str| -> Result<u64> {
+        meminfo
+            .lines()
+            .find_map(|line| line.strip_prefix(name)?.strip_prefix(':'))
+            .and_then(|rest| rest.split_whitespace().next()?.parse::<u64>().ok())
+            .map(|kib| kib * 1024)
+            .ok_or_else(|| anyhow!("{} missing from /proc/meminfo", name))
+    };
+
+    Ok((field("MemTotal")?, field("MemAvailable")?))
+}
+
+fn read_loadavg() -> Result<(f32, f32, f32)> {
+    parse_loadavg(&fs::read_to_string("/proc/loadavg").context("reading /proc/loadavg")?)
+}
+
+fn parse_loadavg(loadavg: &str) -> Result<(f32, f32, f32)> {
+    let mut values = loadavg.split_whitespace().map(|v| v.parse::<f32>());
+
+    match (values.next(), values.next(), values.next()) {
+        (Some(Ok(one)), Some(Ok(five)), Some(Ok(fifteen))) => Ok((one, five, fifteen)),
+        _ => Err(anyhow!("malformed /proc/loadavg")),
+    }
+}
+
+fn read_network() -> Result<(u64, u64)> {
+    Ok(parse_network(&fs::read_to_string("/proc/net/dev").context("reading /proc/net/dev")?))
+}
+
+/// Received and transmitted bytes summed over every interface except loopback
+fn parse_network(dev: &str) -> (u64, u64) {
+    let mut received = 0u64;
+    let mut transmitted = 0u64;
+    for line in dev.lines().skip(2) {
+        let Some((interface, counters)) = line.split_once(':') else { continue };
+        if interface.trim() == "lo" {
+            continue;
+        }
+
+        let counters: Vec<u64> = counters.split_whitespace().map(|v| v.parse().unwrap_or(0)).collect();
+        received = received.wrapping_add(counters.first().copied().unwrap_or(0));
+        transmitted = transmitted.wrapping_add(counters.get(8).copied().unwrap_or(0));
+    }
+
+    // The panel stores counters as BIGINT
+    (received & i64::MAX as u64, transmitted & i64::MAX as u64)
+}
+
+fn read_mounts() -> Result<Vec<Mount>> {
+    let mounts = fs::read_to_string("/proc/mounts").context("reading /proc/mounts")?;
+
+    let mut result = Vec::new();
+    for (mount_point, filesystem) in mount_candidates(&mounts) {
+        // Mounts can vanish or be unreadable (e.g. stale NFS); skip rather than fail the sample
+        let Ok(stats) = usage(&mount_point) else { continue };
+        if stats.total_bytes == 0 {
+            continue;
+        }
+
+        result.push(Mount {
+            mount_point,
+            filesystem,
+            total_bytes: stats.total_bytes,
+            used_bytes: stats.used_bytes,
+        });
+
+        if result.len() == MAX_MOUNTS {
+            break;
+        }
+    }
+
+    Ok(result)
+}
+
+/// `(mount point, filesystem)` of real filesystems in `/proc/mounts`, first occurrence only
+fn mount_candidates(mounts: &str) -> Vec<(String, String)> {
+    let mut seen = HashSet::new();
+
+    mounts
+        .lines()
+        .filter_map(|line| {
+            let fields: Vec<&str> = line.split_whitespace().collect();
+            let [_device, mount_point, filesystem, ..] = fields[..] else { return None };
+            if IGNORED_FILESYSTEMS.contains(&filesystem) {
+                return None;
+            }
+
+            let mount_point = unescape_mount_path(mount_point);
+            seen.insert(mount_point.clone()).then(|| (mount_point, filesystem.to_string()))
+        })
+        .collect()
+}
+
+/// `/proc/mounts` escapes space, tab, newline and backslash as octal
+fn unescape_mount_path(path: &str) -> String {
+    let bytes = path.as_bytes();
+    let mut out = Vec::with_capacity(bytes.len());
+    let mut i = 0;
+    while i < bytes.len() {
+        if bytes[i] == b'\\' && i + 3 < bytes.len() && bytes[i + 1..i + 4].iter().all(|b| (b'0'..=b'7').contains(b)) {
+            let code = (bytes[i + 1] - b'0') * 64 + (bytes[i + 2] - b'0') * 8 + (bytes[i + 3] - b'0');
+            out.push(code);
+            i += 4;
+        } else {
+            out.push(bytes[i]);
+            i += 1;
+        }
+    }
+    String::from_utf8_lossy(&out).into_owned()
+}
+
+struct FsUsage {
+    total_bytes: i64,
+    used_bytes: i64,
+    available_bytes: i64,
+}
+
+/// Usage as `df` reports it: blocks reserved for root count as neither used nor available
+fn usage(path: &str) -> Result<FsUsage> {
+    let c_path = CString::new(path)?;
+    let mut stats: libc::statvfs = unsafe { std::mem::zeroed() };
+
+    // SAFETY: `c_path` is NUL-terminated and `stats` is a valid out-pointer
+    if unsafe { libc::statvfs(c_path.as_ptr(), &mut stats) } != 0 {
+        return Err(std::io::Error::last_os_error()).with_context(|| format!("statvfs {}", path));
+    }
+
+    let block = stats.f_frsize as i64;
+    Ok(FsUsage {
+        total_bytes: stats.f_blocks as i64 * block,
+        used_bytes: (stats.f_blocks as i64 - stats.f_bfree as i64) * block,
+        available_bytes: stats.f_bavail as i64 * block,
+    })
+}
+
+fn read_os_name() -> Option<String> {
+    let release = fs::read_to_string("/etc/os-release").ok()?;
+    release
+        .lines()
+        .find_map(|line| line.strip_prefix("PRETTY_NAME="))
+        .map(|name| name.trim_matches('"').chars().take(100).collect())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn cpu_times_exclude_idle_and_iowait() {
+        let stat = "cpu  100 5 50 800 40 3 2 0 10 0\ncpu0 50 2 25 400 20 1 1 0 5 0\nintr 12345\n";
+
+        let times = parse_cpu_times(stat).unwrap();
+
+        assert_eq!(times.total, 1_000);
+        assert_eq!(times.busy, 160);
+        assert!(parse_cpu_times("intr 1\n").is_err());
+    }
+
+    #[test]
+    fn memory_is_read_in_bytes() {
+        let meminfo = "MemTotal:       16384000 kB\nMemFree:         1000000 kB\nMemAvailable:    8192000 kB\n";
+
+        assert_eq!(parse_memory(meminfo).unwrap(), (16_384_000 * 1024, 8_192_000 * 1024));
+        assert!(parse_memory("MemTotal: 1 kB\n").is_err());
+    }
+
+    #[test]
+    fn loadavg_takes_the_first_three_fields() {
+        assert_eq!(parse_loadavg("0.52 0.58 0.59 1/467 12345\n").unwrap(), (0.52, 0.58, 0.59));
+        assert!(parse_loadavg("0.52 oops").is_err());
+    }
+
+    #[test]
+    fn network_sums_every_interface_but_loopback() {
+        let dev = "\
+Inter-|   Receive                                                |  Transmit
+ face |bytes    packets errs drop fifo frame compressed multicast|bytes    packets errs drop fifo colls carrier compressed
+    lo: 9999999    100    0    0    0     0          0         0  9999999     100    0    0    0     0       0          0
+  eth0: 1000      10    0    0    0     0          0         0     2000      20    0    0    0     0       0          0
+ wlan0:  500       5    0    0    0     0          0         0      700       7    0    0    0     0       0          0
+";
+
+        assert_eq!(parse_network(dev), (1_500, 2_700));
+    }
+
+    #[test]
+    fn network_counters_fit_in_bigint() {
+        let dev = format!("h1\nh2\n eth0: {} 0 0 0 0 0 0 0 {} 0 0 0 0 0 0 0\n", u64::MAX, 5);
+
+        let (received, transmitted) = parse_network(&dev);
+
+        assert!(received <= i64::MAX as u64);
+        assert_eq!(transmitted, 5);
+    }
+
+    #[test]
+    fn mounts_skip_pseudo_filesystems_and_duplicates() {
+        let mounts = "\
+sysfs /sys sysfs rw 0 0
+proc /proc proc rw 0 0
+/dev/sda1 / ext4 rw 0 0
+tmpfs /run tmpfs rw 0 0
+/dev/sdb1 /mnt/my\\040data xfs rw 0 0
+/dev/sda1 / ext4 rw 0 0
+truncated-line
+";
+
+        assert_eq!(
+            mount_candidates(mounts),
+            [
+                ("/".to_string(), "ext4".to_string()),
+                ("/mnt/my data".to_string(), "xfs".to_string()),
+            ]
+        );
+    }
+
+    #[test]
+    fn mount_paths_are_unescaped() {
+        assert_eq!(unescape_mount_path("/a\\040b\\011c\\134d"), "/a b\tc\\d");
+        assert_eq!(unescape_mount_path("/trailing\\04"), "/trailing\\04");
+    }
+
+    #[test]
+    fn percent_and_gibibytes_handle_edges() {
+        assert_eq!(percent(1, 4), 25.0);
+        assert_eq!(percent(5, 0), 0.0);
+        assert_eq!(percent(10, 5), 100.0);
+        assert_eq!(gibibytes(3 * (1 << 30)), 3);
+        assert_eq!(gibibytes(1024), 1);
+    }
+}
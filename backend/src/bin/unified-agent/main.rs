@@ -0,0 +1,285 @@
+//! `unified-agent`: collects host metrics from `/proc` and ships them to the panel.
+//!
+//! Configuration comes from the environment (or a `.env` file):
+//! `UNIFIED_PANEL_URL`, `UNIFIED_API_KEY` (scopes `servers:write` and `metrics:write`),
+//! and optionally `UNIFIED_SERVER_ID`, `UNIFIED_SERVER_NAME`, `UNIFIED_SERVER_TYPE`,
+//! `UNIFIED_IP_ADDRESS`, `UNIFIED_INTERVAL_SECONDS` and `UNIFIED_STATE_DIR`.
+
+mod buffer;
+mod client;
+mod collector;
+#[cfg(test)]
+mod test_support;
+
+use anyhow::{anyhow, Context, Result};
+use buffer::Buffer;
+use client::{PanelClient, SendError};
+use collector::{Collector, HostInfo, Sample};
+use std::{env, fs, path::PathBuf, time::Duration};
+use tokio::time::{interval_at, Instant, MissedTickBehavior};
+use tracing_subscriber::{layer::SubscriberExt, util::SubscriberInitExt};
+use uuid::Uuid;
+
+/// Matches the panel's per-request sample limit
+const BATCH_SIZE: usize = 1000;
+
+struct AgentConfig {
+    panel_url: String,
+    api_key: String,
+    /// Pinned server; when unset the agent registers itself and remembers the id
+    server_id: Option<Uuid>,
+    server_name: Option<String>,
+    server_type: String,
+    ip_address: Option<String>,
+    interval_seconds: u64,
+    state_dir: PathBuf,
+}
+
+impl AgentConfig {
+    fn from_env() -> Result<Self> {
+        Ok(Self {
+            panel_url: env::var("UNIFIED_PANEL_URL").context("UNIFIED_PANEL_URL must be set")?,
+            api_key: env::var("UNIFIED_API_KEY").context("UNIFIED_API_KEY must be set")?,
+            server_id: env::var("UNIFIED_SERVER_ID")
+                .ok()
+                .map(|id| id.parse().context("UNIFIED_SERVER_ID must be a UUID"))
+                .transpose()?,
+            server_name: env::var("UNIFIED_SERVER_NAME").ok(),
+            server_type: env::var("UNIFIED_SERVER_TYPE").unwrap_or_else(|_| "dedicated".to_string()),
+            ip_address: env::var("UNIFIED_IP_ADDRESS").ok(),
+            interval_seconds: env::var("UNIFIED_INTERVAL_SECONDS")
+                .unwrap_or_else(|_| "60".to_string())
+                .parse()
+                .context("UNIFIED_INTERVAL_SECONDS must be a number")?,
+            state_dir: env::var("UNIFIED_STATE_DIR")
+                .unwrap_or_else(|_| "/var/lib/unified-agent".to_string())
+                .into(),
+        })
+    }
+
+    fn server_id_path(&self) -> PathBuf {
+        self.state_dir.join("server-id")
+    }
+}
+
+#[tokio::main]
+async fn main() -> Result<()> {
+    tracing_subscriber::registry()
+        .with(
+            tracing_subscriber::EnvFilter::try_from_default_env()
+                .unwrap_or_else(|_| "unified_agent=info".into()),
+        )
+        .with(tracing_subscriber::fmt::layer())
+        .init();
+
+    dotenvy::dotenv().ok();
+    let config = AgentConfig::from_env()?;
+    if config.interval_seconds == 0 {
+        return Err(anyhow!("UNIFIED_INTERVAL_SECONDS must be at least 1"));
+    }
+
+    fs::create_dir_all(&config.state_dir)
+        .with_context(|| format!("creating {}", config.state_dir.display()))?;
+
+    let client = PanelClient::new(&config.panel_url, config.api_key.clone())?;
+    let buffer = Buffer::new(config.state_dir.join("buffer.jsonl"));
+    let mut pending = buffer.load()?;
+    if !pending.is_empty() {
+        tracing::info!("Loaded {} buffered samples", pending.len());
+    }
+
+    let mut collector = Collector::new()?;
+    let host = collector.host_info()?;
+    let mut server_id = config.server_id.or_else(|| read_server_id(&config));
+
+    // The first tick waits a full interval so CPU usage has something to be measured against
+    let period = Duration::from_secs(config.interval_seconds);
+    let mut ticker = interval_at(Instant::now() + period, period);
+    ticker.set_missed_tick_behavior(MissedTickBehavior::Delay);
+
+    tracing::info!("Reporting {} to {} every {}s", host.hostname, config.panel_url, config.interval_seconds);
+
+    loop {
+        ticker.tick().await;
+
+        match collector.collect() {
+            Ok(sample) => pending.push(sample),
+            Err(e) => tracing::warn!("Failed to collect metrics: {:#}", e),
+        }
+
+        if server_id.is_none() {
+            server_id = register(&client, &config, &host).await;
+        }
+
+        if let Some(id) = server_id {
+            match flush(&client, id, &mut pending).await {
+                Ok(()) => {}
+                Err(SendError::Unavailable(reason) | SendError::Rejected(reason)) => {
+                    tracing::warn!("Panel unavailable ({}), {} samples buffered", reason, pending.len());
+                }
+                Err(SendError::Unauthorized(reason)) => {
+                    tracing::error!(
+                        "Panel refused the API key ({}); check UNIFIED_API_KEY and its metrics:write scope, {} samples buffered",
+                        reason,
+                        pending.len()
+                    );
+                }
+                Err(SendError::UnknownServer) if config.server_id.is_some() => {
+                    tracing::error!("Server {} does not exist or is not visible to this API key", id);
+                }
+                Err(SendError::UnknownServer) => {
+                    tracing::warn!("Server {} was removed from the panel, registering again", id);
+                    let _ = fs::remove_file(config.server_id_path());
+                    server_id = None;
+                }
+            }
+        }
+
+        buffer::discard_stale(&mut pending);
+        if let Err(e) = buffer.store(&pending) {
+            tracing::error!("Failed to persist sample buffer: {:#}", e);
+        }
+    }
+}
+
+fn read_server_id(config: &AgentConfig) -> Option<Uuid> {
+    fs::read_to_string(config.server_id_path()).ok()?.trim().parse().ok()
+}
+
+async fn register(client: &PanelClient, config: &AgentConfig, host: &HostInfo) -> Option<Uuid> {
+    let name = config.server_name.as_deref().unwrap_or(&host.hostname);
+
+    match client.register(host, name, &config.server_type, config.ip_address.as_deref()).await {
+        Ok(id) => {
+            tracing::info!("Registered as server {}", id);
+            if let Err(e) = fs::write(config.server_id_path(), id.to_string()) {
+                tracing::warn!("Failed to save server id: {}", e);
+            }
+            Some(id)
+        }
+        Err(e) => {
+            tracing::warn!("Registration failed: {:#}", e);
+            None
+        }
+    }
+}
+
+/// Deliver pending samples oldest first; samples stay queued unless delivered or refused for good
+async fn flush(client: &PanelClient, server_id: Uuid, pending: &mut Vec<Sample>) -> Result<(), SendError> {
+    while !pending.is_empty() {
+        let count = pending.len().min(BATCH_SIZE);
+
+        match client.send(server_id, &pending[..count]).await {
+            Ok(()) => {}
+            Err(SendError::Rejected(reason)) => {
+                tracing::error!("Panel rejected {} samples, dropping them: {}", count, reason);
+            }
+            Err(e) => return Err(e),
+        }
+
+        pending.drain(..count);
+    }
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_support::{sample, serve};
+    use axum::{extract::State, http::StatusCode, routing::post, Json, Router};
+    use chrono::Utc;
+    use std::sync::{Arc, Mutex};
+
+    /// Records the size of every batch and answers with the next queued status, then 202
+    #[derive(Clone, Default)]
+    struct Panel {
+        batches: Arc<Mutex<Vec<usize>>>,
+        responses: Arc<Mutex<Vec<StatusCode>>>,
+    }
+
+    async fn ingest(State(panel): State<Panel>, Json(body): Json<serde_json::Value>) -> StatusCode {
+        panel.batches.lock().unwrap().push(body["samples"].as_array().unwrap().len());
+        let mut responses = panel.responses.lock().unwrap();
+        if responses.is_empty() {
+            StatusCode::ACCEPTED
+        } else {
+            responses.remove(0)
+        }
+    }
+
+    async fn client(responses: Vec<StatusCode>) -> (PanelClient, Panel) {
+        let panel = Panel { responses: Arc::new(Mutex::new(responses)), ..Default::default() };
+        let url = serve(
+            Router::new()
+                .route("/api/servers/:id/metrics", post(ingest))
+                .with_state(panel.clone()),
+        )
+        .await;
+
+        (PanelClient::new(&url, "key".to_string()).unwrap(), panel)
+    }
+
+    fn samples(count: usize) -> Vec<Sample> {
+        (0..count).map(|_| sample(Utc::now())).collect()
+    }
+
+    #[tokio::test]
+    async fn pending_samples_are_sent_in_batches() {
+        let (client, panel) = client(Vec::new()).await;
+        let mut pending = samples(2_500);
+
+        flush(&client, Uuid::new_v4(), &mut pending).await.unwrap();
+
+        assert!(pending.is_empty());
+        assert_eq!(*panel.batches.lock().unwrap(), [1_000, 1_000, 500]);
+    }
+
+    #[tokio::test]
+    async fn unavailable_panel_keeps_the_rest_pending() {
+        let (client, panel) = client(vec![StatusCode::ACCEPTED, StatusCode::SERVICE_UNAVAILABLE]).await;
+        let mut pending = samples(2_500);
+
+        let result = flush(&client, Uuid::new_v4(), &mut pending).await;
+
+        assert!(matches!(result, Err(SendError::Unavailable(_))));
+        assert_eq!(pending.len(), 1_500);
+        assert_eq!(panel.batches.lock().unwrap().len(), 2);
+    }
+
+    #[tokio::test]
+    async fn rejected_batches_are_dropped() {
+        let (client, panel) = client(vec![StatusCode::UNPROCESSABLE_ENTITY]).await;
+        let mut pending = samples(1_200);
+
+        flush(&client, Uuid::new_v4(), &mut pending).await.unwrap();
+
+        assert!(pending.is_empty());
+        assert_eq!(*panel.batches.lock().unwrap(), [1_000, 200]);
+    }
+
+    #[tokio::test]
+    async fn unknown_server_keeps_everything_pending() {
+        let (client, _) = client(vec![StatusCode::NOT_FOUND]).await;
+        let mut pending = samples(10);
+
+        let result = flush(&client, Uuid::new_v4(), &mut pending).await;
+
+        assert!(matches!(result, Err(SendError::UnknownServer)));
+        assert_eq!(pending.len(), 10);
+    }
+
+    #[tokio::test]
+    async fn refused_api_key_keeps_everything_pending() {
+        for status in [StatusCode::UNAUTHORIZED, StatusCode::FORBIDDEN] {
+            let (client, panel) = client(vec![status]).await;
+            let mut pending = samples(1_200);
+
+            let result = flush(&client, Uuid::new_v4(), &mut pending).await;
+
+            assert!(matches!(result, Err(SendError::Unauthorized(_))));
+            assert_eq!(pending.len(), 1_200);
+            assert_eq!(panel.batches.lock().unwrap().len(), 1);
+        }
+    }
+}
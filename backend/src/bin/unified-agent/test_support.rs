@@ -0,0 +1,34 @@
+//! Helpers shared by the agent's unit tests
+
+use crate::collector::Sample;
+use axum::Router;
+use chrono::{DateTime, Utc};
+
+/// Serve `router` on an ephemeral loopback port and return its base URL
+pub async fn serve(router: Router) -> String {
+    let listener = tokio::net::TcpListener::bind("127.0.0.1:0")
+        .await
+        .expect("Failed to bind test listener");
+    let addr = listener.local_addr().expect("Test listener has no address");
+
+    tokio::spawn(async move {
+        axum::serve(listener, router).await.ok();
+    });
+
+    format!("http://{}", addr)
+}
+
+pub fn sample(timestamp: DateTime<Utc>) -> Sample {
+    Sample {
+        cpu_usage: 12.5,
+        memory_usage: 40.0,
+        disk_usage: 70.0,
+        network_in: 1_000,
+        network_out: 2_000,
+        load_1: 0.5,
+        load_5: 0.4,
+        load_15: 0.3,
+        mounts: Vec::new(),
+        timestamp,
+    }
+}
@@ -1,3 +1,4 @@
+use crate::utils::crypto::SecretKey;
 use serde::Deserialize;
 
 #[derive(Debug, Clone, Deserialize)]
@@ -7,14 +8,53 @@ pub struct Config {
     pub database_url: String,
     pub jwt_secret: String,
     pub jwt_expiration: i64,
+    pub refresh_token_expiration: i64,
+    #[allow(dead_code)]
     pub session_secret: String,
     pub cors_origin: String,
     pub hetzner_api_token: Option<String>,
+    pub digitalocean_api_token: Option<String>,
+    pub digitalocean_api_url: String,
+    pub hetzner_api_url: String,
+    /// Hex AES-256 key sealing users' provider API tokens at rest
+    pub provider_token_key: Option<String>,
+    pub catalog_cache_seconds: u64,
+    pub vps_reconcile_interval_seconds: u64,
+    pub trust_proxy_headers: bool,
+    pub login_rate_limit: u32,
+    pub login_account_rate_limit: u32,
+    pub register_rate_limit: u32,
+    pub login_max_failures: i32,
+    pub login_lockout_seconds: i64,
+    pub email_rate_limit: u32,
+    pub public_url: String,
+    pub require_email_verification: bool,
+    pub mail_transport: String,
+    pub mail_from: String,
+    pub mail_file_path: Option<String>,
+    pub smtp_host: Option<String>,
+    pub smtp_port: u16,
+    pub smtp_username: Option<String>,
+    pub smtp_password: Option<String>,
+    pub password_min_length: usize,
+    pub password_min_classes: usize,
+    pub argon2_memory_kib: u32,
+    pub argon2_iterations: u32,
+    pub argon2_parallelism: u32,
+    pub metrics_raw_retention_days: i64,
+    pub metrics_minute_retention_days: i64,
+    pub metrics_hour_retention_days: i64,
+    pub alert_evaluation_interval_seconds: u64,
+    pub alert_no_metrics_minutes: i64,
+    pub notification_max_attempts: i32,
+    pub telegram_api_url: String,
+    pub notification_allow_private_targets: bool,
+    pub events_pg_notify: bool,
 }
 
 impl Config {
     pub fn from_env() -> Result<Self, Box<dyn std::error::Error>> {
-        Ok(Config {
+        let config = Config {
             host: std::env::var("HOST").unwrap_or_else(|_| "0.0.0.0".to_string()),
             port: std::env::var("PORT")
                 .unwrap_or_else(|_| "3000".to_string())
@@ -24,13 +64,112 @@ impl Config {
             jwt_secret: std::env::var("JWT_SECRET")
                 .expect("JWT_SECRET must be set"),
             jwt_expiration: std::env::var("JWT_EXPIRATION")
-                .unwrap_or_else(|_| "86400".to_string())
+                .unwrap_or_else(|_| "900".to_string())
+                .parse()?,
+            refresh_token_expiration: std::env::var("REFRESH_TOKEN_EXPIRATION")
+                .unwrap_or_else(|_| "2592000".to_string())
                 .parse()?,
             session_secret: std::env::var("SESSION_SECRET")
                 .expect("SESSION_SECRET must be set"),
             cors_origin: std::env::var("CORS_ORIGIN")
                 .unwrap_or_else(|_| "http://localhost:3000".to_string()),
             hetzner_api_token: std::env::var("HETZNER_API_TOKEN").ok(),
-        })
+            digitalocean_api_token: std::env::var("DIGITALOCEAN_API_TOKEN").ok(),
+            digitalocean_api_url: std::env::var("DIGITALOCEAN_API_URL")
+                .unwrap_or_else(|_| "https://api.digitalocean.com/v2".to_string()),
+            hetzner_api_url: std::env::var("HETZNER_API_URL")
+                .unwrap_or_else(|_| "https://api.hetzner.cloud/v1".to_string()),
+            provider_token_key: std::env::var("PROVIDER_TOKEN_KEY").ok().filter(|key| !key.is_empty()),
+            catalog_cache_seconds: std::env::var("CATALOG_CACHE_SECONDS")
+                .unwrap_or_else(|_| "3600".to_string())
+                .parse()?,
+            vps_reconcile_interval_seconds: std::env::var("VPS_RECONCILE_INTERVAL_SECONDS")
+                .unwrap_or_else(|_| "300".to_string())
+                .parse()?,
+            trust_proxy_headers: std::env::var("TRUST_PROXY_HEADERS")
+                .map(|v| v == "true" || v == "1")
+                .unwrap_or(false),
+            login_rate_limit: std::env::var("LOGIN_RATE_LIMIT")
+                .unwrap_or_else(|_| "10".to_string())
+                .parse()?,
+            login_account_rate_limit: std::env::var("LOGIN_ACCOUNT_RATE_LIMIT")
+                .unwrap_or_else(|_| "5".to_string())
+                .parse()?,
+            register_rate_limit: std::env::var("REGISTER_RATE_LIMIT")
+                .unwrap_or_else(|_| "3".to_string())
+                .parse()?,
+            login_max_failures: std::env::var("LOGIN_MAX_FAILURES")
+                .unwrap_or_else(|_| "5".to_string())
+                .parse()?,
+            login_lockout_seconds: std::env::var("LOGIN_LOCKOUT_SECONDS")
+                .unwrap_or_else(|_| "900".to_string())
+                .parse()?,
+            email_rate_limit: std::env::var("EMAIL_RATE_LIMIT")
+                .unwrap_or_else(|_| "3".to_string())
+                .parse()?,
+            public_url: std::env::var("PUBLIC_URL")
+                .unwrap_or_else(|_| "http://localhost:3000".to_string()),
+            require_email_verification: std::env::var("REQUIRE_EMAIL_VERIFICATION")
+                .map(|v| v == "true" || v == "1")
+                .unwrap_or(false),
+            mail_transport: std::env::var("MAIL_TRANSPORT")
+                .unwrap_or_else(|_| "log".to_string()),
+            mail_from: std::env::var("MAIL_FROM")
+                .unwrap_or_else(|_| "Unified Panel <noreply@localhost>".to_string()),
+            mail_file_path: std::env::var("MAIL_FILE_PATH").ok(),
+            smtp_host: std::env::var("SMTP_HOST").ok(),
+            smtp_port: std::env::var("SMTP_PORT")
+                .unwrap_or_else(|_| "587".to_string())
+                .parse()?,
+            smtp_username: std::env::var("SMTP_USERNAME").ok(),
+            smtp_password: std::env::var("SMTP_PASSWORD").ok(),
+            password_min_length: std::env::var("PASSWORD_MIN_LENGTH")
+                .unwrap_or_else(|_| "10".to_string())
+                .parse()?,
+            password_min_classes: std::env::var("PASSWORD_MIN_CLASSES")
+                .unwrap_or_else(|_| "3".to_string())
+                .parse()?,
+            argon2_memory_kib: std::env::var("ARGON2_MEMORY_KIB")
+                .unwrap_or_else(|_| "19456".to_string())
+                .parse()?,
+            argon2_iterations: std::env::var("ARGON2_ITERATIONS")
+                .unwrap_or_else(|_| "2".to_string())
+                .parse()?,
+            argon2_parallelism: std::env::var("ARGON2_PARALLELISM")
+                .unwrap_or_else(|_| "1".to_string())
+                .parse()?,
+            metrics_raw_retention_days: std::env::var("METRICS_RAW_RETENTION_DAYS")
+                .unwrap_or_else(|_| "7".to_string())
+                .parse()?,
+            metrics_minute_retention_days: std::env::var("METRICS_MINUTE_RETENTION_DAYS")
+                .unwrap_or_else(|_| "30".to_string())
+                .parse()?,
+            metrics_hour_retention_days: std::env::var("METRICS_HOUR_RETENTION_DAYS")
+                .unwrap_or_else(|_| "365".to_string())
+                .parse()?,
+            alert_evaluation_interval_seconds: std::env::var("ALERT_EVALUATION_INTERVAL_SECONDS")
+                .unwrap_or_else(|_| "60".to_string())
+                .parse()?,
+            alert_no_metrics_minutes: std::env::var("ALERT_NO_METRICS_MINUTES")
+                .unwrap_or_else(|_| "10".to_string())
+                .parse()?,
+            notification_max_attempts: std::env::var("NOTIFICATION_MAX_ATTEMPTS")
+                .unwrap_or_else(|_| "6".to_string())
+                .parse()?,
+            telegram_api_url: std::env::var("TELEGRAM_API_URL")
+                .unwrap_or_else(|_| "https://api.telegram.org".to_string()),
+            notification_allow_private_targets: std::env::var("NOTIFICATION_ALLOW_PRIVATE_TARGETS")
+                .map(|v| v == "true" || v == "1")
+                .unwrap_or(false),
+            events_pg_notify: std::env::var("EVENTS_PG_NOTIFY")
+                .map(|v| v == "true" || v == "1")
+                .unwrap_or(false),
+        };
+
+        if let Some(key) = &config.provider_token_key {
+            SecretKey::from_hex(key).map_err(|e| format!("PROVIDER_TOKEN_KEY {}", e))?;
+        }
+
+        Ok(config)
     }
 }
@@ -1,5 +1,14 @@
 use askama::Template;
-use axum::response::{Html, IntoResponse};
+use axum::{
+    extract::State,
+    response::{Html, IntoResponse, Redirect, Response},
+};
+use crate::{
+    middleware::auth::AuthUser,
+    models::{auth::Scope, AppState},
+    services::{dashboard_service, user_service},
+    utils::errors::AppError,
+};
 
 #[derive(Template)]
 #[template(path = "index.html")]
@@ -18,9 +27,9 @@ struct RegisterTemplate {}
 struct DashboardTemplate {
     user_email: String,
     user_role: String,
-    total_servers: i32,
-    total_websites: i32,
-    total_users: i32,
+    total_servers: i64,
+    total_websites: i64,
+    total_users: i64,
 }
 
 #[derive(Template)]
@@ -68,16 +77,26 @@ pub async fn register_page() -> impl IntoResponse {
     Html(template.render().unwrap())
 }
 
-pub async fn dashboard() -> impl IntoResponse {
-    // TODO: Get user from JWT and fetch real stats from database
+/// Rendered for the signed-in user; anyone else is sent to the login page
+pub async fn dashboard(
+    State(state): State<AppState>,
+    auth: Option<AuthUser>,
+) -> Result<Response, AppError> {
+    let Some(auth) = auth else {
+        return Ok(Redirect::to("/login").into_response());
+    };
+
+    let user = user_service::get_user(&state.db, &Scope::All, auth.id).await?;
+    let stats = dashboard_service::get_stats(&state.db, &auth.scope()).await?;
+
     let template = DashboardTemplate {
-        user_email: "admin@example.com".to_string(),
-        user_role: "admin".to_string(), // Can be: admin, reseller, or client (any other value defaults to client behavior)
-        total_servers: 12,
-        total_websites: 45,
-        total_users: 156,
+        user_email: user.email,
+        user_role: user.role,
+        total_servers: stats.servers,
+        total_websites: stats.websites,
+        total_users: stats.users,
     };
-    Html(template.render().unwrap())
+    Ok(Html(template.render().unwrap()).into_response())
 }
 
 pub async fn servers_page() -> impl IntoResponse {
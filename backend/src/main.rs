@@ -5,12 +5,16 @@ mod handlers;
 mod middleware;
 mod models;
 mod services;
+#[cfg(test)]
+mod test_support;
 mod utils;
 
 use axum::{
-    routing::{get, post},
+    http::{header, HeaderValue, Method},
+    routing::get,
     Router,
 };
+use middleware::request_id::{assign_request_id, REQUEST_ID_HEADER};
 use std::net::SocketAddr;
 use tower_http::{
     compression::CompressionLayer,
@@ -45,13 +49,130 @@ async fn main() -> Result<(), Box<dyn std::error::Error>> {
 
     tracing::info!("Database migrations completed successfully");
 
-    // Create Hetzner client
-    let hetzner_client = services::vps_service::HetznerClient::new(
-        config.hetzner_api_token.clone().unwrap_or_default()
-    );
+    // Create mail transport
+    let mailer = services::mail_service::from_config(&config)?;
 
     // Create application state
-    let app_state = models::AppState::new(db_pool, config.clone(), hetzner_client);
+    let app_state = models::AppState::new(db_pool, config.clone(), mailer);
+
+    // Seal provider tokens stored in plaintext by earlier versions
+    match app_state.providers.seal_stored_tokens(&app_state.db).await {
+        Ok(0) => {}
+        Ok(sealed) => tracing::info!("Encrypted {} stored provider tokens", sealed),
+        Err(e) => tracing::error!("Stored provider tokens were left unencrypted: {}", e),
+    }
+
+    // Periodically forget rate-limit state for idle clients
+    let rate_limiters = app_state.rate_limiters.clone();
+    tokio::spawn(async move {
+        let mut interval = tokio::time::interval(std::time::Duration::from_secs(60));
+        loop {
+            interval.tick().await;
+            rate_limiters.prune();
+        }
+    });
+
+    // Roll up metrics into 1m/1h/1d tables, then enforce retention
+    let db = app_state.db.clone();
+    let metrics_config = app_state.config.clone();
+    tokio::spawn(async move {
+        let mut interval = tokio::time::interval(std::time::Duration::from_secs(60));
+        loop {
+            interval.tick().await;
+            if let Err(e) = services::metrics_service::roll_up(&db).await {
+                tracing::error!("Metrics rollup failed: {}", e);
+                continue;
+            }
+            if let Err(e) = services::metrics_service::prune(&db, &metrics_config).await {
+                tracing::error!("Metrics retention pruning failed: {}", e);
+            }
+        }
+    });
+
+    // Relay events published by any instance to this instance's `/api/events` subscribers
+    if config.events_pg_notify {
+        let db = app_state.db.clone();
+        let events = app_state.events.clone();
+        tokio::spawn(async move { events.forward_notifications(&db).await });
+    }
+
+    // Evaluate alert rules and the built-in no-metrics / VPS error checks
+    let db = app_state.db.clone();
+    let events = app_state.events.clone();
+    let alert_config = app_state.config.clone();
+    tokio::spawn(async move {
+        let mut interval = tokio::time::interval(std::time::Duration::from_secs(
+            alert_config.alert_evaluation_interval_seconds.max(1),
+        ));
+        loop {
+            interval.tick().await;
+            match services::alert_service::evaluate(&db, &alert_config).await {
+                Ok(transitions) => {
+                    for alert in transitions {
+                        tracing::info!("Alert {} {}: {}", alert.id, alert.state, alert.message);
+                        let notification = models::notification::Notification::from(&alert);
+                        if let Err(e) = services::notification_service::notify(&db, alert.user_id, notification).await {
+                            tracing::error!("Failed to queue alert notification: {}", e);
+                        }
+                        let data = serde_json::json!(alert);
+                        events.publish(models::event::Event::new(models::event::EventKind::Alert, alert.user_id, data)).await;
+                    }
+                }
+                Err(e) => tracing::error!("Alert evaluation failed: {}", e),
+            }
+        }
+    });
+
+    // Deliver queued notifications, retrying failures with backoff
+    let db = app_state.db.clone();
+    let notification_config = app_state.config.clone();
+    let notifier = services::notification_service::Notifier::new(&config, app_state.mailer.clone());
+    tokio::spawn(async move {
+        let mut interval = tokio::time::interval(std::time::Duration::from_secs(5));
+        loop {
+            interval.tick().await;
+            if let Err(e) = services::notification_service::process_due(&db, &notifier, &notification_config).await {
+                tracing::error!("Notification delivery failed: {}", e);
+            }
+        }
+    });
+
+    // Follow provider actions started by VPS power requests until they finish
+    let db = app_state.db.clone();
+    let providers = app_state.providers.clone();
+    let events = app_state.events.clone();
+    tokio::spawn(async move {
+        let mut interval = tokio::time::interval(std::time::Duration::from_secs(5));
+        loop {
+            interval.tick().await;
+            if let Err(e) = services::vps_service::poll_actions(&db, &providers, &events).await {
+                tracing::error!("VPS action polling failed: {}", e);
+            }
+        }
+    });
+
+    // Reconcile the vps table with what each provider account actually runs
+    let db = app_state.db.clone();
+    let providers = app_state.providers.clone();
+    let events = app_state.events.clone();
+    let reconcile_interval = config.vps_reconcile_interval_seconds.max(1);
+    tokio::spawn(async move {
+        let mut interval = tokio::time::interval(std::time::Duration::from_secs(reconcile_interval));
+        loop {
+            interval.tick().await;
+            if let Err(e) = services::vps_service::reconcile(&db, &providers, &events).await {
+                tracing::error!("VPS reconciliation failed: {}", e);
+            }
+        }
+    });
+
+    // Session cookies require credentialed CORS, which cannot be combined with a wildcard origin
+    let cors = CorsLayer::new()
+        .allow_origin(config.cors_origin.parse::<HeaderValue>()?)
+        .allow_methods([Method::GET, Method::POST, Method::PUT, Method::DELETE])
+        .allow_headers([header::AUTHORIZATION, header::CONTENT_TYPE, REQUEST_ID_HEADER])
+        .expose_headers([REQUEST_ID_HEADER, models::list::TOTAL_COUNT_HEADER])
+        .allow_credentials(true);
 
     // Build routes
     let app = Router::new()
@@ -59,10 +180,8 @@ async fn main() -> Result<(), Box<dyn std::error::Error>> {
         .route("/", get(handlers::pages::index))
         .route("/login", get(handlers::pages::login_page))
         .route("/register", get(handlers::pages::register_page))
-        .route("/api/auth/login", post(api::auth::login))
-        .route("/api/auth/register", post(api::auth::register))
         // API routes
-        .nest("/api", api::router())
+        .nest("/api", api::router(app_state.clone()))
 
         // Dashboard routes (protected)
         .route("/dashboard", get(handlers::pages::dashboard))
@@ -78,17 +197,18 @@ async fn main() -> Result<(), Box<dyn std::error::Error>> {
         // Middleware
         .layer(CompressionLayer::new())
         .layer(TraceLayer::new_for_http())
-        .layer(CorsLayer::permissive())
+        .layer(axum::middleware::from_fn(assign_request_id))
+        .layer(cors)
 
         // State
         .with_state(app_state);
 
     // Start server
-    let addr = SocketAddr::from(([0, 0, 0, 0], config.port));
+    let addr: SocketAddr = format!("{}:{}", config.host, config.port).parse()?;
     tracing::info!("🚀 Unified Hosting Panel starting on http://{}", addr);
 
     let listener = tokio::net::TcpListener::bind(addr).await?;
-    axum::serve(listener, app).await?;
+    axum::serve(listener, app.into_make_service_with_connect_info::<SocketAddr>()).await?;
 
     Ok(())
 }
@@ -0,0 +1,221 @@
+use axum::{
+    extract::{FromRequestParts, MatchedPath, OriginalUri, Request, State},
+    http::{Method, StatusCode},
+    middleware::Next,
+    response::Response,
+};
+use serde::Serialize;
+use serde_json::{Map, Value};
+use std::sync::{Arc, Mutex};
+use uuid::Uuid;
+use crate::{
+    middleware::{auth::AuthUser, request_id},
+    models::{
+        audit::{LogLevel, NewLogEntry},
+        auth::ClientInfo,
+        AppState,
+    },
+    services::audit_service,
+};
+
+/// What handlers learned about the mutation while serving it
+#[derive(Debug, Default)]
+struct AuditContext {
+    actor: Option<Uuid>,
+    api_key_id: Option<Uuid>,
+    message: Option<String>,
+    server_id: Option<Uuid>,
+    metadata: Map<String, Value>,
+    skip: bool,
+}
+
+tokio::task_local! {
+    static AUDIT: Arc<Mutex<AuditContext>>;
+}
+
+/// No-op outside an audited request
+fn update(f: impl FnOnce(&mut AuditContext)) {
+    let _ = AUDIT.try_with(|context| {
+        if let Ok(mut context) = context.lock() {
+            f(&mut context);
+        }
+    });
+}
+
+fn snapshot<T: Serialize>(value: &T) -> Value {
+    audit_service::redact(&serde_json::to_value(value).unwrap_or(Value::Null))
+}
+
+/// Attribute the request to the authenticated caller
+pub fn set_actor(user: &AuthUser) {
+    let api_key_id = user.api_scopes.as_ref().map(|_| user.session_id);
+    update(|context| {
+        context.actor = Some(user.id);
+        context.api_key_id = api_key_id;
+    });
+}
+
+/// Attribute an unauthenticated request (login, registration) to the account it acted on
+pub fn set_user(user_id: Uuid) {
+    update(|context| context.actor = Some(user_id));
+}
+
+pub fn describe(message: impl Into<String>) {
+    let message = message.into();
+    update(|context| context.message = Some(message));
+}
+
+pub fn server(server_id: Uuid) {
+    update(|context| context.server_id = Some(server_id));
+}
+
+pub fn created<T: Serialize>(label: &str, after: &T) {
+    let after = snapshot(after);
+    update(|context| {
+        context.message = Some(format!("Created {}", label));
+        context.metadata.insert("after".to_string(), after);
+    });
+}
+
+/// Record only the fields that differ between the two snapshots
+pub fn changed<T: Serialize>(label: &str, before: &T, after: &T) {
+    let changes = audit_service::diff(
+        &serde_json::to_value(before).unwrap_or(Value::Null),
+        &serde_json::to_value(after).unwrap_or(Value::Null),
+    );
+    update(|context| {
+        context.message = Some(format!("Updated {}", label));
+        context.metadata.insert("changes".to_string(), changes);
+    });
+}
+
+pub fn deleted<T: Serialize>(label: &str, before: &T) {
+    let before = snapshot(before);
+    update(|context| {
+        context.message = Some(format!("Deleted {}", label));
+        context.metadata.insert("before".to_string(), before);
+    });
+}
+
+/// Extra detail for the entry, e.g. the email a failed sign-in used
+pub fn note<T: Serialize>(key: &str, value: &T) {
+    let value = snapshot(value);
+    update(|context| {
+        context.metadata.insert(key.to_string(), value);
+    });
+}
+
+/// Leave this request out of the audit log (high-volume machine traffic)
+pub fn skip() {
+    update(|context| context.skip = true);
+}
+
+/// `logs.category` for a route, e.g. `/api/servers/:id` is `server`
+fn category(route: &str) -> &'static str {
+    let mut segments = route.trim_start_matches('/').split('/');
+    let resource = match segments.next() {
+        Some("api") => segments.next(),
+        other => other,
+    };
+
+    match (resource, segments.next()) {
+        (Some("auth"), Some("api-keys")) => "api_key",
+        (Some("auth"), _) => "auth",
+        (Some("servers"), _) => "server",
+        (Some("vps"), _) => "vps",
+        (Some("users"), _) => "user",
+        (Some("alerts"), _) => "alert",
+        (Some("notifications"), _) => "notification",
+        (Some("providers"), _) => "provider",
+        (Some("admin"), _) => "admin",
+        _ => "api",
+    }
+}
+
+fn level(status: StatusCode) -> LogLevel {
+    if status.is_server_error() {
+        LogLevel::Error
+    } else if status.is_client_error() {
+        LogLevel::Warning
+    } else {
+        LogLevel::Info
+    }
+}
+
+/// Write a `logs` entry for every mutating API call once its handler has run
+pub async fn audit_mutations(
+    State(state): State<AppState>,
+    request: Request,
+    next: Next,
+) -> Response {
+    if matches!(*request.method(), Method::GET | Method::HEAD | Method::OPTIONS) {
+        return next.run(request).await;
+    }
+
+    let (mut parts, body) = request.into_parts();
+    let Ok(client) = ClientInfo::from_request_parts(&mut parts, &state).await;
+    let method = parts.method.clone();
+    // Nesting under `/api` strips the prefix from `parts.uri`
+    let path = parts
+        .extensions
+        .get::<OriginalUri>()
+        .map(|OriginalUri(uri)| uri.path().to_string())
+        .unwrap_or_else(|| parts.uri.path().to_string());
+    let route = parts
+        .extensions
+        .get::<MatchedPath>()
+        .map(|matched| matched.as_str().to_string())
+        .unwrap_or_else(|| path.clone());
+
+    let context = Arc::new(Mutex::new(AuditContext::default()));
+    let response = AUDIT
+        .scope(context.clone(), next.run(Request::from_parts(parts, body)))
+        .await;
+
+    let context = match context.lock() {
+        Ok(mut context) => std::mem::take(&mut *context),
+        Err(_) => return response,
+    };
+    if context.skip {
+        return response;
+    }
+
+    let status = response.status();
+    let mut metadata = context.metadata;
+    metadata.insert("method".to_string(), method.as_str().into());
+    metadata.insert("route".to_string(), route.as_str().into());
+    metadata.insert("status".to_string(), status.as_u16().into());
+    if let Some(request_id) = request_id::current() {
+        metadata.insert("request_id".to_string(), request_id.into());
+    }
+    if let Some(user_agent) = client.user_agent {
+        metadata.insert("user_agent".to_string(), user_agent.into());
+    }
+    if let Some(api_key_id) = context.api_key_id {
+        metadata.insert("api_key_id".to_string(), api_key_id.to_string().into());
+    }
+
+    // Handlers only describe successful mutations; failures keep the raw request line
+    let message = match context.message {
+        Some(message) if status.is_success() => message,
+        _ => format!("{} {}", method, path),
+    };
+
+    let entry = NewLogEntry {
+        level: level(status),
+        category: category(&route).to_string(),
+        message,
+        user_id: context.actor,
+        server_id: context.server_id,
+        // Proxy-supplied addresses can exceed the column width
+        ip_address: Some(client.ip.chars().take(45).collect()),
+        metadata: Value::Object(metadata),
+    };
+
+    // The mutation already happened; a lost audit entry must not turn it into an error
+    if let Err(e) = audit_service::record(&state.db, &state.events, entry).await {
+        tracing::error!("Failed to write audit log for {} {}: {}", method, path, e);
+    }
+
+    response
+}
@@ -0,0 +1,352 @@
+use axum::{
+    async_trait,
+    extract::{FromRequestParts, Request, State},
+    http::{header, request::Parts, HeaderMap},
+    middleware::Next,
+    response::Response,
+};
+use uuid::Uuid;
+use crate::{
+    middleware::audit,
+    models::{api_key::ApiScope, auth::{Claims, Scope}, user::UserRole, AppState},
+    services::{api_key_service, auth_service},
+    utils::{errors::AppError, jwt},
+};
+
+/// Name of the HTTP-only cookie carrying the access token for browser sessions
+pub const SESSION_COOKIE: &str = "session";
+
+/// Name of the HTTP-only cookie carrying the refresh token, scoped to `/api/auth`
+pub const REFRESH_COOKIE: &str = "refresh_token";
+
+/// Authenticated caller, resolved from a verified JWT or an API key
+#[derive(Debug, Clone)]
+pub struct AuthUser {
+    pub id: Uuid,
+    pub role: UserRole,
+    /// Session for JWT callers, the key id for API key callers
+    pub session_id: Uuid,
+    pub mfa_enrollment_required: bool,
+    /// Scopes granted by the API key; `None` for full interactive sessions
+    pub api_scopes: Option<Vec<ApiScope>>,
+}
+
+impl AuthUser {
+    pub fn scope(&self) -> Scope {
+        Scope::for_user(self.id, &self.role)
+    }
+
+    pub fn require_role(&self, allowed: &[UserRole]) -> Result<(), AppError> {
+        if allowed.contains(&self.role) {
+            Ok(())
+        } else {
+            Err(AppError::Forbidden("Insufficient permissions".to_string()))
+        }
+    }
+
+    /// API keys must carry `scope`; sessions are not restricted
+    pub fn require_scope(&self, scope: ApiScope) -> Result<(), AppError> {
+        match &self.api_scopes {
+            Some(scopes) if !scopes.contains(&scope) => Err(AppError::Forbidden(format!(
+                "API key lacks the {} scope",
+                scope.as_str()
+            ))),
+            _ => Ok(()),
+        }
+    }
+}
+
+impl From<Claims> for AuthUser {
+    fn from(claims: Claims) -> Self {
+        AuthUser {
+            id: claims.sub,
+            role: claims.role,
+            session_id: claims.sid,
+            mfa_enrollment_required: claims.mfa_enrollment_required,
+            api_scopes: None,
+        }
+    }
+}
+
+/// Read the token from `Authorization: Bearer ...`, falling back to the session cookie
+fn extract_token(headers: &HeaderMap) -> Option<String> {
+    if let Some(value) = headers.get(header::AUTHORIZATION).and_then(|v| v.to_str().ok()) {
+        if let Some(token) = value.strip_prefix("Bearer ") {
+            return Some(token.trim().to_string());
+        }
+    }
+
+    cookie_value(headers, SESSION_COOKIE)
+}
+
+pub fn cookie_value(headers: &HeaderMap, cookie: &str) -> Option<String> {
+    headers
+        .get_all(header::COOKIE)
+        .iter()
+        .filter_map(|v| v.to_str().ok())
+        .flat_map(|v| v.split(';'))
+        .filter_map(|pair| pair.trim().split_once('='))
+        .find(|(name, _)| *name == cookie)
+        .map(|(_, value)| value.to_string())
+}
+
+#[async_trait]
+impl FromRequestParts<AppState> for AuthUser {
+    type Rejection = AppError;
+
+    async fn from_request_parts(parts: &mut Parts, state: &AppState) -> Result<Self, Self::Rejection> {
+        // Already resolved by `require_auth`
+        if let Some(user) = parts.extensions.get::<AuthUser>() {
+            return Ok(user.clone());
+        }
+
+        let token = extract_token(&parts.headers)
+            .ok_or(AppError::Unauthorized("Missing authentication token".to_string()))?;
+
+        if token.starts_with(api_key_service::API_KEY_PREFIX) {
+            let (api_key, role) = api_key_service::authenticate(&state.db, &token).await?;
+            return Ok(AuthUser {
+                id: api_key.user_id,
+                role,
+                session_id: api_key.id,
+                mfa_enrollment_required: false,
+                api_scopes: Some(api_key.get_scopes()),
+            });
+        }
+
+        let claims = jwt::verify_token(&token, &state.config)?;
+
+        // Access tokens die with their session on logout, password change or token theft
+        if !auth_service::is_session_active(&state.db, claims.sid).await? {
+            return Err(AppError::Unauthorized("Session has been revoked".to_string()));
+        }
+
+        Ok(claims.into())
+    }
+}
+
+/// Reject requests without a valid token and stash the caller in request extensions
+pub async fn require_auth(
+    State(state): State<AppState>,
+    request: Request,
+    next: Next,
+) -> Result<Response, AppError> {
+    let (mut parts, body) = request.into_parts();
+    let user = AuthUser::from_request_parts(&mut parts, &state).await?;
+    audit::set_actor(&user);
+    parts.extensions.insert(user);
+
+    Ok(next.run(Request::from_parts(parts, body)).await)
+}
+
+/// Keep API keys away from account, admin and session management endpoints
+pub async fn require_session(
+    user: AuthUser,
+    request: Request,
+    next: Next,
+) -> Result<Response, AppError> {
+    if user.api_scopes.is_some() {
+        return Err(AppError::Forbidden("API keys cannot access this endpoint".to_string()));
+    }
+
+    Ok(next.run(request).await)
+}
+
+/// Block users who must enroll in MFA from everything except the session and MFA endpoints
+pub async fn require_mfa_enrollment(
+    user: AuthUser,
+    request: Request,
+    next: Next,
+) -> Result<Response, AppError> {
+    if user.mfa_enrollment_required {
+        return Err(AppError::Forbidden("MFA enrollment required".to_string()));
+    }
+
+    Ok(next.run(request).await)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        api,
+        models::{api_key::CreateApiKey, user::User},
+        services::user_service,
+        test_support::{self, delete_users, user, RecordingMailer},
+    };
+    use axum::{body::Body, http::{HeaderValue, Method, StatusCode}, Router};
+    use std::sync::Arc;
+    use tower::ServiceExt;
+
+    async fn state() -> Option<AppState> {
+        let db = test_support::database().await?;
+        Some(AppState::new(db, test_support::config(), Arc::new(RecordingMailer::default())))
+    }
+
+    async fn call(state: &AppState, method: Method, path: &str, token: &str) -> (StatusCode, String) {
+        send(state, method, path, token, serde_json::json!({})).await
+    }
+
+    async fn send(
+        state: &AppState,
+        method: Method,
+        path: &str,
+        token: &str,
+        body: serde_json::Value,
+    ) -> (StatusCode, String) {
+        let app = Router::new().nest("/api", api::router(state.clone())).with_state(state.clone());
+        let request = Request::builder()
+            .method(method)
+            .uri(path)
+            .header(header::AUTHORIZATION, format!("Bearer {}", token))
+            .header(header::CONTENT_TYPE, "application/json")
+            .body(Body::from(body.to_string()))
+            .unwrap();
+
+        let response = app.oneshot(request).await.unwrap();
+        let status = response.status();
+        let body = axum::body::to_bytes(response.into_body(), usize::MAX).await.unwrap();
+        (status, String::from_utf8_lossy(&body).into_owned())
+    }
+
+    async fn key(state: &AppState, user_id: Uuid, scopes: Vec<ApiScope>) -> String {
+        let payload = CreateApiKey { name: "test".to_string(), scopes, expires_at: None };
+        api_key_service::create_key(&state.db, user_id, payload).await.unwrap().key
+    }
+
+    async fn session(state: &AppState, user_id: Uuid) -> String {
+        let user: User = user_service::get_user(&state.db, &Scope::All, user_id).await.unwrap();
+        auth_service::reissue_session(&state.db, &state.config, &user, Uuid::new_v4()).await.unwrap().access_token
+    }
+
+    #[test]
+    fn bearer_header_takes_precedence_over_the_cookie() {
+        let mut headers = HeaderMap::new();
+        headers.insert(header::COOKIE, HeaderValue::from_static("theme=dark; session=from-cookie"));
+        assert_eq!(extract_token(&headers).as_deref(), Some("from-cookie"));
+
+        headers.insert(header::AUTHORIZATION, HeaderValue::from_static("Bearer  upk_abcd_secret "));
+        assert_eq!(extract_token(&headers).as_deref(), Some("upk_abcd_secret"));
+
+        headers.insert(header::AUTHORIZATION, HeaderValue::from_static("Basic dXNlcjpwYXNz"));
+        assert_eq!(extract_token(&headers).as_deref(), Some("from-cookie"));
+
+        headers.remove(header::COOKIE);
+        assert_eq!(extract_token(&headers), None);
+    }
+
+    #[test]
+    fn api_keys_need_every_scope_they_use() {
+        let caller = |api_scopes| AuthUser {
+            id: Uuid::new_v4(),
+            role: UserRole::User,
+            session_id: Uuid::new_v4(),
+            mfa_enrollment_required: false,
+            api_scopes,
+        };
+
+        let key = caller(Some(vec![ApiScope::ServersRead]));
+        assert!(key.require_scope(ApiScope::ServersRead).is_ok());
+        assert!(matches!(key.require_scope(ApiScope::ServersWrite), Err(AppError::Forbidden(_))));
+        assert!(caller(None).require_scope(ApiScope::VpsPower).is_ok());
+    }
+
+    #[tokio::test]
+    async fn api_keys_are_looked_up_by_hash() {
+        let Some(state) = state().await else { return };
+        let owner = user(&state.db, UserRole::User, None).await;
+        let valid = key(&state, owner, vec![ApiScope::ServersRead]).await;
+        let revoked = key(&state, owner, vec![ApiScope::ServersRead]).await;
+        let expired = key(&state, owner, vec![ApiScope::ServersRead]).await;
+        sqlx::query("UPDATE api_keys SET revoked_at = NOW() WHERE key_hash = $1")
+            .bind(crate::utils::crypto::sha256_hex(&revoked))
+            .execute(&state.db)
+            .await
+            .unwrap();
+        sqlx::query("UPDATE api_keys SET expires_at = NOW() - INTERVAL '1 second' WHERE key_hash = $1")
+            .bind(crate::utils::crypto::sha256_hex(&expired))
+            .execute(&state.db)
+            .await
+            .unwrap();
+        // Right prefix, wrong secret
+        let forged = format!("{}_{}", &valid[..valid.rfind('_').unwrap()], "x".repeat(24));
+
+        let accepted = call(&state, Method::GET, "/api/servers", &valid).await;
+        let last_used: Option<chrono::DateTime<chrono::Utc>> =
+            sqlx::query_scalar("SELECT last_used_at FROM api_keys WHERE key_hash = $1")
+                .bind(crate::utils::crypto::sha256_hex(&valid))
+                .fetch_one(&state.db)
+                .await
+                .unwrap();
+        let refused = [
+            call(&state, Method::GET, "/api/servers", &revoked).await,
+            call(&state, Method::GET, "/api/servers", &expired).await,
+            call(&state, Method::GET, "/api/servers", &forged).await,
+            call(&state, Method::GET, "/api/servers", "upk_unknown").await,
+        ];
+
+        delete_users(&state.db, &[owner]).await;
+        assert_eq!(accepted.0, StatusCode::OK);
+        assert!(last_used.is_some());
+        for (status, body) in refused {
+            assert_eq!(status, StatusCode::UNAUTHORIZED);
+            assert!(body.contains("Invalid API key"), "{}", body);
+        }
+    }
+
+    #[tokio::test]
+    async fn api_keys_are_limited_to_their_scopes() {
+        let Some(state) = state().await else { return };
+        let owner = user(&state.db, UserRole::User, None).await;
+        let key = key(&state, owner, vec![ApiScope::ServersRead]).await;
+
+        let read = call(&state, Method::GET, "/api/servers", &key).await;
+        let server = serde_json::json!({
+            "name": "web",
+            "hostname": "web.example.com",
+            "ip_address": "203.0.113.10",
+            "server_type": "dedicated",
+        });
+        let write = send(&state, Method::POST, "/api/servers", &key, server).await;
+        let other = call(&state, Method::GET, "/api/vps", &key).await;
+
+        delete_users(&state.db, &[owner]).await;
+        assert_eq!(read.0, StatusCode::OK);
+        assert_eq!(write.0, StatusCode::FORBIDDEN);
+        assert!(write.1.contains("servers:write"), "{}", write.1);
+        assert_eq!(other.0, StatusCode::FORBIDDEN);
+        assert!(other.1.contains("vps:read"), "{}", other.1);
+    }
+
+    #[tokio::test]
+    async fn api_keys_cannot_reach_session_only_routes() {
+        let Some(state) = state().await else { return };
+        let owner = user(&state.db, UserRole::Admin, None).await;
+        let every_scope = vec![
+            ApiScope::ServersRead,
+            ApiScope::ServersWrite,
+            ApiScope::MetricsRead,
+            ApiScope::MetricsWrite,
+            ApiScope::VpsRead,
+            ApiScope::VpsWrite,
+            ApiScope::VpsPower,
+            ApiScope::AlertsRead,
+            ApiScope::AlertsWrite,
+        ];
+        let key = key(&state, owner, every_scope).await;
+        let session = session(&state, owner).await;
+
+        let mut refused = Vec::new();
+        for path in ["/api/users", "/api/auth/api-keys", "/api/auth/me", "/api/logs", "/api/admin/lockouts"] {
+            refused.push((path, call(&state, Method::GET, path, &key).await));
+        }
+        let with_session = call(&state, Method::GET, "/api/users", &session).await;
+
+        delete_users(&state.db, &[owner]).await;
+        for (path, (status, body)) in refused {
+            assert_eq!(status, StatusCode::FORBIDDEN, "{}", path);
+            assert!(body.contains("API keys cannot access this endpoint"), "{}: {}", path, body);
+        }
+        assert_eq!(with_session.0, StatusCode::OK);
+    }
+}
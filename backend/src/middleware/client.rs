@@ -0,0 +1,89 @@
+use axum::{
+    async_trait,
+    extract::{ConnectInfo, FromRequestParts},
+    http::{header, request::Parts, HeaderMap},
+};
+use std::{
+    convert::Infallible,
+    net::{IpAddr, SocketAddr},
+};
+use crate::models::{auth::ClientInfo, AppState};
+
+/// Client address as reported by a trusted reverse proxy.
+///
+/// `X-Real-IP` is set (not appended) by the proxy. Otherwise only the rightmost
+/// `X-Forwarded-For` hop is the proxy's own; entries left of it come from the client.
+fn forwarded_ip(headers: &HeaderMap) -> Option<IpAddr> {
+    let header = |name: &str| headers.get(name).and_then(|v| v.to_str().ok());
+
+    header("x-real-ip")
+        .and_then(|ip| ip.trim().parse().ok())
+        .or_else(|| {
+            header("x-forwarded-for")
+                .and_then(|v| v.rsplit(',').next())
+                .and_then(|ip| ip.trim().parse().ok())
+        })
+}
+
+#[async_trait]
+impl FromRequestParts<AppState> for ClientInfo {
+    type Rejection = Infallible;
+
+    async fn from_request_parts(parts: &mut Parts, state: &AppState) -> Result<Self, Self::Rejection> {
+        // Forwarded headers are client-controlled unless a trusted proxy overwrites them
+        let forwarded = if state.config.trust_proxy_headers {
+            forwarded_ip(&parts.headers)
+        } else {
+            None
+        };
+
+        let ip = forwarded
+            .or_else(|| {
+                parts.extensions
+                    .get::<ConnectInfo<SocketAddr>>()
+                    .map(|ConnectInfo(addr)| addr.ip())
+            })
+            .map(|ip| ip.to_string())
+            .unwrap_or_else(|| "unknown".to_string());
+
+        let user_agent = parts.headers
+            .get(header::USER_AGENT)
+            .and_then(|v| v.to_str().ok())
+            .map(|v| v.to_string());
+
+        Ok(ClientInfo { ip, user_agent })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn headers(pairs: &[(&'static str, &str)]) -> HeaderMap {
+        let mut headers = HeaderMap::new();
+        for (name, value) in pairs {
+            headers.insert(*name, value.parse().unwrap());
+        }
+        headers
+    }
+
+    #[test]
+    fn rightmost_forwarded_hop_is_used() {
+        let headers = headers(&[("x-forwarded-for", "6.6.6.6, 203.0.113.7")]);
+
+        assert_eq!(forwarded_ip(&headers), Some("203.0.113.7".parse().unwrap()));
+    }
+
+    #[test]
+    fn real_ip_takes_precedence() {
+        let headers = headers(&[("x-forwarded-for", "6.6.6.6"), ("x-real-ip", "198.51.100.4")]);
+
+        assert_eq!(forwarded_ip(&headers), Some("198.51.100.4".parse().unwrap()));
+    }
+
+    #[test]
+    fn malformed_addresses_are_ignored() {
+        assert_eq!(forwarded_ip(&headers(&[("x-forwarded-for", "1.2.3.4, not-an-ip")])), None);
+        assert_eq!(forwarded_ip(&HeaderMap::new()), None);
+    }
+}
@@ -1,2 +1,6 @@
-// Middleware for authentication, authorization, rate limiting, etc.
-// Will be implemented as needed
+pub mod audit;
+pub mod auth;
+pub mod client;
+pub mod rate_limit;
+pub mod validation;
+pub mod request_id;
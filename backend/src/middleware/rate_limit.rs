@@ -0,0 +1,54 @@
+use governor::{DefaultKeyedRateLimiter, Quota, RateLimiter};
+use std::num::NonZeroU32;
+use crate::{config::Config, utils::errors::AppError};
+
+/// In-process limiters for unauthenticated auth endpoints
+pub struct RateLimiters {
+    login_by_ip: DefaultKeyedRateLimiter<String>,
+    login_by_account: DefaultKeyedRateLimiter<String>,
+    register_by_ip: DefaultKeyedRateLimiter<String>,
+    email_by_ip: DefaultKeyedRateLimiter<String>,
+}
+
+fn per_minute(limit: u32) -> Quota {
+    Quota::per_minute(NonZeroU32::new(limit).unwrap_or(NonZeroU32::MIN))
+}
+
+fn check(limiter: &DefaultKeyedRateLimiter<String>, key: &str) -> Result<(), AppError> {
+    limiter
+        .check_key(&key.to_string())
+        .map_err(|_| AppError::RateLimited("Too many requests, please try again later".to_string()))
+}
+
+impl RateLimiters {
+    pub fn new(config: &Config) -> Self {
+        Self {
+            login_by_ip: RateLimiter::keyed(per_minute(config.login_rate_limit)),
+            login_by_account: RateLimiter::keyed(per_minute(config.login_account_rate_limit)),
+            register_by_ip: RateLimiter::keyed(per_minute(config.register_rate_limit)),
+            email_by_ip: RateLimiter::keyed(per_minute(config.email_rate_limit)),
+        }
+    }
+
+    pub fn check_login(&self, ip: &str, email: &str) -> Result<(), AppError> {
+        check(&self.login_by_ip, ip)?;
+        check(&self.login_by_account, &email.to_lowercase())
+    }
+
+    pub fn check_register(&self, ip: &str) -> Result<(), AppError> {
+        check(&self.register_by_ip, ip)
+    }
+
+    /// Password reset and verification requests, which send mail
+    pub fn check_email(&self, ip: &str) -> Result<(), AppError> {
+        check(&self.email_by_ip, ip)
+    }
+
+    /// Drop state for keys that are back to a full quota, bounding memory use
+    pub fn prune(&self) {
+        self.login_by_ip.retain_recent();
+        self.login_by_account.retain_recent();
+        self.register_by_ip.retain_recent();
+        self.email_by_ip.retain_recent();
+    }
+}
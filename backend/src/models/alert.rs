@@ -0,0 +1,286 @@
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use sqlx::FromRow;
+use uuid::Uuid;
+use validator::Validate;
+
+/// Server metric an alert rule watches; the value doubles as the `server_metrics` column
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq)]
+#[serde(rename_all = "snake_case")]
+pub enum AlertMetric {
+    CpuUsage,
+    MemoryUsage,
+    DiskUsage,
+    NetworkIn,
+    NetworkOut,
+    #[serde(rename = "load_1")]
+    Load1,
+    #[serde(rename = "load_5")]
+    Load5,
+    #[serde(rename = "load_15")]
+    Load15,
+}
+
+impl AlertMetric {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            AlertMetric::CpuUsage => "cpu_usage",
+            AlertMetric::MemoryUsage => "memory_usage",
+            AlertMetric::DiskUsage => "disk_usage",
+            AlertMetric::NetworkIn => "network_in",
+            AlertMetric::NetworkOut => "network_out",
+            AlertMetric::Load1 => "load_1",
+            AlertMetric::Load5 => "load_5",
+            AlertMetric::Load15 => "load_15",
+        }
+    }
+}
+
+impl std::str::FromStr for AlertMetric {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "cpu_usage" => Ok(AlertMetric::CpuUsage),
+            "memory_usage" => Ok(AlertMetric::MemoryUsage),
+            "disk_usage" => Ok(AlertMetric::DiskUsage),
+            "network_in" => Ok(AlertMetric::NetworkIn),
+            "network_out" => Ok(AlertMetric::NetworkOut),
+            "load_1" => Ok(AlertMetric::Load1),
+            "load_5" => Ok(AlertMetric::Load5),
+            "load_15" => Ok(AlertMetric::Load15),
+            _ => Err(format!("Invalid alert metric: {}", s)),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq)]
+#[serde(rename_all = "lowercase")]
+pub enum AlertComparison {
+    Gt,
+    Gte,
+    Lt,
+    Lte,
+}
+
+impl AlertComparison {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            AlertComparison::Gt => "gt",
+            AlertComparison::Gte => "gte",
+            AlertComparison::Lt => "lt",
+            AlertComparison::Lte => "lte",
+        }
+    }
+
+    pub fn symbol(&self) -> &'static str {
+        match self {
+            AlertComparison::Gt => ">",
+            AlertComparison::Gte => ">=",
+            AlertComparison::Lt => "<",
+            AlertComparison::Lte => "<=",
+        }
+    }
+
+    pub fn holds(&self, value: f64, threshold: f64) -> bool {
+        match self {
+            AlertComparison::Gt => value > threshold,
+            AlertComparison::Gte => value >= threshold,
+            AlertComparison::Lt => value < threshold,
+            AlertComparison::Lte => value <= threshold,
+        }
+    }
+}
+
+impl std::str::FromStr for AlertComparison {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "gt" => Ok(AlertComparison::Gt),
+            "gte" => Ok(AlertComparison::Gte),
+            "lt" => Ok(AlertComparison::Lt),
+            "lte" => Ok(AlertComparison::Lte),
+            _ => Err(format!("Invalid comparison: {}", s)),
+        }
+    }
+}
+
+/// Ordered from least to most severe
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, Eq, PartialOrd, Ord, Default)]
+#[serde(rename_all = "lowercase")]
+pub enum AlertSeverity {
+    Info,
+    #[default]
+    Warning,
+    Critical,
+}
+
+impl AlertSeverity {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            AlertSeverity::Info => "info",
+            AlertSeverity::Warning => "warning",
+            AlertSeverity::Critical => "critical",
+        }
+    }
+}
+
+impl std::str::FromStr for AlertSeverity {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "info" => Ok(AlertSeverity::Info),
+            "warning" => Ok(AlertSeverity::Warning),
+            "critical" => Ok(AlertSeverity::Critical),
+            _ => Err(format!("Invalid severity: {}", s)),
+        }
+    }
+}
+
+/// Pending alerts wait out the rule's duration before firing; if the condition clears first they
+/// resolve without ever having a `fired_at`
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq)]
+#[serde(rename_all = "lowercase")]
+pub enum AlertState {
+    Pending,
+    Firing,
+    Resolved,
+}
+
+impl AlertState {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            AlertState::Pending => "pending",
+            AlertState::Firing => "firing",
+            AlertState::Resolved => "resolved",
+        }
+    }
+}
+
+impl std::str::FromStr for AlertState {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "pending" => Ok(AlertState::Pending),
+            "firing" => Ok(AlertState::Firing),
+            "resolved" => Ok(AlertState::Resolved),
+            _ => Err(format!("Invalid alert state: {}", s)),
+        }
+    }
+}
+
+/// What raised an alert: a user rule or one of the built-in checks
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq)]
+#[serde(rename_all = "snake_case")]
+pub enum AlertKind {
+    Rule,
+    NoMetrics,
+    VpsError,
+}
+
+impl AlertKind {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            AlertKind::Rule => "rule",
+            AlertKind::NoMetrics => "no_metrics",
+            AlertKind::VpsError => "vps_error",
+        }
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, FromRow)]
+pub struct AlertRule {
+    pub id: Uuid,
+    pub user_id: Uuid,
+    pub server_id: Option<Uuid>,
+    pub name: String,
+    pub metric: String,
+    pub comparison: String,
+    pub threshold: f64,
+    pub duration_seconds: i32,
+    pub severity: String,
+    pub enabled: bool,
+    pub created_at: DateTime<Utc>,
+    pub updated_at: DateTime<Utc>,
+}
+
+impl AlertRule {
+    pub fn get_metric(&self) -> Option<AlertMetric> {
+        self.metric.parse().ok()
+    }
+
+    pub fn get_comparison(&self) -> Option<AlertComparison> {
+        self.comparison.parse().ok()
+    }
+}
+
+#[derive(Debug, Deserialize, Validate)]
+pub struct CreateAlertRule {
+    #[validate(length(min = 1, max = 255, message = "Must be between 1 and 255 characters"))]
+    pub name: String,
+    /// Omit to watch every server visible to the rule's owner
+    pub server_id: Option<Uuid>,
+    pub metric: AlertMetric,
+    pub comparison: AlertComparison,
+    pub threshold: f64,
+    /// How long the condition must hold before the alert fires
+    #[serde(default)]
+    #[validate(range(min = 0, max = 86400, message = "Must be between 0 and 86400 seconds"))]
+    pub duration_seconds: i32,
+    #[serde(default)]
+    pub severity: AlertSeverity,
+    #[serde(default = "default_enabled")]
+    pub enabled: bool,
+}
+
+fn default_enabled() -> bool {
+    true
+}
+
+#[derive(Debug, Deserialize, Validate)]
+pub struct UpdateAlertRule {
+    #[validate(length(min = 1, max = 255, message = "Must be between 1 and 255 characters"))]
+    pub name: Option<String>,
+    pub metric: Option<AlertMetric>,
+    pub comparison: Option<AlertComparison>,
+    pub threshold: Option<f64>,
+    #[validate(range(min = 0, max = 86400, message = "Must be between 0 and 86400 seconds"))]
+    pub duration_seconds: Option<i32>,
+    pub severity: Option<AlertSeverity>,
+    pub enabled: Option<bool>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, FromRow)]
+pub struct Alert {
+    pub id: Uuid,
+    pub kind: String,
+    pub rule_id: Option<Uuid>,
+    pub user_id: Uuid,
+    pub server_id: Option<Uuid>,
+    pub vps_id: Option<Uuid>,
+    pub severity: String,
+    pub state: String,
+    pub message: String,
+    /// Latest observed value for rule alerts
+    pub value: Option<f64>,
+    pub started_at: DateTime<Utc>,
+    pub fired_at: Option<DateTime<Utc>>,
+    pub resolved_at: Option<DateTime<Utc>>,
+    pub updated_at: DateTime<Utc>,
+}
+
+impl Alert {
+    pub fn get_state(&self) -> AlertState {
+        self.state.parse().unwrap_or(AlertState::Resolved)
+    }
+}
+
+#[derive(Debug, Deserialize)]
+pub struct AlertsQuery {
+    pub state: Option<AlertState>,
+    pub server_id: Option<Uuid>,
+    pub limit: Option<i64>,
+}
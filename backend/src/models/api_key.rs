@@ -0,0 +1,124 @@
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use validator::Validate;
+use sqlx::FromRow;
+use uuid::Uuid;
+
+/// Permission granted to an API key; session users implicitly hold all of them
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq)]
+pub enum ApiScope {
+    #[serde(rename = "servers:read")]
+    ServersRead,
+    #[serde(rename = "servers:write")]
+    ServersWrite,
+    #[serde(rename = "metrics:read")]
+    MetricsRead,
+    #[serde(rename = "metrics:write")]
+    MetricsWrite,
+    #[serde(rename = "vps:read")]
+    VpsRead,
+    #[serde(rename = "vps:write")]
+    VpsWrite,
+    #[serde(rename = "vps:power")]
+    VpsPower,
+    #[serde(rename = "alerts:read")]
+    AlertsRead,
+    #[serde(rename = "alerts:write")]
+    AlertsWrite,
+}
+
+impl ApiScope {
+    pub fn as_str(&self) -> &str {
+        match self {
+            ApiScope::ServersRead => "servers:read",
+            ApiScope::ServersWrite => "servers:write",
+            ApiScope::MetricsRead => "metrics:read",
+            ApiScope::MetricsWrite => "metrics:write",
+            ApiScope::VpsRead => "vps:read",
+            ApiScope::VpsWrite => "vps:write",
+            ApiScope::VpsPower => "vps:power",
+            ApiScope::AlertsRead => "alerts:read",
+            ApiScope::AlertsWrite => "alerts:write",
+        }
+    }
+}
+
+impl std::str::FromStr for ApiScope {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "servers:read" => Ok(ApiScope::ServersRead),
+            "servers:write" => Ok(ApiScope::ServersWrite),
+            "metrics:read" => Ok(ApiScope::MetricsRead),
+            "metrics:write" => Ok(ApiScope::MetricsWrite),
+            "vps:read" => Ok(ApiScope::VpsRead),
+            "vps:write" => Ok(ApiScope::VpsWrite),
+            "vps:power" => Ok(ApiScope::VpsPower),
+            "alerts:read" => Ok(ApiScope::AlertsRead),
+            "alerts:write" => Ok(ApiScope::AlertsWrite),
+            _ => Err(format!("Invalid API key scope: {}", s)),
+        }
+    }
+}
+
+#[derive(Debug, Clone, FromRow)]
+pub struct ApiKey {
+    pub id: Uuid,
+    pub user_id: Uuid,
+    pub name: String,
+    pub prefix: String,
+    pub scopes: Vec<String>,
+    pub expires_at: Option<DateTime<Utc>>,
+    pub last_used_at: Option<DateTime<Utc>>,
+    pub created_at: DateTime<Utc>,
+}
+
+impl ApiKey {
+    pub fn get_scopes(&self) -> Vec<ApiScope> {
+        self.scopes.iter().filter_map(|scope| scope.parse().ok()).collect()
+    }
+}
+
+#[derive(Debug, Serialize)]
+pub struct ApiKeyResponse {
+    pub id: Uuid,
+    pub name: String,
+    /// Leading part of the key, enough to recognise it in a list
+    pub prefix: String,
+    pub scopes: Vec<ApiScope>,
+    pub expires_at: Option<DateTime<Utc>>,
+    pub last_used_at: Option<DateTime<Utc>>,
+    pub created_at: DateTime<Utc>,
+}
+
+impl From<ApiKey> for ApiKeyResponse {
+    fn from(key: ApiKey) -> Self {
+        ApiKeyResponse {
+            scopes: key.get_scopes(),
+            id: key.id,
+            name: key.name,
+            prefix: key.prefix,
+            expires_at: key.expires_at,
+            last_used_at: key.last_used_at,
+            created_at: key.created_at,
+        }
+    }
+}
+
+#[derive(Debug, Deserialize, Validate)]
+pub struct CreateApiKey {
+    #[validate(length(min = 1, max = 100, message = "Must be between 1 and 100 characters"))]
+    pub name: String,
+    #[validate(length(min = 1, message = "At least one scope is required"))]
+    pub scopes: Vec<ApiScope>,
+    pub expires_at: Option<DateTime<Utc>>,
+}
+
+#[derive(Debug, Serialize)]
+pub struct CreatedApiKey {
+    /// Full secret; shown once, only its hash is stored
+    pub key: String,
+    #[serde(flatten)]
+    pub api_key: ApiKeyResponse,
+}
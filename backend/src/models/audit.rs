@@ -0,0 +1,73 @@
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use sqlx::FromRow;
+use uuid::Uuid;
+
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq)]
+#[serde(rename_all = "lowercase")]
+pub enum LogLevel {
+    Info,
+    Warning,
+    Error,
+}
+
+impl LogLevel {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            LogLevel::Info => "info",
+            LogLevel::Warning => "warning",
+            LogLevel::Error => "error",
+        }
+    }
+}
+
+/// Row of the `logs` table
+#[derive(Debug, Clone, Serialize, FromRow)]
+pub struct LogEntry {
+    pub id: Uuid,
+    pub level: String,
+    pub category: String,
+    pub message: String,
+    /// Who made the call; `None` for unauthenticated requests
+    pub user_id: Option<Uuid>,
+    pub server_id: Option<Uuid>,
+    pub ip_address: Option<String>,
+    /// Request details plus `changes`, `before` or `after` snapshots of the resource
+    pub metadata: Option<serde_json::Value>,
+    pub created_at: DateTime<Utc>,
+}
+
+/// Entry to append to the audit log
+#[derive(Debug, Clone)]
+pub struct NewLogEntry {
+    pub level: LogLevel,
+    pub category: String,
+    pub message: String,
+    pub user_id: Option<Uuid>,
+    pub server_id: Option<Uuid>,
+    pub ip_address: Option<String>,
+    pub metadata: serde_json::Value,
+}
+
+#[derive(Debug, Clone, Copy, Deserialize, Default, PartialEq)]
+#[serde(rename_all = "lowercase")]
+pub enum ExportFormat {
+    #[default]
+    Json,
+    Csv,
+}
+
+/// `GET /api/logs` filters; `format` only applies to `/api/logs/export`
+#[derive(Debug, Deserialize)]
+pub struct LogsQuery {
+    pub category: Option<String>,
+    pub level: Option<LogLevel>,
+    pub user_id: Option<Uuid>,
+    pub server_id: Option<Uuid>,
+    pub from: Option<DateTime<Utc>>,
+    pub to: Option<DateTime<Utc>>,
+    pub limit: Option<i64>,
+    pub offset: Option<i64>,
+    #[serde(default)]
+    pub format: ExportFormat,
+}
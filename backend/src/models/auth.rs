@@ -1,23 +1,119 @@
+use chrono::{DateTime, Utc};
 use serde::{Deserialize, Serialize};
+use validator::Validate;
+use sqlx::FromRow;
 use uuid::Uuid;
+use crate::models::user::UserRole;
 
-#[derive(Debug, Deserialize)]
+#[derive(Debug, Deserialize, Validate)]
 pub struct LoginRequest {
+    #[validate(email(message = "Must be a valid email address"))]
     pub email: String,
+    #[validate(length(min = 1, max = 1024, message = "Must be between 1 and 1024 characters"))]
     pub password: String,
+    #[validate(length(max = 32, message = "Must be at most 32 characters"))]
     pub totp_code: Option<String>,
+    #[validate(length(max = 32, message = "Must be at most 32 characters"))]
+    pub recovery_code: Option<String>,
 }
 
 #[derive(Debug, Serialize)]
 pub struct LoginResponse {
     pub token: String,
+    pub refresh_token: String,
+    pub expires_in: i64,
     pub user: crate::models::user::UserResponse,
 }
 
-#[derive(Debug, Deserialize)]
+#[derive(Debug, Deserialize, Validate)]
+pub struct RefreshRequest {
+    #[validate(length(min = 1, max = 256, message = "Must not be empty"))]
+    pub refresh_token: String,
+}
+
+#[derive(Debug, Deserialize, Validate)]
+pub struct ChangePasswordRequest {
+    #[validate(length(min = 1, max = 1024, message = "Must be between 1 and 1024 characters"))]
+    pub current_password: String,
+    #[validate(length(max = 1024, message = "Must be at most 1024 characters"))]
+    pub new_password: String,
+}
+
+#[derive(Debug, Deserialize, Validate)]
+pub struct EmailRequest {
+    #[validate(email(message = "Must be a valid email address"))]
+    pub email: String,
+}
+
+#[derive(Debug, Deserialize, Validate)]
+pub struct ResetPasswordRequest {
+    #[validate(length(min = 1, max = 256, message = "Must not be empty"))]
+    pub token: String,
+    #[validate(length(max = 1024, message = "Must be at most 1024 characters"))]
+    pub new_password: String,
+}
+
+#[derive(Debug, Deserialize, Validate)]
+pub struct VerifyEmailRequest {
+    #[validate(length(min = 1, max = 256, message = "Must not be empty"))]
+    pub token: String,
+}
+
+/// What a one-time `user_tokens` row can be redeemed for
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum TokenPurpose {
+    PasswordReset,
+    EmailVerification,
+}
+
+impl TokenPurpose {
+    pub fn as_str(&self) -> &str {
+        match self {
+            TokenPurpose::PasswordReset => "password_reset",
+            TokenPurpose::EmailVerification => "email_verification",
+        }
+    }
+}
+
+/// Network origin of a request, used for login auditing and rate limiting
+#[derive(Debug, Clone)]
+pub struct ClientInfo {
+    pub ip: String,
+    pub user_agent: Option<String>,
+}
+
+#[derive(Debug, Serialize, FromRow)]
+pub struct AccountLockout {
+    pub email: String,
+    pub failed_attempts: i32,
+    pub locked_until: Option<DateTime<Utc>>,
+    pub updated_at: DateTime<Utc>,
+}
+
+/// Access/refresh token pair issued on login and on every refresh
+#[derive(Debug)]
+pub struct SessionTokens {
+    pub access_token: String,
+    pub refresh_token: String,
+}
+
+#[derive(Debug, FromRow)]
+pub struct RefreshToken {
+    pub id: Uuid,
+    pub user_id: Uuid,
+    pub family_id: Uuid,
+    pub expires_at: DateTime<Utc>,
+    pub replaced_by: Option<Uuid>,
+    pub revoked_at: Option<DateTime<Utc>>,
+}
+
+#[derive(Debug, Deserialize, Validate)]
 pub struct RegisterRequest {
+    #[validate(email(message = "Must be a valid email address"))]
     pub email: String,
+    #[validate(length(max = 1024, message = "Must be at most 1024 characters"))]
     pub password: String,
+    #[validate(length(max = 255, message = "Must be at most 255 characters"))]
     pub company: Option<String>,
 }
 
@@ -25,7 +121,82 @@ pub struct RegisterRequest {
 pub struct Claims {
     pub sub: Uuid,
     pub email: String,
-    pub role: String,
+    pub role: UserRole,
+    /// Session (refresh token family) this access token belongs to
+    pub sid: Uuid,
+    /// Role policy requires MFA but the user has not enrolled yet
+    #[serde(default)]
+    pub mfa_enrollment_required: bool,
     pub exp: i64,
     pub iat: i64,
 }
+
+#[derive(Debug, Serialize)]
+pub struct MfaEnrollResponse {
+    pub secret: String,
+    pub otpauth_uri: String,
+}
+
+#[derive(Debug, Deserialize, Validate)]
+pub struct MfaConfirmRequest {
+    #[validate(length(min = 1, max = 32, message = "Must be between 1 and 32 characters"))]
+    pub code: String,
+}
+
+/// Recovery codes plus a fresh session, since the old access token still demands enrollment
+#[derive(Debug, Serialize)]
+pub struct MfaConfirmResponse {
+    /// Shown once; only hashes are stored
+    pub recovery_codes: Vec<String>,
+    pub token: String,
+    pub refresh_token: String,
+    pub expires_in: i64,
+}
+
+#[derive(Debug, Deserialize, Validate)]
+pub struct MfaDisableRequest {
+    #[validate(length(min = 1, max = 1024, message = "Must be between 1 and 1024 characters"))]
+    pub password: String,
+    /// Current TOTP code or an unused recovery code
+    #[validate(length(min = 1, max = 32, message = "Must be between 1 and 32 characters"))]
+    pub code: String,
+}
+
+#[derive(Debug, Serialize, Deserialize, Validate)]
+pub struct MfaPolicy {
+    pub required_roles: Vec<UserRole>,
+}
+
+/// Set of tenants whose rows a caller may see or modify
+#[derive(Debug, Clone, Copy)]
+pub enum Scope {
+    /// Every row (admins)
+    All,
+    /// Only rows owned by this user
+    Owner(Uuid),
+    /// Rows owned by this reseller or by customers whose `parent_id` is this reseller
+    Reseller(Uuid),
+}
+
+impl Scope {
+    pub fn for_user(id: Uuid, role: &UserRole) -> Self {
+        match role {
+            UserRole::Admin => Scope::All,
+            UserRole::Reseller => Scope::Reseller(id),
+            UserRole::User => Scope::Owner(id),
+        }
+    }
+
+    /// Owner to filter on, bound to `$owner::uuid IS NULL OR ...` predicates; `None` means unrestricted
+    pub fn owner_id(&self) -> Option<Uuid> {
+        match self {
+            Scope::All => None,
+            Scope::Owner(id) | Scope::Reseller(id) => Some(*id),
+        }
+    }
+
+    /// Whether rows belonging to the owner's customers are visible too
+    pub fn includes_customers(&self) -> bool {
+        matches!(self, Scope::Reseller(_))
+    }
+}
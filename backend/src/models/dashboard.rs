@@ -0,0 +1,58 @@
+use chrono::{DateTime, Utc};
+use sqlx::FromRow;
+
+/// Resource counts for the dashboard stat cards, within the caller's scope
+#[derive(Debug, Clone, FromRow)]
+pub struct DashboardStats {
+    pub servers: i64,
+    pub servers_online: i64,
+    pub websites: i64,
+    pub websites_active: i64,
+    pub users: i64,
+    pub vps: i64,
+    pub vps_running: i64,
+}
+
+/// Entry from the `logs` table with the server name or user email it refers to
+#[derive(Debug, Clone, FromRow)]
+pub struct ActivityEntry {
+    pub level: String,
+    pub category: String,
+    pub message: String,
+    pub subject: Option<String>,
+    pub created_at: DateTime<Utc>,
+}
+
+/// Averages over the latest sample of every server that reported recently
+#[derive(Debug, Clone, FromRow)]
+pub struct HealthSummary {
+    pub servers_reporting: i64,
+    pub cpu_usage: Option<f64>,
+    pub memory_usage: Option<f64>,
+    pub disk_usage: Option<f64>,
+}
+
+#[derive(Debug, Clone, FromRow)]
+pub struct ServerOverview {
+    pub name: String,
+    pub status: String,
+    pub location: Option<String>,
+    /// Latest sample, if the server has reported recently
+    pub cpu_usage: Option<f64>,
+    pub memory_usage: Option<f64>,
+}
+
+#[derive(Debug, Clone, FromRow)]
+pub struct MountOverview {
+    pub server_name: String,
+    pub mount_point: String,
+    pub total_bytes: i64,
+    pub used_bytes: i64,
+}
+
+#[derive(Debug, Clone)]
+pub struct StorageSummary {
+    pub total_bytes: i64,
+    pub used_bytes: i64,
+    pub mounts: Vec<MountOverview>,
+}
@@ -0,0 +1,161 @@
+use serde::{Deserialize, Serialize};
+
+// DigitalOcean API request and response models
+#[derive(Debug, Deserialize)]
+pub struct DigitalOceanDroplet {
+    pub id: i64,
+    pub name: String,
+    /// MiB
+    pub memory: i64,
+    pub vcpus: i32,
+    /// GiB
+    pub disk: i32,
+    pub status: String,
+    pub size_slug: String,
+    pub size: DigitalOceanDropletSize,
+    pub region: DigitalOceanRegion,
+    pub image: DigitalOceanImage,
+    pub networks: DigitalOceanNetworks,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct DigitalOceanDropletSize {
+    pub price_monthly: Option<f64>,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct DigitalOceanNetworks {
+    #[serde(default)]
+    pub v4: Vec<DigitalOceanNetwork>,
+    #[serde(default)]
+    pub v6: Vec<DigitalOceanNetwork>,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct DigitalOceanNetwork {
+    pub ip_address: String,
+    /// `public` or `private`
+    #[serde(rename = "type")]
+    pub network_type: String,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct DigitalOceanSize {
+    pub slug: String,
+    #[serde(default)]
+    pub description: String,
+    /// MiB
+    pub memory: i64,
+    pub vcpus: i32,
+    /// GiB
+    pub disk: i32,
+    pub price_monthly: f64,
+    /// Region slugs the size can be created in
+    pub regions: Vec<String>,
+    pub available: bool,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct DigitalOceanRegion {
+    pub slug: String,
+    pub name: String,
+    #[serde(default)]
+    pub available: bool,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct DigitalOceanImage {
+    pub name: String,
+    #[serde(default)]
+    pub distribution: String,
+    /// Only public images have a slug
+    pub slug: Option<String>,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct DigitalOceanAction {
+    pub id: i64,
+    /// `in-progress`, `completed` or `errored`
+    pub status: String,
+    pub started_at: Option<String>,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct DigitalOceanSnapshot {
+    pub id: i64,
+    pub name: String,
+    pub created_at: Option<String>,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct DigitalOceanDropletResponse {
+    pub droplet: DigitalOceanDroplet,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct DigitalOceanDropletsResponse {
+    pub droplets: Vec<DigitalOceanDroplet>,
+    pub links: Option<DigitalOceanLinks>,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct DigitalOceanSizesResponse {
+    pub sizes: Vec<DigitalOceanSize>,
+    pub links: Option<DigitalOceanLinks>,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct DigitalOceanRegionsResponse {
+    pub regions: Vec<DigitalOceanRegion>,
+    pub links: Option<DigitalOceanLinks>,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct DigitalOceanImagesResponse {
+    pub images: Vec<DigitalOceanImage>,
+    pub links: Option<DigitalOceanLinks>,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct DigitalOceanSnapshotsResponse {
+    pub snapshots: Vec<DigitalOceanSnapshot>,
+    pub links: Option<DigitalOceanLinks>,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct DigitalOceanActionResponse {
+    pub action: DigitalOceanAction,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct DigitalOceanLinks {
+    pub pages: Option<DigitalOceanPages>,
+}
+
+/// Only present while there are more pages
+#[derive(Debug, Deserialize)]
+pub struct DigitalOceanPages {
+    pub next: Option<String>,
+}
+
+#[derive(Debug, Serialize)]
+pub struct DigitalOceanCreateDropletRequest {
+    pub name: String,
+    pub region: String,
+    pub size: String,
+    pub image: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub ssh_keys: Option<Vec<String>>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub user_data: Option<String>,
+    pub ipv6: bool,
+}
+
+#[derive(Debug, Serialize)]
+pub struct DigitalOceanActionRequest {
+    #[serde(rename = "type")]
+    pub action_type: String,
+    /// Snapshot name, for `snapshot` actions
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub name: Option<String>,
+}
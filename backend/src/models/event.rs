@@ -0,0 +1,73 @@
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use uuid::Uuid;
+
+/// Live update types pushed over `/api/events`; the value is the SSE `event:` name
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq)]
+pub enum EventKind {
+    #[serde(rename = "vps.status")]
+    VpsStatus,
+    #[serde(rename = "vps.action")]
+    VpsAction,
+    #[serde(rename = "metrics.sample")]
+    MetricsSample,
+    #[serde(rename = "alert")]
+    Alert,
+    #[serde(rename = "activity")]
+    Activity,
+}
+
+impl EventKind {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            EventKind::VpsStatus => "vps.status",
+            EventKind::VpsAction => "vps.action",
+            EventKind::MetricsSample => "metrics.sample",
+            EventKind::Alert => "alert",
+            EventKind::Activity => "activity",
+        }
+    }
+}
+
+impl std::str::FromStr for EventKind {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "vps.status" => Ok(EventKind::VpsStatus),
+            "vps.action" => Ok(EventKind::VpsAction),
+            "metrics.sample" => Ok(EventKind::MetricsSample),
+            "alert" => Ok(EventKind::Alert),
+            "activity" => Ok(EventKind::Activity),
+            _ => Err(format!("Invalid event type: {}", s)),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Event {
+    pub id: Uuid,
+    pub kind: EventKind,
+    /// Owner of the resource; subscribers only see events inside their scope
+    pub user_id: Uuid,
+    pub data: serde_json::Value,
+    pub occurred_at: DateTime<Utc>,
+}
+
+impl Event {
+    pub fn new(kind: EventKind, user_id: Uuid, data: serde_json::Value) -> Self {
+        Event {
+            id: Uuid::new_v4(),
+            kind,
+            user_id,
+            data,
+            occurred_at: Utc::now(),
+        }
+    }
+}
+
+#[derive(Debug, Deserialize)]
+pub struct EventsQuery {
+    /// Comma-separated event types to receive; omit for all
+    pub types: Option<String>,
+}
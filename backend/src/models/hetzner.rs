@@ -0,0 +1,162 @@
+use serde::{Deserialize, Serialize};
+
+// Hetzner Cloud API request and response models
+#[derive(Debug, Deserialize, Serialize)]
+pub struct HetznerServer {
+    pub id: i64,
+    pub name: String,
+    pub status: String,
+    pub public_net: HetznerPublicNet,
+    pub server_type: HetznerServerType,
+    pub datacenter: HetznerDatacenter,
+    /// `None` when the server was created from an image that has since been deleted
+    pub image: Option<HetznerImage>,
+    pub created: String,
+}
+
+#[derive(Debug, Deserialize, Serialize)]
+pub struct HetznerPublicNet {
+    pub ipv4: Option<HetznerIpv4>,
+    pub ipv6: Option<HetznerIpv6>,
+}
+
+#[derive(Debug, Deserialize, Serialize)]
+pub struct HetznerIpv4 {
+    pub ip: String,
+}
+
+#[derive(Debug, Deserialize, Serialize)]
+pub struct HetznerIpv6 {
+    pub ip: String,
+}
+
+#[derive(Debug, Deserialize, Serialize)]
+pub struct HetznerServerType {
+    pub name: String,
+    #[serde(default)]
+    pub description: String,
+    pub cores: i32,
+    pub memory: f64,
+    pub disk: i32,
+    pub prices: Vec<HetznerPrice>,
+}
+
+#[derive(Debug, Deserialize, Serialize)]
+pub struct HetznerPrice {
+    pub location: String,
+    pub price_monthly: HetznerPriceDetails,
+}
+
+#[derive(Debug, Deserialize, Serialize)]
+pub struct HetznerPriceDetails {
+    pub gross: String,
+    pub net: String,
+}
+
+#[derive(Debug, Deserialize, Serialize)]
+pub struct HetznerDatacenter {
+    pub name: String,
+    pub location: HetznerLocation,
+}
+
+#[derive(Debug, Deserialize, Serialize)]
+pub struct HetznerLocation {
+    pub name: String,
+    #[serde(default)]
+    pub description: String,
+    pub city: String,
+    pub country: String,
+}
+
+#[derive(Debug, Deserialize, Serialize)]
+pub struct HetznerImage {
+    pub id: i64,
+    /// Only system images have a name; snapshots and backups are identified by id
+    pub name: Option<String>,
+    pub description: String,
+    pub created: Option<String>,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct HetznerServerResponse {
+    pub server: HetznerServer,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct HetznerServersResponse {
+    pub servers: Vec<HetznerServer>,
+    pub meta: Option<HetznerMeta>,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct HetznerServerTypesResponse {
+    pub server_types: Vec<HetznerServerType>,
+    pub meta: Option<HetznerMeta>,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct HetznerLocationsResponse {
+    pub locations: Vec<HetznerLocation>,
+    pub meta: Option<HetznerMeta>,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct HetznerImagesResponse {
+    pub images: Vec<HetznerImage>,
+    pub meta: Option<HetznerMeta>,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct HetznerImageResponse {
+    pub image: HetznerImage,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct HetznerAction {
+    pub id: i64,
+    /// `running`, `success` or `error`
+    pub status: String,
+    pub progress: i32,
+    pub error: Option<HetznerActionError>,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct HetznerActionError {
+    pub code: String,
+    pub message: String,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct HetznerActionResponse {
+    pub action: HetznerAction,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct HetznerMeta {
+    pub pagination: HetznerPagination,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct HetznerPagination {
+    pub next_page: Option<u32>,
+}
+
+#[derive(Debug, Serialize)]
+pub struct HetznerCreateServerRequest {
+    pub name: String,
+    pub server_type: String,
+    pub location: String,
+    pub image: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub ssh_keys: Option<Vec<String>>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub user_data: Option<String>,
+    pub start_after_create: bool,
+}
+
+#[derive(Debug, Serialize)]
+pub struct HetznerCreateImageRequest {
+    #[serde(rename = "type")]
+    pub image_type: String,
+    pub description: String,
+}
@@ -0,0 +1,204 @@
+use axum::{
+    http::{HeaderName, HeaderValue},
+    response::{IntoResponse, Response},
+    Json,
+};
+use serde::{Deserialize, Serialize};
+use validator::Validate;
+use crate::utils::errors::AppError;
+
+/// Number of rows matching a list request before `limit`/`offset` are applied
+pub const TOTAL_COUNT_HEADER: HeaderName = HeaderName::from_static("x-total-count");
+
+const DEFAULT_PAGE_SIZE: i64 = 50;
+
+#[derive(Debug, Clone, Copy, Deserialize, Default, PartialEq)]
+#[serde(rename_all = "lowercase")]
+pub enum SortOrder {
+    Asc,
+    #[default]
+    Desc,
+}
+
+impl SortOrder {
+    pub fn as_sql(&self) -> &'static str {
+        match self {
+            SortOrder::Asc => "ASC",
+            SortOrder::Desc => "DESC",
+        }
+    }
+}
+
+/// Query string shared by the server, VPS and user lists; each list ignores filters it has no column for
+#[derive(Debug, Deserialize, Validate)]
+pub struct ListQuery {
+    #[validate(range(min = 1, max = 500, message = "Must be between 1 and 500"))]
+    pub limit: Option<i64>,
+    #[validate(range(min = 0, message = "Must not be negative"))]
+    pub offset: Option<i64>,
+    /// Case-insensitive substring match on the list's text columns
+    #[validate(length(max = 255, message = "Must be at most 255 characters"))]
+    pub search: Option<String>,
+    /// Column to sort by; each list has its own whitelist
+    pub sort: Option<String>,
+    #[serde(default)]
+    pub order: SortOrder,
+    pub status: Option<String>,
+    pub server_type: Option<String>,
+    pub location: Option<String>,
+    pub role: Option<String>,
+}
+
+/// Empty values and `all` (the UI's "any" option) do not filter
+fn filter(value: &Option<String>) -> Option<&str> {
+    value
+        .as_deref()
+        .map(str::trim)
+        .filter(|value| !value.is_empty() && *value != "all")
+}
+
+impl ListQuery {
+    pub fn limit(&self) -> i64 {
+        self.limit.unwrap_or(DEFAULT_PAGE_SIZE)
+    }
+
+    pub fn offset(&self) -> i64 {
+        self.offset.unwrap_or(0)
+    }
+
+    pub fn status(&self) -> Option<&str> {
+        filter(&self.status)
+    }
+
+    pub fn server_type(&self) -> Option<&str> {
+        filter(&self.server_type)
+    }
+
+    pub fn location(&self) -> Option<&str> {
+        filter(&self.location)
+    }
+
+    pub fn role(&self) -> Option<&str> {
+        filter(&self.role)
+    }
+
+    /// `ILIKE` pattern for `search`, with the user's own `%`, `_` and `\` matched literally
+    pub fn search_pattern(&self) -> Option<String> {
+        let search = self.search.as_deref().map(str::trim).filter(|s| !s.is_empty())?;
+        let escaped = search
+            .replace('\\', "\\\\")
+            .replace('%', "\\%")
+            .replace('_', "\\_");
+        Some(format!("%{}%", escaped))
+    }
+
+    /// `ORDER BY` body for `sort`, which must be one of `allowed`; ties fall back to `id` so pages are stable
+    pub fn order_by(&self, allowed: &[&str], default: &str) -> Result<String, AppError> {
+        let column = match self.sort.as_deref() {
+            None | Some("") => default,
+            Some(sort) => allowed.iter().copied().find(|column| *column == sort).ok_or_else(|| {
+                AppError::BadRequest(format!("Cannot sort by {}; expected one of: {}", sort, allowed.join(", ")))
+            })?,
+        };
+
+        let order = self.order.as_sql();
+        Ok(format!("{} {} NULLS LAST, id {}", column, order, order))
+    }
+}
+
+/// One page of a list: the rows as a JSON array, the unpaginated total in `X-Total-Count`
+pub struct Page<T> {
+    pub items: Vec<T>,
+    pub total: i64,
+}
+
+impl<T> Page<T> {
+    pub fn map<U>(self, f: impl FnMut(T) -> U) -> Page<U> {
+        Page {
+            items: self.items.into_iter().map(f).collect(),
+            total: self.total,
+        }
+    }
+}
+
+impl<T: Serialize> IntoResponse for Page<T> {
+    fn into_response(self) -> Response {
+        ([(TOTAL_COUNT_HEADER, HeaderValue::from(self.total))], Json(self.items)).into_response()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const SORT_FIELDS: [&str; 3] = ["name", "status", "created_at"];
+
+    fn query(value: serde_json::Value) -> ListQuery {
+        serde_json::from_value(value).unwrap()
+    }
+
+    #[test]
+    fn order_by_uses_the_whitelisted_column() {
+        let sorted = |value| query(value).order_by(&SORT_FIELDS, "created_at").unwrap();
+
+        assert_eq!(sorted(serde_json::json!({})), "created_at DESC NULLS LAST, id DESC");
+        assert_eq!(sorted(serde_json::json!({ "sort": "" })), "created_at DESC NULLS LAST, id DESC");
+        assert_eq!(
+            sorted(serde_json::json!({ "sort": "name", "order": "asc" })),
+            "name ASC NULLS LAST, id ASC"
+        );
+    }
+
+    #[test]
+    fn order_by_rejects_anything_off_the_whitelist() {
+        for sort in [
+            "password_hash",
+            "Name",
+            "name ",
+            "name; DROP TABLE users",
+            "name DESC, (SELECT 1)",
+            "1",
+            "status--",
+        ] {
+            let result = query(serde_json::json!({ "sort": sort })).order_by(&SORT_FIELDS, "created_at");
+            assert!(matches!(result, Err(AppError::BadRequest(_))), "{:?} was accepted", sort);
+        }
+    }
+
+    #[test]
+    fn sort_order_only_accepts_asc_or_desc() {
+        assert!(serde_json::from_value::<ListQuery>(serde_json::json!({ "order": "asc; DROP TABLE users" })).is_err());
+        assert!(serde_json::from_value::<ListQuery>(serde_json::json!({ "order": "ASC" })).is_err());
+    }
+
+    #[test]
+    fn search_wildcards_are_matched_literally() {
+        let pattern = |search: &str| query(serde_json::json!({ "search": search })).search_pattern();
+
+        assert_eq!(pattern("web").as_deref(), Some("%web%"));
+        assert_eq!(pattern("100%").as_deref(), Some("%100\\%%"));
+        assert_eq!(pattern("web_1").as_deref(), Some("%web\\_1%"));
+        assert_eq!(pattern("C:\\temp").as_deref(), Some("%C:\\\\temp%"));
+        assert_eq!(pattern("  db  ").as_deref(), Some("%db%"));
+        assert_eq!(pattern("   "), None);
+        assert_eq!(query(serde_json::json!({})).search_pattern(), None);
+    }
+
+    #[test]
+    fn empty_and_all_filters_are_ignored() {
+        let query = query(serde_json::json!({ "status": "all", "server_type": " ", "location": " fsn1 " }));
+
+        assert_eq!(query.status(), None);
+        assert_eq!(query.server_type(), None);
+        assert_eq!(query.location(), Some("fsn1"));
+    }
+
+    #[test]
+    fn limits_are_bounded() {
+        assert!(query(serde_json::json!({ "limit": 500 })).validate().is_ok());
+        assert!(query(serde_json::json!({ "limit": 501 })).validate().is_err());
+        assert!(query(serde_json::json!({ "limit": 0 })).validate().is_err());
+        assert!(query(serde_json::json!({ "offset": -1 })).validate().is_err());
+        assert_eq!(query(serde_json::json!({})).limit(), DEFAULT_PAGE_SIZE);
+    }
+}
@@ -1,26 +1,51 @@
 use crate::config::Config;
 use crate::database::DbPool;
-use crate::services::vps_service::HetznerClient;
+use crate::middleware::rate_limit::RateLimiters;
+use crate::services::{cloud_service::CloudProviders, event_service::EventBus, mail_service::MailSender};
 use std::sync::Arc;
 
 pub mod user;
 pub mod server;
 pub mod auth;
 pub mod vps;
+pub mod website;
+pub mod api_key;
+pub mod alert;
+pub mod notification;
+pub mod dashboard;
+pub mod event;
+pub mod audit;
+pub mod provider;
+pub mod hetzner;
+pub mod digitalocean;
+pub mod list;
 
 #[derive(Clone)]
 pub struct AppState {
     pub db: DbPool,
     pub config: Arc<Config>,
-    pub hetzner_client: Arc<HetznerClient>,
+    pub providers: Arc<CloudProviders>,
+    pub rate_limiters: Arc<RateLimiters>,
+    pub mailer: Arc<dyn MailSender>,
+    pub events: Arc<EventBus>,
 }
 
 impl AppState {
-    pub fn new(db: DbPool, config: Config, hetzner_client: HetznerClient) -> Self {
+    pub fn new(
+        db: DbPool,
+        config: Config,
+        mailer: Arc<dyn MailSender>,
+    ) -> Self {
+        // With LISTEN/NOTIFY every instance's subscribers see events published by any instance
+        let events = EventBus::new(config.events_pg_notify.then(|| db.clone()));
+
         Self {
             db,
+            rate_limiters: Arc::new(RateLimiters::new(&config)),
+            providers: Arc::new(CloudProviders::new(&config)),
             config: Arc::new(config),
-            hetzner_client: Arc::new(hetzner_client),
+            mailer,
+            events: Arc::new(events),
         }
     }
 }
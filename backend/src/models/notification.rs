@@ -0,0 +1,323 @@
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use sqlx::{types::Json, FromRow};
+use uuid::Uuid;
+use validator::{Validate, ValidationErrors};
+use crate::{
+    models::alert::{Alert, AlertSeverity, AlertState},
+    utils::crypto::mask_secret,
+};
+
+/// Events a channel can subscribe to
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq)]
+pub enum NotificationEvent {
+    #[serde(rename = "alert.firing")]
+    AlertFiring,
+    #[serde(rename = "alert.resolved")]
+    AlertResolved,
+    #[serde(rename = "vps.created")]
+    VpsCreated,
+    #[serde(rename = "auth.new_ip_login")]
+    NewIpLogin,
+    /// Sent by the channel test endpoint; always delivered
+    #[serde(rename = "test")]
+    Test,
+}
+
+impl NotificationEvent {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            NotificationEvent::AlertFiring => "alert.firing",
+            NotificationEvent::AlertResolved => "alert.resolved",
+            NotificationEvent::VpsCreated => "vps.created",
+            NotificationEvent::NewIpLogin => "auth.new_ip_login",
+            NotificationEvent::Test => "test",
+        }
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, Validate)]
+pub struct EmailChannel {
+    #[validate(email(message = "Must be a valid email address"))]
+    pub address: String,
+}
+
+/// Generic webhook; bodies are signed with HMAC-SHA256 over `<timestamp>.<body>`
+#[derive(Debug, Clone, Serialize, Deserialize, Validate)]
+pub struct WebhookChannel {
+    #[validate(url(message = "Must be a valid URL"))]
+    pub url: String,
+    #[validate(length(min = 16, max = 255, message = "Must be between 16 and 255 characters"))]
+    pub secret: String,
+}
+
+/// Slack or Mattermost incoming webhook
+#[derive(Debug, Clone, Serialize, Deserialize, Validate)]
+pub struct SlackChannel {
+    #[validate(url(message = "Must be a valid URL"))]
+    pub url: String,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, Validate)]
+pub struct TelegramChannel {
+    #[validate(length(min = 1, max = 255, message = "Must be between 1 and 255 characters"))]
+    pub bot_token: String,
+    #[validate(length(min = 1, max = 64, message = "Must be between 1 and 64 characters"))]
+    pub chat_id: String,
+}
+
+/// Destination of a channel: `{"kind": "webhook", "config": {"url": ..., "secret": ...}}`
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(tag = "kind", content = "config", rename_all = "snake_case")]
+pub enum ChannelConfig {
+    Email(EmailChannel),
+    Webhook(WebhookChannel),
+    Slack(SlackChannel),
+    Telegram(TelegramChannel),
+}
+
+impl ChannelConfig {
+    pub fn kind(&self) -> &'static str {
+        match self {
+            ChannelConfig::Email(_) => "email",
+            ChannelConfig::Webhook(_) => "webhook",
+            ChannelConfig::Slack(_) => "slack",
+            ChannelConfig::Telegram(_) => "telegram",
+        }
+    }
+
+    /// Copy safe to return to clients: secrets and tokens are masked
+    pub fn redacted(&self) -> ChannelConfig {
+        match self {
+            ChannelConfig::Webhook(webhook) => ChannelConfig::Webhook(WebhookChannel {
+                url: webhook.url.clone(),
+                secret: mask_secret(&webhook.secret),
+            }),
+            ChannelConfig::Telegram(telegram) => ChannelConfig::Telegram(TelegramChannel {
+                bot_token: mask_secret(&telegram.bot_token),
+                chat_id: telegram.chat_id.clone(),
+            }),
+            other => other.clone(),
+        }
+    }
+
+    /// Destination of the same kind built from an update's `config`; secrets sent back
+    /// masked, as `redacted` returns them, keep their current value
+    pub fn updated(&self, config: serde_json::Value) -> Result<ChannelConfig, serde_json::Error> {
+        let mut updated: ChannelConfig = serde_json::from_value(serde_json::json!({
+            "kind": self.kind(),
+            "config": config,
+        }))?;
+
+        match (&mut updated, self) {
+            (ChannelConfig::Webhook(new), ChannelConfig::Webhook(current))
+                if new.secret == mask_secret(&current.secret) =>
+            {
+                new.secret = current.secret.clone();
+            }
+            (ChannelConfig::Telegram(new), ChannelConfig::Telegram(current))
+                if new.bot_token == mask_secret(&current.bot_token) =>
+            {
+                new.bot_token = current.bot_token.clone();
+            }
+            _ => {}
+        }
+
+        Ok(updated)
+    }
+}
+
+impl Validate for ChannelConfig {
+    fn validate(&self) -> Result<(), ValidationErrors> {
+        match self {
+            ChannelConfig::Email(config) => config.validate(),
+            ChannelConfig::Webhook(config) => config.validate(),
+            ChannelConfig::Slack(config) => config.validate(),
+            ChannelConfig::Telegram(config) => config.validate(),
+        }
+    }
+}
+
+#[derive(Debug, Clone, FromRow)]
+pub struct NotificationChannel {
+    pub id: Uuid,
+    pub user_id: Uuid,
+    pub name: String,
+    pub kind: String,
+    pub config: Json<ChannelConfig>,
+    pub event_types: Vec<String>,
+    pub min_severity: String,
+    pub enabled: bool,
+    pub created_at: DateTime<Utc>,
+    pub updated_at: DateTime<Utc>,
+}
+
+impl NotificationChannel {
+    pub fn get_min_severity(&self) -> AlertSeverity {
+        self.min_severity.parse().unwrap_or(AlertSeverity::Info)
+    }
+
+    /// Whether this channel wants `notification`, by event type and severity
+    pub fn accepts(&self, notification: &Notification) -> bool {
+        if notification.event == NotificationEvent::Test {
+            return true;
+        }
+
+        self.enabled
+            && (self.event_types.is_empty()
+                || self.event_types.iter().any(|event| event == notification.event.as_str()))
+            && notification.severity >= self.get_min_severity()
+    }
+}
+
+#[derive(Debug, Serialize)]
+pub struct NotificationChannelResponse {
+    pub id: Uuid,
+    pub name: String,
+    #[serde(flatten)]
+    pub config: ChannelConfig,
+    pub event_types: Vec<String>,
+    pub min_severity: String,
+    pub enabled: bool,
+    pub created_at: DateTime<Utc>,
+    pub updated_at: DateTime<Utc>,
+}
+
+impl From<NotificationChannel> for NotificationChannelResponse {
+    fn from(channel: NotificationChannel) -> Self {
+        NotificationChannelResponse {
+            id: channel.id,
+            name: channel.name,
+            config: channel.config.redacted(),
+            event_types: channel.event_types,
+            min_severity: channel.min_severity,
+            enabled: channel.enabled,
+            created_at: channel.created_at,
+            updated_at: channel.updated_at,
+        }
+    }
+}
+
+#[derive(Debug, Deserialize, Validate)]
+pub struct CreateNotificationChannel {
+    #[validate(length(min = 1, max = 255, message = "Must be between 1 and 255 characters"))]
+    pub name: String,
+    #[serde(flatten)]
+    #[validate(nested)]
+    pub config: ChannelConfig,
+    /// Empty subscribes to every event
+    #[serde(default)]
+    pub event_types: Vec<NotificationEvent>,
+    #[serde(default = "default_min_severity")]
+    pub min_severity: AlertSeverity,
+    #[serde(default = "default_enabled")]
+    pub enabled: bool,
+}
+
+fn default_min_severity() -> AlertSeverity {
+    AlertSeverity::Info
+}
+
+fn default_enabled() -> bool {
+    true
+}
+
+/// Routing and naming changes, and a new destination of the channel's kind
+#[derive(Debug, Deserialize, Validate)]
+pub struct UpdateNotificationChannel {
+    #[validate(length(min = 1, max = 255, message = "Must be between 1 and 255 characters"))]
+    pub name: Option<String>,
+    /// Shaped like `config` on create; checked against the channel's kind by `ChannelConfig::updated`
+    pub config: Option<serde_json::Value>,
+    pub event_types: Option<Vec<NotificationEvent>>,
+    pub min_severity: Option<AlertSeverity>,
+    pub enabled: Option<bool>,
+}
+
+/// Something worth telling a user about, rendered per channel
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Notification {
+    pub event: NotificationEvent,
+    pub severity: AlertSeverity,
+    pub title: String,
+    pub message: String,
+    /// Event-specific identifiers (alert, server, VPS, IP address)
+    pub data: serde_json::Value,
+    pub occurred_at: DateTime<Utc>,
+}
+
+impl From<&Alert> for Notification {
+    fn from(alert: &Alert) -> Self {
+        let severity = alert.severity.parse().unwrap_or_default();
+        let (event, title, occurred_at) = match alert.get_state() {
+            AlertState::Resolved => (
+                NotificationEvent::AlertResolved,
+                "Alert resolved",
+                alert.resolved_at.unwrap_or(alert.updated_at),
+            ),
+            _ => (
+                NotificationEvent::AlertFiring,
+                "Alert firing",
+                alert.fired_at.unwrap_or(alert.updated_at),
+            ),
+        };
+
+        Notification {
+            event,
+            severity,
+            title: title.to_string(),
+            message: alert.message.clone(),
+            data: serde_json::json!({
+                "alert_id": alert.id,
+                "kind": alert.kind,
+                "rule_id": alert.rule_id,
+                "server_id": alert.server_id,
+                "vps_id": alert.vps_id,
+                "value": alert.value,
+            }),
+            occurred_at,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq)]
+#[serde(rename_all = "lowercase")]
+pub enum DeliveryStatus {
+    Pending,
+    Delivered,
+    Failed,
+}
+
+impl DeliveryStatus {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            DeliveryStatus::Pending => "pending",
+            DeliveryStatus::Delivered => "delivered",
+            DeliveryStatus::Failed => "failed",
+        }
+    }
+}
+
+#[derive(Debug, Clone, Serialize, FromRow)]
+pub struct NotificationDelivery {
+    pub id: Uuid,
+    pub channel_id: Uuid,
+    pub user_id: Uuid,
+    pub event_type: String,
+    pub severity: String,
+    pub payload: Json<Notification>,
+    pub status: String,
+    pub attempts: i32,
+    pub next_attempt_at: DateTime<Utc>,
+    pub last_error: Option<String>,
+    pub delivered_at: Option<DateTime<Utc>>,
+    pub created_at: DateTime<Utc>,
+    pub updated_at: DateTime<Utc>,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct DeliveriesQuery {
+    pub channel_id: Option<Uuid>,
+    pub status: Option<DeliveryStatus>,
+    pub limit: Option<i64>,
+}
@@ -0,0 +1,347 @@
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use sqlx::FromRow;
+use uuid::Uuid;
+use validator::{Validate, ValidationErrors};
+use crate::{
+    models::vps::VpsStatus,
+    utils::{crypto::mask_secret, validation::invalid},
+};
+
+/// Cloud a VPS lives in; stored in `vps.provider` and `provider_credentials.provider`
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, Eq, Hash, Default)]
+#[serde(rename_all = "lowercase")]
+pub enum ProviderKind {
+    #[default]
+    Hetzner,
+    DigitalOcean,
+}
+
+impl ProviderKind {
+    pub const ALL: [ProviderKind; 2] = [ProviderKind::Hetzner, ProviderKind::DigitalOcean];
+
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            ProviderKind::Hetzner => "hetzner",
+            ProviderKind::DigitalOcean => "digitalocean",
+        }
+    }
+
+    pub fn label(&self) -> &'static str {
+        match self {
+            ProviderKind::Hetzner => "Hetzner",
+            ProviderKind::DigitalOcean => "DigitalOcean",
+        }
+    }
+}
+
+impl std::str::FromStr for ProviderKind {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "hetzner" => Ok(ProviderKind::Hetzner),
+            "digitalocean" => Ok(ProviderKind::DigitalOcean),
+            _ => Err(format!("Invalid cloud provider: {}", s)),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq)]
+#[serde(rename_all = "lowercase")]
+pub enum ActionStatus {
+    Running,
+    Success,
+    Error,
+}
+
+impl ActionStatus {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            ActionStatus::Running => "running",
+            ActionStatus::Success => "success",
+            ActionStatus::Error => "error",
+        }
+    }
+}
+
+/// An asynchronous provider operation such as a power action
+#[derive(Debug, Clone)]
+pub struct CloudAction {
+    pub external_id: String,
+    pub status: ActionStatus,
+    /// 0-100
+    pub progress: i32,
+    pub error: Option<String>,
+}
+
+/// A server as reported by its provider, in provider-neutral terms
+#[derive(Debug, Clone, Serialize)]
+pub struct CloudServer {
+    /// The provider's own id, e.g. a Hetzner server or DigitalOcean droplet id
+    pub external_id: String,
+    pub name: String,
+    pub status: VpsStatus,
+    pub server_type: String,
+    pub location: String,
+    pub image: String,
+    pub ipv4: Option<String>,
+    pub ipv6: Option<String>,
+    pub cpu_cores: i32,
+    pub ram_gb: i32,
+    pub disk_gb: i32,
+    pub monthly_cost: Option<f64>,
+}
+
+#[derive(Debug, Clone)]
+pub struct CloudServerRequest {
+    pub name: String,
+    pub server_type: String,
+    pub location: String,
+    pub image: String,
+    pub ssh_keys: Option<Vec<String>>,
+    pub user_data: Option<String>,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct CloudSnapshot {
+    pub external_id: String,
+    pub description: String,
+    pub created_at: Option<DateTime<Utc>>,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct ServerTypePrice {
+    pub location: String,
+    pub monthly_gross: Option<f64>,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct ServerTypeOption {
+    pub name: String,
+    pub description: String,
+    pub cores: i32,
+    pub memory_gb: f64,
+    pub disk_gb: i32,
+    pub prices: Vec<ServerTypePrice>,
+}
+
+impl ServerTypeOption {
+    /// Monthly gross price in `location`; `None` where the type is not offered
+    pub fn monthly_price(&self, location: &str) -> Option<f64> {
+        self.prices
+            .iter()
+            .find(|price| price.location == location)
+            .and_then(|price| price.monthly_gross)
+    }
+
+    pub fn offered_in(&self, location: &str) -> bool {
+        self.prices.iter().any(|price| price.location == location)
+    }
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct LocationOption {
+    pub name: String,
+    pub description: String,
+    pub city: String,
+    pub country: String,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct ImageOption {
+    pub name: String,
+    pub description: String,
+}
+
+/// What a provider offers, cached by `CloudProviders::catalog`
+#[derive(Debug, Clone)]
+pub struct CloudCatalog {
+    pub server_types: Vec<ServerTypeOption>,
+    pub locations: Vec<LocationOption>,
+    pub images: Vec<ImageOption>,
+}
+
+impl CloudCatalog {
+    /// Check a create request's server type, location and image against the catalogue
+    pub fn validate(&self, server_type: &str, location: &str, image: &str) -> Result<(), ValidationErrors> {
+        let mut errors = ValidationErrors::new();
+
+        let known_location = self.locations.iter().any(|l| l.name == location);
+        if !known_location {
+            errors.add("location", invalid("catalog", "Not a location offered by this provider"));
+        }
+
+        match self.server_types.iter().find(|t| t.name == server_type) {
+            None => errors.add("server_type", invalid("catalog", "Not a server type offered by this provider")),
+            Some(option) if known_location && !option.offered_in(location) => {
+                errors.add("server_type", invalid("catalog", "Not available in the selected location"))
+            }
+            Some(_) => {}
+        }
+
+        if !self.images.iter().any(|i| i.name == image) {
+            errors.add("image", invalid("catalog", "Not an image offered by this provider"));
+        }
+
+        if errors.is_empty() {
+            Ok(())
+        } else {
+            Err(errors)
+        }
+    }
+}
+
+#[derive(Debug, Deserialize)]
+pub struct CatalogQuery {
+    /// Defaults to Hetzner
+    #[serde(default)]
+    pub provider: ProviderKind,
+    /// Only server types offered in this location
+    pub location: Option<String>,
+}
+
+/// API token for one provider, owned by a user or by a reseller on behalf of their customers
+#[derive(Debug, Clone, FromRow)]
+pub struct ProviderCredential {
+    pub user_id: Uuid,
+    pub provider: String,
+    /// Sealed with `PROVIDER_TOKEN_KEY`; only `CloudProviders` opens it
+    pub api_token: String,
+    /// First characters of the token, for display
+    pub token_hint: String,
+    pub created_at: DateTime<Utc>,
+    pub updated_at: DateTime<Utc>,
+}
+
+#[derive(Debug, Serialize)]
+pub struct ProviderCredentialResponse {
+    pub provider: String,
+    /// Masked; the token itself is never returned
+    pub api_token: String,
+    pub created_at: DateTime<Utc>,
+    pub updated_at: DateTime<Utc>,
+}
+
+impl From<ProviderCredential> for ProviderCredentialResponse {
+    fn from(credential: ProviderCredential) -> Self {
+        ProviderCredentialResponse {
+            provider: credential.provider,
+            api_token: mask_secret(&credential.token_hint),
+            created_at: credential.created_at,
+            updated_at: credential.updated_at,
+        }
+    }
+}
+
+#[derive(Debug, Deserialize, Validate)]
+pub struct SetProviderCredential {
+    #[validate(length(min = 1, max = 512, message = "Must be between 1 and 512 characters"))]
+    pub api_token: String,
+}
+
+#[derive(Debug, Deserialize, Validate)]
+pub struct CreateSnapshot {
+    #[validate(length(max = 255, message = "Must be at most 255 characters"))]
+    pub description: Option<String>,
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn server_type(name: &str, prices: &[(&str, Option<f64>)]) -> ServerTypeOption {
+        ServerTypeOption {
+            name: name.to_string(),
+            description: String::new(),
+            cores: 2,
+            memory_gb: 4.0,
+            disk_gb: 40,
+            prices: prices
+                .iter()
+                .map(|(location, monthly_gross)| ServerTypePrice {
+                    location: location.to_string(),
+                    monthly_gross: *monthly_gross,
+                })
+                .collect(),
+        }
+    }
+
+    fn catalog() -> CloudCatalog {
+        let location = |name: &str| LocationOption {
+            name: name.to_string(),
+            description: String::new(),
+            city: String::new(),
+            country: "DE".to_string(),
+        };
+
+        CloudCatalog {
+            server_types: vec![
+                server_type("cx22", &[("fsn1", Some(4.51)), ("nbg1", Some(5.05))]),
+                server_type("ccx13", &[("fsn1", Some(14.86))]),
+            ],
+            locations: vec![location("fsn1"), location("nbg1")],
+            images: vec![ImageOption { name: "ubuntu-24.04".to_string(), description: String::new() }],
+        }
+    }
+
+    /// Fields rejected by `validate`, with their messages
+    fn rejected(server_type: &str, location: &str, image: &str) -> Vec<(String, String)> {
+        let Err(errors) = catalog().validate(server_type, location, image) else {
+            return Vec::new();
+        };
+
+        let mut fields: Vec<_> = errors
+            .field_errors()
+            .into_iter()
+            .flat_map(|(field, errors)| {
+                errors.iter().map(move |error| (field.to_string(), error.message.clone().unwrap_or_default().to_string()))
+            })
+            .collect();
+        fields.sort();
+        fields
+    }
+
+    #[test]
+    fn offered_combinations_are_accepted() {
+        assert!(catalog().validate("cx22", "nbg1", "ubuntu-24.04").is_ok());
+        assert!(catalog().validate("ccx13", "fsn1", "ubuntu-24.04").is_ok());
+    }
+
+    #[test]
+    fn unknown_catalog_entries_are_rejected() {
+        let fields = |server_type, location, image| -> Vec<String> {
+            rejected(server_type, location, image).into_iter().map(|(field, _)| field).collect()
+        };
+
+        assert_eq!(fields("cx99", "nbg1", "ubuntu-24.04"), ["server_type"]);
+        assert_eq!(fields("cx22", "mars1", "ubuntu-24.04"), ["location"]);
+        assert_eq!(fields("cx22", "nbg1", "windows-95"), ["image"]);
+        assert_eq!(fields("cx99", "mars1", "windows-95"), ["image", "location", "server_type"]);
+    }
+
+    #[test]
+    fn server_types_must_be_offered_in_the_location() {
+        assert_eq!(
+            rejected("ccx13", "nbg1", "ubuntu-24.04"),
+            [("server_type".to_string(), "Not available in the selected location".to_string())],
+        );
+        // An unknown location is reported once, not again against the server type
+        assert_eq!(
+            rejected("ccx13", "mars1", "ubuntu-24.04"),
+            [("location".to_string(), "Not a location offered by this provider".to_string())],
+        );
+    }
+
+    #[test]
+    fn monthly_price_is_the_locations_own() {
+        let option = server_type("cx22", &[("fsn1", Some(4.51)), ("nbg1", Some(5.05)), ("hel1", None)]);
+
+        assert_eq!(option.monthly_price("fsn1"), Some(4.51));
+        assert_eq!(option.monthly_price("nbg1"), Some(5.05));
+        assert_eq!(option.monthly_price("hel1"), None);
+        assert!(option.offered_in("hel1"));
+        assert_eq!(option.monthly_price("ash"), None);
+        assert!(!option.offered_in("ash"));
+    }
+}
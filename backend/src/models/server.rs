@@ -1,5 +1,7 @@
 use chrono::{DateTime, Utc};
 use serde::{Deserialize, Serialize};
+use validator::Validate;
+use crate::utils::validation;
 use sqlx::FromRow;
 use uuid::Uuid;
 
@@ -54,6 +56,8 @@ pub struct Server {
     pub ram_gb: Option<i32>,
     pub disk_gb: Option<i32>,
     pub os: Option<String>,
+    /// Time of the newest metrics sample received from the server's agent
+    pub last_seen_at: Option<DateTime<Utc>>,
     pub created_at: DateTime<Utc>,
     pub updated_at: DateTime<Utc>,
 }
@@ -64,29 +68,46 @@ impl Server {
     }
 }
 
-#[derive(Debug, Deserialize)]
+#[derive(Debug, Deserialize, Validate)]
 pub struct CreateServer {
+    #[validate(length(min = 1, max = 255, message = "Must be between 1 and 255 characters"))]
     pub name: String,
+    #[validate(custom(function = "validation::hostname"))]
     pub hostname: String,
+    #[validate(custom(function = "validation::ip_address"))]
     pub ip_address: String,
+    #[validate(length(min = 1, max = 50, message = "Must be between 1 and 50 characters"))]
     pub server_type: String,
+    #[validate(length(max = 255, message = "Must be at most 255 characters"))]
     pub location: Option<String>,
+    #[validate(range(min = 1, message = "Must be at least 1"))]
     pub cpu_cores: Option<i32>,
+    #[validate(range(min = 1, message = "Must be at least 1"))]
     pub ram_gb: Option<i32>,
+    #[validate(range(min = 1, message = "Must be at least 1"))]
     pub disk_gb: Option<i32>,
+    #[validate(length(max = 100, message = "Must be at most 100 characters"))]
     pub os: Option<String>,
 }
 
-#[derive(Debug, Deserialize)]
+#[derive(Debug, Deserialize, Validate)]
 pub struct UpdateServer {
+    #[validate(length(min = 1, max = 255, message = "Must be between 1 and 255 characters"))]
     pub name: Option<String>,
+    #[validate(custom(function = "validation::hostname"))]
     pub hostname: Option<String>,
+    #[validate(custom(function = "validation::ip_address"))]
     pub ip_address: Option<String>,
     pub status: Option<ServerStatus>,
+    #[validate(length(max = 255, message = "Must be at most 255 characters"))]
     pub location: Option<String>,
+    #[validate(range(min = 1, message = "Must be at least 1"))]
     pub cpu_cores: Option<i32>,
+    #[validate(range(min = 1, message = "Must be at least 1"))]
     pub ram_gb: Option<i32>,
+    #[validate(range(min = 1, message = "Must be at least 1"))]
     pub disk_gb: Option<i32>,
+    #[validate(length(max = 100, message = "Must be at most 100 characters"))]
     pub os: Option<String>,
 }
 
@@ -97,7 +118,137 @@ pub struct ServerMetrics {
     pub cpu_usage: f32,
     pub memory_usage: f32,
     pub disk_usage: f32,
+    /// Bytes per second, derived from the agent's cumulative counters
+    pub network_in: i64,
+    pub network_out: i64,
+    pub load_1: Option<f32>,
+    pub load_5: Option<f32>,
+    pub load_15: Option<f32>,
+    pub timestamp: DateTime<Utc>,
+}
+
+/// One agent sample; network values are cumulative byte counters since boot
+#[derive(Debug, Clone, Serialize, Deserialize, Validate)]
+pub struct MetricSample {
+    #[validate(range(min = 0.0, max = 100.0, message = "Must be between 0 and 100"))]
+    pub cpu_usage: f32,
+    #[validate(range(min = 0.0, max = 100.0, message = "Must be between 0 and 100"))]
+    pub memory_usage: f32,
+    #[validate(range(min = 0.0, max = 100.0, message = "Must be between 0 and 100"))]
+    pub disk_usage: f32,
+    #[validate(range(min = 0, message = "Cannot be negative"))]
     pub network_in: i64,
+    #[validate(range(min = 0, message = "Cannot be negative"))]
     pub network_out: i64,
+    #[validate(range(min = 0.0, message = "Cannot be negative"))]
+    pub load_1: Option<f32>,
+    #[validate(range(min = 0.0, message = "Cannot be negative"))]
+    pub load_5: Option<f32>,
+    #[validate(range(min = 0.0, message = "Cannot be negative"))]
+    pub load_15: Option<f32>,
+    #[validate(length(max = 256, message = "At most 256 mounts"), nested)]
+    pub mounts: Option<Vec<MountUsage>>,
+    /// Defaults to the time of receipt
+    #[validate(custom(function = "validation::sample_timestamp"))]
+    pub timestamp: Option<DateTime<Utc>>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, Validate)]
+pub struct MountUsage {
+    #[validate(length(min = 1, max = 1024, message = "Must be between 1 and 1024 characters"))]
+    pub mount_point: String,
+    #[validate(length(min = 1, max = 255, message = "Must be between 1 and 255 characters"))]
+    pub filesystem: String,
+    #[validate(range(min = 0, message = "Cannot be negative"))]
+    pub total_bytes: i64,
+    #[validate(range(min = 0, message = "Cannot be negative"))]
+    pub used_bytes: i64,
+}
+
+/// Latest reported usage of one mount point
+#[derive(Debug, Clone, Serialize, FromRow)]
+pub struct ServerMount {
+    pub mount_point: String,
+    pub filesystem: String,
+    pub total_bytes: i64,
+    pub used_bytes: i64,
+    pub updated_at: DateTime<Utc>,
+}
+
+/// Accepted request shapes: a single sample, an array, or `{"samples": [...]}`
+#[derive(Debug, Deserialize)]
+#[serde(untagged)]
+enum MetricsPayload {
+    Wrapped { samples: Vec<MetricSample> },
+    Batch(Vec<MetricSample>),
+    Single(MetricSample),
+}
+
+#[derive(Debug, Deserialize, Validate)]
+#[serde(from = "MetricsPayload")]
+pub struct IngestMetrics {
+    #[validate(length(min = 1, max = 1000, message = "Send between 1 and 1000 samples"), nested)]
+    pub samples: Vec<MetricSample>,
+}
+
+impl From<MetricsPayload> for IngestMetrics {
+    fn from(payload: MetricsPayload) -> Self {
+        let samples = match payload {
+            MetricsPayload::Wrapped { samples } | MetricsPayload::Batch(samples) => samples,
+            MetricsPayload::Single(sample) => vec![sample],
+        };
+        IngestMetrics { samples }
+    }
+}
+
+#[derive(Debug, Serialize)]
+pub struct IngestMetricsResponse {
+    pub accepted: usize,
+    /// Samples at or before the newest one already stored
+    pub skipped: usize,
+}
+
+/// `GET /api/servers/:id/metrics` query; without `step` raw samples are returned
+#[derive(Debug, Deserialize)]
+pub struct MetricsQuery {
+    pub from: Option<DateTime<Utc>>,
+    pub to: Option<DateTime<Utc>>,
+    /// Bucket width: seconds, or a number suffixed with `s`, `m`, `h` or `d`
+    pub step: Option<String>,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct MetricAggregate {
+    pub avg: f64,
+    pub min: f64,
+    pub max: f64,
+    pub p95: f64,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct MetricBucket {
     pub timestamp: DateTime<Utc>,
+    pub samples: i64,
+    pub cpu: MetricAggregate,
+    pub memory: MetricAggregate,
+    pub disk: MetricAggregate,
+    pub network_in: MetricAggregate,
+    pub network_out: MetricAggregate,
+}
+
+#[derive(Debug, Serialize)]
+pub struct MetricSeries {
+    pub from: DateTime<Utc>,
+    pub to: DateTime<Utc>,
+    pub step_seconds: i64,
+    /// Table the buckets were computed from: `raw`, `1m`, `1h` or `1d`
+    pub resolution: String,
+    pub buckets: Vec<MetricBucket>,
+}
+
+#[derive(Debug, Serialize)]
+#[serde(untagged)]
+pub enum MetricsResponse {
+    Raw(Vec<ServerMetrics>),
+    Series(MetricSeries),
 }
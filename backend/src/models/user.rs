@@ -1,5 +1,6 @@
 use chrono::{DateTime, Utc};
 use serde::{Deserialize, Serialize};
+use validator::Validate;
 use sqlx::FromRow;
 use uuid::Uuid;
 
@@ -45,6 +46,8 @@ pub struct User {
     pub avatar_url: Option<String>,
     pub mfa_enabled: bool,
     pub mfa_secret: Option<String>,
+    pub parent_id: Option<Uuid>,
+    pub email_verified_at: Option<DateTime<Utc>>,
     pub created_at: DateTime<Utc>,
     pub updated_at: DateTime<Utc>,
 }
@@ -55,19 +58,28 @@ impl User {
     }
 }
 
-#[derive(Debug, Deserialize)]
+#[derive(Debug, Deserialize, Validate)]
 pub struct CreateUser {
+    #[validate(email(message = "Must be a valid email address"))]
     pub email: String,
+    #[validate(length(max = 1024, message = "Must be at most 1024 characters"))]
     pub password: String,
     pub role: Option<UserRole>,
+    #[validate(length(max = 255, message = "Must be at most 255 characters"))]
     pub company: Option<String>,
+    /// Reseller managing this customer; set implicitly when a reseller creates the user
+    pub parent_id: Option<Uuid>,
 }
 
-#[derive(Debug, Deserialize)]
+#[derive(Debug, Deserialize, Validate)]
 pub struct UpdateUser {
+    #[validate(email(message = "Must be a valid email address"))]
     pub email: Option<String>,
+    #[validate(length(max = 255, message = "Must be at most 255 characters"))]
     pub company: Option<String>,
+    #[validate(length(min = 1, max = 64, message = "Must be between 1 and 64 characters"))]
     pub timezone: Option<String>,
+    #[validate(url(message = "Must be a valid URL"), length(max = 2048, message = "Must be at most 2048 characters"))]
     pub avatar_url: Option<String>,
 }
 
@@ -80,6 +92,8 @@ pub struct UserResponse {
     pub timezone: Option<String>,
     pub avatar_url: Option<String>,
     pub mfa_enabled: bool,
+    pub parent_id: Option<Uuid>,
+    pub email_verified_at: Option<DateTime<Utc>>,
     pub created_at: DateTime<Utc>,
 }
 
@@ -93,7 +107,41 @@ impl From<User> for UserResponse {
             timezone: user.timezone,
             avatar_url: user.avatar_url,
             mfa_enabled: user.mfa_enabled,
+            parent_id: user.parent_id,
+            email_verified_at: user.email_verified_at,
             created_at: user.created_at,
         }
     }
 }
+
+#[derive(Debug, Clone, Serialize, Deserialize, FromRow)]
+pub struct UserQuota {
+    pub user_id: Uuid,
+    pub max_servers: Option<i32>,
+    pub max_vps: Option<i32>,
+    pub max_websites: Option<i32>,
+    pub updated_by: Option<Uuid>,
+    pub updated_at: DateTime<Utc>,
+}
+
+#[derive(Debug, Deserialize, Validate)]
+pub struct UpdateQuota {
+    #[validate(range(min = 0, message = "Cannot be negative"))]
+    pub max_servers: Option<i32>,
+    #[validate(range(min = 0, message = "Cannot be negative"))]
+    pub max_vps: Option<i32>,
+    #[validate(range(min = 0, message = "Cannot be negative"))]
+    pub max_websites: Option<i32>,
+}
+
+/// Quota limits alongside current usage; `None` limits are unlimited
+#[derive(Debug, Serialize, FromRow)]
+pub struct QuotaResponse {
+    pub user_id: Uuid,
+    pub max_servers: Option<i32>,
+    pub max_vps: Option<i32>,
+    pub max_websites: Option<i32>,
+    pub servers_used: i64,
+    pub vps_used: i64,
+    pub websites_used: i64,
+}
@@ -2,6 +2,11 @@ use serde::{Deserialize, Serialize};
 use chrono::{DateTime, Utc};
 use uuid::Uuid;
 use sqlx::FromRow;
+use validator::Validate;
+use crate::{
+    models::provider::ProviderKind,
+    utils::{errors::AppError, validation},
+};
 
 #[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
 #[serde(rename_all = "lowercase")]
@@ -43,12 +48,68 @@ impl std::str::FromStr for VpsStatus {
     }
 }
 
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum PowerAction {
+    PowerOn,
+    PowerOff,
+    Reboot,
+}
+
+impl PowerAction {
+    pub fn describe(&self) -> &'static str {
+        match self {
+            PowerAction::PowerOn => "Requested power on of",
+            PowerAction::PowerOff => "Requested power off of",
+            PowerAction::Reboot => "Requested reboot of",
+        }
+    }
+
+    /// Stored in `vps_actions.command`
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            PowerAction::PowerOn => "power_on",
+            PowerAction::PowerOff => "power_off",
+            PowerAction::Reboot => "reboot",
+        }
+    }
+
+    /// Status shown while the action runs
+    pub fn pending_status(&self) -> VpsStatus {
+        match self {
+            PowerAction::PowerOn | PowerAction::Reboot => VpsStatus::Starting,
+            PowerAction::PowerOff => VpsStatus::Stopping,
+        }
+    }
+}
+
+/// A provider action on a VPS, followed by the action poller until it succeeds or fails
+#[derive(Debug, Clone, Serialize, Deserialize, FromRow)]
+pub struct VpsAction {
+    pub id: Uuid,
+    pub vps_id: Uuid,
+    pub user_id: Uuid,
+    /// The provider's id for the action; `None` until the provider has accepted it
+    pub external_id: Option<String>,
+    pub command: String,
+    /// `ActionStatus`
+    pub status: String,
+    /// 0-100
+    pub progress: i32,
+    pub error: Option<String>,
+    pub finished_at: Option<DateTime<Utc>>,
+    pub created_at: DateTime<Utc>,
+    pub updated_at: DateTime<Utc>,
+}
+
 #[derive(Debug, Clone, Serialize, Deserialize, FromRow)]
 pub struct Vps {
     pub id: Uuid,
     pub user_id: Uuid,
     pub name: String,
-    pub hetzner_id: Option<i64>,
+    /// `ProviderKind` the server lives in
+    pub provider: String,
+    /// The provider's id for the server; `None` for rows not linked to a cloud server
+    pub external_id: Option<String>,
     pub status: String,
     pub server_type: String,
     pub location: String,
@@ -59,120 +120,70 @@ pub struct Vps {
     pub ram_gb: i32,
     pub disk_gb: i32,
     pub monthly_cost: Option<f64>,
+    /// Set by the reconciler when the provider no longer lists the server
+    pub missing_since: Option<DateTime<Utc>>,
     pub created_at: DateTime<Utc>,
     pub updated_at: DateTime<Utc>,
 }
 
 impl Vps {
-    pub fn get_status(&self) -> VpsStatus {
-        self.status.parse().unwrap_or(VpsStatus::Error)
+    pub fn get_provider(&self) -> Result<ProviderKind, AppError> {
+        self.provider.parse().map_err(AppError::InternalError)
     }
 }
 
-#[derive(Debug, Deserialize)]
+#[derive(Debug, Deserialize, Validate)]
 pub struct CreateVps {
+    /// Defaults to Hetzner
+    #[serde(default)]
+    pub provider: ProviderKind,
+    #[validate(custom(function = "validation::cloud_name"))]
     pub name: String,
+    #[validate(custom(function = "validation::cloud_identifier"))]
     pub server_type: String,
+    #[validate(custom(function = "validation::cloud_identifier"))]
     pub location: String,
+    #[validate(custom(function = "validation::cloud_identifier"))]
     pub image: String,
+    #[validate(length(max = 100, message = "At most 100 SSH keys"))]
     pub ssh_keys: Option<Vec<String>>,
+    /// Hetzner caps cloud-init user data at 32 KiB, DigitalOcean at 64 KiB
+    #[validate(length(max = 32768, message = "Must be at most 32768 bytes"))]
     pub user_data: Option<String>,
 }
 
-#[derive(Debug, Deserialize)]
-pub struct UpdateVps {
-    pub name: Option<String>,
-    pub status: Option<VpsStatus>,
-}
-
-// Hetzner API Response Models
-#[derive(Debug, Deserialize, Serialize)]
-pub struct HetznerServer {
-    pub id: i64,
+/// A cloud server found by the reconciler that no VPS row tracks, offered for adoption
+#[derive(Debug, Clone, Serialize, Deserialize, FromRow)]
+pub struct UnmanagedServer {
+    pub id: Uuid,
+    pub provider: String,
+    pub external_id: String,
+    /// Owner of the credentials it was listed with; `None` for the platform-wide token
+    pub credential_user_id: Option<Uuid>,
     pub name: String,
     pub status: String,
-    pub public_net: HetznerPublicNet,
-    pub server_type: HetznerServerType,
-    pub datacenter: HetznerDatacenter,
-    pub image: HetznerImage,
-    pub created: String,
-}
-
-#[derive(Debug, Deserialize, Serialize)]
-pub struct HetznerPublicNet {
-    pub ipv4: Option<HetznerIpv4>,
-    pub ipv6: Option<HetznerIpv6>,
-}
-
-#[derive(Debug, Deserialize, Serialize)]
-pub struct HetznerIpv4 {
-    pub ip: String,
-}
-
-#[derive(Debug, Deserialize, Serialize)]
-pub struct HetznerIpv6 {
-    pub ip: String,
-}
-
-#[derive(Debug, Deserialize, Serialize)]
-pub struct HetznerServerType {
-    pub name: String,
-    pub cores: i32,
-    pub memory: f64,
-    pub disk: i32,
-    pub prices: Vec<HetznerPrice>,
-}
-
-#[derive(Debug, Deserialize, Serialize)]
-pub struct HetznerPrice {
+    pub server_type: String,
     pub location: String,
-    pub price_monthly: HetznerPriceDetails,
-}
-
-#[derive(Debug, Deserialize, Serialize)]
-pub struct HetznerPriceDetails {
-    pub gross: String,
-    pub net: String,
-}
-
-#[derive(Debug, Deserialize, Serialize)]
-pub struct HetznerDatacenter {
-    pub name: String,
-    pub location: HetznerLocation,
-}
-
-#[derive(Debug, Deserialize, Serialize)]
-pub struct HetznerLocation {
-    pub name: String,
-    pub city: String,
-    pub country: String,
-}
-
-#[derive(Debug, Deserialize, Serialize)]
-pub struct HetznerImage {
-    pub name: String,
-    pub description: String,
-}
-
-#[derive(Debug, Deserialize)]
-pub struct HetznerServerResponse {
-    pub server: HetznerServer,
+    pub image: String,
+    pub ipv4: Option<String>,
+    pub ipv6: Option<String>,
+    pub cpu_cores: i32,
+    pub ram_gb: i32,
+    pub disk_gb: i32,
+    pub monthly_cost: Option<f64>,
+    pub first_seen_at: DateTime<Utc>,
+    pub last_seen_at: DateTime<Utc>,
 }
 
-#[derive(Debug, Deserialize)]
-pub struct HetznerServersResponse {
-    pub servers: Vec<HetznerServer>,
+#[derive(Debug, Deserialize, Validate)]
+pub struct AdoptServer {
+    /// Account to adopt into; defaults to the caller, or one of a reseller's customers
+    pub user_id: Option<Uuid>,
 }
 
-#[derive(Debug, Serialize)]
-pub struct HetznerCreateServerRequest {
-    pub name: String,
-    pub server_type: String,
-    pub location: String,
-    pub image: String,
-    #[serde(skip_serializing_if = "Option::is_none")]
-    pub ssh_keys: Option<Vec<String>>,
-    #[serde(skip_serializing_if = "Option::is_none")]
-    pub user_data: Option<String>,
-    pub start_after_create: bool,
+#[derive(Debug, Deserialize, Validate)]
+pub struct UpdateVps {
+    #[validate(custom(function = "validation::cloud_name"))]
+    pub name: Option<String>,
+    pub status: Option<VpsStatus>,
 }
@@ -0,0 +1,35 @@
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use validator::Validate;
+use crate::utils::validation;
+use sqlx::FromRow;
+use uuid::Uuid;
+
+#[derive(Debug, Clone, Serialize, Deserialize, FromRow)]
+pub struct Website {
+    pub id: Uuid,
+    pub user_id: Uuid,
+    pub server_id: Option<Uuid>,
+    pub domain: String,
+    /// `pending` until the site is deployed, then `active`
+    pub status: String,
+    pub application_type: String,
+    pub php_version: Option<String>,
+    pub document_root: Option<String>,
+    pub created_at: DateTime<Utc>,
+    pub updated_at: DateTime<Utc>,
+}
+
+#[derive(Debug, Deserialize, Validate)]
+pub struct CreateWebsite {
+    #[validate(custom(function = "validation::hostname"))]
+    pub domain: String,
+    /// Server to host the site on; must be visible to the caller
+    pub server_id: Option<Uuid>,
+    #[validate(length(min = 1, max = 50, message = "Must be between 1 and 50 characters"))]
+    pub application_type: String,
+    #[validate(length(max = 20, message = "Must be at most 20 characters"))]
+    pub php_version: Option<String>,
+    #[validate(length(max = 500, message = "Must be at most 500 characters"))]
+    pub document_root: Option<String>,
+}
@@ -0,0 +1,694 @@
+use crate::{
+    config::Config,
+    database::DbPool,
+    models::{alert::*, auth::Scope, server::ServerStatus, user::UserRole, vps::VpsStatus},
+    services::server_service,
+    utils::errors::AppError,
+};
+use chrono::{DateTime, Duration, Utc};
+use std::collections::HashMap;
+use uuid::Uuid;
+
+/// Samples older than this no longer count as the current value of a metric
+const SAMPLE_MAX_AGE_SECONDS: i64 = 300;
+
+const DEFAULT_ALERT_LIMIT: i64 = 100;
+const MAX_ALERT_LIMIT: i64 = 1000;
+
+pub async fn list_rules(db: &DbPool, scope: &Scope) -> Result<Vec<AlertRule>, AppError> {
+    let rules = sqlx::query_as::<_, AlertRule>(
+        "SELECT * FROM alert_rules
+         WHERE ($1::uuid IS NULL OR user_id = $1
+                OR ($2 AND user_id IN (SELECT id FROM users WHERE parent_id = $1)))
+         ORDER BY created_at DESC"
+    )
+    .bind(scope.owner_id())
+    .bind(scope.includes_customers())
+    .fetch_all(db)
+    .await?;
+
+    Ok(rules)
+}
+
+pub async fn get_rule(db: &DbPool, scope: &Scope, id: Uuid) -> Result<AlertRule, AppError> {
+    let rule = sqlx::query_as::<_, AlertRule>(
+        "SELECT * FROM alert_rules
+         WHERE id = $1
+           AND ($2::uuid IS NULL OR user_id = $2
+                OR ($3 AND user_id IN (SELECT id FROM users WHERE parent_id = $2)))"
+    )
+    .bind(id)
+    .bind(scope.owner_id())
+    .bind(scope.includes_customers())
+    .fetch_optional(db)
+    .await?
+    .ok_or(AppError::NotFound("Alert rule not found".to_string()))?;
+
+    Ok(rule)
+}
+
+pub async fn create_rule(
+    db: &DbPool,
+    scope: &Scope,
+    user_id: Uuid,
+    payload: CreateAlertRule,
+) -> Result<AlertRule, AppError> {
+    // Rules may only target servers the caller can see
+    if let Some(server_id) = payload.server_id {
+        server_service::get_server(db, scope, server_id).await?;
+    }
+
+    let rule = sqlx::query_as::<_, AlertRule>(
+        "INSERT INTO alert_rules (
+            id, user_id, server_id, name, metric, comparison, threshold,
+            duration_seconds, severity, enabled, created_at, updated_at
+         )
+         VALUES ($1, $2, $3, $4, $5, $6, $7, $8, $9, $10, $11, $12)
+         RETURNING *"
+    )
+    .bind(Uuid::new_v4())
+    .bind(user_id)
+    .bind(payload.server_id)
+    .bind(&payload.name)
+    .bind(payload.metric.as_str())
+    .bind(payload.comparison.as_str())
+    .bind(payload.threshold)
+    .bind(payload.duration_seconds)
+    .bind(payload.severity.as_str())
+    .bind(payload.enabled)
+    .bind(Utc::now())
+    .bind(Utc::now())
+    .fetch_one(db)
+    .await?;
+
+    Ok(rule)
+}
+
+pub async fn update_rule(
+    db: &DbPool,
+    scope: &Scope,
+    id: Uuid,
+    payload: UpdateAlertRule,
+) -> Result<AlertRule, AppError> {
+    let mut rule = get_rule(db, scope, id).await?;
+
+    if let Some(name) = payload.name {
+        rule.name = name;
+    }
+    if let Some(metric) = payload.metric {
+        rule.metric = metric.as_str().to_string();
+    }
+    if let Some(comparison) = payload.comparison {
+        rule.comparison = comparison.as_str().to_string();
+    }
+    if let Some(threshold) = payload.threshold {
+        rule.threshold = threshold;
+    }
+    if let Some(duration_seconds) = payload.duration_seconds {
+        rule.duration_seconds = duration_seconds;
+    }
+    if let Some(severity) = payload.severity {
+        rule.severity = severity.as_str().to_string();
+    }
+    if let Some(enabled) = payload.enabled {
+        rule.enabled = enabled;
+    }
+
+    let rule = sqlx::query_as::<_, AlertRule>(
+        "UPDATE alert_rules
+         SET name = $1, metric = $2, comparison = $3, threshold = $4,
+             duration_seconds = $5, severity = $6, enabled = $7, updated_at = $8
+         WHERE id = $9
+         RETURNING *"
+    )
+    .bind(&rule.name)
+    .bind(&rule.metric)
+    .bind(&rule.comparison)
+    .bind(rule.threshold)
+    .bind(rule.duration_seconds)
+    .bind(&rule.severity)
+    .bind(rule.enabled)
+    .bind(Utc::now())
+    .bind(id)
+    .fetch_one(db)
+    .await?;
+
+    Ok(rule)
+}
+
+/// Alerts raised by the rule stay as history; open ones resolve on the next evaluation
+pub async fn delete_rule(db: &DbPool, scope: &Scope, id: Uuid) -> Result<(), AppError> {
+    get_rule(db, scope, id).await?;
+
+    sqlx::query("DELETE FROM alert_rules WHERE id = $1")
+        .bind(id)
+        .execute(db)
+        .await?;
+
+    Ok(())
+}
+
+pub async fn list_alerts(db: &DbPool, scope: &Scope, query: AlertsQuery) -> Result<Vec<Alert>, AppError> {
+    let limit = query.limit.unwrap_or(DEFAULT_ALERT_LIMIT).clamp(1, MAX_ALERT_LIMIT);
+
+    let alerts = sqlx::query_as::<_, Alert>(
+        "SELECT * FROM alerts
+         WHERE ($1::uuid IS NULL OR user_id = $1
+                OR ($2 AND user_id IN (SELECT id FROM users WHERE parent_id = $1)))
+           AND ($3::varchar IS NULL OR state = $3)
+           AND ($4::uuid IS NULL OR server_id = $4)
+         ORDER BY started_at DESC
+         LIMIT $5"
+    )
+    .bind(scope.owner_id())
+    .bind(scope.includes_customers())
+    .bind(query.state.map(|state| state.as_str()))
+    .bind(query.server_id)
+    .bind(limit)
+    .fetch_all(db)
+    .await?;
+
+    Ok(alerts)
+}
+
+#[derive(sqlx::FromRow)]
+struct EnabledRule {
+    #[sqlx(flatten)]
+    rule: AlertRule,
+    owner_role: String,
+}
+
+#[derive(sqlx::FromRow)]
+struct OpenAlert {
+    id: Uuid,
+    fingerprint: String,
+    state: String,
+    started_at: DateTime<Utc>,
+}
+
+/// A condition observed for one resource during an evaluation pass
+struct Candidate {
+    fingerprint: String,
+    kind: AlertKind,
+    rule_id: Option<Uuid>,
+    user_id: Uuid,
+    server_id: Option<Uuid>,
+    vps_id: Option<Uuid>,
+    severity: String,
+    message: String,
+    value: Option<f64>,
+    /// How long the condition must hold before a pending alert fires
+    hold_for: Duration,
+}
+
+/// Evaluate user rules and built-in checks once, returning alerts that started firing or resolved
+pub async fn evaluate(db: &DbPool, config: &Config) -> Result<Vec<Alert>, AppError> {
+    let now = Utc::now();
+
+    let mut open: HashMap<String, OpenAlert> = sqlx::query_as::<_, OpenAlert>(
+        "SELECT id, fingerprint, state, started_at FROM alerts WHERE state <> $1"
+    )
+    .bind(AlertState::Resolved.as_str())
+    .fetch_all(db)
+    .await?
+    .into_iter()
+    .map(|alert| (alert.fingerprint.clone(), alert))
+    .collect();
+
+    let mut transitions = Vec::new();
+
+    let rules = sqlx::query_as::<_, EnabledRule>(
+        "SELECT r.*, u.role AS owner_role
+         FROM alert_rules r
+         JOIN users u ON u.id = r.user_id
+         WHERE r.enabled"
+    )
+    .fetch_all(db)
+    .await?;
+
+    for EnabledRule { rule, owner_role } in rules {
+        let (Some(metric), Some(comparison)) = (rule.get_metric(), rule.get_comparison()) else {
+            continue;
+        };
+        let scope = Scope::for_user(rule.user_id, &owner_role.parse().unwrap_or(UserRole::User));
+
+        // Newest recent value of the metric for every server the rule covers
+        let servers = sqlx::query_as::<_, (Uuid, Uuid, String, Option<f64>)>(&format!(
+            "SELECT s.id, s.user_id, s.name,
+                    (SELECT m.{column}::float8 FROM server_metrics m
+                     WHERE m.server_id = s.id AND m.timestamp > $4
+                     ORDER BY m.timestamp DESC
+                     LIMIT 1)
+             FROM servers s
+             WHERE ($1::uuid IS NULL OR s.id = $1)
+               AND ($2::uuid IS NULL OR s.user_id = $2
+                    OR ($3 AND s.user_id IN (SELECT id FROM users WHERE parent_id = $2)))",
+            column = metric.as_str(),
+        ))
+        .bind(rule.server_id)
+        .bind(scope.owner_id())
+        .bind(scope.includes_customers())
+        .bind(now - Duration::seconds(SAMPLE_MAX_AGE_SECONDS))
+        .fetch_all(db)
+        .await?;
+
+        for (server_id, user_id, server_name, value) in servers {
+            let candidate = Candidate {
+                fingerprint: format!("{}:{}:{}", AlertKind::Rule.as_str(), rule.id, server_id),
+                kind: AlertKind::Rule,
+                rule_id: Some(rule.id),
+                user_id,
+                server_id: Some(server_id),
+                vps_id: None,
+                severity: rule.severity.clone(),
+                message: format!(
+                    "{}: {} {} {} on {}",
+                    rule.name,
+                    metric.as_str(),
+                    comparison.symbol(),
+                    rule.threshold,
+                    server_name
+                ),
+                value,
+                hold_for: Duration::seconds(rule.duration_seconds.into()),
+            };
+            let holds = value.map(|v| comparison.holds(v, rule.threshold));
+
+            transitions.extend(apply(db, &mut open, candidate, holds, now).await?);
+        }
+    }
+
+    // Built-in: agents that stopped reporting
+    if config.alert_no_metrics_minutes > 0 {
+        let cutoff = now - Duration::minutes(config.alert_no_metrics_minutes);
+
+        // Servers that never reported count from when they were added
+        let silent = sqlx::query_as::<_, (Uuid, Uuid, String, Option<DateTime<Utc>>)>(
+            "SELECT id, user_id, name, last_seen_at FROM servers
+             WHERE COALESCE(last_seen_at, created_at) < $1 AND status <> $2"
+        )
+        .bind(cutoff)
+        .bind(ServerStatus::Maintenance.as_str())
+        .fetch_all(db)
+        .await?;
+
+        for (server_id, user_id, server_name, last_seen_at) in silent {
+            let candidate = Candidate {
+                fingerprint: format!("{}:{}", AlertKind::NoMetrics.as_str(), server_id),
+                kind: AlertKind::NoMetrics,
+                rule_id: None,
+                user_id,
+                server_id: Some(server_id),
+                vps_id: None,
+                severity: AlertSeverity::Critical.as_str().to_string(),
+                message: match last_seen_at {
+                    Some(last_seen_at) => format!(
+                        "No metrics received from {} since {}",
+                        server_name,
+                        last_seen_at.format("%Y-%m-%d %H:%M:%S UTC")
+                    ),
+                    None => format!("No metrics ever received from {}", server_name),
+                },
+                value: None,
+                hold_for: Duration::zero(),
+            };
+
+            transitions.extend(apply(db, &mut open, candidate, Some(true), now).await?);
+        }
+
+        // Ingestion flips these back to online when samples arrive again
+        sqlx::query(
+            "UPDATE servers SET status = $1, updated_at = $2
+             WHERE COALESCE(last_seen_at, created_at) < $3 AND status = $4"
+        )
+        .bind(ServerStatus::Offline.as_str())
+        .bind(now)
+        .bind(cutoff)
+        .bind(ServerStatus::Online.as_str())
+        .execute(db)
+        .await?;
+    }
+
+    // Built-in: VPS in the error state
+    let failed = sqlx::query_as::<_, (Uuid, Uuid, String)>(
+        "SELECT id, user_id, name FROM vps WHERE status = $1"
+    )
+    .bind(VpsStatus::Error.as_str())
+    .fetch_all(db)
+    .await?;
+
+    for (vps_id, user_id, vps_name) in failed {
+        let candidate = Candidate {
+            fingerprint: format!("{}:{}", AlertKind::VpsError.as_str(), vps_id),
+            kind: AlertKind::VpsError,
+            rule_id: None,
+            user_id,
+            server_id: None,
+            vps_id: Some(vps_id),
+            severity: AlertSeverity::Critical.as_str().to_string(),
+            message: format!("VPS {} is in the error state", vps_name),
+            value: None,
+            hold_for: Duration::zero(),
+        };
+
+        transitions.extend(apply(db, &mut open, candidate, Some(true), now).await?);
+    }
+
+    // Whatever was not observed this pass (condition cleared, rule disabled or deleted) closes
+    for alert in open.into_values() {
+        if let Some(resolved) = close(db, &alert, now).await? {
+            transitions.push(resolved);
+        }
+    }
+
+    Ok(transitions)
+}
+
+/// What an evaluation pass does with the alert for one fingerprint
+#[derive(Debug, PartialEq)]
+enum Step {
+    /// Nothing is open and nothing holds
+    Idle,
+    /// Raise a new alert in this state
+    Open(AlertState),
+    /// Refresh the open alert's value and message, firing it once it has been pending long enough
+    Refresh { fire: bool },
+    /// Leave a firing alert alone while its server is silent; the no-metrics check covers that
+    Keep,
+    /// Resolve the open alert
+    Close,
+}
+
+/// Pending → firing → resolved; `holds` is `None` when there is no recent data
+fn step(existing: Option<&OpenAlert>, holds: Option<bool>, hold_for: Duration, now: DateTime<Utc>) -> Step {
+    match (holds, existing) {
+        (Some(true), None) if hold_for > Duration::zero() => Step::Open(AlertState::Pending),
+        (Some(true), None) => Step::Open(AlertState::Firing),
+        (Some(true), Some(alert)) => Step::Refresh {
+            fire: alert.state == AlertState::Pending.as_str() && now - alert.started_at >= hold_for,
+        },
+        (None, Some(alert)) if alert.state == AlertState::Firing.as_str() => Step::Keep,
+        (_, Some(_)) => Step::Close,
+        (_, None) => Step::Idle,
+    }
+}
+
+/// Apply the evaluation step for one candidate, returning the alert if it started firing or resolved
+async fn apply(
+    db: &DbPool,
+    open: &mut HashMap<String, OpenAlert>,
+    candidate: Candidate,
+    holds: Option<bool>,
+    now: DateTime<Utc>,
+) -> Result<Option<Alert>, AppError> {
+    let existing = open.remove(&candidate.fingerprint);
+
+    match (step(existing.as_ref(), holds, candidate.hold_for, now), existing) {
+        (Step::Open(state), _) => {
+            let alert = sqlx::query_as::<_, Alert>(
+                "INSERT INTO alerts (
+                    id, fingerprint, kind, rule_id, user_id, server_id, vps_id, severity,
+                    state, message, value, started_at, fired_at, updated_at
+                 )
+                 VALUES ($1, $2, $3, $4, $5, $6, $7, $8, $9, $10, $11, $12, $13, $12)
+                 ON CONFLICT (fingerprint) WHERE state <> 'resolved' DO NOTHING
+                 RETURNING *"
+            )
+            .bind(Uuid::new_v4())
+            .bind(&candidate.fingerprint)
+            .bind(candidate.kind.as_str())
+            .bind(candidate.rule_id)
+            .bind(candidate.user_id)
+            .bind(candidate.server_id)
+            .bind(candidate.vps_id)
+            .bind(&candidate.severity)
+            .bind(state.as_str())
+            .bind(&candidate.message)
+            .bind(candidate.value)
+            .bind(now)
+            .bind((state == AlertState::Firing).then_some(now))
+            .fetch_optional(db)
+            .await?;
+
+            Ok(alert.filter(|alert| alert.get_state() == AlertState::Firing))
+        }
+        (Step::Refresh { fire }, Some(alert)) => {
+            let updated = sqlx::query_as::<_, Alert>(
+                "UPDATE alerts
+                 SET state = $1, fired_at = COALESCE(fired_at, $2), value = $3,
+                     severity = $4, message = $5, updated_at = $6
+                 WHERE id = $7
+                 RETURNING *"
+            )
+            .bind(if fire { AlertState::Firing.as_str() } else { alert.state.as_str() })
+            .bind(fire.then_some(now))
+            .bind(candidate.value)
+            .bind(&candidate.severity)
+            .bind(&candidate.message)
+            .bind(now)
+            .bind(alert.id)
+            .fetch_one(db)
+            .await?;
+
+            Ok(fire.then_some(updated))
+        }
+        (Step::Close, Some(alert)) => close(db, &alert, now).await,
+        _ => Ok(None),
+    }
+}
+
+/// Mark an alert resolved; only alerts that fired are reported, pending ones close without `fired_at`
+async fn close(db: &DbPool, alert: &OpenAlert, now: DateTime<Utc>) -> Result<Option<Alert>, AppError> {
+    let resolved = sqlx::query_as::<_, Alert>(
+        "UPDATE alerts SET state = $1, resolved_at = $2, updated_at = $2
+         WHERE id = $3
+         RETURNING *"
+    )
+    .bind(AlertState::Resolved.as_str())
+    .bind(now)
+    .bind(alert.id)
+    .fetch_one(db)
+    .await?;
+
+    Ok((alert.state == AlertState::Firing.as_str()).then_some(resolved))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_support::{self, delete_users, server, user};
+    use tokio::sync::Mutex;
+
+    /// `evaluate` looks at every server, so passes from different tests must not interleave
+    static EVALUATION: Mutex<()> = Mutex::const_new(());
+
+    fn open(state: AlertState, started_at: DateTime<Utc>) -> OpenAlert {
+        OpenAlert {
+            id: Uuid::new_v4(),
+            fingerprint: "rule:test".to_string(),
+            state: state.as_str().to_string(),
+            started_at,
+        }
+    }
+
+    fn config() -> Config {
+        let mut config = test_support::config();
+        config.alert_no_metrics_minutes = 10;
+        config
+    }
+
+    async fn sample(db: &DbPool, server_id: Uuid, cpu_usage: f32) {
+        sqlx::query(
+            "INSERT INTO server_metrics (server_id, cpu_usage, memory_usage, disk_usage, network_in, network_out, timestamp)
+             VALUES ($1, $2, 10, 10, 0, 0, NOW())"
+        )
+        .bind(server_id)
+        .bind(cpu_usage)
+        .execute(db)
+        .await
+        .unwrap();
+    }
+
+    async fn rule(db: &DbPool, user_id: Uuid, server_id: Uuid, duration_seconds: i32) -> Uuid {
+        let rule = CreateAlertRule {
+            server_id: Some(server_id),
+            name: "High CPU".to_string(),
+            metric: AlertMetric::CpuUsage,
+            comparison: AlertComparison::Gt,
+            threshold: 90.0,
+            duration_seconds,
+            severity: AlertSeverity::Warning,
+            enabled: true,
+        };
+        create_rule(db, &Scope::Owner(user_id), user_id, rule).await.unwrap().id
+    }
+
+    async fn alert(db: &DbPool, fingerprint: &str) -> Alert {
+        sqlx::query_as::<_, Alert>("SELECT * FROM alerts WHERE fingerprint = $1 ORDER BY started_at DESC LIMIT 1")
+            .bind(fingerprint)
+            .fetch_one(db)
+            .await
+            .unwrap()
+    }
+
+    #[test]
+    fn conditions_open_pending_or_firing_alerts() {
+        let now = Utc::now();
+
+        assert_eq!(step(None, Some(true), Duration::minutes(5), now), Step::Open(AlertState::Pending));
+        assert_eq!(step(None, Some(true), Duration::zero(), now), Step::Open(AlertState::Firing));
+        assert_eq!(step(None, Some(false), Duration::zero(), now), Step::Idle);
+        assert_eq!(step(None, None, Duration::zero(), now), Step::Idle);
+    }
+
+    #[test]
+    fn pending_alerts_fire_once_the_duration_has_passed() {
+        let now = Utc::now();
+        let hold_for = Duration::minutes(5);
+
+        let young = open(AlertState::Pending, now - Duration::minutes(4));
+        let due = open(AlertState::Pending, now - Duration::minutes(5));
+        let firing = open(AlertState::Firing, now - Duration::hours(1));
+
+        assert_eq!(step(Some(&young), Some(true), hold_for, now), Step::Refresh { fire: false });
+        assert_eq!(step(Some(&due), Some(true), hold_for, now), Step::Refresh { fire: true });
+        assert_eq!(step(Some(&firing), Some(true), hold_for, now), Step::Refresh { fire: false });
+    }
+
+    #[test]
+    fn cleared_or_silent_conditions_close_alerts() {
+        let now = Utc::now();
+        let pending = open(AlertState::Pending, now);
+        let firing = open(AlertState::Firing, now);
+
+        assert_eq!(step(Some(&pending), Some(false), Duration::zero(), now), Step::Close);
+        assert_eq!(step(Some(&pending), None, Duration::zero(), now), Step::Close);
+        assert_eq!(step(Some(&firing), Some(false), Duration::zero(), now), Step::Close);
+        assert_eq!(step(Some(&firing), None, Duration::zero(), now), Step::Keep);
+    }
+
+    #[tokio::test]
+    async fn rules_fire_after_their_duration_and_resolve() {
+        let Some(db) = test_support::database().await else { return };
+        let _evaluation = EVALUATION.lock().await;
+        let owner = user(&db, UserRole::User, None).await;
+        let server_id = server(&db, owner).await;
+        sqlx::query("UPDATE servers SET last_seen_at = NOW() WHERE id = $1").bind(server_id).execute(&db).await.unwrap();
+        let rule_id = rule(&db, owner, server_id, 300).await;
+        let fingerprint = format!("rule:{}:{}", rule_id, server_id);
+        sample(&db, server_id, 95.0).await;
+
+        let first = evaluate(&db, &config()).await.unwrap();
+        let pending = alert(&db, &fingerprint).await;
+        sqlx::query("UPDATE alerts SET started_at = started_at - INTERVAL '301 seconds' WHERE id = $1")
+            .bind(pending.id)
+            .execute(&db)
+            .await
+            .unwrap();
+        let second = evaluate(&db, &config()).await.unwrap();
+        let firing = alert(&db, &fingerprint).await;
+
+        sqlx::query("DELETE FROM server_metrics WHERE server_id = $1").bind(server_id).execute(&db).await.unwrap();
+        evaluate(&db, &config()).await.unwrap();
+        let silent = alert(&db, &fingerprint).await;
+
+        sample(&db, server_id, 10.0).await;
+        let fourth = evaluate(&db, &config()).await.unwrap();
+        let resolved = alert(&db, &fingerprint).await;
+
+        delete_users(&db, &[owner]).await;
+        assert_eq!(pending.get_state(), AlertState::Pending);
+        assert!(!first.iter().any(|alert| alert.id == pending.id));
+        assert_eq!(firing.get_state(), AlertState::Firing);
+        assert!(second.iter().any(|alert| alert.id == firing.id));
+        assert_eq!(silent.get_state(), AlertState::Firing);
+        assert_eq!(resolved.get_state(), AlertState::Resolved);
+        assert!(resolved.fired_at.is_some() && resolved.resolved_at.is_some());
+        assert!(fourth.iter().any(|alert| alert.id == resolved.id));
+    }
+
+    #[tokio::test]
+    async fn pending_alerts_that_clear_are_kept_as_resolved() {
+        let Some(db) = test_support::database().await else { return };
+        let _evaluation = EVALUATION.lock().await;
+        let owner = user(&db, UserRole::User, None).await;
+        let server_id = server(&db, owner).await;
+        sqlx::query("UPDATE servers SET last_seen_at = NOW() WHERE id = $1").bind(server_id).execute(&db).await.unwrap();
+        let rule_id = rule(&db, owner, server_id, 300).await;
+        let fingerprint = format!("rule:{}:{}", rule_id, server_id);
+        sample(&db, server_id, 95.0).await;
+
+        evaluate(&db, &config()).await.unwrap();
+        sqlx::query("DELETE FROM server_metrics WHERE server_id = $1").bind(server_id).execute(&db).await.unwrap();
+        sample(&db, server_id, 10.0).await;
+        let transitions = evaluate(&db, &config()).await.unwrap();
+        let closed = alert(&db, &fingerprint).await;
+
+        delete_users(&db, &[owner]).await;
+        assert_eq!(closed.get_state(), AlertState::Resolved);
+        assert!(closed.fired_at.is_none() && closed.resolved_at.is_some());
+        assert!(!transitions.iter().any(|alert| alert.id == closed.id));
+    }
+
+    #[tokio::test]
+    async fn servers_that_never_reported_are_flagged() {
+        let Some(db) = test_support::database().await else { return };
+        let _evaluation = EVALUATION.lock().await;
+        let owner = user(&db, UserRole::User, None).await;
+        let server_id = server(&db, owner).await;
+        sqlx::query("UPDATE servers SET created_at = NOW() - INTERVAL '1 hour' WHERE id = $1")
+            .bind(server_id)
+            .execute(&db)
+            .await
+            .unwrap();
+        let fingerprint = format!("no_metrics:{}", server_id);
+
+        let transitions = evaluate(&db, &config()).await.unwrap();
+        let silent = alert(&db, &fingerprint).await;
+        let status: String = sqlx::query_scalar("SELECT status FROM servers WHERE id = $1")
+            .bind(server_id)
+            .fetch_one(&db)
+            .await
+            .unwrap();
+
+        sqlx::query("UPDATE servers SET last_seen_at = NOW() WHERE id = $1").bind(server_id).execute(&db).await.unwrap();
+        evaluate(&db, &config()).await.unwrap();
+        let resolved = alert(&db, &fingerprint).await;
+
+        delete_users(&db, &[owner]).await;
+        assert_eq!(silent.get_state(), AlertState::Firing);
+        assert_eq!(silent.message, "No metrics ever received from test");
+        assert!(transitions.iter().any(|alert| alert.id == silent.id));
+        assert_eq!(status, ServerStatus::Offline.as_str());
+        assert_eq!(resolved.get_state(), AlertState::Resolved);
+    }
+
+    #[tokio::test]
+    async fn failed_vps_raise_an_alert() {
+        let Some(db) = test_support::database().await else { return };
+        let _evaluation = EVALUATION.lock().await;
+        let owner = user(&db, UserRole::User, None).await;
+        let vps_id: Uuid = sqlx::query_scalar(
+            "INSERT INTO vps (id, user_id, name, status, server_type, location, image, cpu_cores, ram_gb, disk_gb)
+             VALUES ($1, $2, 'broken', 'error', 'cx22', 'fsn1', 'ubuntu-24.04', 2, 4, 40)
+             RETURNING id"
+        )
+        .bind(Uuid::new_v4())
+        .bind(owner)
+        .fetch_one(&db)
+        .await
+        .unwrap();
+        let fingerprint = format!("vps_error:{}", vps_id);
+
+        evaluate(&db, &config()).await.unwrap();
+        let failed = alert(&db, &fingerprint).await;
+        sqlx::query("UPDATE vps SET status = 'running' WHERE id = $1").bind(vps_id).execute(&db).await.unwrap();
+        evaluate(&db, &config()).await.unwrap();
+        let resolved = alert(&db, &fingerprint).await;
+
+        delete_users(&db, &[owner]).await;
+        assert_eq!((failed.get_state(), failed.kind.as_str()), (AlertState::Firing, "vps_error"));
+        assert_eq!(failed.severity, AlertSeverity::Critical.as_str());
+        assert_eq!(resolved.get_state(), AlertState::Resolved);
+    }
+}
@@ -0,0 +1,108 @@
+use crate::{
+    database::DbPool,
+    models::{api_key::*, user::UserRole},
+    utils::{crypto, errors::AppError},
+};
+use chrono::{Duration, Utc};
+use uuid::Uuid;
+
+/// Marks a bearer token as an API key rather than a JWT
+pub const API_KEY_PREFIX: &str = "upk_";
+
+/// `last_used_at` is only written once per interval to keep hot keys from updating on every request
+const LAST_USED_RESOLUTION_SECONDS: i64 = 60;
+
+pub async fn list_keys(db: &DbPool, user_id: Uuid) -> Result<Vec<ApiKey>, AppError> {
+    let keys = sqlx::query_as::<_, ApiKey>(
+        "SELECT * FROM api_keys WHERE user_id = $1 AND revoked_at IS NULL ORDER BY created_at DESC"
+    )
+    .bind(user_id)
+    .fetch_all(db)
+    .await?;
+
+    Ok(keys)
+}
+
+/// Create a key of the form `upk_<prefix>_<secret>`; the full key is only returned here
+pub async fn create_key(
+    db: &DbPool,
+    user_id: Uuid,
+    payload: CreateApiKey,
+) -> Result<CreatedApiKey, AppError> {
+    if payload.name.trim().is_empty() {
+        return Err(AppError::BadRequest("API key name is required".to_string()));
+    }
+
+    if payload.expires_at.is_some_and(|expires_at| expires_at <= Utc::now()) {
+        return Err(AppError::BadRequest("API key expiry must be in the future".to_string()));
+    }
+
+    let prefix = format!("{}{}", API_KEY_PREFIX, crypto::random_token(4));
+    let key = format!("{}_{}", prefix, crypto::random_token(24));
+    let scopes: Vec<&str> = payload.scopes.iter().map(|scope| scope.as_str()).collect();
+
+    let api_key = sqlx::query_as::<_, ApiKey>(
+        "INSERT INTO api_keys (id, user_id, name, prefix, key_hash, scopes, expires_at, created_at)
+         VALUES ($1, $2, $3, $4, $5, $6, $7, $8)
+         RETURNING *"
+    )
+    .bind(Uuid::new_v4())
+    .bind(user_id)
+    .bind(payload.name.trim())
+    .bind(&prefix)
+    .bind(crypto::sha256_hex(&key))
+    .bind(&scopes)
+    .bind(payload.expires_at)
+    .bind(Utc::now())
+    .fetch_one(db)
+    .await?;
+
+    Ok(CreatedApiKey { key, api_key: api_key.into() })
+}
+
+pub async fn revoke_key(db: &DbPool, user_id: Uuid, id: Uuid) -> Result<(), AppError> {
+    let result = sqlx::query(
+        "UPDATE api_keys SET revoked_at = $1 WHERE id = $2 AND user_id = $3 AND revoked_at IS NULL"
+    )
+    .bind(Utc::now())
+    .bind(id)
+    .bind(user_id)
+    .execute(db)
+    .await?;
+
+    if result.rows_affected() == 0 {
+        return Err(AppError::NotFound("API key not found".to_string()));
+    }
+
+    Ok(())
+}
+
+/// Resolve a presented key to the key row and its owner's role, recording its use
+pub async fn authenticate(db: &DbPool, key: &str) -> Result<(ApiKey, UserRole), AppError> {
+    let api_key = sqlx::query_as::<_, ApiKey>(
+        "SELECT * FROM api_keys
+         WHERE key_hash = $1 AND revoked_at IS NULL AND (expires_at IS NULL OR expires_at > $2)"
+    )
+    .bind(crypto::sha256_hex(key))
+    .bind(Utc::now())
+    .fetch_optional(db)
+    .await?
+    .ok_or(AppError::Unauthorized("Invalid API key".to_string()))?;
+
+    let role = sqlx::query_scalar::<_, String>("SELECT role FROM users WHERE id = $1")
+        .bind(api_key.user_id)
+        .fetch_one(db)
+        .await?;
+
+    sqlx::query(
+        "UPDATE api_keys SET last_used_at = $1
+         WHERE id = $2 AND (last_used_at IS NULL OR last_used_at < $3)"
+    )
+    .bind(Utc::now())
+    .bind(api_key.id)
+    .bind(Utc::now() - Duration::seconds(LAST_USED_RESOLUTION_SECONDS))
+    .execute(db)
+    .await?;
+
+    Ok((api_key, role.parse().unwrap_or(UserRole::User)))
+}
@@ -0,0 +1,246 @@
+use crate::{
+    database::DbPool,
+    models::{
+        audit::*,
+        auth::Scope,
+        event::{Event, EventKind},
+        list::Page,
+    },
+    services::event_service::EventBus,
+    utils::errors::AppError,
+};
+use chrono::Utc;
+use serde_json::{Map, Value};
+use uuid::Uuid;
+
+const DEFAULT_LOG_LIMIT: i64 = 100;
+const MAX_LOG_LIMIT: i64 = 1000;
+const MAX_EXPORT_ROWS: i64 = 100_000;
+
+/// Fields whose values never reach the audit log
+const REDACTED_FIELDS: [&str; 8] = [
+    "password",
+    "password_hash",
+    "mfa_secret",
+    "secret",
+    "bot_token",
+    "token",
+    "key",
+    "key_hash",
+];
+
+const REDACTED: &str = "[redacted]";
+
+/// Shared WHERE clause: $1/$2 tenant scope, then category, level, user, server, from, to
+const LOG_FILTERS: &str = "
+    WHERE ($1::uuid IS NULL OR user_id = $1
+           OR ($2 AND user_id IN (SELECT id FROM users WHERE parent_id = $1)))
+      AND ($3::text IS NULL OR category = $3)
+      AND ($4::text IS NULL OR level = $4)
+      AND ($5::uuid IS NULL OR user_id = $5)
+      AND ($6::uuid IS NULL OR server_id = $6)
+      AND ($7::timestamptz IS NULL OR created_at >= $7)
+      AND ($8::timestamptz IS NULL OR created_at < $8)";
+
+/// Append an entry and push it to the owner's `/api/events` stream
+pub async fn record(db: &DbPool, events: &EventBus, entry: NewLogEntry) -> Result<LogEntry, AppError> {
+    let log = sqlx::query_as::<_, LogEntry>(
+        "INSERT INTO logs (id, level, category, message, user_id, server_id, ip_address, metadata, created_at)
+         VALUES ($1, $2, $3, $4, $5, $6, $7, $8, $9)
+         RETURNING *"
+    )
+    .bind(Uuid::new_v4())
+    .bind(entry.level.as_str())
+    .bind(&entry.category)
+    .bind(&entry.message)
+    .bind(entry.user_id)
+    .bind(entry.server_id)
+    .bind(&entry.ip_address)
+    .bind(&entry.metadata)
+    .bind(Utc::now())
+    .fetch_one(db)
+    .await?;
+
+    if let Some(user_id) = log.user_id {
+        events.publish(Event::new(EventKind::Activity, user_id, serde_json::json!(log))).await;
+    }
+
+    Ok(log)
+}
+
+/// Newest first, with the total number of matching entries
+pub async fn list_logs(db: &DbPool, scope: &Scope, query: &LogsQuery) -> Result<Page<LogEntry>, AppError> {
+    let limit = query.limit.unwrap_or(DEFAULT_LOG_LIMIT).clamp(1, MAX_LOG_LIMIT);
+    let offset = query.offset.unwrap_or(0).max(0);
+
+    let items = fetch_logs(db, scope, query, limit, offset).await?;
+
+    let total = sqlx::query_scalar::<_, i64>(&format!("SELECT COUNT(*) FROM logs {}", LOG_FILTERS))
+        .bind(scope.owner_id())
+        .bind(scope.includes_customers())
+        .bind(&query.category)
+        .bind(query.level.map(|level| level.as_str()))
+        .bind(query.user_id)
+        .bind(query.server_id)
+        .bind(query.from)
+        .bind(query.to)
+        .fetch_one(db)
+        .await?;
+
+    Ok(Page { items, total })
+}
+
+/// Every matching entry up to the export cap, for admins
+pub async fn export_logs(db: &DbPool, query: &LogsQuery) -> Result<Vec<LogEntry>, AppError> {
+    fetch_logs(db, &Scope::All, query, MAX_EXPORT_ROWS, 0).await
+}
+
+async fn fetch_logs(
+    db: &DbPool,
+    scope: &Scope,
+    query: &LogsQuery,
+    limit: i64,
+    offset: i64,
+) -> Result<Vec<LogEntry>, AppError> {
+    let logs = sqlx::query_as::<_, LogEntry>(&format!(
+        "SELECT * FROM logs {} ORDER BY created_at DESC LIMIT $9 OFFSET $10",
+        LOG_FILTERS
+    ))
+    .bind(scope.owner_id())
+    .bind(scope.includes_customers())
+    .bind(&query.category)
+    .bind(query.level.map(|level| level.as_str()))
+    .bind(query.user_id)
+    .bind(query.server_id)
+    .bind(query.from)
+    .bind(query.to)
+    .bind(limit)
+    .bind(offset)
+    .fetch_all(db)
+    .await?;
+
+    Ok(logs)
+}
+
+pub fn to_csv(logs: &[LogEntry]) -> String {
+    let mut csv = String::from("created_at,level,category,message,user_id,server_id,ip_address,metadata\n");
+
+    for log in logs {
+        let fields = [
+            log.created_at.to_rfc3339(),
+            log.level.clone(),
+            log.category.clone(),
+            log.message.clone(),
+            log.user_id.map(|id| id.to_string()).unwrap_or_default(),
+            log.server_id.map(|id| id.to_string()).unwrap_or_default(),
+            log.ip_address.clone().unwrap_or_default(),
+            log.metadata.as_ref().map(Value::to_string).unwrap_or_default(),
+        ];
+        let row: Vec<String> = fields.iter().map(|field| csv_field(field)).collect();
+        csv.push_str(&row.join(","));
+        csv.push('\n');
+    }
+
+    csv
+}
+
+/// Quote per RFC 4180 and defuse values spreadsheets would evaluate as formulas
+fn csv_field(value: &str) -> String {
+    let value = if value.starts_with(['=', '+', '-', '@']) {
+        format!("'{}", value)
+    } else {
+        value.to_string()
+    };
+
+    if value.contains([',', '"', '\n', '\r']) {
+        format!("\"{}\"", value.replace('"', "\"\""))
+    } else {
+        value
+    }
+}
+
+/// Changed top-level fields as `{"field": {"before": ..., "after": ...}}`, with secrets masked at any depth
+pub fn diff(before: &Value, after: &Value) -> Value {
+    let empty = Map::new();
+    let before_fields = before.as_object().unwrap_or(&empty);
+    let after_fields = after.as_object().unwrap_or(&empty);
+
+    let mut changes = Map::new();
+    for (field, after_value) in after_fields {
+        // Bumped on every write, so never interesting
+        if field == "updated_at" {
+            continue;
+        }
+
+        // Compare the raw values so a changed secret still shows up, masked
+        let before_value = before_fields.get(field).unwrap_or(&Value::Null);
+        if before_value == after_value {
+            continue;
+        }
+
+        let change = if REDACTED_FIELDS.contains(&field.as_str()) {
+            serde_json::json!({ "before": REDACTED, "after": REDACTED })
+        } else {
+            serde_json::json!({ "before": redact(before_value), "after": redact(after_value) })
+        };
+        changes.insert(field.clone(), change);
+    }
+
+    Value::Object(changes)
+}
+
+/// Copy of `value` with secrets masked at any depth
+pub fn redact(value: &Value) -> Value {
+    match value {
+        Value::Object(fields) => Value::Object(
+            fields
+                .iter()
+                .map(|(field, value)| {
+                    let value = if REDACTED_FIELDS.contains(&field.as_str()) {
+                        Value::String(REDACTED.to_string())
+                    } else {
+                        redact(value)
+                    };
+                    (field.clone(), value)
+                })
+                .collect(),
+        ),
+        Value::Array(items) => Value::Array(items.iter().map(redact).collect()),
+        other => other.clone(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    #[test]
+    fn diff_masks_nested_secrets() {
+        let before = json!({ "name": "alerts", "config": { "url": "https://a.example", "secret": "old-secret" } });
+        let after = json!({ "name": "alerts", "config": { "url": "https://b.example", "secret": "new-secret" } });
+
+        let changes = diff(&before, &after);
+
+        assert_eq!(changes, json!({
+            "config": {
+                "before": { "url": "https://a.example", "secret": REDACTED },
+                "after": { "url": "https://b.example", "secret": REDACTED }
+            }
+        }));
+    }
+
+    #[test]
+    fn diff_masks_changed_top_level_secrets() {
+        let changes = diff(&json!({ "token": "a", "updated_at": 1 }), &json!({ "token": "b", "updated_at": 2 }));
+
+        assert_eq!(changes, json!({ "token": { "before": REDACTED, "after": REDACTED } }));
+    }
+
+    #[test]
+    fn redact_masks_inside_arrays() {
+        let value = json!([{ "key": "k", "items": [{ "password": "p", "name": "n" }] }]);
+
+        assert_eq!(redact(&value), json!([{ "key": REDACTED, "items": [{ "password": REDACTED, "name": "n" }] }]));
+    }
+}
@@ -1,17 +1,99 @@
 use crate::{
     config::Config,
     database::DbPool,
-    models::{auth::*, user::{User, UserRole}},
-    utils::{errors::AppError, jwt, password},
+    models::{
+        alert::AlertSeverity,
+        auth::*,
+        notification::{Notification, NotificationEvent},
+        user::{User, UserRole},
+    },
+    services::{lockout_service, mfa_service, notification_service},
+    utils::{crypto, errors::AppError, jwt, password},
 };
-use chrono::Utc;
+use chrono::{Duration, Utc};
 use uuid::Uuid;
 
 pub async fn login(
     db: &DbPool,
     config: &Config,
     payload: LoginRequest,
-) -> Result<(String, User), AppError> {
+    client: &ClientInfo,
+) -> Result<(SessionTokens, User), AppError> {
+    lockout_service::ensure_not_locked(db, &payload.email).await?;
+
+    let result = authenticate(db, &payload).await;
+
+    // Known accounts signing in from an address never seen before get a heads-up
+    let new_ip = result.is_ok() && is_new_login_ip(db, &payload.email, &client.ip).await?;
+
+    // Log login attempt
+    sqlx::query(
+        "INSERT INTO login_attempts (email, ip_address, user_agent, success, attempted_at)
+         VALUES ($1, $2, $3, $4, $5)"
+    )
+    .bind(&payload.email)
+    .bind(&client.ip)
+    .bind(&client.user_agent)
+    .bind(result.is_ok())
+    .bind(Utc::now())
+    .execute(db)
+    .await?;
+
+    let user = match result {
+        Ok(user) => user,
+        Err(AppError::Unauthorized(msg)) => {
+            lockout_service::register_failure(db, config, &payload.email).await?;
+            return Err(AppError::Unauthorized(msg));
+        }
+        Err(e) => return Err(e),
+    };
+
+    lockout_service::clear(db, &payload.email).await?;
+
+    if password::needs_rehash(&user.password_hash, config) {
+        upgrade_password_hash(db, config, user.id, &payload.password).await?;
+    }
+
+    if config.require_email_verification && user.email_verified_at.is_none() {
+        return Err(AppError::Forbidden("Email address not verified".to_string()));
+    }
+
+    // Start a new session (refresh token family)
+    let tokens = issue_tokens(db, config, &user, Uuid::new_v4()).await?;
+
+    if new_ip {
+        let notification = Notification {
+            event: NotificationEvent::NewIpLogin,
+            severity: AlertSeverity::Warning,
+            title: "New sign-in location".to_string(),
+            message: format!("{} signed in from a new IP address: {}", user.email, client.ip),
+            data: serde_json::json!({ "ip_address": client.ip, "user_agent": client.user_agent }),
+            occurred_at: Utc::now(),
+        };
+        if let Err(e) = notification_service::notify(db, user.id, notification).await {
+            tracing::error!("Failed to queue login notification: {}", e);
+        }
+    }
+
+    Ok((tokens, user))
+}
+
+/// Whether the account has signed in before, but never from `ip`
+async fn is_new_login_ip(db: &DbPool, email: &str, ip: &str) -> Result<bool, AppError> {
+    let (any_success, from_ip) = sqlx::query_as::<_, (bool, bool)>(
+        "SELECT EXISTS(SELECT 1 FROM login_attempts WHERE email = $1 AND success),
+                EXISTS(SELECT 1 FROM login_attempts WHERE email = $1 AND success AND ip_address = $2)"
+    )
+    .bind(email)
+    .bind(ip)
+    .fetch_one(db)
+    .await?;
+
+    Ok(any_success && !from_ip)
+}
+
+/// Check password and, when enabled, the second factor
+async fn authenticate(db: &DbPool, payload: &LoginRequest) -> Result<User, AppError> {
     // Find user by email
     let user = sqlx::query_as::<_, User>(
         "SELECT * FROM users WHERE email = $1"
@@ -28,38 +110,237 @@ pub async fn login(
 
     // Check MFA if enabled
     if user.mfa_enabled {
-        if let Some(totp_code) = payload.totp_code {
-            let secret = user.mfa_secret.as_ref()
-                .ok_or(AppError::Unauthorized("MFA secret not found".to_string()))?;
-
-            if !verify_totp(&totp_code, secret)? {
-                return Err(AppError::Unauthorized("Invalid TOTP code".to_string()));
-            }
-        } else {
-            return Err(AppError::Unauthorized("TOTP code required".to_string()));
-        }
+        mfa_service::verify_second_factor(
+            db,
+            &user,
+            payload.totp_code.as_deref(),
+            payload.recovery_code.as_deref(),
+        )
+        .await?;
     }
 
-    // Log login attempt
+    Ok(user)
+}
+
+/// Re-hash with the current argon2 parameters while the plaintext is at hand
+async fn upgrade_password_hash(
+    db: &DbPool,
+    config: &Config,
+    user_id: Uuid,
+    plaintext: &str,
+) -> Result<(), AppError> {
+    let password_hash = password::hash_password(plaintext, config)?;
+
+    sqlx::query("UPDATE users SET password_hash = $1 WHERE id = $2")
+        .bind(&password_hash)
+        .bind(user_id)
+        .execute(db)
+        .await?;
+
+    Ok(())
+}
+
+/// Store a fresh refresh token in `family_id` and mint a matching access token
+async fn issue_tokens(
+    db: &DbPool,
+    config: &Config,
+    user: &User,
+    family_id: Uuid,
+) -> Result<SessionTokens, AppError> {
+    let refresh_token = crypto::random_token(32);
+
     sqlx::query(
-        "INSERT INTO login_attempts (email, ip_address, success, attempted_at)
-         VALUES ($1, $2, $3, $4)"
+        "INSERT INTO refresh_tokens (id, user_id, family_id, token_hash, expires_at, created_at)
+         VALUES ($1, $2, $3, $4, $5, $6)"
     )
-    .bind(&payload.email)
-    .bind("0.0.0.0") // TODO: Get real IP from request
-    .bind(true)
+    .bind(Uuid::new_v4())
+    .bind(user.id)
+    .bind(family_id)
+    .bind(crypto::sha256_hex(&refresh_token))
+    .bind(Utc::now() + Duration::seconds(config.refresh_token_expiration))
     .bind(Utc::now())
     .execute(db)
     .await?;
 
-    // Generate JWT token
-    let token = jwt::generate_token(&user, config)?;
+    let mfa_enrollment_required = mfa_service::enrollment_required(db, user).await?;
+
+    Ok(SessionTokens {
+        access_token: jwt::generate_token(user, family_id, mfa_enrollment_required, config)?,
+        refresh_token,
+    })
+}
+
+/// Rotate a refresh token. Presenting an already-rotated token is treated as theft
+/// and revokes every token in its family.
+pub async fn refresh(
+    db: &DbPool,
+    config: &Config,
+    refresh_token: &str,
+) -> Result<(SessionTokens, User), AppError> {
+    let mut tx = db.begin().await?;
+
+    let token = sqlx::query_as::<_, RefreshToken>(
+        "SELECT id, user_id, family_id, expires_at, replaced_by, revoked_at
+         FROM refresh_tokens
+         WHERE token_hash = $1
+         FOR UPDATE"
+    )
+    .bind(crypto::sha256_hex(refresh_token))
+    .fetch_optional(&mut *tx)
+    .await?
+    .ok_or(AppError::Unauthorized("Invalid refresh token".to_string()))?;
+
+    if token.revoked_at.is_some() {
+        return Err(AppError::Unauthorized("Session has been revoked".to_string()));
+    }
+
+    if token.replaced_by.is_some() {
+        sqlx::query(
+            "UPDATE refresh_tokens SET revoked_at = $1 WHERE family_id = $2 AND revoked_at IS NULL"
+        )
+        .bind(Utc::now())
+        .bind(token.family_id)
+        .execute(&mut *tx)
+        .await?;
+        tx.commit().await?;
+
+        tracing::warn!(
+            "Refresh token reuse detected for user {}; revoked session {}",
+            token.user_id,
+            token.family_id
+        );
+        return Err(AppError::Unauthorized("Refresh token reuse detected".to_string()));
+    }
+
+    if token.expires_at < Utc::now() {
+        return Err(AppError::Unauthorized("Refresh token expired".to_string()));
+    }
+
+    let user = sqlx::query_as::<_, User>("SELECT * FROM users WHERE id = $1")
+        .bind(token.user_id)
+        .fetch_optional(&mut *tx)
+        .await?
+        .ok_or(AppError::Unauthorized("Invalid refresh token".to_string()))?;
+
+    let new_id = Uuid::new_v4();
+    let new_refresh_token = crypto::random_token(32);
+
+    sqlx::query(
+        "INSERT INTO refresh_tokens (id, user_id, family_id, token_hash, expires_at, created_at)
+         VALUES ($1, $2, $3, $4, $5, $6)"
+    )
+    .bind(new_id)
+    .bind(user.id)
+    .bind(token.family_id)
+    .bind(crypto::sha256_hex(&new_refresh_token))
+    .bind(Utc::now() + Duration::seconds(config.refresh_token_expiration))
+    .bind(Utc::now())
+    .execute(&mut *tx)
+    .await?;
+
+    sqlx::query("UPDATE refresh_tokens SET replaced_by = $1 WHERE id = $2")
+        .bind(new_id)
+        .bind(token.id)
+        .execute(&mut *tx)
+        .await?;
+
+    tx.commit().await?;
 
-    Ok((token, user))
+    let mfa_enrollment_required = mfa_service::enrollment_required(db, &user).await?;
+    let tokens = SessionTokens {
+        access_token: jwt::generate_token(&user, token.family_id, mfa_enrollment_required, config)?,
+        refresh_token: new_refresh_token,
+    };
+
+    Ok((tokens, user))
+}
+
+/// Replace a session with a new one, e.g. after MFA enrollment changes what its access token claims
+pub async fn reissue_session(
+    db: &DbPool,
+    config: &Config,
+    user: &User,
+    session_id: Uuid,
+) -> Result<SessionTokens, AppError> {
+    revoke_session(db, user.id, session_id).await?;
+    issue_tokens(db, config, user, Uuid::new_v4()).await
+}
+
+/// Whether the session an access token belongs to has not been logged out or revoked
+pub async fn is_session_active(db: &DbPool, session_id: Uuid) -> Result<bool, AppError> {
+    let active = sqlx::query_scalar::<_, bool>(
+        "SELECT EXISTS(
+            SELECT 1 FROM refresh_tokens
+            WHERE family_id = $1 AND revoked_at IS NULL AND expires_at > $2
+         )"
+    )
+    .bind(session_id)
+    .bind(Utc::now())
+    .fetch_one(db)
+    .await?;
+
+    Ok(active)
+}
+
+pub async fn revoke_session(db: &DbPool, user_id: Uuid, session_id: Uuid) -> Result<(), AppError> {
+    sqlx::query(
+        "UPDATE refresh_tokens SET revoked_at = $1
+         WHERE user_id = $2 AND family_id = $3 AND revoked_at IS NULL"
+    )
+    .bind(Utc::now())
+    .bind(user_id)
+    .bind(session_id)
+    .execute(db)
+    .await?;
+
+    Ok(())
+}
+
+pub async fn revoke_all_sessions(db: &DbPool, user_id: Uuid) -> Result<(), AppError> {
+    sqlx::query(
+        "UPDATE refresh_tokens SET revoked_at = $1 WHERE user_id = $2 AND revoked_at IS NULL"
+    )
+    .bind(Utc::now())
+    .bind(user_id)
+    .execute(db)
+    .await?;
+
+    Ok(())
+}
+
+/// Change the caller's password and sign out every session, including the current one
+pub async fn change_password(
+    db: &DbPool,
+    config: &Config,
+    user_id: Uuid,
+    payload: ChangePasswordRequest,
+) -> Result<(), AppError> {
+    let user = sqlx::query_as::<_, User>("SELECT * FROM users WHERE id = $1")
+        .bind(user_id)
+        .fetch_optional(db)
+        .await?
+        .ok_or(AppError::NotFound("User not found".to_string()))?;
+
+    if !password::verify_password(&payload.current_password, &user.password_hash)? {
+        return Err(AppError::Unauthorized("Current password is incorrect".to_string()));
+    }
+
+    password::validate_password(&payload.new_password, &user.email, "new_password", config)?;
+    let password_hash = password::hash_password(&payload.new_password, config)?;
+
+    sqlx::query("UPDATE users SET password_hash = $1, updated_at = $2 WHERE id = $3")
+        .bind(&password_hash)
+        .bind(Utc::now())
+        .bind(user_id)
+        .execute(db)
+        .await?;
+
+    revoke_all_sessions(db, user_id).await
 }
 
 pub async fn register(
     db: &DbPool,
+    config: &Config,
     payload: RegisterRequest,
 ) -> Result<User, AppError> {
     // Check if user already exists
@@ -71,11 +352,13 @@ pub async fn register(
     .await?;
 
     if existing.is_some() {
-        return Err(AppError::BadRequest("Email already registered".to_string()));
+        return Err(AppError::Conflict("Email already registered".to_string()));
     }
 
+    password::validate_password(&payload.password, &payload.email, "password", config)?;
+
     // Hash password
-    let password_hash = password::hash_password(&payload.password)?;
+    let password_hash = password::hash_password(&payload.password, config)?;
 
     // Create user
     let user = sqlx::query_as::<_, User>(
@@ -97,20 +380,157 @@ pub async fn register(
     Ok(user)
 }
 
-fn verify_totp(code: &str, secret: &str) -> Result<bool, AppError> {
-    use totp_rs::{TOTP, Algorithm, Secret};
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_support::{self, delete_users};
+
+    const PASSWORD: &str = "correct horse battery staple 42";
+
+    /// Cheap hashing parameters; debug builds would otherwise spend seconds per login
+    fn config() -> Config {
+        let mut config = test_support::config();
+        config.argon2_memory_kib = 1024;
+        config.argon2_iterations = 1;
+        config
+    }
+
+    async fn account(db: &DbPool, config: &Config) -> User {
+        let payload = RegisterRequest {
+            email: format!("session-{}@example.com", Uuid::new_v4()),
+            password: PASSWORD.to_string(),
+            company: None,
+        };
+        register(db, config, payload).await.unwrap()
+    }
+
+    async fn sign_in(db: &DbPool, config: &Config, user: &User) -> SessionTokens {
+        let payload = LoginRequest {
+            email: user.email.clone(),
+            password: PASSWORD.to_string(),
+            totp_code: None,
+            recovery_code: None,
+        };
+        let client = ClientInfo { ip: "198.51.100.7".to_string(), user_agent: None };
+        login(db, config, payload, &client).await.unwrap().0
+    }
+
+    fn session_id(tokens: &SessionTokens, config: &Config) -> Uuid {
+        jwt::verify_token(&tokens.access_token, config).unwrap().sid
+    }
 
-    let secret = Secret::Encoded(secret.to_string()).to_bytes()
-        .map_err(|_| AppError::InternalError("Invalid TOTP secret".to_string()))?;
+    fn unauthorized(result: Result<(SessionTokens, User), AppError>) -> String {
+        match result {
+            Err(AppError::Unauthorized(message)) => message,
+            other => panic!("Expected 401, got {:?}", other.map(|(_, user)| user.id)),
+        }
+    }
 
-    let totp = TOTP::new(
-        Algorithm::SHA1,
-        6,
-        1,
-        30,
-        secret,
-    ).map_err(|_| AppError::InternalError("Failed to create TOTP".to_string()))?;
+    #[tokio::test]
+    async fn refreshing_rotates_the_token_within_the_session() {
+        let Some(db) = test_support::database().await else { return };
+        let config = config();
+        let user = account(&db, &config).await;
+        let first = sign_in(&db, &config, &user).await;
 
-    Ok(totp.check_current(code)
-        .map_err(|_| AppError::Unauthorized("Invalid TOTP code".to_string()))?)
+        let (second, _) = refresh(&db, &config, &first.refresh_token).await.unwrap();
+        let (third, refreshed) = refresh(&db, &config, &second.refresh_token).await.unwrap();
+        let active = is_session_active(&db, session_id(&third, &config)).await.unwrap();
+
+        delete_users(&db, &[user.id]).await;
+        assert_ne!(second.refresh_token, first.refresh_token);
+        assert_eq!(session_id(&second, &config), session_id(&first, &config));
+        assert_eq!(session_id(&third, &config), session_id(&first, &config));
+        assert_eq!(refreshed.id, user.id);
+        assert!(active);
+    }
+
+    #[tokio::test]
+    async fn reusing_a_rotated_token_revokes_the_whole_session() {
+        let Some(db) = test_support::database().await else { return };
+        let config = config();
+        let user = account(&db, &config).await;
+        let stolen = sign_in(&db, &config, &user).await;
+        let other = sign_in(&db, &config, &user).await;
+        let (current, _) = refresh(&db, &config, &stolen.refresh_token).await.unwrap();
+
+        let reuse = unauthorized(refresh(&db, &config, &stolen.refresh_token).await);
+        let after = unauthorized(refresh(&db, &config, &current.refresh_token).await);
+        let revoked = is_session_active(&db, session_id(&stolen, &config)).await.unwrap();
+        let untouched = is_session_active(&db, session_id(&other, &config)).await.unwrap();
+
+        delete_users(&db, &[user.id]).await;
+        assert_eq!(reuse, "Refresh token reuse detected");
+        assert_eq!(after, "Session has been revoked");
+        assert!(!revoked);
+        assert!(untouched);
+    }
+
+    #[tokio::test]
+    async fn expired_tokens_are_refused() {
+        let Some(db) = test_support::database().await else { return };
+        let config = config();
+        let user = account(&db, &config).await;
+        let tokens = sign_in(&db, &config, &user).await;
+        sqlx::query("UPDATE refresh_tokens SET expires_at = NOW() - INTERVAL '1 second' WHERE user_id = $1")
+            .bind(user.id)
+            .execute(&db)
+            .await
+            .unwrap();
+
+        let expired = unauthorized(refresh(&db, &config, &tokens.refresh_token).await);
+        let active = is_session_active(&db, session_id(&tokens, &config)).await.unwrap();
+
+        delete_users(&db, &[user.id]).await;
+        assert_eq!(expired, "Refresh token expired");
+        assert!(!active);
+    }
+
+    #[tokio::test]
+    async fn unknown_tokens_are_refused() {
+        let Some(db) = test_support::database().await else { return };
+        let config = config();
+
+        let unknown = unauthorized(refresh(&db, &config, "not-a-token").await);
+
+        assert_eq!(unknown, "Invalid refresh token");
+    }
+
+    #[tokio::test]
+    async fn logging_out_ends_only_that_session() {
+        let Some(db) = test_support::database().await else { return };
+        let config = config();
+        let user = account(&db, &config).await;
+        let current = sign_in(&db, &config, &user).await;
+        let other = sign_in(&db, &config, &user).await;
+
+        revoke_session(&db, user.id, session_id(&current, &config)).await.unwrap();
+        let logged_out = is_session_active(&db, session_id(&current, &config)).await.unwrap();
+        let still_signed_in = is_session_active(&db, session_id(&other, &config)).await.unwrap();
+        let refused = unauthorized(refresh(&db, &config, &current.refresh_token).await);
+
+        revoke_all_sessions(&db, user.id).await.unwrap();
+        let everywhere = is_session_active(&db, session_id(&other, &config)).await.unwrap();
+
+        delete_users(&db, &[user.id]).await;
+        assert!(!logged_out);
+        assert!(still_signed_in);
+        assert_eq!(refused, "Session has been revoked");
+        assert!(!everywhere);
+    }
+
+    #[tokio::test]
+    async fn another_users_session_cannot_be_revoked() {
+        let Some(db) = test_support::database().await else { return };
+        let config = config();
+        let victim = account(&db, &config).await;
+        let attacker = account(&db, &config).await;
+        let tokens = sign_in(&db, &config, &victim).await;
+
+        revoke_session(&db, attacker.id, session_id(&tokens, &config)).await.unwrap();
+        let active = is_session_active(&db, session_id(&tokens, &config)).await.unwrap();
+
+        delete_users(&db, &[victim.id, attacker.id]).await;
+        assert!(active);
+    }
 }
@@ -0,0 +1,409 @@
+use crate::{
+    config::Config,
+    database::DbPool,
+    models::{provider::*, vps::Vps},
+    services::{digitalocean_service::DigitalOceanClient, hetzner_service::HetznerClient},
+    utils::{
+        crypto::{self, SecretKey},
+        errors::AppError,
+    },
+};
+use async_trait::async_trait;
+use chrono::Utc;
+use std::{
+    collections::HashMap,
+    sync::Arc,
+    time::{Duration, Instant},
+};
+use tokio::sync::RwLock;
+use uuid::Uuid;
+
+const PROVIDER_TIMEOUT_SECONDS: u64 = 30;
+
+/// Operations `vps_service` needs from a cloud; ids are the provider's own (`vps.external_id`)
+#[async_trait]
+pub trait CloudProvider: Send + Sync {
+    async fn create_server(&self, request: CloudServerRequest) -> Result<CloudServer, AppError>;
+    async fn get_server(&self, external_id: &str) -> Result<CloudServer, AppError>;
+    async fn list_servers(&self) -> Result<Vec<CloudServer>, AppError>;
+    async fn delete_server(&self, external_id: &str) -> Result<(), AppError>;
+    async fn power_on(&self, external_id: &str) -> Result<CloudAction, AppError>;
+    async fn power_off(&self, external_id: &str) -> Result<CloudAction, AppError>;
+    async fn reboot(&self, external_id: &str) -> Result<CloudAction, AppError>;
+    async fn get_action(&self, external_id: &str) -> Result<CloudAction, AppError>;
+    async fn create_snapshot(&self, external_id: &str, description: &str) -> Result<CloudSnapshot, AppError>;
+    async fn list_server_types(&self) -> Result<Vec<ServerTypeOption>, AppError>;
+    async fn list_locations(&self) -> Result<Vec<LocationOption>, AppError>;
+    async fn list_images(&self) -> Result<Vec<ImageOption>, AppError>;
+}
+
+/// Builds provider clients with the right account's credentials
+pub struct CloudProviders {
+    http: reqwest::Client,
+    /// Platform-wide tokens used when neither a user nor their reseller has one
+    default_hetzner_token: Option<String>,
+    default_digitalocean_token: Option<String>,
+    digitalocean_api_url: String,
+    hetzner_api_url: String,
+    /// Seals users' tokens in `provider_credentials`; without it they can be neither stored nor used
+    token_key: Option<SecretKey>,
+    catalog_ttl: Duration,
+    /// Catalogues are the same for every account, so one fetch serves everyone until it expires
+    catalogs: RwLock<HashMap<ProviderKind, (Instant, Arc<CloudCatalog>)>>,
+}
+
+impl CloudProviders {
+    pub fn new(config: &Config) -> Self {
+        Self {
+            http: reqwest::Client::builder()
+                .timeout(Duration::from_secs(PROVIDER_TIMEOUT_SECONDS))
+                .build()
+                .expect("Failed to build HTTP client"),
+            default_hetzner_token: config.hetzner_api_token.clone().filter(|token| !token.is_empty()),
+            default_digitalocean_token: config.digitalocean_api_token.clone().filter(|token| !token.is_empty()),
+            digitalocean_api_url: config.digitalocean_api_url.trim_end_matches('/').to_string(),
+            hetzner_api_url: config.hetzner_api_url.trim_end_matches('/').to_string(),
+            // `Config::from_env` has already rejected malformed keys
+            token_key: config.provider_token_key.as_deref().and_then(|key| SecretKey::from_hex(key).ok()),
+            catalog_ttl: Duration::from_secs(config.catalog_cache_seconds),
+            catalogs: RwLock::new(HashMap::new()),
+        }
+    }
+
+    fn default_token(&self, kind: ProviderKind) -> Option<&String> {
+        match kind {
+            ProviderKind::Hetzner => self.default_hetzner_token.as_ref(),
+            ProviderKind::DigitalOcean => self.default_digitalocean_token.as_ref(),
+        }
+    }
+
+    fn client(&self, kind: ProviderKind, api_token: String) -> Arc<dyn CloudProvider> {
+        match kind {
+            ProviderKind::Hetzner => Arc::new(HetznerClient::new(
+                self.http.clone(),
+                self.hetzner_api_url.clone(),
+                api_token,
+            )),
+            ProviderKind::DigitalOcean => Arc::new(DigitalOceanClient::new(
+                self.http.clone(),
+                self.digitalocean_api_url.clone(),
+                api_token,
+            )),
+        }
+    }
+
+    fn token_key(&self) -> Result<&SecretKey, AppError> {
+        self.token_key.as_ref().ok_or_else(|| {
+            AppError::InternalError("PROVIDER_TOKEN_KEY must be set to use stored provider tokens".to_string())
+        })
+    }
+
+    /// Ciphertext to store in `provider_credentials.api_token`
+    fn seal(&self, api_token: &str) -> Result<String, AppError> {
+        Ok(self.token_key()?.encrypt(api_token))
+    }
+
+    /// Token stored in `provider_credentials.api_token`
+    fn open(&self, sealed: &str) -> Result<String, AppError> {
+        self.token_key()?.decrypt(sealed).ok_or_else(|| {
+            AppError::InternalError("Stored provider token cannot be decrypted with PROVIDER_TOKEN_KEY".to_string())
+        })
+    }
+
+    /// Seal tokens stored before encryption was introduced; returns how many were sealed
+    pub async fn seal_stored_tokens(&self, db: &DbPool) -> Result<u64, AppError> {
+        let plaintext: Vec<_> = sqlx::query_as::<_, (Uuid, String)>("SELECT id, api_token FROM provider_credentials")
+            .fetch_all(db)
+            .await?
+            .into_iter()
+            .filter(|(_, api_token)| !crypto::is_sealed(api_token))
+            .collect();
+
+        if plaintext.is_empty() {
+            return Ok(0);
+        }
+
+        let key = self.token_key()?;
+        for (id, api_token) in &plaintext {
+            sqlx::query("UPDATE provider_credentials SET api_token = $2 WHERE id = $1 AND api_token = $3")
+                .bind(id)
+                .bind(key.encrypt(api_token))
+                .bind(api_token)
+                .execute(db)
+                .await?;
+        }
+
+        Ok(plaintext.len() as u64)
+    }
+
+    /// Client acting with `user_id`'s own credentials, else their reseller's, else the platform default
+    pub async fn for_user(&self, db: &DbPool, user_id: Uuid, kind: ProviderKind) -> Result<Arc<dyn CloudProvider>, AppError> {
+        let api_token = sqlx::query_scalar::<_, String>(
+            "SELECT c.api_token
+             FROM provider_credentials c
+             JOIN users u ON u.id = $1
+             WHERE c.provider = $2 AND (c.user_id = u.id OR c.user_id = u.parent_id)
+             ORDER BY (c.user_id = u.id) DESC
+             LIMIT 1"
+        )
+        .bind(user_id)
+        .bind(kind.as_str())
+        .fetch_optional(db)
+        .await?;
+
+        let api_token = match api_token {
+            Some(sealed) => Some(self.open(&sealed)?),
+            None => self.default_token(kind).cloned(),
+        };
+
+        let api_token = api_token.ok_or_else(|| {
+            AppError::BadRequest(format!("No {} API token configured for this account", kind.label()))
+        })?;
+
+        Ok(self.client(kind, api_token))
+    }
+
+    /// Client for the account that owns `vps`
+    pub async fn for_vps(&self, db: &DbPool, vps: &Vps) -> Result<Arc<dyn CloudProvider>, AppError> {
+        self.for_user(db, vps.user_id, vps.get_provider()?).await
+    }
+
+    /// Every account of `kind` the platform can see: the platform-wide token (`None`) and each user's own
+    pub async fn accounts(&self, db: &DbPool, kind: ProviderKind) -> Result<Vec<(Option<Uuid>, Arc<dyn CloudProvider>)>, AppError> {
+        let mut accounts: Vec<_> = self
+            .default_token(kind)
+            .into_iter()
+            .map(|api_token| (None, self.client(kind, api_token.clone())))
+            .collect();
+
+        let credentials = sqlx::query_as::<_, ProviderCredential>(
+            "SELECT * FROM provider_credentials WHERE provider = $1 ORDER BY created_at"
+        )
+        .bind(kind.as_str())
+        .fetch_all(db)
+        .await?;
+
+        // One unreadable token must not hide every other account
+        for credential in credentials {
+            match self.open(&credential.api_token) {
+                Ok(api_token) => accounts.push((Some(credential.user_id), self.client(kind, api_token))),
+                Err(e) => tracing::error!("Skipping {} account of user {}: {}", kind.label(), credential.user_id, e),
+            }
+        }
+
+        Ok(accounts)
+    }
+
+    /// Server types, locations and images of `kind`, fetched with `user_id`'s credentials on a cache miss
+    pub async fn catalog(&self, db: &DbPool, user_id: Uuid, kind: ProviderKind) -> Result<Arc<CloudCatalog>, AppError> {
+        if let Some((fetched_at, catalog)) = self.catalogs.read().await.get(&kind) {
+            if fetched_at.elapsed() < self.catalog_ttl {
+                return Ok(catalog.clone());
+            }
+        }
+
+        let provider = self.for_user(db, user_id, kind).await?;
+        let (server_types, locations, images) = tokio::try_join!(
+            provider.list_server_types(),
+            provider.list_locations(),
+            provider.list_images(),
+        )?;
+
+        let catalog = Arc::new(CloudCatalog { server_types, locations, images });
+        self.catalogs.write().await.insert(kind, (Instant::now(), catalog.clone()));
+
+        Ok(catalog)
+    }
+}
+
+pub async fn list_credentials(db: &DbPool, user_id: Uuid) -> Result<Vec<ProviderCredential>, AppError> {
+    let credentials = sqlx::query_as::<_, ProviderCredential>(
+        "SELECT * FROM provider_credentials WHERE user_id = $1 ORDER BY provider"
+    )
+    .bind(user_id)
+    .fetch_all(db)
+    .await?;
+
+    Ok(credentials)
+}
+
+pub async fn get_credential(db: &DbPool, user_id: Uuid, kind: ProviderKind) -> Result<Option<ProviderCredential>, AppError> {
+    let credential = sqlx::query_as::<_, ProviderCredential>(
+        "SELECT * FROM provider_credentials WHERE user_id = $1 AND provider = $2"
+    )
+    .bind(user_id)
+    .bind(kind.as_str())
+    .fetch_optional(db)
+    .await?;
+
+    Ok(credential)
+}
+
+/// Create or replace the user's token for `kind`, sealed with `PROVIDER_TOKEN_KEY`
+pub async fn set_credential(
+    db: &DbPool,
+    providers: &CloudProviders,
+    user_id: Uuid,
+    kind: ProviderKind,
+    payload: SetProviderCredential,
+) -> Result<ProviderCredential, AppError> {
+    let api_token = payload.api_token.trim();
+    let now = Utc::now();
+    let credential = sqlx::query_as::<_, ProviderCredential>(
+        "INSERT INTO provider_credentials (id, user_id, provider, api_token, token_hint, created_at, updated_at)
+         VALUES ($1, $2, $3, $4, $5, $6, $6)
+         ON CONFLICT (user_id, provider)
+         DO UPDATE SET api_token = EXCLUDED.api_token, token_hint = EXCLUDED.token_hint, updated_at = EXCLUDED.updated_at
+         RETURNING *"
+    )
+    .bind(Uuid::new_v4())
+    .bind(user_id)
+    .bind(kind.as_str())
+    .bind(providers.seal(api_token)?)
+    .bind(api_token.chars().take(4).collect::<String>())
+    .bind(now)
+    .fetch_one(db)
+    .await?;
+
+    Ok(credential)
+}
+
+pub async fn delete_credential(db: &DbPool, user_id: Uuid, kind: ProviderKind) -> Result<(), AppError> {
+    let result = sqlx::query("DELETE FROM provider_credentials WHERE user_id = $1 AND provider = $2")
+        .bind(user_id)
+        .bind(kind.as_str())
+        .execute(db)
+        .await?;
+
+    if result.rows_affected() == 0 {
+        return Err(AppError::NotFound("Provider credentials not found".to_string()));
+    }
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_support;
+    use axum::{extract::Path, http::HeaderMap, routing::get, Json, Router};
+
+    /// Hetzner mock naming every server after the token it was requested with
+    async fn get_server(Path(id): Path<i64>, headers: HeaderMap) -> Json<serde_json::Value> {
+        let token = headers["authorization"].to_str().unwrap().trim_start_matches("Bearer ").to_string();
+        Json(serde_json::json!({ "server": test_support::hetzner_server(id, &token, "running") }))
+    }
+
+    async fn mock_providers(default_token: Option<&str>) -> CloudProviders {
+        let mut config = test_support::config();
+        config.hetzner_api_url = test_support::serve(Router::new().route("/servers/:id", get(get_server))).await;
+        config.hetzner_api_token = default_token.map(str::to_string);
+        CloudProviders::new(&config)
+    }
+
+    fn credential(api_token: &str) -> SetProviderCredential {
+        SetProviderCredential { api_token: api_token.to_string() }
+    }
+
+    /// Token `for_user` picked, as echoed back by the mock
+    async fn token_for(providers: &CloudProviders, db: &DbPool, user_id: Uuid) -> Result<String, AppError> {
+        let provider = providers.for_user(db, user_id, ProviderKind::Hetzner).await?;
+        Ok(provider.get_server("1").await?.name)
+    }
+
+    #[tokio::test]
+    async fn for_user_prefers_own_then_reseller_then_default_token() {
+        let Some(db) = test_support::database().await else { return };
+        let tenants = test_support::Tenants::create(&db).await;
+        let providers = mock_providers(Some("platform-token")).await;
+
+        let platform = token_for(&providers, &db, tenants.customer).await.unwrap();
+        set_credential(&db, &providers, tenants.reseller, ProviderKind::Hetzner, credential("reseller-token")).await.unwrap();
+        let reseller = token_for(&providers, &db, tenants.customer).await.unwrap();
+        set_credential(&db, &providers, tenants.customer, ProviderKind::Hetzner, credential(" customer-token ")).await.unwrap();
+        let own = token_for(&providers, &db, tenants.customer).await.unwrap();
+        let reseller_own = token_for(&providers, &db, tenants.reseller).await.unwrap();
+        let other_customer = token_for(&providers, &db, tenants.other_customer).await.unwrap();
+        let unconfigured = token_for(&mock_providers(None).await, &db, tenants.user).await;
+
+        tenants.delete(&db).await;
+
+        assert_eq!(platform, "platform-token");
+        assert_eq!(reseller, "reseller-token");
+        assert_eq!(own, "customer-token");
+        assert_eq!(reseller_own, "reseller-token");
+        assert_eq!(other_customer, "platform-token");
+        assert!(matches!(unconfigured, Err(AppError::BadRequest(_))));
+    }
+
+    #[tokio::test]
+    async fn stored_tokens_are_encrypted() {
+        let Some(db) = test_support::database().await else { return };
+        let user = test_support::user(&db, crate::models::user::UserRole::User, None).await;
+        let providers = mock_providers(None).await;
+
+        let credential = set_credential(&db, &providers, user, ProviderKind::Hetzner, credential("hcloud-secret-token")).await.unwrap();
+        let stored: String = sqlx::query_scalar("SELECT api_token FROM provider_credentials WHERE user_id = $1")
+            .bind(user)
+            .fetch_one(&db)
+            .await
+            .unwrap();
+        let accounts = providers.accounts(&db, ProviderKind::Hetzner).await.unwrap();
+        let used = token_for(&providers, &db, user).await.unwrap();
+
+        test_support::delete_users(&db, &[user]).await;
+
+        assert!(crypto::is_sealed(&stored));
+        assert!(!stored.contains("hcloud-secret-token"));
+        assert_eq!(ProviderCredentialResponse::from(credential).api_token, "hclo…");
+        assert!(accounts.iter().any(|(owner, _)| *owner == Some(user)));
+        assert_eq!(used, "hcloud-secret-token");
+    }
+
+    #[tokio::test]
+    async fn stored_tokens_need_the_token_key() {
+        let Some(db) = test_support::database().await else { return };
+        let user = test_support::user(&db, crate::models::user::UserRole::User, None).await;
+        let providers = mock_providers(None).await;
+        let mut unkeyed = mock_providers(None).await;
+        unkeyed.token_key = None;
+
+        let refused = set_credential(&db, &unkeyed, user, ProviderKind::Hetzner, credential("hcloud-secret-token")).await;
+        set_credential(&db, &providers, user, ProviderKind::Hetzner, credential("hcloud-secret-token")).await.unwrap();
+        let unreadable = token_for(&unkeyed, &db, user).await;
+
+        test_support::delete_users(&db, &[user]).await;
+
+        assert!(matches!(refused, Err(AppError::InternalError(_))));
+        assert!(matches!(unreadable, Err(AppError::InternalError(_))));
+    }
+
+    #[tokio::test]
+    async fn plaintext_tokens_are_sealed_in_place() {
+        let Some(db) = test_support::database().await else { return };
+        let user = test_support::user(&db, crate::models::user::UserRole::User, None).await;
+        let providers = mock_providers(None).await;
+        sqlx::query(
+            "INSERT INTO provider_credentials (user_id, provider, api_token, token_hint)
+             VALUES ($1, 'hetzner', 'legacy-plain-token', 'lega')"
+        )
+        .bind(user)
+        .execute(&db)
+        .await
+        .unwrap();
+
+        let sealed = providers.seal_stored_tokens(&db).await.unwrap();
+        let stored: String = sqlx::query_scalar("SELECT api_token FROM provider_credentials WHERE user_id = $1")
+            .bind(user)
+            .fetch_one(&db)
+            .await
+            .unwrap();
+        let used = token_for(&providers, &db, user).await.unwrap();
+
+        test_support::delete_users(&db, &[user]).await;
+
+        assert!(sealed >= 1);
+        assert!(crypto::is_sealed(&stored));
+        assert_eq!(used, "legacy-plain-token");
+    }
+}
@@ -0,0 +1,159 @@
+use crate::{
+    database::DbPool,
+    models::{auth::Scope, dashboard::*},
+    utils::errors::AppError,
+};
+use chrono::{Duration, Utc};
+
+/// Samples older than this are treated as missing rather than current health
+const SAMPLE_MAX_AGE_SECONDS: i64 = 300;
+
+const ACTIVITY_LIMIT: i64 = 10;
+const SERVER_OVERVIEW_LIMIT: i64 = 6;
+const MOUNT_OVERVIEW_LIMIT: usize = 4;
+
+/// `logs.category` values shown in the hosting activity feed
+const HOSTING_CATEGORIES: [&str; 6] = ["website", "domain", "database", "email", "ssl", "backup"];
+
+pub async fn get_stats(db: &DbPool, scope: &Scope) -> Result<DashboardStats, AppError> {
+    let stats = sqlx::query_as::<_, DashboardStats>(
+        "WITH visible AS (
+             SELECT id FROM users
+             WHERE $1::uuid IS NULL OR id = $1 OR ($2 AND parent_id = $1)
+         )
+         SELECT
+             (SELECT COUNT(*) FROM servers WHERE user_id IN (SELECT id FROM visible)) AS servers,
+             (SELECT COUNT(*) FROM servers
+              WHERE user_id IN (SELECT id FROM visible) AND status = 'online') AS servers_online,
+             (SELECT COUNT(*) FROM websites WHERE user_id IN (SELECT id FROM visible)) AS websites,
+             (SELECT COUNT(*) FROM websites
+              WHERE user_id IN (SELECT id FROM visible) AND status = 'active') AS websites_active,
+             (SELECT COUNT(*) FROM visible) AS users,
+             (SELECT COUNT(*) FROM vps WHERE user_id IN (SELECT id FROM visible)) AS vps,
+             (SELECT COUNT(*) FROM vps
+              WHERE user_id IN (SELECT id FROM visible) AND status = 'running') AS vps_running"
+    )
+    .bind(scope.owner_id())
+    .bind(scope.includes_customers())
+    .fetch_one(db)
+    .await?;
+
+    Ok(stats)
+}
+
+pub async fn recent_activity(db: &DbPool, scope: &Scope) -> Result<Vec<ActivityEntry>, AppError> {
+    let entries = sqlx::query_as::<_, ActivityEntry>(
+        "SELECT l.level, l.category, l.message, COALESCE(s.name, u.email) AS subject, l.created_at
+         FROM logs l
+         LEFT JOIN servers s ON s.id = l.server_id
+         LEFT JOIN users u ON u.id = l.user_id
+         WHERE ($1::uuid IS NULL OR l.user_id = $1
+                OR ($2 AND l.user_id IN (SELECT id FROM users WHERE parent_id = $1)))
+         ORDER BY l.created_at DESC
+         LIMIT $3"
+    )
+    .bind(scope.owner_id())
+    .bind(scope.includes_customers())
+    .bind(ACTIVITY_LIMIT)
+    .fetch_all(db)
+    .await?;
+
+    Ok(entries)
+}
+
+pub async fn hosting_activity(db: &DbPool, scope: &Scope) -> Result<Vec<ActivityEntry>, AppError> {
+    let entries = sqlx::query_as::<_, ActivityEntry>(
+        "SELECT l.level, l.category, l.message,
+                COALESCE(l.metadata->>'domain', s.name, u.email) AS subject, l.created_at
+         FROM logs l
+         LEFT JOIN servers s ON s.id = l.server_id
+         LEFT JOIN users u ON u.id = l.user_id
+         WHERE l.category = ANY($3)
+           AND ($1::uuid IS NULL OR l.user_id = $1
+                OR ($2 AND l.user_id IN (SELECT id FROM users WHERE parent_id = $1)))
+         ORDER BY l.created_at DESC
+         LIMIT $4"
+    )
+    .bind(scope.owner_id())
+    .bind(scope.includes_customers())
+    .bind(&HOSTING_CATEGORIES[..])
+    .bind(ACTIVITY_LIMIT)
+    .fetch_all(db)
+    .await?;
+
+    Ok(entries)
+}
+
+pub async fn health(db: &DbPool, scope: &Scope) -> Result<HealthSummary, AppError> {
+    let summary = sqlx::query_as::<_, HealthSummary>(
+        "SELECT COUNT(*) AS servers_reporting,
+                AVG(m.cpu_usage)::float8 AS cpu_usage,
+                AVG(m.memory_usage)::float8 AS memory_usage,
+                AVG(m.disk_usage)::float8 AS disk_usage
+         FROM servers s
+         JOIN LATERAL (
+             SELECT cpu_usage, memory_usage, disk_usage FROM server_metrics
+             WHERE server_id = s.id AND timestamp >= $3
+             ORDER BY timestamp DESC
+             LIMIT 1
+         ) m ON TRUE
+         WHERE ($1::uuid IS NULL OR s.user_id = $1
+                OR ($2 AND s.user_id IN (SELECT id FROM users WHERE parent_id = $1)))"
+    )
+    .bind(scope.owner_id())
+    .bind(scope.includes_customers())
+    .bind(Utc::now() - Duration::seconds(SAMPLE_MAX_AGE_SECONDS))
+    .fetch_one(db)
+    .await?;
+
+    Ok(summary)
+}
+
+/// Most recently updated servers with their latest sample
+pub async fn server_overview(db: &DbPool, scope: &Scope) -> Result<Vec<ServerOverview>, AppError> {
+    let servers = sqlx::query_as::<_, ServerOverview>(
+        "SELECT s.name, s.status, s.location, m.cpu_usage::float8 AS cpu_usage,
+                m.memory_usage::float8 AS memory_usage
+         FROM servers s
+         LEFT JOIN LATERAL (
+             SELECT cpu_usage, memory_usage FROM server_metrics
+             WHERE server_id = s.id AND timestamp >= $3
+             ORDER BY timestamp DESC
+             LIMIT 1
+         ) m ON TRUE
+         WHERE ($1::uuid IS NULL OR s.user_id = $1
+                OR ($2 AND s.user_id IN (SELECT id FROM users WHERE parent_id = $1)))
+         ORDER BY s.updated_at DESC
+         LIMIT $4"
+    )
+    .bind(scope.owner_id())
+    .bind(scope.includes_customers())
+    .bind(Utc::now() - Duration::seconds(SAMPLE_MAX_AGE_SECONDS))
+    .bind(SERVER_OVERVIEW_LIMIT)
+    .fetch_all(db)
+    .await?;
+
+    Ok(servers)
+}
+
+/// Disk usage reported by agents, summed over every mount with the fullest mounts listed
+pub async fn storage(db: &DbPool, scope: &Scope) -> Result<StorageSummary, AppError> {
+    let mounts = sqlx::query_as::<_, MountOverview>(
+        "SELECT s.name AS server_name, m.mount_point, m.total_bytes, m.used_bytes
+         FROM server_mounts m
+         JOIN servers s ON s.id = m.server_id
+         WHERE ($1::uuid IS NULL OR s.user_id = $1
+                OR ($2 AND s.user_id IN (SELECT id FROM users WHERE parent_id = $1)))
+         ORDER BY m.used_bytes DESC"
+    )
+    .bind(scope.owner_id())
+    .bind(scope.includes_customers())
+    .fetch_all(db)
+    .await?;
+
+    Ok(StorageSummary {
+        total_bytes: mounts.iter().map(|mount| mount.total_bytes).sum(),
+        used_bytes: mounts.iter().map(|mount| mount.used_bytes).sum(),
+        mounts: mounts.into_iter().take(MOUNT_OVERVIEW_LIMIT).collect(),
+    })
+}
@@ -0,0 +1,604 @@
+use crate::{
+    models::{digitalocean::*, provider::*, vps::VpsStatus},
+    services::cloud_service::CloudProvider,
+    utils::errors::AppError,
+};
+use async_trait::async_trait;
+use chrono::{DateTime, Utc};
+
+/// Largest page DigitalOcean serves for list endpoints
+const DIGITALOCEAN_PAGE_SIZE: u32 = 200;
+
+/// How often a snapshot action is polled, and how long it may take to finish
+const SNAPSHOT_POLL_SECONDS: u64 = 5;
+const SNAPSHOT_TIMEOUT_SECONDS: u64 = 900;
+
+/// Map a DigitalOcean error response (`{"id", "message"}`) onto our taxonomy
+fn digitalocean_error(status: reqwest::StatusCode, body: &str) -> AppError {
+    let message = serde_json::from_str::<serde_json::Value>(body)
+        .ok()
+        .and_then(|v| v["message"].as_str().map(str::to_string))
+        .unwrap_or_else(|| body.to_string());
+
+    match status.as_u16() {
+        400 | 422 => AppError::BadRequest(format!("DigitalOcean rejected the request: {}", message)),
+        404 => AppError::NotFound(format!("DigitalOcean resource not found: {}", message)),
+        409 => AppError::Conflict(format!("DigitalOcean conflict: {}", message)),
+        429 => AppError::RateLimited("DigitalOcean API rate limit reached".to_string()),
+        _ => AppError::UpstreamError(format!("DigitalOcean API error {}: {}", status, message)),
+    }
+}
+
+/// Droplet states collapsed onto ours
+fn vps_status(status: &str) -> VpsStatus {
+    match status {
+        "active" => VpsStatus::Running,
+        "new" => VpsStatus::Starting,
+        "off" => VpsStatus::Stopped,
+        "archive" => VpsStatus::Deleting,
+        _ => VpsStatus::Error,
+    }
+}
+
+/// DigitalOcean only names regions ("New York 3"); the slug prefix tells the country
+fn region_country(slug: &str) -> &'static str {
+    match slug.trim_end_matches(|c: char| c.is_ascii_digit()) {
+        "nyc" | "sfo" | "atl" => "US",
+        "ams" => "NL",
+        "sgp" => "SG",
+        "lon" => "GB",
+        "fra" => "DE",
+        "tor" => "CA",
+        "blr" => "IN",
+        "syd" => "AU",
+        _ => "",
+    }
+}
+
+fn parse_time(value: &Option<String>) -> Option<DateTime<Utc>> {
+    value
+        .as_deref()
+        .and_then(|value| DateTime::parse_from_rfc3339(value).ok())
+        .map(|time| time.with_timezone(&Utc))
+}
+
+fn droplet_id(external_id: &str) -> Result<i64, AppError> {
+    external_id
+        .parse()
+        .map_err(|_| AppError::BadRequest(format!("Invalid DigitalOcean droplet id: {}", external_id)))
+}
+
+fn action_id(external_id: &str) -> Result<i64, AppError> {
+    external_id
+        .parse()
+        .map_err(|_| AppError::BadRequest(format!("Invalid DigitalOcean action id: {}", external_id)))
+}
+
+fn public_ip(networks: &[DigitalOceanNetwork]) -> Option<String> {
+    networks
+        .iter()
+        .find(|network| network.network_type == "public")
+        .map(|network| network.ip_address.clone())
+}
+
+impl From<DigitalOceanDroplet> for CloudServer {
+    fn from(droplet: DigitalOceanDroplet) -> Self {
+        CloudServer {
+            external_id: droplet.id.to_string(),
+            status: vps_status(&droplet.status),
+            image: droplet.image.slug
+                .unwrap_or_else(|| format!("{} {}", droplet.image.distribution, droplet.image.name)),
+            ipv4: public_ip(&droplet.networks.v4),
+            ipv6: public_ip(&droplet.networks.v6),
+            cpu_cores: droplet.vcpus,
+            ram_gb: (droplet.memory / 1024) as i32,
+            disk_gb: droplet.disk,
+            server_type: droplet.size_slug,
+            location: droplet.region.slug,
+            name: droplet.name,
+            monthly_cost: droplet.size.price_monthly,
+        }
+    }
+}
+
+impl From<DigitalOceanAction> for CloudAction {
+    fn from(action: DigitalOceanAction) -> Self {
+        // DigitalOcean reports no progress, only whether the action has finished
+        let (status, progress, error) = match action.status.as_str() {
+            "completed" => (ActionStatus::Success, 100, None),
+            "errored" => (ActionStatus::Error, 100, Some("DigitalOcean action errored".to_string())),
+            _ => (ActionStatus::Running, 0, None),
+        };
+
+        CloudAction {
+            external_id: action.id.to_string(),
+            status,
+            progress,
+            error,
+        }
+    }
+}
+
+/// One page of a DigitalOcean list endpoint
+trait DigitalOceanPage: serde::de::DeserializeOwned {
+    type Item;
+
+    fn into_page(self) -> (Vec<Self::Item>, bool);
+}
+
+macro_rules! digitalocean_page {
+    ($response:ty, $field:ident, $item:ty) => {
+        impl DigitalOceanPage for $response {
+            type Item = $item;
+
+            fn into_page(self) -> (Vec<$item>, bool) {
+                let has_next = self.links
+                    .and_then(|links| links.pages)
+                    .is_some_and(|pages| pages.next.is_some());
+                (self.$field, has_next)
+            }
+        }
+    };
+}
+
+digitalocean_page!(DigitalOceanDropletsResponse, droplets, DigitalOceanDroplet);
+digitalocean_page!(DigitalOceanSizesResponse, sizes, DigitalOceanSize);
+digitalocean_page!(DigitalOceanRegionsResponse, regions, DigitalOceanRegion);
+digitalocean_page!(DigitalOceanImagesResponse, images, DigitalOceanImage);
+digitalocean_page!(DigitalOceanSnapshotsResponse, snapshots, DigitalOceanSnapshot);
+
+/// DigitalOcean API client acting with one account's token
+#[derive(Clone)]
+pub struct DigitalOceanClient {
+    /// `DIGITALOCEAN_API_URL`, overridable to point at a mock server
+    api_url: String,
+    api_token: String,
+    client: reqwest::Client,
+}
+
+impl DigitalOceanClient {
+    pub fn new(client: reqwest::Client, api_url: String, api_token: String) -> Self {
+        Self { api_url, api_token, client }
+    }
+
+    /// Send a request and fail on any non-2xx response
+    async fn send<B: serde::Serialize>(
+        &self,
+        method: reqwest::Method,
+        endpoint: &str,
+        body: Option<B>,
+    ) -> Result<reqwest::Response, AppError> {
+        let url = format!("{}{}", self.api_url, endpoint);
+
+        let mut req = self.client
+            .request(method, &url)
+            .header("Authorization", format!("Bearer {}", self.api_token));
+
+        if let Some(body) = body {
+            req = req.json(&body);
+        }
+
+        let response = req.send().await.map_err(|e| {
+            if e.is_timeout() {
+                AppError::Timeout("DigitalOcean API did not respond in time".to_string())
+            } else {
+                AppError::UpstreamError(format!("DigitalOcean API request failed: {}", e))
+            }
+        })?;
+
+        if !response.status().is_success() {
+            let status = response.status();
+            let error_text = response.text().await.unwrap_or_default();
+            return Err(digitalocean_error(status, &error_text));
+        }
+
+        Ok(response)
+    }
+
+    async fn request<T, B>(
+        &self,
+        method: reqwest::Method,
+        endpoint: &str,
+        body: Option<B>,
+    ) -> Result<T, AppError>
+    where
+        T: serde::de::DeserializeOwned,
+        B: serde::Serialize,
+    {
+        self.send(method, endpoint, body).await?
+            .json().await
+            .map_err(|e| AppError::UpstreamError(format!("Failed to parse DigitalOcean response: {}", e)))
+    }
+
+    /// Every item of a paginated list endpoint
+    async fn list_all<P: DigitalOceanPage>(&self, endpoint: &str) -> Result<Vec<P::Item>, AppError> {
+        let separator = if endpoint.contains('?') { '&' } else { '?' };
+        let mut items = Vec::new();
+        let mut page = 1;
+
+        loop {
+            let response: P = self.request(
+                reqwest::Method::GET,
+                &format!("{}{}per_page={}&page={}", endpoint, separator, DIGITALOCEAN_PAGE_SIZE, page),
+                None::<()>,
+            ).await?;
+
+            let (mut batch, has_next) = response.into_page();
+            let empty = batch.is_empty();
+            items.append(&mut batch);
+
+            if !has_next || empty {
+                return Ok(items);
+            }
+            page += 1;
+        }
+    }
+
+    async fn action(&self, external_id: &str, action: &str, name: Option<String>) -> Result<DigitalOceanAction, AppError> {
+        let request = DigitalOceanActionRequest {
+            action_type: action.to_string(),
+            name,
+        };
+
+        let response: DigitalOceanActionResponse = self.request(
+            reqwest::Method::POST,
+            &format!("/droplets/{}/actions", droplet_id(external_id)?),
+            Some(request),
+        ).await?;
+
+        Ok(response.action)
+    }
+
+    /// Poll an action until it completes; fails if it errors or outlasts `SNAPSHOT_TIMEOUT_SECONDS`
+    async fn wait_for_action(&self, id: i64) -> Result<(), AppError> {
+        let deadline = std::time::Instant::now() + std::time::Duration::from_secs(SNAPSHOT_TIMEOUT_SECONDS);
+
+        loop {
+            let response: DigitalOceanActionResponse = self.request(
+                reqwest::Method::GET,
+                &format!("/actions/{}", id),
+                None::<()>,
+            ).await?;
+
+            match response.action.status.as_str() {
+                "completed" => return Ok(()),
+                "errored" => return Err(AppError::UpstreamError(format!("DigitalOcean action {} errored", id))),
+                _ if std::time::Instant::now() >= deadline => {
+                    return Err(AppError::Timeout(format!("DigitalOcean action {} did not finish in time", id)));
+                }
+                _ => tokio::time::sleep(std::time::Duration::from_secs(SNAPSHOT_POLL_SECONDS)).await,
+            }
+        }
+    }
+}
+
+#[async_trait]
+impl CloudProvider for DigitalOceanClient {
+    async fn create_server(&self, request: CloudServerRequest) -> Result<CloudServer, AppError> {
+        let request = DigitalOceanCreateDropletRequest {
+            name: request.name,
+            region: request.location,
+            size: request.server_type,
+            image: request.image,
+            ssh_keys: request.ssh_keys,
+            user_data: request.user_data,
+            ipv6: true,
+        };
+
+        let response: DigitalOceanDropletResponse = self.request(
+            reqwest::Method::POST,
+            "/droplets",
+            Some(request),
+        ).await?;
+
+        Ok(response.droplet.into())
+    }
+
+    async fn get_server(&self, external_id: &str) -> Result<CloudServer, AppError> {
+        let response: DigitalOceanDropletResponse = self.request(
+            reqwest::Method::GET,
+            &format!("/droplets/{}", droplet_id(external_id)?),
+            None::<()>,
+        ).await?;
+
+        Ok(response.droplet.into())
+    }
+
+    async fn list_servers(&self) -> Result<Vec<CloudServer>, AppError> {
+        let droplets = self.list_all::<DigitalOceanDropletsResponse>("/droplets").await?;
+        Ok(droplets.into_iter().map(CloudServer::from).collect())
+    }
+
+    async fn delete_server(&self, external_id: &str) -> Result<(), AppError> {
+        // Answers 204 with an empty body
+        self.send(
+            reqwest::Method::DELETE,
+            &format!("/droplets/{}", droplet_id(external_id)?),
+            None::<()>,
+        ).await?;
+
+        Ok(())
+    }
+
+    async fn power_on(&self, external_id: &str) -> Result<CloudAction, AppError> {
+        self.action(external_id, "power_on", None).await.map(CloudAction::from)
+    }
+
+    async fn power_off(&self, external_id: &str) -> Result<CloudAction, AppError> {
+        self.action(external_id, "power_off", None).await.map(CloudAction::from)
+    }
+
+    async fn reboot(&self, external_id: &str) -> Result<CloudAction, AppError> {
+        self.action(external_id, "reboot", None).await.map(CloudAction::from)
+    }
+
+    async fn get_action(&self, external_id: &str) -> Result<CloudAction, AppError> {
+        let response: DigitalOceanActionResponse = self.request(
+            reqwest::Method::GET,
+            &format!("/actions/{}", action_id(external_id)?),
+            None::<()>,
+        ).await?;
+
+        Ok(response.action.into())
+    }
+
+    async fn create_snapshot(&self, external_id: &str, description: &str) -> Result<CloudSnapshot, AppError> {
+        // The snapshot only gets an id once its action completes, so wait and then look it up by name
+        let action = self.action(external_id, "snapshot", Some(description.to_string())).await?;
+        self.wait_for_action(action.id).await?;
+
+        let snapshot = self
+            .list_all::<DigitalOceanSnapshotsResponse>(&format!("/droplets/{}/snapshots", droplet_id(external_id)?))
+            .await?
+            .into_iter()
+            .filter(|snapshot| snapshot.name == description)
+            .max_by_key(|snapshot| parse_time(&snapshot.created_at))
+            .ok_or_else(|| AppError::UpstreamError(format!("DigitalOcean snapshot action {} left no snapshot", action.id)))?;
+
+        Ok(CloudSnapshot {
+            external_id: snapshot.id.to_string(),
+            description: snapshot.name,
+            created_at: parse_time(&snapshot.created_at).or_else(|| parse_time(&action.started_at)),
+        })
+    }
+
+    async fn list_server_types(&self) -> Result<Vec<ServerTypeOption>, AppError> {
+        let sizes = self.list_all::<DigitalOceanSizesResponse>("/sizes").await?;
+
+        Ok(sizes
+            .into_iter()
+            .filter(|size| size.available)
+            .map(|size| ServerTypeOption {
+                prices: size.regions
+                    .iter()
+                    .map(|region| ServerTypePrice {
+                        location: region.clone(),
+                        monthly_gross: Some(size.price_monthly),
+                    })
+                    .collect(),
+                name: size.slug,
+                description: size.description,
+                cores: size.vcpus,
+                memory_gb: size.memory as f64 / 1024.0,
+                disk_gb: size.disk,
+            })
+            .collect())
+    }
+
+    async fn list_locations(&self) -> Result<Vec<LocationOption>, AppError> {
+        let regions = self.list_all::<DigitalOceanRegionsResponse>("/regions").await?;
+
+        Ok(regions
+            .into_iter()
+            .filter(|region| region.available)
+            .map(|region| LocationOption {
+                country: region_country(&region.slug).to_string(),
+                city: region.name.trim_end_matches(|c: char| c.is_ascii_digit() || c == ' ').to_string(),
+                description: region.name,
+                name: region.slug,
+            })
+            .collect())
+    }
+
+    async fn list_images(&self) -> Result<Vec<ImageOption>, AppError> {
+        let images = self.list_all::<DigitalOceanImagesResponse>("/images?type=distribution").await?;
+
+        Ok(images
+            .into_iter()
+            .filter_map(|image| {
+                let description = format!("{} {}", image.distribution, image.name);
+                image.slug.map(|name| ImageOption { name, description })
+            })
+            .collect())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_support;
+    use axum::{
+        extract::{Path, Query},
+        http::{HeaderMap, StatusCode},
+        response::{IntoResponse, Response},
+        routing::{get, post},
+        Json, Router,
+    };
+    use std::collections::HashMap;
+
+    fn droplet(id: i64, status: &str) -> serde_json::Value {
+        serde_json::json!({
+            "id": id,
+            "name": format!("droplet-{}", id),
+            "memory": 2048,
+            "vcpus": 2,
+            "disk": 50,
+            "status": status,
+            "size_slug": "s-2vcpu-2gb",
+            "size": { "price_monthly": 18.0 },
+            "region": { "slug": "fra1", "name": "Frankfurt 1", "available": true },
+            "image": { "name": "24.04 (LTS) x64", "distribution": "Ubuntu", "slug": "ubuntu-24-04-x64" },
+            "networks": {
+                "v4": [
+                    { "ip_address": "10.110.0.2", "type": "private" },
+                    { "ip_address": "203.0.113.10", "type": "public" }
+                ],
+                "v6": []
+            }
+        })
+    }
+
+    /// Droplet ids pick the response: 1-5 map to each status, 4xx/5xx ids answer with that error
+    async fn get_droplet(Path(id): Path<i64>, headers: HeaderMap) -> Response {
+        if headers.get("authorization").and_then(|v| v.to_str().ok()) != Some("Bearer test-token") {
+            return StatusCode::UNAUTHORIZED.into_response();
+        }
+
+        if id >= 400 {
+            let status = StatusCode::from_u16(id as u16).unwrap();
+            return (status, Json(serde_json::json!({ "id": "error", "message": "mock failure" }))).into_response();
+        }
+
+        let status = ["active", "new", "off", "archive", "migrating"][(id - 1) as usize];
+        Json(serde_json::json!({ "droplet": droplet(id, status) })).into_response()
+    }
+
+    async fn list_droplets(Query(query): Query<HashMap<String, String>>) -> Json<serde_json::Value> {
+        match query.get("page").map(String::as_str) {
+            Some("1") => Json(serde_json::json!({
+                "droplets": [droplet(1, "active"), droplet(2, "new")],
+                "links": { "pages": { "next": "https://api.digitalocean.com/v2/droplets?page=2" } }
+            })),
+            _ => Json(serde_json::json!({
+                "droplets": [droplet(3, "off")],
+                "links": { "pages": { "prev": "https://api.digitalocean.com/v2/droplets?page=1" } }
+            })),
+        }
+    }
+
+    async fn droplet_action(Path(id): Path<i64>, Json(body): Json<serde_json::Value>) -> Json<serde_json::Value> {
+        // Droplet 2 gets an action that errors
+        let action_id = if id == 2 { 78 } else { 77 };
+        assert!(body["type"].is_string());
+        Json(serde_json::json!({
+            "action": { "id": action_id, "status": "in-progress", "started_at": "2026-10-17T10:00:00Z" }
+        }))
+    }
+
+    async fn get_action(Path(id): Path<i64>) -> Json<serde_json::Value> {
+        let status = if id == 78 { "errored" } else { "completed" };
+        Json(serde_json::json!({
+            "action": { "id": id, "status": status, "started_at": "2026-10-17T10:00:00Z" }
+        }))
+    }
+
+    async fn droplet_snapshots() -> Json<serde_json::Value> {
+        Json(serde_json::json!({
+            "snapshots": [
+                { "id": 9001, "name": "nightly", "created_at": "2026-10-16T10:00:00Z" },
+                { "id": 9002, "name": "nightly", "created_at": "2026-10-17T10:05:00Z" },
+                { "id": 9003, "name": "other", "created_at": "2026-10-17T11:00:00Z" }
+            ],
+            "links": {}
+        }))
+    }
+
+    async fn client() -> DigitalOceanClient {
+        let router = Router::new()
+            .route("/droplets", get(list_droplets))
+            .route("/droplets/:id", get(get_droplet))
+            .route("/droplets/:id/actions", post(droplet_action))
+            .route("/droplets/:id/snapshots", get(droplet_snapshots))
+            .route("/actions/:id", get(get_action));
+        let base = test_support::serve(router).await;
+
+        DigitalOceanClient::new(reqwest::Client::new(), base, "test-token".to_string())
+    }
+
+    #[tokio::test]
+    async fn droplet_states_map_onto_vps_status() {
+        let client = client().await;
+        let expected = [
+            VpsStatus::Running,
+            VpsStatus::Starting,
+            VpsStatus::Stopped,
+            VpsStatus::Deleting,
+            VpsStatus::Error,
+        ];
+
+        for (id, status) in expected.into_iter().enumerate() {
+            let server = client.get_server(&(id + 1).to_string()).await.unwrap();
+            assert_eq!(server.status, status);
+        }
+    }
+
+    #[tokio::test]
+    async fn droplet_fields_are_converted() {
+        let server = client().await.get_server("1").await.unwrap();
+
+        assert_eq!(server.external_id, "1");
+        assert_eq!(server.ipv4.as_deref(), Some("203.0.113.10"));
+        assert_eq!(server.ipv6, None);
+        assert_eq!(server.ram_gb, 2);
+        assert_eq!(server.location, "fra1");
+        assert_eq!(server.image, "ubuntu-24-04-x64");
+        assert_eq!(server.monthly_cost, Some(18.0));
+    }
+
+    #[tokio::test]
+    async fn list_servers_follows_pagination() {
+        let servers = client().await.list_servers().await.unwrap();
+
+        let ids: Vec<_> = servers.iter().map(|server| server.external_id.as_str()).collect();
+        assert_eq!(ids, ["1", "2", "3"]);
+    }
+
+    #[tokio::test]
+    async fn error_responses_map_onto_app_errors() {
+        let client = client().await;
+
+        for code in [400, 422] {
+            let error = client.get_server(&code.to_string()).await.unwrap_err();
+            assert!(matches!(&error, AppError::BadRequest(message) if message.contains("mock failure")), "{:?}", error);
+        }
+        assert!(matches!(client.get_server("404").await, Err(AppError::NotFound(_))));
+        assert!(matches!(client.get_server("409").await, Err(AppError::Conflict(_))));
+        assert!(matches!(client.get_server("429").await, Err(AppError::RateLimited(_))));
+        assert!(matches!(client.get_server("503").await, Err(AppError::UpstreamError(_))));
+        assert!(matches!(client.get_server("not-a-number").await, Err(AppError::BadRequest(_))));
+    }
+
+    #[tokio::test]
+    async fn actions_are_parsed() {
+        let client = client().await;
+
+        let started = client.power_on("1").await.unwrap();
+        assert_eq!(started.external_id, "77");
+        assert_eq!(started.status, ActionStatus::Running);
+        assert_eq!(started.progress, 0);
+
+        let finished = client.get_action("77").await.unwrap();
+        assert_eq!(finished.status, ActionStatus::Success);
+        assert_eq!(finished.progress, 100);
+
+        let errored = client.get_action("78").await.unwrap();
+        assert_eq!(errored.status, ActionStatus::Error);
+        assert!(errored.error.is_some());
+    }
+
+    #[tokio::test]
+    async fn snapshot_returns_the_snapshot_id_once_finished() {
+        let snapshot = client().await.create_snapshot("1", "nightly").await.unwrap();
+
+        assert_eq!(snapshot.external_id, "9002");
+        assert_eq!(snapshot.description, "nightly");
+        assert_eq!(snapshot.created_at.unwrap().to_rfc3339(), "2026-10-17T10:05:00+00:00");
+    }
+
+    #[tokio::test]
+    async fn snapshot_fails_when_its_action_errors() {
+        let error = client().await.create_snapshot("2", "nightly").await.unwrap_err();
+
+        assert!(matches!(error, AppError::UpstreamError(_)));
+    }
+}
@@ -0,0 +1,285 @@
+use crate::{
+    database::DbPool,
+    models::{
+        auth::Scope,
+        event::{Event, EventKind},
+    },
+    services::auth_service,
+};
+use sqlx::postgres::PgListener;
+use std::collections::HashMap;
+use tokio::{
+    sync::broadcast::{self, error::RecvError},
+    time::{Duration, Instant},
+};
+use uuid::Uuid;
+
+/// Events buffered per subscriber before it is told to resync
+const CHANNEL_CAPACITY: usize = 1024;
+
+const NOTIFY_CHANNEL: &str = "unified_panel_events";
+
+/// Postgres rejects NOTIFY payloads of 8000 bytes or more
+const MAX_NOTIFY_PAYLOAD: usize = 7999;
+
+/// How often open streams re-check that their session has not been revoked
+const SESSION_CHECK_INTERVAL: Duration = Duration::from_secs(60);
+
+const LISTENER_RETRY_DELAY: Duration = Duration::from_secs(5);
+
+/// In-process fan-out of live updates to `/api/events` subscribers
+pub struct EventBus {
+    sender: broadcast::Sender<Event>,
+    /// Set when events travel through Postgres LISTEN/NOTIFY so every instance sees them
+    notify_db: Option<DbPool>,
+}
+
+impl EventBus {
+    pub fn new(notify_db: Option<DbPool>) -> Self {
+        let (sender, _) = broadcast::channel(CHANNEL_CAPACITY);
+        EventBus { sender, notify_db }
+    }
+
+    /// Never fails: live updates are best effort and must not break the action that caused them
+    pub async fn publish(&self, event: Event) {
+        if let Some(db) = &self.notify_db {
+            match serde_json::to_string(&event) {
+                Ok(payload) if payload.len() <= MAX_NOTIFY_PAYLOAD => {
+                    let notified = sqlx::query("SELECT pg_notify($1, $2)")
+                        .bind(NOTIFY_CHANNEL)
+                        .bind(&payload)
+                        .execute(db)
+                        .await;
+                    match notified {
+                        // Delivered back to this instance by `forward_notifications`
+                        Ok(_) => return,
+                        Err(e) => tracing::warn!("Failed to NOTIFY {} event, delivering locally: {}", event.kind.as_str(), e),
+                    }
+                }
+                Ok(_) => tracing::warn!("{} event too large for NOTIFY, delivering locally", event.kind.as_str()),
+                Err(e) => tracing::warn!("Failed to serialize {} event: {}", event.kind.as_str(), e),
+            }
+        }
+
+        // Nobody listening is fine
+        let _ = self.sender.send(event);
+    }
+
+    pub fn subscribe(&self, db: DbPool, scope: Scope, session_id: Uuid, kinds: Vec<EventKind>) -> Subscription {
+        Subscription {
+            receiver: self.sender.subscribe(),
+            db,
+            scope,
+            session_id,
+            kinds,
+            customers: HashMap::new(),
+            next_session_check: Instant::now() + SESSION_CHECK_INTERVAL,
+        }
+    }
+
+    /// Relay events NOTIFYed by any instance to local subscribers, reconnecting as needed
+    pub async fn forward_notifications(&self, db: &DbPool) {
+        loop {
+            match PgListener::connect_with(db).await {
+                Ok(mut listener) => match listener.listen(NOTIFY_CHANNEL).await {
+                    Ok(()) => loop {
+                        match listener.recv().await {
+                            Ok(notification) => match serde_json::from_str::<Event>(notification.payload()) {
+                                Ok(event) => {
+                                    let _ = self.sender.send(event);
+                                }
+                                Err(e) => tracing::warn!("Ignoring malformed event notification: {}", e),
+                            },
+                            Err(e) => {
+                                tracing::error!("Event listener failed: {}", e);
+                                break;
+                            }
+                        }
+                    },
+                    Err(e) => tracing::error!("Failed to LISTEN for events: {}", e),
+                },
+                Err(e) => tracing::error!("Failed to connect event listener: {}", e),
+            }
+
+            tokio::time::sleep(LISTENER_RETRY_DELAY).await;
+        }
+    }
+}
+
+pub enum Delivery {
+    Event(Event),
+    /// The subscriber fell behind and missed this many events; clients should refetch
+    Lagged(u64),
+}
+
+/// One `/api/events` stream: the caller's tenant scope and event types over the shared bus
+pub struct Subscription {
+    receiver: broadcast::Receiver<Event>,
+    db: DbPool,
+    scope: Scope,
+    session_id: Uuid,
+    /// Empty receives every type
+    kinds: Vec<EventKind>,
+    /// Reseller scope: whether each event owner seen so far is one of the reseller's customers
+    customers: HashMap<Uuid, bool>,
+    next_session_check: Instant,
+}
+
+impl Subscription {
+    /// Next event for this subscriber; `None` once the bus closes or the session is revoked
+    pub async fn next(&mut self) -> Option<Delivery> {
+        loop {
+            tokio::select! {
+                received = self.receiver.recv() => match received {
+                    Ok(event) => {
+                        if self.wants(&event).await {
+                            return Some(Delivery::Event(event));
+                        }
+                    }
+                    Err(RecvError::Lagged(missed)) => return Some(Delivery::Lagged(missed)),
+                    Err(RecvError::Closed) => return None,
+                },
+                _ = tokio::time::sleep_until(self.next_session_check) => {
+                    match auth_service::is_session_active(&self.db, self.session_id).await {
+                        Ok(true) => {}
+                        Ok(false) => return None,
+                        Err(e) => tracing::warn!("Failed to check event stream session: {}", e),
+                    }
+                    self.next_session_check = Instant::now() + SESSION_CHECK_INTERVAL;
+                }
+            }
+        }
+    }
+
+    async fn wants(&mut self, event: &Event) -> bool {
+        if !self.kinds.is_empty() && !self.kinds.contains(&event.kind) {
+            return false;
+        }
+
+        match self.scope {
+            Scope::All => true,
+            Scope::Owner(id) => event.user_id == id,
+            Scope::Reseller(id) if event.user_id == id => true,
+            Scope::Reseller(id) => {
+                if let Some(&visible) = self.customers.get(&event.user_id) {
+                    return visible;
+                }
+
+                let visible = sqlx::query_scalar::<_, bool>(
+                    "SELECT EXISTS(SELECT 1 FROM users WHERE id = $1 AND parent_id = $2)"
+                )
+                .bind(event.user_id)
+                .bind(id)
+                .fetch_one(&self.db)
+                .await;
+
+                match visible {
+                    Ok(visible) => {
+                        self.customers.insert(event.user_id, visible);
+                        visible
+                    }
+                    Err(e) => {
+                        tracing::warn!("Failed to resolve event owner {}: {}", event.user_id, e);
+                        false
+                    }
+                }
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_support::{self, Tenants};
+
+    /// Owners of the events `subscription` delivers until the bus goes quiet
+    async fn delivered(subscription: &mut Subscription) -> Vec<Uuid> {
+        let mut owners = Vec::new();
+        while let Ok(Some(delivery)) = tokio::time::timeout(Duration::from_millis(100), subscription.next()).await {
+            match delivery {
+                Delivery::Event(event) => owners.push(event.user_id),
+                Delivery::Lagged(missed) => panic!("Subscriber lagged by {} events", missed),
+            }
+        }
+        owners
+    }
+
+    #[tokio::test]
+    async fn subscribers_only_see_their_tenants_events() {
+        let Some(db) = test_support::database().await else { return };
+        let tenants = Tenants::create(&db).await;
+        let bus = EventBus::new(None);
+        let subscribe = |scope, kinds| bus.subscribe(db.clone(), scope, Uuid::new_v4(), kinds);
+
+        let mut admin = subscribe(Scope::All, Vec::new());
+        let mut reseller = subscribe(Scope::Reseller(tenants.reseller), Vec::new());
+        let mut customer = subscribe(Scope::Owner(tenants.customer), Vec::new());
+        let mut user = subscribe(Scope::Owner(tenants.user), Vec::new());
+        let mut reseller_alerts = subscribe(Scope::Reseller(tenants.reseller), vec![EventKind::Alert]);
+
+        let owners = [
+            tenants.reseller,
+            tenants.customer,
+            tenants.other_reseller,
+            tenants.other_customer,
+            tenants.user,
+            tenants.customer,
+        ];
+        for owner in owners {
+            bus.publish(Event::new(EventKind::VpsStatus, owner, serde_json::json!({}))).await;
+        }
+        bus.publish(Event::new(EventKind::Alert, tenants.other_customer, serde_json::json!({}))).await;
+        bus.publish(Event::new(EventKind::Alert, tenants.customer, serde_json::json!({}))).await;
+
+        let seen_by_admin = delivered(&mut admin).await;
+        let seen_by_reseller = delivered(&mut reseller).await;
+        let seen_by_customer = delivered(&mut customer).await;
+        let seen_by_user = delivered(&mut user).await;
+        let alerts_seen_by_reseller = delivered(&mut reseller_alerts).await;
+
+        tenants.delete(&db).await;
+
+        assert_eq!(seen_by_admin.len(), owners.len() + 2);
+        assert_eq!(
+            seen_by_reseller,
+            [tenants.reseller, tenants.customer, tenants.customer, tenants.customer]
+        );
+        assert_eq!(seen_by_customer, [tenants.customer, tenants.customer, tenants.customer]);
+        assert_eq!(seen_by_user, [tenants.user]);
+        assert_eq!(alerts_seen_by_reseller, [tenants.customer]);
+
+        // Each owner other than the reseller was looked up once, the repeated customer came from the cache
+        assert_eq!(reseller.customers.len(), 4);
+        assert_eq!(reseller.customers.get(&tenants.customer), Some(&true));
+        assert_eq!(reseller.customers.get(&tenants.other_customer), Some(&false));
+    }
+
+    #[tokio::test]
+    async fn events_relayed_through_postgres_keep_their_tenant() {
+        let Some(db) = test_support::database().await else { return };
+        let tenants = Tenants::create(&db).await;
+        let bus = std::sync::Arc::new(EventBus::new(Some(db.clone())));
+        let mut reseller = bus.subscribe(db.clone(), Scope::Reseller(tenants.reseller), Uuid::new_v4(), Vec::new());
+        let mut other = bus.subscribe(db.clone(), Scope::Owner(tenants.other_customer), Uuid::new_v4(), Vec::new());
+
+        let relay = {
+            let (bus, db) = (bus.clone(), db.clone());
+            tokio::spawn(async move { bus.forward_notifications(&db).await })
+        };
+        // Give the listener time to LISTEN before anything is NOTIFYed
+        tokio::time::sleep(Duration::from_millis(300)).await;
+
+        bus.publish(Event::new(EventKind::Activity, tenants.customer, serde_json::json!({ "action": "login" }))).await;
+        bus.publish(Event::new(EventKind::Activity, tenants.other_customer, serde_json::json!({}))).await;
+
+        let seen_by_reseller = delivered(&mut reseller).await;
+        let seen_by_other = delivered(&mut other).await;
+        relay.abort();
+
+        tenants.delete(&db).await;
+
+        assert_eq!(seen_by_reseller, [tenants.customer]);
+        assert_eq!(seen_by_other, [tenants.other_customer]);
+    }
+}
@@ -0,0 +1,559 @@
+use crate::{
+    models::{hetzner::*, provider::*, vps::VpsStatus},
+    services::cloud_service::CloudProvider,
+    utils::errors::AppError,
+};
+use async_trait::async_trait;
+use chrono::{DateTime, Utc};
+
+/// Largest page Hetzner serves for list endpoints
+const HETZNER_PAGE_SIZE: u32 = 50;
+
+/// Map a Hetzner error response (`{"error": {"code", "message"}}`) onto our taxonomy
+fn hetzner_error(status: reqwest::StatusCode, body: &str) -> AppError {
+    let message = serde_json::from_str::<serde_json::Value>(body)
+        .ok()
+        .and_then(|v| v["error"]["message"].as_str().map(str::to_string))
+        .unwrap_or_else(|| body.to_string());
+
+    match status.as_u16() {
+        400 | 422 => AppError::BadRequest(format!("Hetzner rejected the request: {}", message)),
+        404 => AppError::NotFound(format!("Hetzner resource not found: {}", message)),
+        409 => AppError::Conflict(format!("Hetzner conflict: {}", message)),
+        429 => AppError::RateLimited("Hetzner API rate limit reached".to_string()),
+        _ => AppError::UpstreamError(format!("Hetzner API error {}: {}", status, message)),
+    }
+}
+
+/// Hetzner server states collapsed onto ours
+fn vps_status(status: &str) -> VpsStatus {
+    match status {
+        "running" => VpsStatus::Running,
+        "initializing" | "starting" | "rebuilding" | "migrating" => VpsStatus::Starting,
+        "stopping" => VpsStatus::Stopping,
+        "off" => VpsStatus::Stopped,
+        "deleting" => VpsStatus::Deleting,
+        _ => VpsStatus::Error,
+    }
+}
+
+fn parse_time(value: &Option<String>) -> Option<DateTime<Utc>> {
+    value
+        .as_deref()
+        .and_then(|value| DateTime::parse_from_rfc3339(value).ok())
+        .map(|time| time.with_timezone(&Utc))
+}
+
+fn action_id(external_id: &str) -> Result<i64, AppError> {
+    external_id
+        .parse()
+        .map_err(|_| AppError::BadRequest(format!("Invalid Hetzner action id: {}", external_id)))
+}
+
+fn server_id(external_id: &str) -> Result<i64, AppError> {
+    external_id
+        .parse()
+        .map_err(|_| AppError::BadRequest(format!("Invalid Hetzner server id: {}", external_id)))
+}
+
+impl From<HetznerServer> for CloudServer {
+    fn from(server: HetznerServer) -> Self {
+        // Prices differ per location; take the one the server was placed in
+        let monthly_cost = server.server_type.prices
+            .iter()
+            .find(|p| p.location == server.datacenter.location.name)
+            .and_then(|p| p.price_monthly.gross.parse::<f64>().ok());
+
+        CloudServer {
+            external_id: server.id.to_string(),
+            status: vps_status(&server.status),
+            image: server.image
+                .map(|image| image.name.unwrap_or(image.description))
+                .unwrap_or_default(),
+            ipv4: server.public_net.ipv4.map(|ip| ip.ip),
+            ipv6: server.public_net.ipv6.map(|ip| ip.ip),
+            cpu_cores: server.server_type.cores,
+            ram_gb: server.server_type.memory as i32,
+            disk_gb: server.server_type.disk,
+            server_type: server.server_type.name,
+            location: server.datacenter.location.name,
+            name: server.name,
+            monthly_cost,
+        }
+    }
+}
+
+impl From<HetznerAction> for CloudAction {
+    fn from(action: HetznerAction) -> Self {
+        CloudAction {
+            external_id: action.id.to_string(),
+            status: match action.status.as_str() {
+                "success" => ActionStatus::Success,
+                "error" => ActionStatus::Error,
+                _ => ActionStatus::Running,
+            },
+            progress: action.progress,
+            error: action.error.map(|e| format!("{}: {}", e.code, e.message)),
+        }
+    }
+}
+
+/// One page of a Hetzner list endpoint
+trait HetznerPage: serde::de::DeserializeOwned {
+    type Item;
+
+    fn into_page(self) -> (Vec<Self::Item>, Option<u32>);
+}
+
+macro_rules! hetzner_page {
+    ($response:ty, $field:ident, $item:ty) => {
+        impl HetznerPage for $response {
+            type Item = $item;
+
+            fn into_page(self) -> (Vec<$item>, Option<u32>) {
+                (self.$field, self.meta.and_then(|meta| meta.pagination.next_page))
+            }
+        }
+    };
+}
+
+hetzner_page!(HetznerServersResponse, servers, HetznerServer);
+hetzner_page!(HetznerServerTypesResponse, server_types, HetznerServerType);
+hetzner_page!(HetznerLocationsResponse, locations, HetznerLocation);
+hetzner_page!(HetznerImagesResponse, images, HetznerImage);
+
+/// Hetzner Cloud API client acting with one account's token
+#[derive(Clone)]
+pub struct HetznerClient {
+    api_url: String,
+    api_token: String,
+    client: reqwest::Client,
+}
+
+impl HetznerClient {
+    pub fn new(client: reqwest::Client, api_url: String, api_token: String) -> Self {
+        Self { api_url, api_token, client }
+    }
+
+    async fn request<T, B>(
+        &self,
+        method: reqwest::Method,
+        endpoint: &str,
+        body: Option<B>,
+    ) -> Result<T, AppError>
+    where
+        T: serde::de::DeserializeOwned,
+        B: serde::Serialize,
+    {
+        let url = format!("{}{}", self.api_url, endpoint);
+
+        let mut req = self.client
+            .request(method, &url)
+            .header("Authorization", format!("Bearer {}", self.api_token));
+
+        if let Some(body) = body {
+            req = req.json(&body);
+        }
+
+        let response = req.send().await.map_err(|e| {
+            if e.is_timeout() {
+                AppError::Timeout("Hetzner API did not respond in time".to_string())
+            } else {
+                AppError::UpstreamError(format!("Hetzner API request failed: {}", e))
+            }
+        })?;
+
+        if !response.status().is_success() {
+            let status = response.status();
+            let error_text = response.text().await.unwrap_or_default();
+            return Err(hetzner_error(status, &error_text));
+        }
+
+        response.json().await
+            .map_err(|e| AppError::UpstreamError(format!("Failed to parse Hetzner response: {}", e)))
+    }
+
+    /// Every item of a paginated list endpoint
+    async fn list_all<P: HetznerPage>(&self, endpoint: &str) -> Result<Vec<P::Item>, AppError> {
+        let separator = if endpoint.contains('?') { '&' } else { '?' };
+        let mut items = Vec::new();
+        let mut page = 1;
+
+        loop {
+            let response: P = self.request(
+                reqwest::Method::GET,
+                &format!("{}{}per_page={}&page={}", endpoint, separator, HETZNER_PAGE_SIZE, page),
+                None::<()>,
+            ).await?;
+
+            let (mut batch, next_page) = response.into_page();
+            items.append(&mut batch);
+
+            match next_page {
+                Some(next_page) if next_page > page => page = next_page,
+                _ => return Ok(items),
+            }
+        }
+    }
+
+    async fn action(&self, external_id: &str, action: &str) -> Result<CloudAction, AppError> {
+        let response: HetznerActionResponse = self.request(
+            reqwest::Method::POST,
+            &format!("/servers/{}/actions/{}", server_id(external_id)?, action),
+            None::<()>,
+        ).await?;
+
+        Ok(response.action.into())
+    }
+}
+
+#[async_trait]
+impl CloudProvider for HetznerClient {
+    async fn create_server(&self, request: CloudServerRequest) -> Result<CloudServer, AppError> {
+        let request = HetznerCreateServerRequest {
+            name: request.name,
+            server_type: request.server_type,
+            location: request.location,
+            image: request.image,
+            ssh_keys: request.ssh_keys,
+            user_data: request.user_data,
+            start_after_create: true,
+        };
+
+        let response: HetznerServerResponse = self.request(
+            reqwest::Method::POST,
+            "/servers",
+            Some(request),
+        ).await?;
+
+        Ok(response.server.into())
+    }
+
+    async fn get_server(&self, external_id: &str) -> Result<CloudServer, AppError> {
+        let response: HetznerServerResponse = self.request(
+            reqwest::Method::GET,
+            &format!("/servers/{}", server_id(external_id)?),
+            None::<()>,
+        ).await?;
+
+        Ok(response.server.into())
+    }
+
+    async fn list_servers(&self) -> Result<Vec<CloudServer>, AppError> {
+        let servers = self.list_all::<HetznerServersResponse>("/servers").await?;
+        Ok(servers.into_iter().map(CloudServer::from).collect())
+    }
+
+    async fn delete_server(&self, external_id: &str) -> Result<(), AppError> {
+        self.request::<serde_json::Value, ()>(
+            reqwest::Method::DELETE,
+            &format!("/servers/{}", server_id(external_id)?),
+            None::<()>,
+        ).await?;
+
+        Ok(())
+    }
+
+    async fn power_on(&self, external_id: &str) -> Result<CloudAction, AppError> {
+        self.action(external_id, "poweron").await
+    }
+
+    async fn power_off(&self, external_id: &str) -> Result<CloudAction, AppError> {
+        self.action(external_id, "poweroff").await
+    }
+
+    async fn reboot(&self, external_id: &str) -> Result<CloudAction, AppError> {
+        self.action(external_id, "reboot").await
+    }
+
+    async fn get_action(&self, external_id: &str) -> Result<CloudAction, AppError> {
+        let response: HetznerActionResponse = self.request(
+            reqwest::Method::GET,
+            &format!("/actions/{}", action_id(external_id)?),
+            None::<()>,
+        ).await?;
+
+        Ok(response.action.into())
+    }
+
+    async fn create_snapshot(&self, external_id: &str, description: &str) -> Result<CloudSnapshot, AppError> {
+        let request = HetznerCreateImageRequest {
+            image_type: "snapshot".to_string(),
+            description: description.to_string(),
+        };
+
+        let response: HetznerImageResponse = self.request(
+            reqwest::Method::POST,
+            &format!("/servers/{}/actions/create_image", server_id(external_id)?),
+            Some(request),
+        ).await?;
+
+        Ok(CloudSnapshot {
+            external_id: response.image.id.to_string(),
+            created_at: parse_time(&response.image.created),
+            description: response.image.description,
+        })
+    }
+
+    async fn list_server_types(&self) -> Result<Vec<ServerTypeOption>, AppError> {
+        let server_types = self.list_all::<HetznerServerTypesResponse>("/server_types").await?;
+
+        Ok(server_types
+            .into_iter()
+            .map(|server_type| ServerTypeOption {
+                prices: server_type.prices
+                    .into_iter()
+                    .map(|price| ServerTypePrice {
+                        monthly_gross: price.price_monthly.gross.parse().ok(),
+                        location: price.location,
+                    })
+                    .collect(),
+                name: server_type.name,
+                description: server_type.description,
+                cores: server_type.cores,
+                memory_gb: server_type.memory,
+                disk_gb: server_type.disk,
+            })
+            .collect())
+    }
+
+    async fn list_locations(&self) -> Result<Vec<LocationOption>, AppError> {
+        let locations = self.list_all::<HetznerLocationsResponse>("/locations").await?;
+
+        Ok(locations
+            .into_iter()
+            .map(|location| LocationOption {
+                name: location.name,
+                description: location.description,
+                city: location.city,
+                country: location.country,
+            })
+            .collect())
+    }
+
+    async fn list_images(&self) -> Result<Vec<ImageOption>, AppError> {
+        let images = self.list_all::<HetznerImagesResponse>("/images?type=system").await?;
+
+        Ok(images
+            .into_iter()
+            .filter_map(|image| {
+                image.name.map(|name| ImageOption {
+                    name,
+                    description: image.description,
+                })
+            })
+            .collect())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_support;
+    use axum::{
+        extract::{Path, Query},
+        http::{HeaderMap, StatusCode},
+        response::{IntoResponse, Response},
+        routing::{get, post},
+        Json, Router,
+    };
+    use std::collections::HashMap;
+
+    /// Server ids pick the response: 1-8 map to each status, 4xx/5xx ids answer with that error
+    async fn get_server(Path(id): Path<i64>, headers: HeaderMap) -> Response {
+        if headers.get("authorization").and_then(|v| v.to_str().ok()) != Some("Bearer test-token") {
+            return StatusCode::UNAUTHORIZED.into_response();
+        }
+
+        if id >= 400 {
+            let status = StatusCode::from_u16(id as u16).unwrap();
+            let error = serde_json::json!({ "error": { "code": "mock", "message": "mock failure" } });
+            return (status, Json(error)).into_response();
+        }
+
+        let status = ["running", "initializing", "starting", "rebuilding", "stopping", "off", "deleting", "unknown"]
+            [(id - 1) as usize];
+        let server = test_support::hetzner_server(id, &format!("server-{}", id), status);
+        Json(serde_json::json!({ "server": server })).into_response()
+    }
+
+    async fn list_servers(Query(query): Query<HashMap<String, String>>) -> Json<serde_json::Value> {
+        assert_eq!(query.get("per_page").map(String::as_str), Some("50"));
+        match query.get("page").map(String::as_str) {
+            Some("1") => Json(serde_json::json!({
+                "servers": [
+                    test_support::hetzner_server(1, "server-1", "running"),
+                    test_support::hetzner_server(2, "server-2", "off")
+                ],
+                "meta": { "pagination": { "next_page": 2 } }
+            })),
+            _ => Json(serde_json::json!({
+                "servers": [test_support::hetzner_server(3, "server-3", "running")],
+                "meta": { "pagination": { "next_page": null } }
+            })),
+        }
+    }
+
+    async fn server_action(Path((id, action)): Path<(i64, String)>) -> Json<serde_json::Value> {
+        assert_eq!(action, "poweron");
+        Json(serde_json::json!({
+            "action": { "id": 100 + id, "status": "running", "progress": 0, "error": null }
+        }))
+    }
+
+    async fn create_image(Path(id): Path<i64>, Json(body): Json<serde_json::Value>) -> Json<serde_json::Value> {
+        assert_eq!(id, 1);
+        assert_eq!(body["type"], "snapshot");
+        Json(serde_json::json!({
+            "image": {
+                "id": 5001,
+                "name": null,
+                "description": body["description"],
+                "created": "2026-10-17T10:05:00+00:00"
+            }
+        }))
+    }
+
+    /// Action 77 succeeded, 78 failed, anything else is still running
+    async fn get_action(Path(id): Path<i64>) -> Json<serde_json::Value> {
+        let action = match id {
+            77 => serde_json::json!({ "id": id, "status": "success", "progress": 100, "error": null }),
+            78 => serde_json::json!({
+                "id": id,
+                "status": "error",
+                "progress": 40,
+                "error": { "code": "action_failed", "message": "Action failed" }
+            }),
+            _ => serde_json::json!({ "id": id, "status": "running", "progress": 50, "error": null }),
+        };
+        Json(serde_json::json!({ "action": action }))
+    }
+
+    async fn list_images() -> Json<serde_json::Value> {
+        Json(serde_json::json!({
+            "images": [
+                { "id": 1, "name": "ubuntu-24.04", "description": "Ubuntu 24.04", "created": null },
+                { "id": 2, "name": null, "description": "Backup", "created": null }
+            ]
+        }))
+    }
+
+    async fn client() -> HetznerClient {
+        let router = Router::new()
+            .route("/servers", get(list_servers))
+            .route("/servers/:id", get(get_server))
+            .route("/servers/:id/actions/create_image", post(create_image))
+            .route("/servers/:id/actions/:action", post(server_action))
+            .route("/actions/:id", get(get_action))
+            .route("/images", get(list_images));
+        let base = test_support::serve(router).await;
+
+        HetznerClient::new(reqwest::Client::new(), base, "test-token".to_string())
+    }
+
+    #[tokio::test]
+    async fn server_states_map_onto_vps_status() {
+        let client = client().await;
+        let expected = [
+            VpsStatus::Running,
+            VpsStatus::Starting,
+            VpsStatus::Starting,
+            VpsStatus::Starting,
+            VpsStatus::Stopping,
+            VpsStatus::Stopped,
+            VpsStatus::Deleting,
+            VpsStatus::Error,
+        ];
+
+        for (id, status) in expected.into_iter().enumerate() {
+            let server = client.get_server(&(id + 1).to_string()).await.unwrap();
+            assert_eq!(server.status, status);
+        }
+    }
+
+    #[tokio::test]
+    async fn server_fields_are_converted() {
+        let server = client().await.get_server("1").await.unwrap();
+
+        assert_eq!(server.external_id, "1");
+        assert_eq!(server.ipv4.as_deref(), Some("203.0.113.20"));
+        assert_eq!(server.ipv6.as_deref(), Some("2001:db8::/64"));
+        assert_eq!(server.cpu_cores, 2);
+        assert_eq!(server.ram_gb, 4);
+        assert_eq!(server.server_type, "cx22");
+        assert_eq!(server.location, "nbg1");
+        assert_eq!(server.image, "ubuntu-24.04");
+        // Priced at the server's own location, not the first listed
+        assert_eq!(server.monthly_cost, Some(5.05));
+    }
+
+    #[tokio::test]
+    async fn list_servers_follows_pagination() {
+        let servers = client().await.list_servers().await.unwrap();
+
+        let ids: Vec<_> = servers.iter().map(|server| server.external_id.as_str()).collect();
+        assert_eq!(ids, ["1", "2", "3"]);
+    }
+
+    #[tokio::test]
+    async fn error_responses_map_onto_app_errors() {
+        let client = client().await;
+
+        for code in [400, 422] {
+            let error = client.get_server(&code.to_string()).await.unwrap_err();
+            assert!(matches!(&error, AppError::BadRequest(message) if message.contains("mock failure")), "{:?}", error);
+        }
+        assert!(matches!(client.get_server("404").await, Err(AppError::NotFound(_))));
+        assert!(matches!(client.get_server("409").await, Err(AppError::Conflict(_))));
+        assert!(matches!(client.get_server("429").await, Err(AppError::RateLimited(_))));
+        assert!(matches!(client.get_server("503").await, Err(AppError::UpstreamError(_))));
+        assert!(matches!(client.get_server("not-a-number").await, Err(AppError::BadRequest(_))));
+    }
+
+    #[tokio::test]
+    async fn requests_carry_the_account_token() {
+        let base = test_support::serve(Router::new().route("/servers/:id", get(get_server))).await;
+        let client = HetznerClient::new(reqwest::Client::new(), base, "other-token".to_string());
+
+        assert!(matches!(client.get_server("1").await, Err(AppError::UpstreamError(_))));
+    }
+
+    #[tokio::test]
+    async fn actions_are_parsed() {
+        let client = client().await;
+
+        let started = client.power_on("1").await.unwrap();
+        assert_eq!(started.external_id, "101");
+        assert_eq!(started.status, ActionStatus::Running);
+
+        let running = client.get_action("101").await.unwrap();
+        assert_eq!(running.status, ActionStatus::Running);
+        assert_eq!(running.progress, 50);
+
+        let finished = client.get_action("77").await.unwrap();
+        assert_eq!(finished.status, ActionStatus::Success);
+        assert_eq!(finished.progress, 100);
+        assert_eq!(finished.error, None);
+
+        let errored = client.get_action("78").await.unwrap();
+        assert_eq!(errored.status, ActionStatus::Error);
+        assert_eq!(errored.error.as_deref(), Some("action_failed: Action failed"));
+    }
+
+    #[tokio::test]
+    async fn snapshot_returns_the_image_id() {
+        let snapshot = client().await.create_snapshot("1", "nightly").await.unwrap();
+
+        assert_eq!(snapshot.external_id, "5001");
+        assert_eq!(snapshot.description, "nightly");
+        assert_eq!(snapshot.created_at.unwrap().to_rfc3339(), "2026-10-17T10:05:00+00:00");
+    }
+
+    #[tokio::test]
+    async fn only_named_images_are_offered() {
+        let images = client().await.list_images().await.unwrap();
+
+        let names: Vec<_> = images.iter().map(|image| image.name.as_str()).collect();
+        assert_eq!(names, ["ubuntu-24.04"]);
+    }
+}
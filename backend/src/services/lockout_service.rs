@@ -0,0 +1,81 @@
+use crate::{
+    config::Config,
+    database::DbPool,
+    models::auth::AccountLockout,
+    utils::errors::AppError,
+};
+use chrono::{Duration, Utc};
+
+pub async fn ensure_not_locked(db: &DbPool, email: &str) -> Result<(), AppError> {
+    let locked = sqlx::query_scalar::<_, bool>(
+        "SELECT EXISTS(SELECT 1 FROM account_lockouts WHERE email = $1 AND locked_until > $2)"
+    )
+    .bind(email.to_lowercase())
+    .bind(Utc::now())
+    .fetch_one(db)
+    .await?;
+
+    if locked {
+        return Err(AppError::RateLimited(
+            "Account temporarily locked due to too many failed login attempts".to_string(),
+        ));
+    }
+
+    Ok(())
+}
+
+/// Count a failed login; failures older than the lockout window start a fresh count
+pub async fn register_failure(db: &DbPool, config: &Config, email: &str) -> Result<(), AppError> {
+    let now = Utc::now();
+    let window = Duration::seconds(config.login_lockout_seconds);
+
+    let failed_attempts = sqlx::query_scalar::<_, i32>(
+        "INSERT INTO account_lockouts (email, failed_attempts, updated_at)
+         VALUES ($1, 1, $2)
+         ON CONFLICT (email) DO UPDATE
+         SET failed_attempts = CASE
+                 WHEN account_lockouts.updated_at < $3 THEN 1
+                 ELSE account_lockouts.failed_attempts + 1
+             END,
+             updated_at = $2
+         RETURNING failed_attempts"
+    )
+    .bind(email.to_lowercase())
+    .bind(now)
+    .bind(now - window)
+    .fetch_one(db)
+    .await?;
+
+    if failed_attempts >= config.login_max_failures {
+        sqlx::query("UPDATE account_lockouts SET locked_until = $1 WHERE email = $2")
+            .bind(now + window)
+            .bind(email.to_lowercase())
+            .execute(db)
+            .await?;
+
+        tracing::warn!("Locked account {} after {} failed logins", email, failed_attempts);
+    }
+
+    Ok(())
+}
+
+/// Reset the failure count after a successful login or an admin unlock
+pub async fn clear(db: &DbPool, email: &str) -> Result<bool, AppError> {
+    let result = sqlx::query("DELETE FROM account_lockouts WHERE email = $1")
+        .bind(email.to_lowercase())
+        .execute(db)
+        .await?;
+
+    Ok(result.rows_affected() > 0)
+}
+
+pub async fn list_active(db: &DbPool) -> Result<Vec<AccountLockout>, AppError> {
+    let lockouts = sqlx::query_as::<_, AccountLockout>(
+        "SELECT * FROM account_lockouts WHERE locked_until > $1 ORDER BY locked_until DESC"
+    )
+    .bind(Utc::now())
+    .fetch_all(db)
+    .await?;
+
+    Ok(lockouts)
+}
@@ -0,0 +1,193 @@
+use crate::{config::Config, utils::errors::AppError};
+use async_trait::async_trait;
+use chrono::Utc;
+use lettre::{
+    message::{header::ContentType, Mailbox},
+    transport::smtp::authentication::Credentials,
+    AsyncSmtpTransport, AsyncTransport, Message, Tokio1Executor,
+};
+use std::{path::PathBuf, sync::Arc};
+use tokio::io::AsyncWriteExt;
+
+#[derive(Debug, Clone)]
+pub struct MailMessage {
+    pub to: String,
+    pub subject: String,
+    pub body: String,
+}
+
+/// Outgoing mail transport
+#[async_trait]
+pub trait MailSender: Send + Sync {
+    async fn send(&self, message: &MailMessage) -> Result<(), AppError>;
+}
+
+pub struct SmtpMailer {
+    transport: AsyncSmtpTransport<Tokio1Executor>,
+    from: Mailbox,
+}
+
+impl SmtpMailer {
+    pub fn new(config: &Config) -> Result<Self, AppError> {
+        let host = config.smtp_host.as_deref()
+            .ok_or(AppError::InternalError("SMTP_HOST must be set for the smtp mail transport".to_string()))?;
+
+        let mut builder = AsyncSmtpTransport::<Tokio1Executor>::starttls_relay(host)
+            .map_err(|e| AppError::InternalError(format!("Invalid SMTP relay: {}", e)))?
+            .port(config.smtp_port);
+
+        if let (Some(username), Some(password)) = (&config.smtp_username, &config.smtp_password) {
+            builder = builder.credentials(Credentials::new(username.clone(), password.clone()));
+        }
+
+        Ok(Self {
+            transport: builder.build(),
+            from: parse_mailbox(&config.mail_from)?,
+        })
+    }
+}
+
+#[async_trait]
+impl MailSender for SmtpMailer {
+    async fn send(&self, message: &MailMessage) -> Result<(), AppError> {
+        let email = Message::builder()
+            .from(self.from.clone())
+            .to(parse_mailbox(&message.to)?)
+            .subject(&message.subject)
+            .header(ContentType::TEXT_PLAIN)
+            .body(message.body.clone())
+            .map_err(|e| AppError::InternalError(format!("Failed to build email: {}", e)))?;
+
+        self.transport.send(email).await
+            .map_err(|e| AppError::UpstreamError(format!("SMTP delivery failed: {}", e)))?;
+
+        Ok(())
+    }
+}
+
+/// Appends every message to a local file; for development and tests without a mail server
+pub struct FileMailer {
+    path: PathBuf,
+}
+
+#[async_trait]
+impl MailSender for FileMailer {
+    async fn send(&self, message: &MailMessage) -> Result<(), AppError> {
+        let entry = format!(
+            "Date: {}\nTo: {}\nSubject: {}\n\n{}\n\n---\n",
+            Utc::now().to_rfc2822(),
+            message.to,
+            message.subject,
+            message.body
+        );
+
+        let mut file = tokio::fs::OpenOptions::new()
+            .create(true)
+            .append(true)
+            .open(&self.path)
+            .await
+            .map_err(|e| AppError::InternalError(format!("Failed to open mail file: {}", e)))?;
+
+        file.write_all(entry.as_bytes()).await
+            .map_err(|e| AppError::InternalError(format!("Failed to write mail file: {}", e)))
+    }
+}
+
+/// Writes messages to the application log instead of delivering them
+pub struct LogMailer;
+
+#[async_trait]
+impl MailSender for LogMailer {
+    async fn send(&self, message: &MailMessage) -> Result<(), AppError> {
+        tracing::info!(
+            "Mail to {} - {}\n{}",
+            message.to,
+            message.subject,
+            message.body
+        );
+        Ok(())
+    }
+}
+
+fn parse_mailbox(address: &str) -> Result<Mailbox, AppError> {
+    address.parse()
+        .map_err(|e| AppError::BadRequest(format!("Invalid email address {}: {}", address, e)))
+}
+
+/// Build the transport selected by `MAIL_TRANSPORT`
+pub fn from_config(config: &Config) -> Result<Arc<dyn MailSender>, AppError> {
+    match config.mail_transport.as_str() {
+        "smtp" => Ok(Arc::new(SmtpMailer::new(config)?)),
+        "file" => {
+            let path = config.mail_file_path.clone()
+                .ok_or(AppError::InternalError("MAIL_FILE_PATH must be set for the file mail transport".to_string()))?;
+            Ok(Arc::new(FileMailer { path: PathBuf::from(path) }))
+        }
+        "log" => Ok(Arc::new(LogMailer)),
+        other => Err(AppError::InternalError(format!("Unknown MAIL_TRANSPORT: {}", other))),
+    }
+}
+
+/// Deliver in the background so response timing does not depend on the mail server
+pub fn send_in_background(mailer: Arc<dyn MailSender>, message: MailMessage) {
+    tokio::spawn(async move {
+        if let Err(e) = mailer.send(&message).await {
+            tracing::error!("Failed to send mail to {}: {}", message.to, e);
+        }
+    });
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn message(subject: &str) -> MailMessage {
+        MailMessage {
+            to: "user@example.com".to_string(),
+            subject: subject.to_string(),
+            body: "Hello".to_string(),
+        }
+    }
+
+    #[tokio::test]
+    async fn file_mailer_appends_messages() {
+        let path = std::env::temp_dir().join(format!("unified-panel-mail-{}.log", uuid::Uuid::new_v4()));
+        let mailer = FileMailer { path: path.clone() };
+
+        mailer.send(&message("First")).await.unwrap();
+        mailer.send(&message("Second")).await.unwrap();
+
+        let contents = tokio::fs::read_to_string(&path).await.unwrap();
+        tokio::fs::remove_file(&path).await.ok();
+        assert_eq!(contents.matches("To: user@example.com\n").count(), 2);
+        assert!(contents.find("Subject: First").unwrap() < contents.find("Subject: Second").unwrap());
+        assert!(contents.contains("\n\nHello\n\n---\n"));
+    }
+
+    #[tokio::test]
+    async fn file_mailer_reports_unwritable_paths() {
+        let mailer = FileMailer { path: PathBuf::from("/nonexistent-dir/mail.log") };
+
+        assert!(matches!(mailer.send(&message("Lost")).await, Err(AppError::InternalError(_))));
+    }
+
+    #[tokio::test]
+    async fn log_mailer_accepts_every_message() {
+        assert!(LogMailer.send(&message("Logged")).await.is_ok());
+    }
+
+    #[test]
+    fn transport_is_chosen_from_config() {
+        let mut config = crate::test_support::config();
+
+        config.mail_transport = "log".to_string();
+        assert!(from_config(&config).is_ok());
+
+        config.mail_transport = "file".to_string();
+        config.mail_file_path = None;
+        assert!(from_config(&config).is_err());
+
+        config.mail_transport = "pigeon".to_string();
+        assert!(from_config(&config).is_err());
+    }
+}
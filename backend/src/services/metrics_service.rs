@@ -0,0 +1,878 @@
+use crate::{
+    config::Config,
+    database::DbPool,
+    models::{
+        auth::Scope,
+        event::{Event, EventKind},
+        server::*,
+    },
+    services::{event_service::EventBus, server_service},
+    utils::errors::AppError,
+};
+use chrono::{DateTime, Duration, Utc};
+use uuid::Uuid;
+
+/// Metric name prefixes in rollup tables paired with their raw `server_metrics` column
+const METRIC_COLUMNS: [(&str, &str); 5] = [
+    ("cpu", "cpu_usage"),
+    ("memory", "memory_usage"),
+    ("disk", "disk_usage"),
+    ("network_in", "network_in"),
+    ("network_out", "network_out"),
+];
+
+/// Cap on raw samples returned for a time window queried without `step`
+const RAW_QUERY_LIMIT: i64 = 1000;
+const MAX_BUCKETS: i64 = 10_000;
+
+/// Rows inserted within this window may belong to transactions that have not committed yet
+const ROLLUP_LAG_SECONDS: i64 = 60;
+
+/// Readings closer together than this give no meaningful rate (e.g. a resent sample with a
+/// nudged timestamp) and would divide a byte delta by almost nothing
+const MIN_RATE_INTERVAL_SECONDS: f64 = 1.0;
+
+#[derive(Debug, Clone, Copy)]
+enum Resolution {
+    Minute,
+    Hour,
+    Day,
+}
+
+impl Resolution {
+    const ALL: [Resolution; 3] = [Resolution::Minute, Resolution::Hour, Resolution::Day];
+
+    fn as_str(&self) -> &'static str {
+        match self {
+            Resolution::Minute => "1m",
+            Resolution::Hour => "1h",
+            Resolution::Day => "1d",
+        }
+    }
+
+    fn table(&self) -> &'static str {
+        match self {
+            Resolution::Minute => "server_metrics_1m",
+            Resolution::Hour => "server_metrics_1h",
+            Resolution::Day => "server_metrics_1d",
+        }
+    }
+
+    fn seconds(&self) -> i64 {
+        match self {
+            Resolution::Minute => 60,
+            Resolution::Hour => 3_600,
+            Resolution::Day => 86_400,
+        }
+    }
+
+    /// How long rows are kept; daily rollups are never pruned
+    fn retention(&self, config: &Config) -> Option<Duration> {
+        match self {
+            Resolution::Minute => Some(Duration::days(config.metrics_minute_retention_days)),
+            Resolution::Hour => Some(Duration::days(config.metrics_hour_retention_days)),
+            Resolution::Day => None,
+        }
+    }
+
+    /// The finer resolution this one is built from; `None` means raw samples
+    fn source(&self) -> Option<Resolution> {
+        match self {
+            Resolution::Minute => None,
+            Resolution::Hour => Some(Resolution::Minute),
+            Resolution::Day => Some(Resolution::Hour),
+        }
+    }
+}
+
+/// Aggregate columns over raw samples aliased `m`
+fn raw_aggregates() -> String {
+    METRIC_COLUMNS
+        .iter()
+        .map(|(name, column)| {
+            format!(
+                "AVG(m.{column})::float8 AS {name}_avg, MIN(m.{column})::float8 AS {name}_min, \
+                 MAX(m.{column})::float8 AS {name}_max, \
+                 percentile_cont(0.95) WITHIN GROUP (ORDER BY m.{column}::float8) AS {name}_p95"
+            )
+        })
+        .collect::<Vec<_>>()
+        .join(", ")
+}
+
+/// Aggregate columns over rollup rows aliased `m`; p95 becomes the highest p95 (an upper bound)
+fn rollup_aggregates() -> String {
+    METRIC_COLUMNS
+        .iter()
+        .map(|(name, _)| {
+            format!(
+                "SUM(m.{name}_avg * m.samples) / SUM(m.samples) AS {name}_avg, MIN(m.{name}_min) AS {name}_min, \
+                 MAX(m.{name}_max) AS {name}_max, MAX(m.{name}_p95) AS {name}_p95"
+            )
+        })
+        .collect::<Vec<_>>()
+        .join(", ")
+}
+
+fn rollup_columns() -> String {
+    METRIC_COLUMNS
+        .iter()
+        .map(|(name, _)| format!("{name}_avg, {name}_min, {name}_max, {name}_p95"))
+        .collect::<Vec<_>>()
+        .join(", ")
+}
+
+/// Previous cumulative network counters for a server
+struct Counters {
+    network_in: i64,
+    network_out: i64,
+    sampled_at: DateTime<Utc>,
+}
+
+/// Bytes per second between two counter readings; a smaller reading means the counter reset
+/// (e.g. a reboot), in which case it is assumed to have restarted from zero
+fn counter_rate(previous: i64, current: i64, seconds: f64) -> i64 {
+    if seconds < MIN_RATE_INTERVAL_SECONDS {
+        return 0;
+    }
+
+    let delta = if current >= previous { current - previous } else { current };
+    (delta as f64 / seconds).round() as i64
+}
+
+/// Network rates for a sample taken at `timestamp`; `None` if it is not newer than `previous`.
+/// Without a baseline there is nothing to compare against, so the rates are zero.
+fn network_rates(previous: Option<&Counters>, network_in: i64, network_out: i64, timestamp: DateTime<Utc>) -> Option<(i64, i64)> {
+    match previous {
+        Some(prev) if timestamp <= prev.sampled_at => None,
+        Some(prev) => {
+            let seconds = (timestamp - prev.sampled_at).num_milliseconds() as f64 / 1000.0;
+            Some((
+                counter_rate(prev.network_in, network_in, seconds),
+                counter_rate(prev.network_out, network_out, seconds),
+            ))
+        }
+        None => Some((0, 0)),
+    }
+}
+
+async fn replace_mounts(
+    tx: &mut sqlx::Transaction<'_, sqlx::Postgres>,
+    server_id: Uuid,
+    mounts: &[MountUsage],
+) -> Result<(), AppError> {
+    sqlx::query("DELETE FROM server_mounts WHERE server_id = $1")
+        .bind(server_id)
+        .execute(&mut **tx)
+        .await?;
+
+    for mount in mounts {
+        sqlx::query(
+            "INSERT INTO server_mounts (server_id, mount_point, filesystem, total_bytes, used_bytes, updated_at)
+             VALUES ($1, $2, $3, $4, $5, $6)
+             ON CONFLICT (server_id, mount_point) DO NOTHING"
+        )
+        .bind(server_id)
+        .bind(&mount.mount_point)
+        .bind(&mount.filesystem)
+        .bind(mount.total_bytes)
+        .bind(mount.used_bytes)
+        .bind(Utc::now())
+        .execute(&mut **tx)
+        .await?;
+    }
+
+    Ok(())
+}
+
+pub async fn list_mounts(db: &DbPool, scope: &Scope, server_id: Uuid) -> Result<Vec<ServerMount>, AppError> {
+    server_service::get_server(db, scope, server_id).await?;
+
+    let mounts = sqlx::query_as::<_, ServerMount>(
+        "SELECT mount_point, filesystem, total_bytes, used_bytes, updated_at
+         FROM server_mounts
+         WHERE server_id = $1
+         ORDER BY mount_point"
+    )
+    .bind(server_id)
+    .fetch_all(db)
+    .await?;
+
+    Ok(mounts)
+}
+
+/// Store agent samples for `server`, deriving network rates from the cumulative counters
+///
+/// Samples at or before the newest stored counter reading are skipped so retries are idempotent.
+/// The first sample ever seen for a server only establishes the counter baseline and is stored
+/// with zero network rates.
+pub async fn ingest_metrics(
+    db: &DbPool,
+    events: &EventBus,
+    server: &Server,
+    mut samples: Vec<MetricSample>,
+) -> Result<IngestMetricsResponse, AppError> {
+    let received_at = Utc::now();
+    samples.sort_by_key(|sample| sample.timestamp.unwrap_or(received_at));
+
+    let mut tx = db.begin().await?;
+
+    let mut previous = sqlx::query_as::<_, (i64, i64, DateTime<Utc>)>(
+        "SELECT network_in, network_out, sampled_at FROM server_metric_counters
+         WHERE server_id = $1
+         FOR UPDATE"
+    )
+    .bind(server.id)
+    .fetch_optional(&mut *tx)
+    .await?
+    .map(|(network_in, network_out, sampled_at)| Counters { network_in, network_out, sampled_at });
+
+    let mut accepted = 0;
+    // Only the newest accepted sample's mounts describe the current state
+    let mut latest_mounts = None;
+    let mut latest_sample = None;
+    for sample in &samples {
+        let timestamp = sample.timestamp.unwrap_or(received_at);
+
+        let Some((network_in, network_out)) =
+            network_rates(previous.as_ref(), sample.network_in, sample.network_out, timestamp)
+        else {
+            continue;
+        };
+
+        sqlx::query(
+            "INSERT INTO server_metrics (id, server_id, cpu_usage, memory_usage, disk_usage, network_in, network_out,
+                                         load_1, load_5, load_15, timestamp)
+             VALUES ($1, $2, $3, $4, $5, $6, $7, $8, $9, $10, $11)"
+        )
+        .bind(Uuid::new_v4())
+        .bind(server.id)
+        .bind(sample.cpu_usage)
+        .bind(sample.memory_usage)
+        .bind(sample.disk_usage)
+        .bind(network_in)
+        .bind(network_out)
+        .bind(sample.load_1)
+        .bind(sample.load_5)
+        .bind(sample.load_15)
+        .bind(timestamp)
+        .execute(&mut *tx)
+        .await?;
+
+        previous = Some(Counters {
+            network_in: sample.network_in,
+            network_out: sample.network_out,
+            sampled_at: timestamp,
+        });
+        if sample.mounts.is_some() {
+            latest_mounts = sample.mounts.as_deref();
+        }
+        latest_sample = Some(serde_json::json!({
+            "server_id": server.id,
+            "cpu_usage": sample.cpu_usage,
+            "memory_usage": sample.memory_usage,
+            "disk_usage": sample.disk_usage,
+            "network_in": network_in,
+            "network_out": network_out,
+            "load_1": sample.load_1,
+            "load_5": sample.load_5,
+            "load_15": sample.load_15,
+            "timestamp": timestamp,
+        }));
+        accepted += 1;
+    }
+
+    if let Some(mounts) = latest_mounts {
+        replace_mounts(&mut tx, server.id, mounts).await?;
+    }
+
+    if let Some(latest) = previous.as_ref().filter(|_| accepted > 0) {
+        sqlx::query(
+            "INSERT INTO server_metric_counters (server_id, network_in, network_out, sampled_at)
+             VALUES ($1, $2, $3, $4)
+             ON CONFLICT (server_id) DO UPDATE
+             SET network_in = EXCLUDED.network_in, network_out = EXCLUDED.network_out,
+                 sampled_at = EXCLUDED.sampled_at"
+        )
+        .bind(server.id)
+        .bind(latest.network_in)
+        .bind(latest.network_out)
+        .bind(latest.sampled_at)
+        .execute(&mut *tx)
+        .await?;
+
+        // An agent reporting in means the server is up, unless it was deliberately put in maintenance
+        let status = match server.get_status() {
+            ServerStatus::Maintenance => ServerStatus::Maintenance,
+            _ => ServerStatus::Online,
+        };
+
+        sqlx::query(
+            "UPDATE servers
+             SET status = $1, last_seen_at = GREATEST(last_seen_at, $2), updated_at = $3
+             WHERE id = $4"
+        )
+        .bind(status.as_str())
+        .bind(latest.sampled_at)
+        .bind(Utc::now())
+        .bind(server.id)
+        .execute(&mut *tx)
+        .await?;
+    }
+
+    tx.commit().await?;
+
+    if let Some(sample) = latest_sample {
+        events.publish(Event::new(EventKind::MetricsSample, server.user_id, sample)).await;
+    }
+
+    Ok(IngestMetricsResponse {
+        accepted,
+        skipped: samples.len() - accepted,
+    })
+}
+
+#[derive(sqlx::FromRow)]
+struct MetricBucketRow {
+    bucket: DateTime<Utc>,
+    samples: i64,
+    cpu_avg: f64,
+    cpu_min: f64,
+    cpu_max: f64,
+    cpu_p95: f64,
+    memory_avg: f64,
+    memory_min: f64,
+    memory_max: f64,
+    memory_p95: f64,
+    disk_avg: f64,
+    disk_min: f64,
+    disk_max: f64,
+    disk_p95: f64,
+    network_in_avg: f64,
+    network_in_min: f64,
+    network_in_max: f64,
+    network_in_p95: f64,
+    network_out_avg: f64,
+    network_out_min: f64,
+    network_out_max: f64,
+    network_out_p95: f64,
+}
+
+impl From<MetricBucketRow> for MetricBucket {
+    fn from(row: MetricBucketRow) -> Self {
+        MetricBucket {
+            timestamp: row.bucket,
+            samples: row.samples,
+            cpu: MetricAggregate { avg: row.cpu_avg, min: row.cpu_min, max: row.cpu_max, p95: row.cpu_p95 },
+            memory: MetricAggregate { avg: row.memory_avg, min: row.memory_min, max: row.memory_max, p95: row.memory_p95 },
+            disk: MetricAggregate { avg: row.disk_avg, min: row.disk_min, max: row.disk_max, p95: row.disk_p95 },
+            network_in: MetricAggregate {
+                avg: row.network_in_avg,
+                min: row.network_in_min,
+                max: row.network_in_max,
+                p95: row.network_in_p95,
+            },
+            network_out: MetricAggregate {
+                avg: row.network_out_avg,
+                min: row.network_out_min,
+                max: row.network_out_max,
+                p95: row.network_out_p95,
+            },
+        }
+    }
+}
+
+/// Parse `90`, `30s`, `5m`, `1h` or `1d` into seconds
+fn parse_step(step: &str) -> Result<i64, AppError> {
+    let step = step.trim();
+    let (value, unit) = match step.char_indices().last() {
+        Some((i, c)) if c.is_ascii_alphabetic() => (&step[..i], c),
+        _ => (step, 's'),
+    };
+
+    let multiplier = match unit {
+        's' => 1,
+        'm' => 60,
+        'h' => 3_600,
+        'd' => 86_400,
+        _ => return Err(AppError::BadRequest(format!("Invalid step unit in {}", step))),
+    };
+
+    match value.parse::<i64>() {
+        Ok(value) if value > 0 => Ok(value * multiplier),
+        _ => Err(AppError::BadRequest(format!("Invalid step: {}", step))),
+    }
+}
+
+/// Raw samples within the raw retention window, otherwise the finest rollup that is still
+/// retained for `from` and no coarser than `step`
+fn choose_resolution(
+    config: &Config,
+    from: DateTime<Utc>,
+    step_seconds: i64,
+) -> Result<Option<Resolution>, AppError> {
+    let now = Utc::now();
+
+    if from >= now - Duration::days(config.metrics_raw_retention_days) {
+        return Ok(None);
+    }
+
+    let finest_retained = Resolution::ALL
+        .into_iter()
+        .find(|r| r.retention(config).is_none_or(|keep| from >= now - keep));
+
+    match finest_retained {
+        Some(resolution) if resolution.seconds() <= step_seconds => Ok(Some(resolution)),
+        Some(resolution) => Err(AppError::BadRequest(format!(
+            "Step must be at least {} for data this old",
+            resolution.as_str()
+        ))),
+        None => Err(AppError::BadRequest("No metrics are retained for this time range".to_string())),
+    }
+}
+
+/// Raw samples (newest first) or, when `step` is given, bucketed aggregates for a server
+pub async fn query_metrics(
+    db: &DbPool,
+    config: &Config,
+    scope: &Scope,
+    server_id: Uuid,
+    query: MetricsQuery,
+) -> Result<MetricsResponse, AppError> {
+    server_service::get_server(db, scope, server_id).await?;
+
+    let Some(step) = query.step.as_deref() else {
+        let windowed = query.from.is_some() || query.to.is_some();
+        let metrics = sqlx::query_as::<_, ServerMetrics>(
+            "SELECT * FROM server_metrics
+             WHERE server_id = $1
+               AND ($2::timestamptz IS NULL OR timestamp >= $2)
+               AND ($3::timestamptz IS NULL OR timestamp < $3)
+             ORDER BY timestamp DESC
+             LIMIT $4"
+        )
+        .bind(server_id)
+        .bind(query.from)
+        .bind(query.to)
+        .bind(if windowed { RAW_QUERY_LIMIT } else { 100 })
+        .fetch_all(db)
+        .await?;
+
+        return Ok(MetricsResponse::Raw(metrics));
+    };
+
+    let step_seconds = parse_step(step)?;
+    let to = query.to.unwrap_or_else(Utc::now);
+    let from = query.from.unwrap_or(to - Duration::hours(1));
+
+    if from >= to {
+        return Err(AppError::BadRequest("`from` must be before `to`".to_string()));
+    }
+
+    if (to - from).num_seconds() / step_seconds > MAX_BUCKETS {
+        return Err(AppError::BadRequest(format!(
+            "Too many buckets; use a larger step (at most {} buckets)",
+            MAX_BUCKETS
+        )));
+    }
+
+    let resolution = choose_resolution(config, from, step_seconds)?;
+    let sql = match resolution {
+        None => format!(
+            "SELECT date_bin(make_interval(secs => $1), m.timestamp, TIMESTAMPTZ 'epoch') AS bucket,
+                    COUNT(*) AS samples, {}
+             FROM server_metrics m
+             WHERE m.server_id = $2 AND m.timestamp >= $3 AND m.timestamp < $4
+             GROUP BY 1
+             ORDER BY 1",
+            raw_aggregates()
+        ),
+        Some(resolution) => format!(
+            "SELECT date_bin(make_interval(secs => $1), m.bucket, TIMESTAMPTZ 'epoch') AS bucket,
+                    SUM(m.samples)::bigint AS samples, {}
+             FROM {} m
+             WHERE m.server_id = $2 AND m.bucket >= $3 AND m.bucket < $4
+             GROUP BY 1
+             ORDER BY 1",
+            rollup_aggregates(),
+            resolution.table()
+        ),
+    };
+
+    let buckets = sqlx::query_as::<_, MetricBucketRow>(&sql)
+        .bind(step_seconds as f64)
+        .bind(server_id)
+        .bind(from)
+        .bind(to)
+        .fetch_all(db)
+        .await?;
+
+    Ok(MetricsResponse::Series(MetricSeries {
+        from,
+        to,
+        step_seconds,
+        resolution: resolution.map_or("raw", |r| r.as_str()).to_string(),
+        buckets: buckets.into_iter().map(MetricBucket::from).collect(),
+    }))
+}
+
+/// Recompute every rollup bucket whose source rows arrived since the last run
+///
+/// Minute buckets are rebuilt from raw samples, hour buckets from minutes and day buckets
+/// from hours, so late or buffered samples are folded in wherever they land in time.
+pub async fn roll_up(db: &DbPool) -> Result<(), AppError> {
+    for resolution in Resolution::ALL {
+        // Rollup tables are only written by this loop, so only raw samples need the lag
+        let until = match resolution.source() {
+            None => Utc::now() - Duration::seconds(ROLLUP_LAG_SECONDS),
+            Some(_) => Utc::now(),
+        };
+
+        let mut tx = db.begin().await?;
+
+        let since = sqlx::query_scalar::<_, DateTime<Utc>>(
+            "SELECT processed_until FROM metric_rollup_state WHERE resolution = $1 FOR UPDATE"
+        )
+        .bind(resolution.as_str())
+        .fetch_optional(&mut *tx)
+        .await?
+        .unwrap_or(DateTime::UNIX_EPOCH);
+
+        if since >= until {
+            continue;
+        }
+
+        let (source_table, time_column, changed_column, aggregates, samples) = match resolution.source() {
+            None => ("server_metrics", "timestamp", "received_at", raw_aggregates(), "COUNT(*)"),
+            Some(source) => (source.table(), "bucket", "updated_at", rollup_aggregates(), "SUM(m.samples)::bigint"),
+        };
+
+        let sql = format!(
+            "WITH touched AS (
+                SELECT DISTINCT server_id,
+                       date_bin(make_interval(secs => $1), {time_column}, TIMESTAMPTZ 'epoch') AS bucket
+                FROM {source_table}
+                WHERE {changed_column} >= $2 AND {changed_column} < $3
+             )
+             INSERT INTO {target} (server_id, bucket, samples, {columns}, updated_at)
+             SELECT m.server_id, date_bin(make_interval(secs => $1), m.{time_column}, TIMESTAMPTZ 'epoch'),
+                    {samples}, {aggregates}, $4
+             FROM {source_table} m
+             WHERE m.{time_column} >= (SELECT MIN(bucket) FROM touched)
+               AND m.{time_column} < (SELECT MAX(bucket) FROM touched) + make_interval(secs => $1)
+               AND (m.server_id, date_bin(make_interval(secs => $1), m.{time_column}, TIMESTAMPTZ 'epoch'))
+                   IN (SELECT server_id, bucket FROM touched)
+             GROUP BY 1, 2
+             ON CONFLICT (server_id, bucket) DO UPDATE SET samples = EXCLUDED.samples, {updates},
+                updated_at = EXCLUDED.updated_at",
+            target = resolution.table(),
+            columns = rollup_columns(),
+            updates = rollup_columns()
+                .split(", ")
+                .map(|column| format!("{column} = EXCLUDED.{column}"))
+                .collect::<Vec<_>>()
+                .join(", "),
+        );
+
+        sqlx::query(&sql)
+            .bind(resolution.seconds() as f64)
+            .bind(since)
+            .bind(until)
+            .bind(Utc::now())
+            .execute(&mut *tx)
+            .await?;
+
+        sqlx::query(
+            "INSERT INTO metric_rollup_state (resolution, processed_until) VALUES ($1, $2)
+             ON CONFLICT (resolution) DO UPDATE SET processed_until = EXCLUDED.processed_until"
+        )
+        .bind(resolution.as_str())
+        .bind(until)
+        .execute(&mut *tx)
+        .await?;
+
+        tx.commit().await?;
+    }
+
+    Ok(())
+}
+
+/// Delete raw samples and rollups past their configured retention
+pub async fn prune(db: &DbPool, config: &Config) -> Result<u64, AppError> {
+    let now = Utc::now();
+
+    // Raw rows are only deleted once the minute rollup has consumed them
+    let mut deleted = sqlx::query(
+        "DELETE FROM server_metrics
+         WHERE timestamp < $1
+           AND received_at < COALESCE(
+                (SELECT processed_until FROM metric_rollup_state WHERE resolution = $2),
+                TIMESTAMPTZ 'epoch')"
+    )
+    .bind(now - Duration::days(config.metrics_raw_retention_days))
+    .bind(Resolution::Minute.as_str())
+    .execute(db)
+    .await?
+    .rows_affected();
+
+    for resolution in Resolution::ALL {
+        if let Some(keep) = resolution.retention(config) {
+            deleted += sqlx::query(&format!("DELETE FROM {} WHERE bucket < $1", resolution.table()))
+                .bind(now - keep)
+                .execute(db)
+                .await?
+                .rows_affected();
+        }
+    }
+
+    Ok(deleted)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_support;
+
+    fn counters(network_in: i64, network_out: i64, sampled_at: DateTime<Utc>) -> Counters {
+        Counters { network_in, network_out, sampled_at }
+    }
+
+    /// Rollup progress is shared by every server, so tests moving it must not interleave
+    static ROLLUPS: tokio::sync::Mutex<()> = tokio::sync::Mutex::const_new(());
+
+    async fn set_processed_until(db: &DbPool, resolution: Resolution, processed_until: DateTime<Utc>) {
+        sqlx::query(
+            "INSERT INTO metric_rollup_state (resolution, processed_until) VALUES ($1, $2)
+             ON CONFLICT (resolution) DO UPDATE SET processed_until = EXCLUDED.processed_until"
+        )
+        .bind(resolution.as_str())
+        .bind(processed_until)
+        .execute(db)
+        .await
+        .unwrap();
+    }
+
+    async fn processed_until(db: &DbPool, resolution: Resolution) -> DateTime<Utc> {
+        sqlx::query_scalar("SELECT processed_until FROM metric_rollup_state WHERE resolution = $1")
+            .bind(resolution.as_str())
+            .fetch_one(db)
+            .await
+            .unwrap()
+    }
+
+    async fn insert_sample(db: &DbPool, server_id: Uuid, timestamp: DateTime<Utc>, cpu: f32, received_at: DateTime<Utc>) {
+        sqlx::query(
+            "INSERT INTO server_metrics (server_id, cpu_usage, memory_usage, disk_usage, network_in, network_out, timestamp, received_at)
+             VALUES ($1, $2, 50, 50, 0, 0, $3, $4)"
+        )
+        .bind(server_id)
+        .bind(cpu)
+        .bind(timestamp)
+        .bind(received_at)
+        .execute(db)
+        .await
+        .unwrap();
+    }
+
+    async fn insert_bucket(db: &DbPool, resolution: Resolution, server_id: Uuid, bucket: DateTime<Utc>) {
+        sqlx::query(&format!(
+            "INSERT INTO {} (server_id, bucket, samples, {}) VALUES ($1, $2, 1, {})",
+            resolution.table(),
+            rollup_columns(),
+            vec!["0"; METRIC_COLUMNS.len() * 4].join(", "),
+        ))
+        .bind(server_id)
+        .bind(bucket)
+        .execute(db)
+        .await
+        .unwrap();
+    }
+
+    /// Bucket, sample count and average CPU of a server's rollups
+    async fn buckets(db: &DbPool, resolution: Resolution, server_id: Uuid) -> Vec<(DateTime<Utc>, i64, f64)> {
+        sqlx::query_as(&format!(
+            "SELECT bucket, samples, cpu_avg FROM {} WHERE server_id = $1 ORDER BY bucket",
+            resolution.table()
+        ))
+        .bind(server_id)
+        .fetch_all(db)
+        .await
+        .unwrap()
+    }
+
+    #[test]
+    fn counter_rate_is_bytes_per_second() {
+        assert_eq!(counter_rate(1_000, 7_000, 60.0), 100);
+        assert_eq!(counter_rate(1_000, 1_000, 60.0), 0);
+    }
+
+    #[test]
+    fn counter_reset_counts_from_zero() {
+        // The counter restarted (agent or interface restart) and has counted 600 bytes since
+        assert_eq!(counter_rate(1_000_000, 600, 60.0), 10);
+    }
+
+    #[test]
+    fn near_zero_gaps_yield_no_rate() {
+        assert_eq!(counter_rate(0, 1_000_000, 0.001), 0);
+        assert_eq!(counter_rate(0, 1_000_000, 0.0), 0);
+        assert_eq!(counter_rate(0, 1_000, 1.0), 1_000);
+    }
+
+    #[test]
+    fn first_sample_only_sets_the_baseline() {
+        assert_eq!(network_rates(None, 5_000_000, 9_000_000, Utc::now()), Some((0, 0)));
+    }
+
+    #[test]
+    fn rates_use_the_previous_reading() {
+        let start = Utc::now();
+        let previous = counters(1_000, 2_000, start);
+
+        assert_eq!(network_rates(Some(&previous), 7_000, 2_600, start + Duration::seconds(60)), Some((100, 10)));
+        assert_eq!(network_rates(Some(&previous), 7_000, 2_600, start + Duration::milliseconds(5)), Some((0, 0)));
+    }
+
+    #[test]
+    fn stale_samples_are_skipped() {
+        let start = Utc::now();
+        let previous = counters(1_000, 2_000, start);
+
+        assert_eq!(network_rates(Some(&previous), 7_000, 2_600, start), None);
+        assert_eq!(network_rates(Some(&previous), 7_000, 2_600, start - Duration::seconds(1)), None);
+    }
+
+    #[test]
+    fn steps_parse_with_units() {
+        assert_eq!(parse_step("30").unwrap(), 30);
+        assert_eq!(parse_step("15s").unwrap(), 15);
+        assert_eq!(parse_step("5m").unwrap(), 300);
+        assert_eq!(parse_step(" 2h ").unwrap(), 7_200);
+        assert_eq!(parse_step("1d").unwrap(), 86_400);
+
+        for step in ["", "0m", "-5m", "5w", "m", "1.5h"] {
+            assert!(matches!(parse_step(step), Err(AppError::BadRequest(_))), "{}", step);
+        }
+    }
+
+    #[test]
+    fn resolution_follows_retention_and_step() {
+        let mut config = test_support::config();
+        config.metrics_raw_retention_days = 7;
+        config.metrics_minute_retention_days = 30;
+        config.metrics_hour_retention_days = 365;
+        let now = Utc::now();
+
+        assert!(choose_resolution(&config, now - Duration::days(1), 60).unwrap().is_none());
+        assert!(matches!(choose_resolution(&config, now - Duration::days(10), 60), Ok(Some(Resolution::Minute))));
+        assert!(matches!(choose_resolution(&config, now - Duration::days(10), 3_600), Ok(Some(Resolution::Minute))));
+        assert!(matches!(choose_resolution(&config, now - Duration::days(60), 3_600), Ok(Some(Resolution::Hour))));
+        assert!(matches!(choose_resolution(&config, now - Duration::days(60), 60), Err(AppError::BadRequest(_))));
+        assert!(matches!(choose_resolution(&config, now - Duration::days(400), 86_400), Ok(Some(Resolution::Day))));
+    }
+
+    #[tokio::test]
+    async fn rollups_neither_double_count_nor_skip_samples() {
+        let Some(db) = test_support::database().await else { return };
+        let _serial = ROLLUPS.lock().await;
+        let user = test_support::user(&db, crate::models::user::UserRole::User, None).await;
+        let server_id = test_support::server(&db, user).await;
+        let now = Utc::now();
+        let hour = DateTime::from_timestamp((now.timestamp() / 3_600 - 3) * 3_600, 0).unwrap();
+        let minute = |n: i64| hour + Duration::minutes(n);
+        for resolution in Resolution::ALL {
+            set_processed_until(&db, resolution, now - Duration::minutes(10)).await;
+        }
+
+        let received_at = now - Duration::minutes(5);
+        insert_sample(&db, server_id, minute(0) + Duration::seconds(10), 10.0, received_at).await;
+        insert_sample(&db, server_id, minute(0) + Duration::seconds(20), 30.0, received_at).await;
+        insert_sample(&db, server_id, minute(1) + Duration::seconds(5), 50.0, received_at).await;
+
+        roll_up(&db).await.unwrap();
+        let first = (
+            buckets(&db, Resolution::Minute, server_id).await,
+            buckets(&db, Resolution::Hour, server_id).await,
+            buckets(&db, Resolution::Day, server_id).await,
+        );
+        let progress = processed_until(&db, Resolution::Minute).await;
+
+        roll_up(&db).await.unwrap();
+        let second = (
+            buckets(&db, Resolution::Minute, server_id).await,
+            buckets(&db, Resolution::Hour, server_id).await,
+            buckets(&db, Resolution::Day, server_id).await,
+        );
+        let second_progress = processed_until(&db, Resolution::Minute).await;
+
+        // A buffered sample for an old minute arrives right where the last run stopped
+        insert_sample(&db, server_id, minute(0) + Duration::seconds(40), 50.0, second_progress).await;
+        tokio::time::sleep(std::time::Duration::from_millis(50)).await;
+        roll_up(&db).await.unwrap();
+        let late_minutes = buckets(&db, Resolution::Minute, server_id).await;
+        let late_hours = buckets(&db, Resolution::Hour, server_id).await;
+        let late_days = buckets(&db, Resolution::Day, server_id).await;
+
+        test_support::delete_users(&db, &[user]).await;
+
+        assert_eq!(first.0, [(minute(0), 2, 20.0), (minute(1), 1, 50.0)]);
+        assert_eq!(first.1, [(hour, 3, 30.0)]);
+        assert_eq!(first.2.len(), 1);
+        assert_eq!((first.2[0].1, first.2[0].2), (3, 30.0));
+        assert!(progress > now - Duration::minutes(10));
+        assert!(progress <= Utc::now() - Duration::seconds(ROLLUP_LAG_SECONDS));
+
+        assert_eq!(second, first);
+        assert!(second_progress >= progress);
+
+        assert_eq!(late_minutes, [(minute(0), 3, 30.0), (minute(1), 1, 50.0)]);
+        assert_eq!(late_hours, [(hour, 4, 35.0)]);
+        assert_eq!((late_days[0].1, late_days[0].2), (4, 35.0));
+    }
+
+    #[tokio::test]
+    async fn pruning_keeps_each_resolutions_retention_window() {
+        let Some(db) = test_support::database().await else { return };
+        let _serial = ROLLUPS.lock().await;
+        let mut config = test_support::config();
+        config.metrics_raw_retention_days = 7;
+        config.metrics_minute_retention_days = 30;
+        config.metrics_hour_retention_days = 365;
+        let user = test_support::user(&db, crate::models::user::UserRole::User, None).await;
+        let server_id = test_support::server(&db, user).await;
+        // Whole seconds, as Postgres keeps only microseconds
+        let now = DateTime::from_timestamp(Utc::now().timestamp(), 0).unwrap();
+        let days_ago = |days: i64| now - Duration::days(days);
+        set_processed_until(&db, Resolution::Minute, days_ago(1)).await;
+
+        insert_sample(&db, server_id, days_ago(8), 1.0, days_ago(2)).await;
+        // Expired, but the minute rollup has not consumed it yet
+        insert_sample(&db, server_id, days_ago(8), 2.0, now).await;
+        insert_sample(&db, server_id, days_ago(6), 3.0, days_ago(2)).await;
+        for days in [31, 29] {
+            insert_bucket(&db, Resolution::Minute, server_id, days_ago(days)).await;
+        }
+        for days in [366, 364] {
+            insert_bucket(&db, Resolution::Hour, server_id, days_ago(days)).await;
+        }
+        insert_bucket(&db, Resolution::Day, server_id, days_ago(1_000)).await;
+
+        let deleted = prune(&db, &config).await.unwrap();
+        let raw: Vec<f32> = sqlx::query_scalar("SELECT cpu_usage FROM server_metrics WHERE server_id = $1 ORDER BY cpu_usage")
+            .bind(server_id)
+            .fetch_all(&db)
+            .await
+            .unwrap();
+        let minutes = buckets(&db, Resolution::Minute, server_id).await;
+        let hours = buckets(&db, Resolution::Hour, server_id).await;
+        let days = buckets(&db, Resolution::Day, server_id).await;
+
+        test_support::delete_users(&db, &[user]).await;
+
+        assert!(deleted >= 3);
+        assert_eq!(raw, [2.0, 3.0]);
+        assert_eq!(minutes.iter().map(|row| row.0).collect::<Vec<_>>(), [days_ago(29)]);
+        assert_eq!(hours.iter().map(|row| row.0).collect::<Vec<_>>(), [days_ago(364)]);
+        assert_eq!(days.len(), 1);
+    }
+}
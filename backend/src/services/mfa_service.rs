@@ -0,0 +1,304 @@
+use crate::{
+    database::DbPool,
+    models::{auth::*, user::{User, UserRole}},
+    utils::{crypto, errors::AppError, password},
+};
+use chrono::Utc;
+use totp_rs::{Algorithm, Secret, TOTP};
+use uuid::Uuid;
+
+const TOTP_ISSUER: &str = "Unified Panel";
+const RECOVERY_CODE_COUNT: usize = 10;
+const TOTP_STEP_SECONDS: u64 = 30;
+/// Steps either side of the current one still accepted, for clock drift
+const TOTP_SKEW_STEPS: u64 = 1;
+
+fn build_totp(secret: &str, account_name: &str) -> Result<TOTP, AppError> {
+    let secret = Secret::Encoded(secret.to_string()).to_bytes()
+        .map_err(|_| AppError::InternalError("Invalid TOTP secret".to_string()))?;
+
+    // Skew is applied in `matching_step` so the accepted step is known
+    TOTP::new(
+        Algorithm::SHA1,
+        6,
+        0,
+        TOTP_STEP_SECONDS,
+        secret,
+        Some(TOTP_ISSUER.to_string()),
+        account_name.to_string(),
+    )
+    .map_err(|_| AppError::InternalError("Failed to create TOTP".to_string()))
+}
+
+/// Time step `code` was generated for, if it is within the accepted window around `now`
+fn matching_step(totp: &TOTP, code: &str, now: u64) -> Option<u64> {
+    let current = now / TOTP_STEP_SECONDS;
+
+    (current.saturating_sub(TOTP_SKEW_STEPS)..=current + TOTP_SKEW_STEPS)
+        .find(|step| totp.check(code, step * TOTP_STEP_SECONDS))
+}
+
+/// Accept a TOTP code at most once: its step must be later than the last one accepted
+async fn verify_totp(db: &DbPool, user_id: Uuid, code: &str, secret: &str, account_name: &str) -> Result<bool, AppError> {
+    let totp = build_totp(secret, account_name)?;
+    let now = Utc::now().timestamp().max(0) as u64;
+
+    let Some(step) = matching_step(&totp, code.trim(), now) else {
+        return Ok(false);
+    };
+
+    let result = sqlx::query(
+        "UPDATE users SET mfa_last_totp_step = $1
+         WHERE id = $2 AND (mfa_last_totp_step IS NULL OR mfa_last_totp_step < $1)"
+    )
+    .bind(step as i64)
+    .bind(user_id)
+    .execute(db)
+    .await?;
+
+    Ok(result.rows_affected() > 0)
+}
+
+/// Recovery codes are compared case- and dash-insensitively
+fn normalize_recovery_code(code: &str) -> String {
+    code.trim().replace('-', "").to_lowercase()
+}
+
+/// Mark a matching unused recovery code as spent; returns false if none matched
+async fn consume_recovery_code(db: &DbPool, user_id: Uuid, code: &str) -> Result<bool, AppError> {
+    let result = sqlx::query(
+        "UPDATE mfa_recovery_codes SET used_at = $1
+         WHERE id = (
+            SELECT id FROM mfa_recovery_codes
+            WHERE user_id = $2 AND code_hash = $3 AND used_at IS NULL
+            LIMIT 1
+         )"
+    )
+    .bind(Utc::now())
+    .bind(user_id)
+    .bind(crypto::sha256_hex(&normalize_recovery_code(code)))
+    .execute(db)
+    .await?;
+
+    Ok(result.rows_affected() > 0)
+}
+
+/// Check the second factor for a user with MFA enabled, accepting a TOTP code or a recovery code
+pub async fn verify_second_factor(
+    db: &DbPool,
+    user: &User,
+    totp_code: Option<&str>,
+    recovery_code: Option<&str>,
+) -> Result<(), AppError> {
+    if let Some(code) = recovery_code {
+        if consume_recovery_code(db, user.id, code).await? {
+            return Ok(());
+        }
+        return Err(AppError::Unauthorized("Invalid recovery code".to_string()));
+    }
+
+    let code = totp_code.ok_or(AppError::Unauthorized("TOTP code required".to_string()))?;
+    let secret = user.mfa_secret.as_ref()
+        .ok_or(AppError::Unauthorized("MFA secret not found".to_string()))?;
+
+    if !verify_totp(db, user.id, code, secret, &user.email).await? {
+        return Err(AppError::Unauthorized("Invalid TOTP code".to_string()));
+    }
+
+    Ok(())
+}
+
+/// Generate a new pending secret; MFA stays off until the first code is confirmed
+pub async fn enroll(db: &DbPool, user: &User) -> Result<MfaEnrollResponse, AppError> {
+    if user.mfa_enabled {
+        return Err(AppError::BadRequest("MFA is already enabled".to_string()));
+    }
+
+    let secret = match Secret::generate_secret().to_encoded() {
+        Secret::Encoded(secret) => secret,
+        Secret::Raw(_) => return Err(AppError::InternalError("Failed to encode TOTP secret".to_string())),
+    };
+    let otpauth_uri = build_totp(&secret, &user.email)?.get_url();
+
+    sqlx::query("UPDATE users SET mfa_secret = $1, updated_at = $2 WHERE id = $3")
+        .bind(&secret)
+        .bind(Utc::now())
+        .bind(user.id)
+        .execute(db)
+        .await?;
+
+    Ok(MfaEnrollResponse { secret, otpauth_uri })
+}
+
+/// Enable MFA once the user proves their authenticator works; returns the new recovery codes
+pub async fn confirm(db: &DbPool, user: &User, code: &str) -> Result<Vec<String>, AppError> {
+    if user.mfa_enabled {
+        return Err(AppError::BadRequest("MFA is already enabled".to_string()));
+    }
+
+    let secret = user.mfa_secret.as_ref()
+        .ok_or(AppError::BadRequest("Start MFA enrollment first".to_string()))?;
+
+    if !verify_totp(db, user.id, code, secret, &user.email).await? {
+        return Err(AppError::Unauthorized("Invalid TOTP code".to_string()));
+    }
+
+    let recovery_codes: Vec<String> = (0..RECOVERY_CODE_COUNT)
+        .map(|_| {
+            let raw = crypto::random_token(5);
+            format!("{}-{}", &raw[..5], &raw[5..])
+        })
+        .collect();
+
+    let mut tx = db.begin().await?;
+
+    sqlx::query("UPDATE users SET mfa_enabled = TRUE, updated_at = $1 WHERE id = $2")
+        .bind(Utc::now())
+        .bind(user.id)
+        .execute(&mut *tx)
+        .await?;
+
+    sqlx::query("DELETE FROM mfa_recovery_codes WHERE user_id = $1")
+        .bind(user.id)
+        .execute(&mut *tx)
+        .await?;
+
+    for code in &recovery_codes {
+        sqlx::query(
+            "INSERT INTO mfa_recovery_codes (user_id, code_hash, created_at) VALUES ($1, $2, $3)"
+        )
+        .bind(user.id)
+        .bind(crypto::sha256_hex(&normalize_recovery_code(code)))
+        .bind(Utc::now())
+        .execute(&mut *tx)
+        .await?;
+    }
+
+    tx.commit().await?;
+
+    Ok(recovery_codes)
+}
+
+pub async fn disable(db: &DbPool, user: &User, payload: MfaDisableRequest) -> Result<(), AppError> {
+    if !user.mfa_enabled {
+        return Err(AppError::BadRequest("MFA is not enabled".to_string()));
+    }
+
+    if required_roles(db).await?.contains(&user.get_role()) {
+        return Err(AppError::Forbidden("MFA is required for your role".to_string()));
+    }
+
+    if !password::verify_password(&payload.password, &user.password_hash)? {
+        return Err(AppError::Unauthorized("Invalid credentials".to_string()));
+    }
+
+    // Accept either factor: a TOTP code is six digits, anything else is treated as a recovery code
+    let code = payload.code.trim();
+    if code.len() == 6 && code.chars().all(|c| c.is_ascii_digit()) {
+        verify_second_factor(db, user, Some(code), None).await?;
+    } else {
+        verify_second_factor(db, user, None, Some(code)).await?;
+    }
+
+    sqlx::query(
+        "UPDATE users SET mfa_enabled = FALSE, mfa_secret = NULL, updated_at = $1 WHERE id = $2"
+    )
+    .bind(Utc::now())
+    .bind(user.id)
+    .execute(db)
+    .await?;
+
+    sqlx::query("DELETE FROM mfa_recovery_codes WHERE user_id = $1")
+        .bind(user.id)
+        .execute(db)
+        .await?;
+
+    Ok(())
+}
+
+pub async fn required_roles(db: &DbPool) -> Result<Vec<UserRole>, AppError> {
+    let roles = sqlx::query_scalar::<_, String>("SELECT role FROM mfa_required_roles ORDER BY role")
+        .fetch_all(db)
+        .await?;
+
+    Ok(roles.iter().filter_map(|role| role.parse().ok()).collect())
+}
+
+pub async fn set_required_roles(db: &DbPool, roles: &[UserRole]) -> Result<Vec<UserRole>, AppError> {
+    let mut tx = db.begin().await?;
+
+    sqlx::query("DELETE FROM mfa_required_roles")
+        .execute(&mut *tx)
+        .await?;
+
+    for role in roles {
+        sqlx::query(
+            "INSERT INTO mfa_required_roles (role, created_at) VALUES ($1, $2) ON CONFLICT (role) DO NOTHING"
+        )
+        .bind(role.as_str())
+        .bind(Utc::now())
+        .execute(&mut *tx)
+        .await?;
+    }
+
+    tx.commit().await?;
+
+    required_roles(db).await
+}
+
+/// Whether the user must enroll in MFA before using anything beyond the MFA endpoints
+pub async fn enrollment_required(db: &DbPool, user: &User) -> Result<bool, AppError> {
+    if user.mfa_enabled {
+        return Ok(false);
+    }
+
+    Ok(required_roles(db).await?.contains(&user.get_role()))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_support;
+
+    const SECRET: &str = "JBSWY3DPEHPK3PXPJBSWY3DPEHPK3PXP";
+
+    #[test]
+    fn codes_match_within_one_step_of_now() {
+        let totp = build_totp(SECRET, "user@example.com").unwrap();
+        let now = 1_800_000_015;
+        let step = now / TOTP_STEP_SECONDS;
+
+        for offset in [-1i64, 0, 1] {
+            let code_step = (step as i64 + offset) as u64;
+            let code = totp.generate(code_step * TOTP_STEP_SECONDS);
+            assert_eq!(matching_step(&totp, &code, now), Some(code_step));
+        }
+
+        let stale = totp.generate((step - 2) * TOTP_STEP_SECONDS);
+        assert_eq!(matching_step(&totp, &stale, now), None);
+    }
+
+    #[tokio::test]
+    async fn totp_codes_cannot_be_replayed() {
+        let Some(db) = test_support::database().await else { return };
+        let user = sqlx::query_as::<_, User>(
+            "INSERT INTO users (id, email, password_hash, role, created_at, updated_at, mfa_enabled, mfa_secret)
+             VALUES ($1, $2, 'unused', 'user', NOW(), NOW(), TRUE, $3)
+             RETURNING *"
+        )
+        .bind(Uuid::new_v4())
+        .bind(format!("mfa-{}@example.com", Uuid::new_v4()))
+        .bind(SECRET)
+        .fetch_one(&db)
+        .await
+        .unwrap();
+        let code = build_totp(SECRET, &user.email).unwrap().generate_current().unwrap();
+
+        let first = verify_second_factor(&db, &user, Some(&code), None).await;
+        let replay = verify_second_factor(&db, &user, Some(&code), None).await;
+
+        sqlx::query("DELETE FROM users WHERE id = $1").bind(user.id).execute(&db).await.unwrap();
+        assert!(first.is_ok());
+        assert!(matches!(replay, Err(AppError::Unauthorized(_))));
+    }
+}
@@ -2,3 +2,19 @@ pub mod auth_service;
 pub mod user_service;
 pub mod server_service;
 pub mod vps_service;
+pub mod website_service;
+pub mod cloud_service;
+pub mod digitalocean_service;
+pub mod hetzner_service;
+pub mod quota_service;
+pub mod mfa_service;
+pub mod lockout_service;
+pub mod mail_service;
+pub mod verification_service;
+pub mod api_key_service;
+pub mod metrics_service;
+pub mod alert_service;
+pub mod notification_service;
+pub mod dashboard_service;
+pub mod event_service;
+pub mod audit_service;
@@ -0,0 +1,823 @@
+use crate::{
+    config::Config,
+    database::DbPool,
+    models::{alert::AlertSeverity, notification::*},
+    services::mail_service::{MailMessage, MailSender},
+    utils::{crypto, errors::AppError},
+};
+use chrono::{DateTime, Duration, Utc};
+use sqlx::types::Json;
+use std::{
+    net::{IpAddr, Ipv4Addr, Ipv6Addr, SocketAddr},
+    sync::Arc,
+};
+use uuid::Uuid;
+use validator::Validate;
+
+const DEFAULT_DELIVERY_LIMIT: i64 = 100;
+const MAX_DELIVERY_LIMIT: i64 = 1000;
+
+/// Deliveries claimed per worker pass
+const DELIVERY_BATCH_SIZE: i64 = 50;
+
+/// A claimed delivery becomes due again after this long if the worker dies mid-send
+const DELIVERY_LEASE_SECONDS: i64 = 300;
+
+/// First retry delay, doubled per attempt up to `RETRY_MAX_SECONDS`
+const RETRY_BASE_SECONDS: i64 = 30;
+const RETRY_MAX_SECONDS: i64 = 3600;
+
+const HTTP_TIMEOUT_SECONDS: u64 = 10;
+
+/// Errors are cut to this length in the delivery log
+const MAX_ERROR_LENGTH: usize = 500;
+
+pub async fn list_channels(db: &DbPool, user_id: Uuid) -> Result<Vec<NotificationChannel>, AppError> {
+    let channels = sqlx::query_as::<_, NotificationChannel>(
+        "SELECT * FROM notification_channels WHERE user_id = $1 ORDER BY created_at DESC"
+    )
+    .bind(user_id)
+    .fetch_all(db)
+    .await?;
+
+    Ok(channels)
+}
+
+pub async fn get_channel(db: &DbPool, user_id: Uuid, id: Uuid) -> Result<NotificationChannel, AppError> {
+    let channel = sqlx::query_as::<_, NotificationChannel>(
+        "SELECT * FROM notification_channels WHERE id = $1 AND user_id = $2"
+    )
+    .bind(id)
+    .bind(user_id)
+    .fetch_optional(db)
+    .await?
+    .ok_or(AppError::NotFound("Notification channel not found".to_string()))?;
+
+    Ok(channel)
+}
+
+/// Refuse webhook and Slack URLs that resolve to private addresses when the channel is saved;
+/// deliveries check again, as the host may resolve differently by then
+async fn check_destination(config: &Config, channel: &ChannelConfig) -> Result<(), AppError> {
+    let url = match channel {
+        ChannelConfig::Webhook(webhook) => &webhook.url,
+        ChannelConfig::Slack(slack) => &slack.url,
+        ChannelConfig::Email(_) | ChannelConfig::Telegram(_) => return Ok(()),
+    };
+
+    resolve_target(url, config.notification_allow_private_targets)
+        .await
+        .map_err(|e| AppError::BadRequest(format!("Unusable notification URL: {}", e)))?;
+
+    Ok(())
+}
+
+pub async fn create_channel(
+    db: &DbPool,
+    config: &Config,
+    user_id: Uuid,
+    payload: CreateNotificationChannel,
+) -> Result<NotificationChannel, AppError> {
+    check_destination(config, &payload.config).await?;
+
+    let event_types: Vec<&str> = payload.event_types.iter().map(|event| event.as_str()).collect();
+
+    let channel = sqlx::query_as::<_, NotificationChannel>(
+        "INSERT INTO notification_channels (
+            id, user_id, name, kind, config, event_types, min_severity, enabled, created_at, updated_at
+         )
+         VALUES ($1, $2, $3, $4, $5, $6, $7, $8, $9, $10)
+         RETURNING *"
+    )
+    .bind(Uuid::new_v4())
+    .bind(user_id)
+    .bind(&payload.name)
+    .bind(payload.config.kind())
+    .bind(Json(&payload.config))
+    .bind(&event_types)
+    .bind(payload.min_severity.as_str())
+    .bind(payload.enabled)
+    .bind(Utc::now())
+    .bind(Utc::now())
+    .fetch_one(db)
+    .await?;
+
+    Ok(channel)
+}
+
+pub async fn update_channel(
+    db: &DbPool,
+    config: &Config,
+    user_id: Uuid,
+    id: Uuid,
+    payload: UpdateNotificationChannel,
+) -> Result<NotificationChannel, AppError> {
+    let mut channel = get_channel(db, user_id, id).await?;
+
+    if let Some(name) = payload.name {
+        channel.name = name;
+    }
+    if let Some(destination) = payload.config {
+        let destination = channel.config.updated(destination).map_err(|e| {
+            AppError::BadRequest(format!("Invalid {} channel config: {}", channel.kind, e))
+        })?;
+        destination.validate()?;
+        check_destination(config, &destination).await?;
+        channel.config = Json(destination);
+    }
+    if let Some(event_types) = payload.event_types {
+        channel.event_types = event_types.iter().map(|event| event.as_str().to_string()).collect();
+    }
+    if let Some(min_severity) = payload.min_severity {
+        channel.min_severity = min_severity.as_str().to_string();
+    }
+    if let Some(enabled) = payload.enabled {
+        channel.enabled = enabled;
+    }
+
+    let channel = sqlx::query_as::<_, NotificationChannel>(
+        "UPDATE notification_channels
+         SET name = $1, config = $2, event_types = $3, min_severity = $4, enabled = $5, updated_at = $6
+         WHERE id = $7
+         RETURNING *"
+    )
+    .bind(&channel.name)
+    .bind(&channel.config)
+    .bind(&channel.event_types)
+    .bind(&channel.min_severity)
+    .bind(channel.enabled)
+    .bind(Utc::now())
+    .bind(id)
+    .fetch_one(db)
+    .await?;
+
+    Ok(channel)
+}
+
+pub async fn delete_channel(db: &DbPool, user_id: Uuid, id: Uuid) -> Result<(), AppError> {
+    let result = sqlx::query("DELETE FROM notification_channels WHERE id = $1 AND user_id = $2")
+        .bind(id)
+        .bind(user_id)
+        .execute(db)
+        .await?;
+
+    if result.rows_affected() == 0 {
+        return Err(AppError::NotFound("Notification channel not found".to_string()));
+    }
+
+    Ok(())
+}
+
+/// Queue a test message on one channel, bypassing its routing filters
+pub async fn send_test(db: &DbPool, user_id: Uuid, id: Uuid) -> Result<NotificationDelivery, AppError> {
+    let channel = get_channel(db, user_id, id).await?;
+
+    let notification = Notification {
+        event: NotificationEvent::Test,
+        severity: AlertSeverity::Info,
+        title: "Test notification".to_string(),
+        message: format!("Channel \"{}\" is set up correctly.", channel.name),
+        data: serde_json::json!({ "channel_id": channel.id }),
+        occurred_at: Utc::now(),
+    };
+
+    enqueue(db, &channel, &notification).await
+}
+
+pub async fn list_deliveries(
+    db: &DbPool,
+    user_id: Uuid,
+    query: DeliveriesQuery,
+) -> Result<Vec<NotificationDelivery>, AppError> {
+    let limit = query.limit.unwrap_or(DEFAULT_DELIVERY_LIMIT).clamp(1, MAX_DELIVERY_LIMIT);
+
+    let deliveries = sqlx::query_as::<_, NotificationDelivery>(
+        "SELECT d.* FROM notification_deliveries d
+         JOIN notification_channels c ON c.id = d.channel_id
+         WHERE c.user_id = $1
+           AND ($2::uuid IS NULL OR d.channel_id = $2)
+           AND ($3::varchar IS NULL OR d.status = $3)
+         ORDER BY d.created_at DESC
+         LIMIT $4"
+    )
+    .bind(user_id)
+    .bind(query.channel_id)
+    .bind(query.status.map(|status| status.as_str()))
+    .bind(limit)
+    .fetch_all(db)
+    .await?;
+
+    Ok(deliveries)
+}
+
+/// Queue `notification` on every matching channel of `user_id` and of their reseller
+pub async fn notify(db: &DbPool, user_id: Uuid, notification: Notification) -> Result<(), AppError> {
+    let channels = sqlx::query_as::<_, NotificationChannel>(
+        "SELECT * FROM notification_channels
+         WHERE enabled
+           AND (user_id = $1 OR user_id = (SELECT parent_id FROM users WHERE id = $1))"
+    )
+    .bind(user_id)
+    .fetch_all(db)
+    .await?;
+
+    for channel in channels.iter().filter(|channel| channel.accepts(&notification)) {
+        enqueue(db, channel, &notification).await?;
+    }
+
+    Ok(())
+}
+
+async fn enqueue(
+    db: &DbPool,
+    channel: &NotificationChannel,
+    notification: &Notification,
+) -> Result<NotificationDelivery, AppError> {
+    let delivery = sqlx::query_as::<_, NotificationDelivery>(
+        "INSERT INTO notification_deliveries (
+            id, channel_id, user_id, event_type, severity, payload, status, next_attempt_at, created_at, updated_at
+         )
+         VALUES ($1, $2, $3, $4, $5, $6, $7, $8, $8, $8)
+         RETURNING *"
+    )
+    .bind(Uuid::new_v4())
+    .bind(channel.id)
+    .bind(channel.user_id)
+    .bind(notification.event.as_str())
+    .bind(notification.severity.as_str())
+    .bind(Json(notification))
+    .bind(DeliveryStatus::Pending.as_str())
+    .bind(Utc::now())
+    .fetch_one(db)
+    .await?;
+
+    Ok(delivery)
+}
+
+/// Whether `ip` is reachable on the public internet; user-supplied URLs must not reach anything else
+fn is_public_ip(ip: &IpAddr) -> bool {
+    match ip {
+        IpAddr::V4(ip) => is_public_ipv4(ip),
+        IpAddr::V6(ip) => match ip.to_ipv4_mapped() {
+            Some(mapped) => is_public_ipv4(&mapped),
+            None => is_public_ipv6(ip),
+        },
+    }
+}
+
+fn is_public_ipv4(ip: &Ipv4Addr) -> bool {
+    let [a, b, ..] = ip.octets();
+    !(ip.is_private()
+        || ip.is_loopback()
+        || ip.is_link_local()
+        || ip.is_unspecified()
+        || ip.is_broadcast()
+        || ip.is_documentation()
+        || ip.is_multicast()
+        || a == 0
+        // Carrier-grade NAT 100.64.0.0/10 and benchmarking 198.18.0.0/15
+        || (a == 100 && (64..128).contains(&b))
+        || (a == 198 && (18..20).contains(&b))
+        || a >= 240)
+}
+
+fn is_public_ipv6(ip: &Ipv6Addr) -> bool {
+    let first = ip.segments()[0];
+    !(ip.is_loopback()
+        || ip.is_unspecified()
+        || ip.is_multicast()
+        // Unique local fc00::/7, link-local fe80::/10 and documentation 2001:db8::/32
+        || (first & 0xfe00) == 0xfc00
+        || (first & 0xffc0) == 0xfe80
+        || (first == 0x2001 && ip.segments()[1] == 0x0db8))
+}
+
+/// Host of a user-supplied http(s) URL and the addresses it resolves to, which must all be
+/// public unless `allow_private_targets`
+async fn resolve_target(url: &str, allow_private_targets: bool) -> Result<(String, Vec<SocketAddr>), String> {
+    let url = reqwest::Url::parse(url).map_err(|e| format!("Invalid URL: {}", e))?;
+    if !matches!(url.scheme(), "http" | "https") {
+        return Err("Only http and https URLs are allowed".to_string());
+    }
+
+    let host = url.host_str().ok_or("URL has no host")?.to_string();
+    let port = url.port_or_known_default().unwrap_or(443);
+    let lookup_host = host.trim_start_matches('[').trim_end_matches(']');
+    let addrs: Vec<SocketAddr> = tokio::net::lookup_host((lookup_host, port))
+        .await
+        .map_err(|e| format!("Failed to resolve {}: {}", host, e))?
+        .collect();
+
+    if addrs.is_empty() {
+        return Err(format!("Failed to resolve {}", host));
+    }
+    if !allow_private_targets && addrs.iter().any(|addr| !is_public_ip(&addr.ip())) {
+        return Err(format!("{} resolves to a non-public address", host));
+    }
+
+    Ok((host, addrs))
+}
+
+fn http_client_builder() -> reqwest::ClientBuilder {
+    reqwest::Client::builder()
+        .timeout(std::time::Duration::from_secs(HTTP_TIMEOUT_SECONDS))
+        // A redirect could lead a vetted URL to an internal one
+        .redirect(reqwest::redirect::Policy::none())
+}
+
+/// Sends rendered notifications to channel destinations
+pub struct Notifier {
+    client: reqwest::Client,
+    mailer: Arc<dyn MailSender>,
+    telegram_api_url: String,
+    /// Lets webhooks reach private addresses, for development and tests
+    allow_private_targets: bool,
+}
+
+impl Notifier {
+    pub fn new(config: &Config, mailer: Arc<dyn MailSender>) -> Self {
+        Self::build(mailer, &config.telegram_api_url, config.notification_allow_private_targets)
+    }
+
+    fn build(mailer: Arc<dyn MailSender>, telegram_api_url: &str, allow_private_targets: bool) -> Self {
+        Self {
+            client: http_client_builder().build().expect("Failed to build HTTP client"),
+            mailer,
+            telegram_api_url: telegram_api_url.trim_end_matches('/').to_string(),
+            allow_private_targets,
+        }
+    }
+
+    /// Client for a user-supplied URL, with connections pinned to the addresses `resolve_target`
+    /// checked so a second DNS answer cannot swap them
+    async fn client_for(&self, url: &str) -> Result<reqwest::Client, String> {
+        let (host, addrs) = resolve_target(url, self.allow_private_targets).await?;
+
+        let mut builder = http_client_builder();
+        if host.trim_start_matches('[').trim_end_matches(']').parse::<IpAddr>().is_err() {
+            builder = builder.resolve_to_addrs(&host, &addrs);
+        }
+
+        builder.build().map_err(|e| e.to_string())
+    }
+
+    async fn deliver(
+        &self,
+        delivery_id: Uuid,
+        config: &ChannelConfig,
+        notification: &Notification,
+    ) -> Result<(), String> {
+        match config {
+            ChannelConfig::Email(email) => {
+                let message = MailMessage {
+                    to: email.address.clone(),
+                    subject: format!("[{}] {}", notification.severity.as_str(), notification.title),
+                    body: format!(
+                        "{}\n\nEvent: {}\nTime: {}\n\n{}",
+                        notification.message,
+                        notification.event.as_str(),
+                        notification.occurred_at.to_rfc2822(),
+                        serde_json::to_string_pretty(&notification.data).unwrap_or_default()
+                    ),
+                };
+                self.mailer.send(&message).await.map_err(|e| e.to_string())
+            }
+            ChannelConfig::Webhook(webhook) => {
+                let body = serde_json::to_vec(&serde_json::json!({
+                    "id": delivery_id,
+                    "event": notification.event,
+                    "severity": notification.severity,
+                    "title": notification.title,
+                    "message": notification.message,
+                    "data": notification.data,
+                    "occurred_at": notification.occurred_at,
+                }))
+                .map_err(|e| e.to_string())?;
+
+                // Receivers recompute HMAC-SHA256(secret, "<timestamp>.<body>") and reject stale timestamps
+                let timestamp = Utc::now().timestamp().to_string();
+                let mut signed = format!("{}.", timestamp).into_bytes();
+                signed.extend_from_slice(&body);
+                let signature = crypto::hmac_sha256_hex(&webhook.secret, &signed);
+
+                self.send(
+                    self.client_for(&webhook.url).await?
+                        .post(&webhook.url)
+                        .header(reqwest::header::CONTENT_TYPE, "application/json")
+                        .header("X-Unified-Event", notification.event.as_str())
+                        .header("X-Unified-Delivery", delivery_id.to_string())
+                        .header("X-Unified-Timestamp", timestamp)
+                        .header("X-Unified-Signature", format!("sha256={}", signature))
+                        .body(body),
+                )
+                .await
+            }
+            ChannelConfig::Slack(slack) => {
+                self.send(self.client_for(&slack.url).await?.post(&slack.url).json(&serde_json::json!({
+                    "text": render_text(notification),
+                })))
+                .await
+            }
+            ChannelConfig::Telegram(telegram) => {
+                let url = format!("{}/bot{}/sendMessage", self.telegram_api_url, telegram.bot_token);
+                self.send(self.client.post(url).json(&serde_json::json!({
+                    "chat_id": telegram.chat_id,
+                    "text": render_text(notification),
+                })))
+                .await
+            }
+        }
+    }
+
+    async fn send(&self, request: reqwest::RequestBuilder) -> Result<(), String> {
+        // URLs are left out of errors: Telegram puts the bot token in the path
+        let response = request.send().await.map_err(|e| e.without_url().to_string())?;
+
+        if response.status().is_success() {
+            return Ok(());
+        }
+
+        // Only the status: echoing the body would let users read whatever the URL returns
+        Err(format!("HTTP {}", response.status()))
+    }
+}
+
+/// Plain-text rendering for chat channels
+fn render_text(notification: &Notification) -> String {
+    format!(
+        "[{}] {}\n{}",
+        notification.severity.as_str().to_uppercase(),
+        notification.title,
+        notification.message
+    )
+}
+
+/// Whether a failed delivery is given up on rather than retried
+fn is_exhausted(channel_enabled: bool, attempts: i32, max_attempts: i32) -> bool {
+    !channel_enabled || attempts >= max_attempts
+}
+
+fn retry_delay(attempts: i32) -> Duration {
+    let exponent = attempts.saturating_sub(1).clamp(0, 16) as u32;
+    Duration::seconds((RETRY_BASE_SECONDS * 2i64.pow(exponent)).min(RETRY_MAX_SECONDS))
+}
+
+/// Attempt every due delivery once; returns how many were processed
+pub async fn process_due(db: &DbPool, notifier: &Notifier, config: &Config) -> Result<usize, AppError> {
+    let now = Utc::now();
+
+    // Claim a batch by pushing it past the lease so concurrent workers skip it
+    let claimed = sqlx::query_as::<_, NotificationDelivery>(
+        "UPDATE notification_deliveries
+         SET attempts = attempts + 1, next_attempt_at = $1, updated_at = $2
+         WHERE id IN (
+            SELECT id FROM notification_deliveries
+            WHERE status = $3 AND next_attempt_at <= $2
+            ORDER BY next_attempt_at
+            LIMIT $4
+            FOR UPDATE SKIP LOCKED
+         )
+         RETURNING *"
+    )
+    .bind(now + Duration::seconds(DELIVERY_LEASE_SECONDS))
+    .bind(now)
+    .bind(DeliveryStatus::Pending.as_str())
+    .bind(DELIVERY_BATCH_SIZE)
+    .fetch_all(db)
+    .await?;
+
+    let count = claimed.len();
+    for delivery in claimed {
+        let Some(channel) = sqlx::query_as::<_, NotificationChannel>(
+            "SELECT * FROM notification_channels WHERE id = $1"
+        )
+        .bind(delivery.channel_id)
+        .fetch_optional(db)
+        .await?
+        else {
+            continue;
+        };
+
+        let result = if channel.enabled || delivery.payload.event == NotificationEvent::Test {
+            notifier.deliver(delivery.id, &channel.config, &delivery.payload).await
+        } else {
+            Err("Channel disabled".to_string())
+        };
+
+        match result {
+            Ok(()) => mark_delivered(db, delivery.id, Utc::now()).await?,
+            Err(error) => {
+                let error: String = error.chars().take(MAX_ERROR_LENGTH).collect();
+                let exhausted = is_exhausted(channel.enabled, delivery.attempts, config.notification_max_attempts);
+                tracing::warn!(
+                    "Notification delivery {} via {} failed (attempt {}): {}",
+                    delivery.id, channel.kind, delivery.attempts, error
+                );
+                mark_failed(db, delivery.id, &error, exhausted, Utc::now() + retry_delay(delivery.attempts)).await?;
+            }
+        }
+    }
+
+    Ok(count)
+}
+
+async fn mark_delivered(db: &DbPool, id: Uuid, now: DateTime<Utc>) -> Result<(), AppError> {
+    sqlx::query(
+        "UPDATE notification_deliveries
+         SET status = $1, delivered_at = $2, last_error = NULL, updated_at = $2
+         WHERE id = $3"
+    )
+    .bind(DeliveryStatus::Delivered.as_str())
+    .bind(now)
+    .bind(id)
+    .execute(db)
+    .await?;
+
+    Ok(())
+}
+
+async fn mark_failed(
+    db: &DbPool,
+    id: Uuid,
+    error: &str,
+    exhausted: bool,
+    next_attempt_at: DateTime<Utc>,
+) -> Result<(), AppError> {
+    let status = if exhausted { DeliveryStatus::Failed } else { DeliveryStatus::Pending };
+
+    sqlx::query(
+        "UPDATE notification_deliveries
+         SET status = $1, last_error = $2, next_attempt_at = $3, updated_at = $4
+         WHERE id = $5"
+    )
+    .bind(status.as_str())
+    .bind(error)
+    .bind(next_attempt_at)
+    .bind(Utc::now())
+    .bind(id)
+    .execute(db)
+    .await?;
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        models::{
+            notification::{SlackChannel, WebhookChannel},
+            user::UserRole,
+        },
+        services::mail_service::LogMailer,
+        test_support,
+    };
+    use axum::{http::StatusCode, response::Redirect, routing::post, Router};
+    use std::sync::atomic::{AtomicUsize, Ordering};
+
+    fn notifier(allow_private_targets: bool) -> Notifier {
+        Notifier::build(Arc::new(LogMailer), "https://api.telegram.org", allow_private_targets)
+    }
+
+    fn notification() -> Notification {
+        Notification {
+            event: NotificationEvent::Test,
+            severity: AlertSeverity::Info,
+            title: "Test notification".to_string(),
+            message: "Hello".to_string(),
+            data: serde_json::json!({}),
+            occurred_at: Utc::now(),
+        }
+    }
+
+    fn webhook(url: String) -> ChannelConfig {
+        ChannelConfig::Webhook(WebhookChannel { url, secret: "0123456789abcdef".to_string() })
+    }
+
+    #[tokio::test]
+    async fn webhook_delivery_succeeds() {
+        let base = test_support::serve(Router::new().route("/hook", post(|| async { StatusCode::NO_CONTENT }))).await;
+
+        let result = notifier(true).deliver(Uuid::new_v4(), &webhook(format!("{}/hook", base)), &notification()).await;
+
+        assert_eq!(result, Ok(()));
+    }
+
+    #[tokio::test]
+    async fn failed_delivery_keeps_only_the_status() {
+        let base = test_support::serve(Router::new().route(
+            "/hook",
+            post(|| async { (StatusCode::INTERNAL_SERVER_ERROR, "internal secret") }),
+        ))
+        .await;
+
+        let result = notifier(true).deliver(Uuid::new_v4(), &webhook(format!("{}/hook", base)), &notification()).await;
+
+        let error = result.unwrap_err();
+        assert!(error.starts_with("HTTP 500"), "{}", error);
+        assert!(!error.contains("internal secret"));
+    }
+
+    #[tokio::test]
+    async fn redirects_are_not_followed() {
+        let base = test_support::serve(
+            Router::new()
+                .route("/hook", post(|| async { Redirect::temporary("/target") }))
+                .route("/target", post(|| async { StatusCode::OK })),
+        )
+        .await;
+
+        let result = notifier(true).deliver(Uuid::new_v4(), &webhook(format!("{}/hook", base)), &notification()).await;
+
+        assert!(result.unwrap_err().starts_with("HTTP 307"));
+    }
+
+    #[tokio::test]
+    async fn private_targets_are_refused() {
+        let hits = Arc::new(AtomicUsize::new(0));
+        let counter = hits.clone();
+        let base = test_support::serve(Router::new().route(
+            "/hook",
+            post(move || async move {
+                counter.fetch_add(1, Ordering::SeqCst);
+                StatusCode::OK
+            }),
+        ))
+        .await;
+        let notifier = notifier(false);
+
+        let webhook_result = notifier.deliver(Uuid::new_v4(), &webhook(format!("{}/hook", base)), &notification()).await;
+        let slack = ChannelConfig::Slack(SlackChannel { url: format!("{}/hook", base.replace("127.0.0.1", "localhost")) });
+        let slack_result = notifier.deliver(Uuid::new_v4(), &slack, &notification()).await;
+
+        assert!(webhook_result.unwrap_err().contains("non-public"));
+        assert!(slack_result.unwrap_err().contains("non-public"));
+        assert_eq!(hits.load(Ordering::SeqCst), 0);
+    }
+
+    #[tokio::test]
+    async fn failing_endpoint_is_retried_until_it_recovers() {
+        let hits = Arc::new(AtomicUsize::new(0));
+        let counter = hits.clone();
+        let base = test_support::serve(Router::new().route(
+            "/hook",
+            post(move || async move {
+                match counter.fetch_add(1, Ordering::SeqCst) {
+                    0 | 1 => StatusCode::SERVICE_UNAVAILABLE,
+                    _ => StatusCode::OK,
+                }
+            }),
+        ))
+        .await;
+        let notifier = notifier(true);
+        let channel = webhook(format!("{}/hook", base));
+
+        let mut attempts = 0;
+        let outcome = loop {
+            attempts += 1;
+            match notifier.deliver(Uuid::new_v4(), &channel, &notification()).await {
+                Ok(()) => break Ok(attempts),
+                Err(e) if is_exhausted(true, attempts, 6) => break Err(e),
+                Err(e) => assert_eq!(e, "HTTP 503 Service Unavailable"),
+            }
+        };
+
+        assert_eq!(outcome, Ok(3));
+        assert_eq!(hits.load(Ordering::SeqCst), 3);
+    }
+
+    #[tokio::test]
+    async fn failing_endpoint_is_given_up_on() {
+        let base = test_support::serve(Router::new().route("/hook", post(|| async { StatusCode::BAD_GATEWAY }))).await;
+        let notifier = notifier(true);
+        let channel = webhook(format!("{}/hook", base));
+
+        let mut attempts = 0;
+        let error = loop {
+            attempts += 1;
+            let error = notifier.deliver(Uuid::new_v4(), &channel, &notification()).await.unwrap_err();
+            if is_exhausted(true, attempts, 3) {
+                break error;
+            }
+        };
+
+        assert_eq!(attempts, 3);
+        assert!(error.starts_with("HTTP 502"));
+    }
+
+    fn create_payload(config: ChannelConfig) -> CreateNotificationChannel {
+        CreateNotificationChannel {
+            name: "Ops".to_string(),
+            config,
+            event_types: Vec::new(),
+            min_severity: AlertSeverity::Info,
+            enabled: true,
+        }
+    }
+
+    fn config_update(config: serde_json::Value) -> UpdateNotificationChannel {
+        UpdateNotificationChannel { name: None, config: Some(config), event_types: None, min_severity: None, enabled: None }
+    }
+
+    #[tokio::test]
+    async fn channel_destination_can_be_replaced() {
+        let Some(db) = test_support::database().await else { return };
+        let config = test_support::config();
+        let user = test_support::user(&db, UserRole::User, None).await;
+        let channel = create_channel(&db, &config, user, create_payload(webhook("https://93.184.216.34/old".to_string())))
+            .await
+            .unwrap();
+
+        let masked = channel.config.redacted();
+        let ChannelConfig::Webhook(masked) = masked else { unreachable!() };
+        let kept = update_channel(
+            &db,
+            &config,
+            user,
+            channel.id,
+            config_update(serde_json::json!({"url": "https://93.184.216.34/new", "secret": masked.secret})),
+        )
+        .await;
+        let replaced = update_channel(
+            &db,
+            &config,
+            user,
+            channel.id,
+            config_update(serde_json::json!({"url": "https://93.184.216.34/new", "secret": "fedcba9876543210"})),
+        )
+        .await;
+        let wrong_kind =
+            update_channel(&db, &config, user, channel.id, config_update(serde_json::json!({"address": "ops@example.com"})))
+                .await;
+        let invalid = update_channel(
+            &db,
+            &config,
+            user,
+            channel.id,
+            config_update(serde_json::json!({"url": "not a url", "secret": "short"})),
+        )
+        .await;
+        let stored = get_channel(&db, user, channel.id).await;
+        test_support::delete_users(&db, &[user]).await;
+
+        let ChannelConfig::Webhook(kept) = kept.unwrap().config.0 else { panic!("kind changed") };
+        assert_eq!(kept.url, "https://93.184.216.34/new");
+        assert_eq!(kept.secret, "0123456789abcdef");
+        let ChannelConfig::Webhook(replaced) = replaced.unwrap().config.0 else { panic!("kind changed") };
+        assert_eq!(replaced.secret, "fedcba9876543210");
+        assert!(matches!(wrong_kind, Err(AppError::BadRequest(_))));
+        let Err(AppError::ValidationFailed(errors)) = invalid else { panic!("expected validation errors") };
+        assert!(errors.to_string().contains("url"));
+        let ChannelConfig::Webhook(stored) = stored.unwrap().config.0 else { panic!("kind changed") };
+        assert_eq!(stored.secret, "fedcba9876543210");
+    }
+
+    #[tokio::test]
+    async fn private_destinations_are_refused_when_saved() {
+        let Some(db) = test_support::database().await else { return };
+        let mut config = test_support::config();
+        config.notification_allow_private_targets = false;
+        let user = test_support::user(&db, UserRole::User, None).await;
+
+        let created = create_channel(&db, &config, user, create_payload(webhook("http://127.0.0.1/hook".to_string()))).await;
+        let slack = ChannelConfig::Slack(SlackChannel { url: "https://93.184.216.34/hook".to_string() });
+        let channel = create_channel(&db, &config, user, create_payload(slack)).await.unwrap();
+        let updated =
+            update_channel(&db, &config, user, channel.id, config_update(serde_json::json!({"url": "http://localhost/hook"})))
+                .await;
+        let stored = get_channel(&db, user, channel.id).await;
+        test_support::delete_users(&db, &[user]).await;
+
+        assert!(matches!(created, Err(AppError::BadRequest(message)) if message.contains("non-public")));
+        assert!(matches!(updated, Err(AppError::BadRequest(message)) if message.contains("non-public")));
+        let ChannelConfig::Slack(stored) = stored.unwrap().config.0 else { panic!("kind changed") };
+        assert_eq!(stored.url, "https://93.184.216.34/hook");
+    }
+
+    #[test]
+    fn retry_delay_backs_off_exponentially_up_to_the_cap() {
+        assert_eq!(retry_delay(1), Duration::seconds(RETRY_BASE_SECONDS));
+        assert_eq!(retry_delay(2), Duration::seconds(RETRY_BASE_SECONDS * 2));
+        assert_eq!(retry_delay(4), Duration::seconds(RETRY_BASE_SECONDS * 8));
+        assert_eq!(retry_delay(20), Duration::seconds(RETRY_MAX_SECONDS));
+    }
+
+    #[test]
+    fn delivery_is_exhausted_at_max_attempts_or_when_disabled() {
+        assert!(!is_exhausted(true, 5, 6));
+        assert!(is_exhausted(true, 6, 6));
+        assert!(is_exhausted(false, 1, 6));
+    }
+
+    #[test]
+    fn only_public_addresses_are_allowed() {
+        for ip in ["127.0.0.1", "10.0.0.1", "172.16.0.1", "192.168.1.1", "169.254.169.254", "100.64.0.1", "0.0.0.0",
+            "::1", "fe80::1", "fd00::1", "::ffff:127.0.0.1", "::ffff:169.254.169.254"]
+        {
+            assert!(!is_public_ip(&ip.parse().unwrap()), "{}", ip);
+        }
+        for ip in ["1.1.1.1", "93.184.216.34", "2606:4700:4700::1111"] {
+            assert!(is_public_ip(&ip.parse().unwrap()), "{}", ip);
+        }
+    }
+}
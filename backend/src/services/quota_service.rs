@@ -0,0 +1,256 @@
+use crate::{
+    database::DbPool,
+    models::user::*,
+    utils::{errors::AppError, validation::invalid},
+};
+use chrono::Utc;
+use sqlx::PgConnection;
+use uuid::Uuid;
+use validator::ValidationErrors;
+
+/// Resource kinds a reseller can cap per customer
+#[derive(Debug, Clone, Copy)]
+pub enum QuotaResource {
+    Servers,
+    Vps,
+    Websites,
+}
+
+impl QuotaResource {
+    fn label(&self) -> &str {
+        match self {
+            QuotaResource::Servers => "Server",
+            QuotaResource::Vps => "VPS",
+            QuotaResource::Websites => "Website",
+        }
+    }
+}
+
+pub async fn get_quota(db: &DbPool, user_id: Uuid) -> Result<QuotaResponse, AppError> {
+    let quota = sqlx::query_as::<_, QuotaResponse>(
+        "SELECT u.id AS user_id, q.max_servers, q.max_vps, q.max_websites,
+                (SELECT COUNT(*) FROM servers WHERE user_id = u.id) AS servers_used,
+                (SELECT COUNT(*) FROM vps WHERE user_id = u.id) AS vps_used,
+                (SELECT COUNT(*) FROM websites WHERE user_id = u.id) AS websites_used
+         FROM users u
+         LEFT JOIN user_quotas q ON q.user_id = u.id
+         WHERE u.id = $1"
+    )
+    .bind(user_id)
+    .fetch_optional(db)
+    .await?
+    .ok_or(AppError::NotFound("User not found".to_string()))?;
+
+    Ok(quota)
+}
+
+/// A customer limit is invalid if it is unlimited or higher while the reseller's own is capped
+fn check_within(errors: &mut ValidationErrors, field: &'static str, requested: Option<i32>, own: Option<i32>) {
+    let Some(own) = own else {
+        return;
+    };
+
+    if requested.is_none_or(|requested| requested > own) {
+        errors.add(field, invalid("exceeds_own_quota", "Cannot exceed your own quota"));
+    }
+}
+
+/// Set a customer's quota; resellers cannot grant more than their own quota allows
+pub async fn set_quota(
+    db: &DbPool,
+    user_id: Uuid,
+    updated_by: Uuid,
+    payload: UpdateQuota,
+) -> Result<QuotaResponse, AppError> {
+    let own = sqlx::query_as::<_, UserQuota>(
+        "SELECT q.* FROM user_quotas q
+         JOIN users u ON u.id = q.user_id
+         WHERE q.user_id = $1 AND u.role = $2"
+    )
+    .bind(updated_by)
+    .bind(UserRole::Reseller.as_str())
+    .fetch_optional(db)
+    .await?;
+
+    if let Some(own) = own {
+        let mut errors = ValidationErrors::new();
+        check_within(&mut errors, "max_servers", payload.max_servers, own.max_servers);
+        check_within(&mut errors, "max_vps", payload.max_vps, own.max_vps);
+        check_within(&mut errors, "max_websites", payload.max_websites, own.max_websites);
+        if !errors.is_empty() {
+            return Err(AppError::ValidationFailed(errors));
+        }
+    }
+
+    sqlx::query(
+        "INSERT INTO user_quotas (user_id, max_servers, max_vps, max_websites, updated_by, updated_at)
+         VALUES ($1, $2, $3, $4, $5, $6)
+         ON CONFLICT (user_id) DO UPDATE
+         SET max_servers = EXCLUDED.max_servers, max_vps = EXCLUDED.max_vps,
+             max_websites = EXCLUDED.max_websites, updated_by = EXCLUDED.updated_by, updated_at = EXCLUDED.updated_at"
+    )
+    .bind(user_id)
+    .bind(payload.max_servers)
+    .bind(payload.max_vps)
+    .bind(payload.max_websites)
+    .bind(updated_by)
+    .bind(Utc::now())
+    .execute(db)
+    .await?;
+
+    get_quota(db, user_id).await
+}
+
+/// Fail if creating one more `resource` would exceed the user's quota.
+///
+/// Locks the user's row until the surrounding transaction ends, so call it in the transaction
+/// that inserts the resource: concurrent creations then count one after another.
+pub async fn ensure_capacity(
+    conn: &mut PgConnection,
+    user_id: Uuid,
+    resource: QuotaResource,
+) -> Result<(), AppError> {
+    sqlx::query("SELECT id FROM users WHERE id = $1 FOR UPDATE")
+        .bind(user_id)
+        .execute(&mut *conn)
+        .await?;
+
+    let quota = sqlx::query_as::<_, UserQuota>(
+        "SELECT * FROM user_quotas WHERE user_id = $1"
+    )
+    .bind(user_id)
+    .fetch_optional(&mut *conn)
+    .await?;
+
+    let limit = match (quota, resource) {
+        (Some(q), QuotaResource::Servers) => q.max_servers,
+        (Some(q), QuotaResource::Vps) => q.max_vps,
+        (Some(q), QuotaResource::Websites) => q.max_websites,
+        (None, _) => None,
+    };
+
+    let Some(limit) = limit else {
+        return Ok(());
+    };
+
+    let used: i64 = match resource {
+        QuotaResource::Servers => sqlx::query_scalar("SELECT COUNT(*) FROM servers WHERE user_id = $1"),
+        QuotaResource::Vps => sqlx::query_scalar("SELECT COUNT(*) FROM vps WHERE user_id = $1"),
+        QuotaResource::Websites => sqlx::query_scalar("SELECT COUNT(*) FROM websites WHERE user_id = $1"),
+    }
+    .bind(user_id)
+    .fetch_one(&mut *conn)
+    .await?;
+
+    if used >= i64::from(limit) {
+        return Err(AppError::Forbidden(format!(
+            "{} quota exceeded ({} of {} used)",
+            resource.label(),
+            used,
+            limit
+        )));
+    }
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        models::{auth::Scope, server::CreateServer, website::CreateWebsite},
+        services::{server_service, website_service},
+        test_support::{self, delete_users, user},
+    };
+
+    fn server(name: String) -> CreateServer {
+        CreateServer {
+            hostname: format!("{}.example.com", name),
+            name,
+            ip_address: "203.0.113.10".to_string(),
+            server_type: "dedicated".to_string(),
+            location: None,
+            cpu_cores: None,
+            ram_gb: None,
+            disk_gb: None,
+            os: None,
+        }
+    }
+
+    #[test]
+    fn customer_limits_must_fit_the_resellers() {
+        let mut errors = ValidationErrors::new();
+        check_within(&mut errors, "max_servers", Some(5), Some(5));
+        check_within(&mut errors, "max_servers", None, None);
+        assert!(errors.is_empty());
+
+        check_within(&mut errors, "max_servers", Some(6), Some(5));
+        check_within(&mut errors, "max_vps", None, Some(2));
+        assert_eq!(errors.field_errors().len(), 2);
+    }
+
+    #[tokio::test]
+    async fn concurrent_creations_cannot_exceed_the_quota() {
+        let Some(db) = test_support::database().await else { return };
+        let customer = user(&db, UserRole::User, None).await;
+        let admin = user(&db, UserRole::Admin, None).await;
+        set_quota(&db, customer, admin, UpdateQuota { max_servers: Some(2), max_vps: None, max_websites: None }).await.unwrap();
+
+        let attempts = (0..8).map(|i| {
+            let db = db.clone();
+            tokio::spawn(async move { server_service::create_server(&db, customer, server(format!("s{}", i))).await })
+        });
+        let results = futures::future::join_all(attempts).await;
+
+        let created = results.iter().filter(|result| matches!(result, Ok(Ok(_)))).count();
+        let refused = results.iter().filter(|result| matches!(result, Ok(Err(AppError::Forbidden(_))))).count();
+        delete_users(&db, &[customer, admin]).await;
+        assert_eq!((created, refused), (2, 6));
+    }
+
+    #[tokio::test]
+    async fn resellers_cannot_grant_more_than_their_own_quota() {
+        let Some(db) = test_support::database().await else { return };
+        let admin = user(&db, UserRole::Admin, None).await;
+        let reseller = user(&db, UserRole::Reseller, None).await;
+        let customer = user(&db, UserRole::User, Some(reseller)).await;
+        set_quota(&db, reseller, admin, UpdateQuota { max_servers: Some(10), max_vps: Some(3), max_websites: Some(1) }).await.unwrap();
+
+        let too_many = set_quota(&db, customer, reseller, UpdateQuota { max_servers: Some(10), max_vps: Some(4), max_websites: Some(1) }).await;
+        let unlimited = set_quota(&db, customer, reseller, UpdateQuota { max_servers: None, max_vps: Some(3), max_websites: Some(2) }).await;
+        let within = set_quota(&db, customer, reseller, UpdateQuota { max_servers: Some(10), max_vps: Some(3), max_websites: Some(1) }).await;
+
+        delete_users(&db, &[customer, reseller, admin]).await;
+        assert!(matches!(too_many, Err(AppError::ValidationFailed(e)) if e.field_errors().contains_key("max_vps")));
+        assert!(matches!(
+            unlimited,
+            Err(AppError::ValidationFailed(e))
+                if e.field_errors().contains_key("max_servers") && e.field_errors().contains_key("max_websites")
+        ));
+        assert_eq!(within.unwrap().max_vps, Some(3));
+    }
+
+    #[tokio::test]
+    async fn website_creation_respects_the_quota() {
+        let Some(db) = test_support::database().await else { return };
+        let customer = user(&db, UserRole::User, None).await;
+        let admin = user(&db, UserRole::Admin, None).await;
+        set_quota(&db, customer, admin, UpdateQuota { max_servers: None, max_vps: None, max_websites: Some(1) }).await.unwrap();
+        let website = |domain: &str| CreateWebsite {
+            domain: domain.to_string(),
+            server_id: None,
+            application_type: "php".to_string(),
+            php_version: Some("8.3".to_string()),
+            document_root: None,
+        };
+
+        let first = website_service::create_website(&db, &Scope::Owner(customer), customer, website("one.example.com")).await;
+        let second = website_service::create_website(&db, &Scope::Owner(customer), customer, website("two.example.com")).await;
+        let quota = get_quota(&db, customer).await.unwrap();
+
+        delete_users(&db, &[customer, admin]).await;
+        assert_eq!(first.unwrap().status, "pending");
+        assert!(matches!(second, Err(AppError::Forbidden(_))));
+        assert_eq!((quota.max_websites, quota.websites_used), (Some(1), 1));
+    }
+}
@@ -1,26 +1,65 @@
 use crate::{
     database::DbPool,
-    models::server::*,
+    models::{auth::Scope, list::{ListQuery, Page}, server::*},
+    services::quota_service::{self, QuotaResource},
     utils::errors::AppError,
 };
 use chrono::Utc;
 use uuid::Uuid;
 
-pub async fn list_servers(db: &DbPool) -> Result<Vec<Server>, AppError> {
-    let servers = sqlx::query_as::<_, Server>(
-        "SELECT * FROM servers ORDER BY created_at DESC"
-    )
+const SERVER_SORT_FIELDS: [&str; 7] = ["name", "hostname", "status", "server_type", "location", "last_seen_at", "created_at"];
+
+/// $1/$2 tenant scope, then status, server type, location and search pattern
+const SERVER_FILTERS: &str = "
+    WHERE ($1::uuid IS NULL OR user_id = $1
+           OR ($2 AND user_id IN (SELECT id FROM users WHERE parent_id = $1)))
+      AND ($3::text IS NULL OR status = $3)
+      AND ($4::text IS NULL OR server_type = $4)
+      AND ($5::text IS NULL OR location = $5)
+      AND ($6::text IS NULL OR name ILIKE $6 OR hostname ILIKE $6 OR ip_address ILIKE $6)";
+
+pub async fn list_servers(db: &DbPool, scope: &Scope, query: &ListQuery) -> Result<Page<Server>, AppError> {
+    let order_by = query.order_by(&SERVER_SORT_FIELDS, "created_at")?;
+    let search = query.search_pattern();
+
+    let items = sqlx::query_as::<_, Server>(&format!(
+        "SELECT * FROM servers {} ORDER BY {} LIMIT $7 OFFSET $8",
+        SERVER_FILTERS, order_by
+    ))
+    .bind(scope.owner_id())
+    .bind(scope.includes_customers())
+    .bind(query.status())
+    .bind(query.server_type())
+    .bind(query.location())
+    .bind(&search)
+    .bind(query.limit())
+    .bind(query.offset())
     .fetch_all(db)
     .await?;
 
-    Ok(servers)
+    let total = sqlx::query_scalar::<_, i64>(&format!("SELECT COUNT(*) FROM servers {}", SERVER_FILTERS))
+        .bind(scope.owner_id())
+        .bind(scope.includes_customers())
+        .bind(query.status())
+        .bind(query.server_type())
+        .bind(query.location())
+        .bind(&search)
+        .fetch_one(db)
+        .await?;
+
+    Ok(Page { items, total })
 }
 
-pub async fn get_server(db: &DbPool, id: Uuid) -> Result<Server, AppError> {
+pub async fn get_server(db: &DbPool, scope: &Scope, id: Uuid) -> Result<Server, AppError> {
     let server = sqlx::query_as::<_, Server>(
-        "SELECT * FROM servers WHERE id = $1"
+        "SELECT * FROM servers
+         WHERE id = $1
+           AND ($2::uuid IS NULL OR user_id = $2
+                OR ($3 AND user_id IN (SELECT id FROM users WHERE parent_id = $2)))"
     )
     .bind(id)
+    .bind(scope.owner_id())
+    .bind(scope.includes_customers())
     .fetch_optional(db)
     .await?
     .ok_or(AppError::NotFound("Server not found".to_string()))?;
@@ -33,6 +72,9 @@ pub async fn create_server(
     user_id: Uuid,
     payload: CreateServer,
 ) -> Result<Server, AppError> {
+    let mut tx = db.begin().await?;
+    quota_service::ensure_capacity(&mut tx, user_id, QuotaResource::Servers).await?;
+
     let server = sqlx::query_as::<_, Server>(
         "INSERT INTO servers (
             id, user_id, name, hostname, ip_address, status, server_type,
@@ -49,25 +91,59 @@ pub async fn create_server(
     .bind(ServerStatus::Provisioning.as_str())
     .bind(&payload.server_type)
     .bind(&payload.location)
-    .bind(&payload.cpu_cores)
-    .bind(&payload.ram_gb)
-    .bind(&payload.disk_gb)
+    .bind(payload.cpu_cores)
+    .bind(payload.ram_gb)
+    .bind(payload.disk_gb)
     .bind(&payload.os)
     .bind(Utc::now())
     .bind(Utc::now())
-    .fetch_one(db)
+    .fetch_one(&mut *tx)
     .await?;
 
+    tx.commit().await?;
+
     Ok(server)
 }
 
+/// Idempotent agent registration: reuse the caller's server with this hostname or create one
+pub async fn register_agent(
+    db: &DbPool,
+    user_id: Uuid,
+    payload: CreateServer,
+) -> Result<Server, AppError> {
+    let existing = sqlx::query_as::<_, Server>(
+        "UPDATE servers
+         SET ip_address = $1, cpu_cores = COALESCE($2, cpu_cores), ram_gb = COALESCE($3, ram_gb),
+             disk_gb = COALESCE($4, disk_gb), os = COALESCE($5, os), updated_at = $6
+         WHERE id = (SELECT id FROM servers WHERE user_id = $7 AND hostname = $8
+                     ORDER BY created_at LIMIT 1)
+         RETURNING *"
+    )
+    .bind(&payload.ip_address)
+    .bind(payload.cpu_cores)
+    .bind(payload.ram_gb)
+    .bind(payload.disk_gb)
+    .bind(&payload.os)
+    .bind(Utc::now())
+    .bind(user_id)
+    .bind(&payload.hostname)
+    .fetch_optional(db)
+    .await?;
+
+    match existing {
+        Some(server) => Ok(server),
+        None => create_server(db, user_id, payload).await,
+    }
+}
+
 pub async fn update_server(
     db: &DbPool,
+    scope: &Scope,
     id: Uuid,
     payload: UpdateServer,
 ) -> Result<Server, AppError> {
-    // Get existing server
-    let mut server = get_server(db, id).await?;
+    // Get existing server (also enforces tenant scope)
+    let mut server = get_server(db, scope, id).await?;
 
     // Update fields
     if let Some(name) = payload.name {
@@ -111,9 +187,9 @@ pub async fn update_server(
     .bind(&server.ip_address)
     .bind(&server.status)
     .bind(&server.location)
-    .bind(&server.cpu_cores)
-    .bind(&server.ram_gb)
-    .bind(&server.disk_gb)
+    .bind(server.cpu_cores)
+    .bind(server.ram_gb)
+    .bind(server.disk_gb)
     .bind(&server.os)
     .bind(Utc::now())
     .bind(id)
@@ -123,11 +199,18 @@ pub async fn update_server(
     Ok(server)
 }
 
-pub async fn delete_server(db: &DbPool, id: Uuid) -> Result<(), AppError> {
-    let result = sqlx::query("DELETE FROM servers WHERE id = $1")
-        .bind(id)
-        .execute(db)
-        .await?;
+pub async fn delete_server(db: &DbPool, scope: &Scope, id: Uuid) -> Result<(), AppError> {
+    let result = sqlx::query(
+        "DELETE FROM servers
+         WHERE id = $1
+           AND ($2::uuid IS NULL OR user_id = $2
+                OR ($3 AND user_id IN (SELECT id FROM users WHERE parent_id = $2)))"
+    )
+    .bind(id)
+    .bind(scope.owner_id())
+    .bind(scope.includes_customers())
+    .execute(db)
+    .await?;
 
     if result.rows_affected() == 0 {
         return Err(AppError::NotFound("Server not found".to_string()));
@@ -136,19 +219,106 @@ pub async fn delete_server(db: &DbPool, id: Uuid) -> Result<(), AppError> {
     Ok(())
 }
 
-pub async fn get_server_metrics(
-    db: &DbPool,
-    server_id: Uuid,
-) -> Result<Vec<ServerMetrics>, AppError> {
-    let metrics = sqlx::query_as::<_, ServerMetrics>(
-        "SELECT * FROM server_metrics
-         WHERE server_id = $1
-         ORDER BY timestamp DESC
-         LIMIT 100"
-    )
-    .bind(server_id)
-    .fetch_all(db)
-    .await?;
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_support::{self, server, Tenants};
+    use std::collections::HashMap;
+
+    fn rename() -> UpdateServer {
+        UpdateServer {
+            name: Some("renamed".to_string()),
+            hostname: None,
+            ip_address: None,
+            status: None,
+            location: None,
+            cpu_cores: None,
+            ram_gb: None,
+            disk_gb: None,
+            os: None,
+        }
+    }
+
+    async fn listed(db: &DbPool, scope: &Scope, id: Uuid) -> bool {
+        let query: ListQuery = serde_json::from_value(serde_json::json!({ "limit": 500 })).unwrap();
+        list_servers(db, scope, &query).await.unwrap().items.iter().any(|server| server.id == id)
+    }
 
-    Ok(metrics)
+    #[tokio::test]
+    async fn other_tenants_servers_are_not_found() {
+        let Some(db) = test_support::database().await else { return };
+        let tenants = Tenants::create(&db).await;
+        let id = server(&db, tenants.customer).await;
+
+        let outsiders = [
+            Scope::Owner(tenants.user),
+            Scope::Owner(tenants.other_customer),
+            Scope::Reseller(tenants.other_reseller),
+            // Resellers see their customers' servers, but customers never see their reseller's
+            Scope::Owner(tenants.reseller),
+        ];
+        let mut leaks = Vec::new();
+        for scope in &outsiders {
+            if get_server(&db, scope, id).await.is_ok()
+                || !matches!(update_server(&db, scope, id, rename()).await, Err(AppError::NotFound(_)))
+                || !matches!(delete_server(&db, scope, id).await, Err(AppError::NotFound(_)))
+                || listed(&db, scope, id).await
+            {
+                leaks.push(format!("{:?}", scope));
+            }
+        }
+        let untouched = get_server(&db, &Scope::All, id).await.unwrap();
+
+        tenants.delete(&db).await;
+        assert!(leaks.is_empty(), "visible to {:?}", leaks);
+        assert_eq!(untouched.name, "test");
+    }
+
+    #[tokio::test]
+    async fn owners_and_their_reseller_can_manage_servers() {
+        let Some(db) = test_support::database().await else { return };
+        let tenants = Tenants::create(&db).await;
+        let owned = server(&db, tenants.customer).await;
+        let managed = server(&db, tenants.customer).await;
+
+        let owner = Scope::Owner(tenants.customer);
+        let reseller = Scope::Reseller(tenants.reseller);
+        let seen_by_owner = get_server(&db, &owner, owned).await.is_ok() && listed(&db, &owner, owned).await;
+        let seen_by_reseller = get_server(&db, &reseller, owned).await.is_ok() && listed(&db, &reseller, owned).await;
+        let renamed = update_server(&db, &reseller, owned, rename()).await.unwrap();
+        let deleted = delete_server(&db, &reseller, managed).await;
+        let deleted_by_owner = delete_server(&db, &owner, owned).await;
+
+        tenants.delete(&db).await;
+        assert!(seen_by_owner && seen_by_reseller);
+        assert_eq!(renamed.name, "renamed");
+        assert!(deleted.is_ok() && deleted_by_owner.is_ok());
+    }
+
+    #[tokio::test]
+    async fn search_matches_wildcards_literally() {
+        let Some(db) = test_support::database().await else { return };
+        let user = test_support::user(&db, crate::models::user::UserRole::User, None).await;
+        let mut ids = HashMap::new();
+        for name in ["disk_100%", "disk-1000", "diskA100x"] {
+            let id = server(&db, user).await;
+            sqlx::query("UPDATE servers SET name = $2 WHERE id = $1").bind(id).bind(name).execute(&db).await.unwrap();
+            ids.insert(id, name);
+        }
+
+        let mut found = Vec::new();
+        for search in ["k_1", "100%", "disk"] {
+            let query: ListQuery = serde_json::from_value(serde_json::json!({ "search": search })).unwrap();
+            let page = list_servers(&db, &Scope::Owner(user), &query).await.unwrap();
+            let mut names: Vec<_> = page.items.iter().map(|server| ids[&server.id]).collect();
+            names.sort();
+            found.push(names);
+        }
+
+        test_support::delete_users(&db, &[user]).await;
+
+        assert_eq!(found[0], ["disk_100%"]);
+        assert_eq!(found[1], ["disk_100%"]);
+        assert_eq!(found[2], ["disk-1000", "diskA100x", "disk_100%"]);
+    }
 }
@@ -1,26 +1,57 @@
 use crate::{
+    config::Config,
     database::DbPool,
-    models::user::*,
+    models::{auth::{Scope, TokenPurpose}, list::{ListQuery, Page}, user::*},
+    services::verification_service,
     utils::{errors::AppError, password},
 };
 use chrono::Utc;
 use uuid::Uuid;
 
-pub async fn list_users(db: &DbPool) -> Result<Vec<User>, AppError> {
-    let users = sqlx::query_as::<_, User>(
-        "SELECT * FROM users ORDER BY created_at DESC"
-    )
+const USER_SORT_FIELDS: [&str; 4] = ["email", "role", "company", "created_at"];
+
+/// $1/$2 tenant scope, then role and search pattern
+const USER_FILTERS: &str = "
+    WHERE ($1::uuid IS NULL OR id = $1 OR ($2 AND parent_id = $1))
+      AND ($3::text IS NULL OR role = $3)
+      AND ($4::text IS NULL OR email ILIKE $4 OR company ILIKE $4)";
+
+pub async fn list_users(db: &DbPool, scope: &Scope, query: &ListQuery) -> Result<Page<User>, AppError> {
+    let order_by = query.order_by(&USER_SORT_FIELDS, "created_at")?;
+    let search = query.search_pattern();
+
+    let items = sqlx::query_as::<_, User>(&format!(
+        "SELECT * FROM users {} ORDER BY {} LIMIT $5 OFFSET $6",
+        USER_FILTERS, order_by
+    ))
+    .bind(scope.owner_id())
+    .bind(scope.includes_customers())
+    .bind(query.role())
+    .bind(&search)
+    .bind(query.limit())
+    .bind(query.offset())
     .fetch_all(db)
     .await?;
 
-    Ok(users)
+    let total = sqlx::query_scalar::<_, i64>(&format!("SELECT COUNT(*) FROM users {}", USER_FILTERS))
+        .bind(scope.owner_id())
+        .bind(scope.includes_customers())
+        .bind(query.role())
+        .bind(&search)
+        .fetch_one(db)
+        .await?;
+
+    Ok(Page { items, total })
 }
 
-pub async fn get_user(db: &DbPool, id: Uuid) -> Result<User, AppError> {
+pub async fn get_user(db: &DbPool, scope: &Scope, id: Uuid) -> Result<User, AppError> {
     let user = sqlx::query_as::<_, User>(
-        "SELECT * FROM users WHERE id = $1"
+        "SELECT * FROM users
+         WHERE id = $1 AND ($2::uuid IS NULL OR id = $2 OR ($3 AND parent_id = $2))"
     )
     .bind(id)
+    .bind(scope.owner_id())
+    .bind(scope.includes_customers())
     .fetch_optional(db)
     .await?
     .ok_or(AppError::NotFound("User not found".to_string()))?;
@@ -28,7 +59,7 @@ pub async fn get_user(db: &DbPool, id: Uuid) -> Result<User, AppError> {
     Ok(user)
 }
 
-pub async fn create_user(db: &DbPool, payload: CreateUser) -> Result<User, AppError> {
+pub async fn create_user(db: &DbPool, config: &Config, payload: CreateUser) -> Result<User, AppError> {
     // Check if email already exists
     let existing = sqlx::query_as::<_, User>(
         "SELECT * FROM users WHERE email = $1"
@@ -38,17 +69,27 @@ pub async fn create_user(db: &DbPool, payload: CreateUser) -> Result<User, AppEr
     .await?;
 
     if existing.is_some() {
-        return Err(AppError::BadRequest("Email already exists".to_string()));
+        return Err(AppError::Conflict("Email already exists".to_string()));
     }
 
+    // Customers may only be attached to a reseller
+    if let Some(parent_id) = payload.parent_id {
+        let parent = get_user(db, &Scope::All, parent_id).await?;
+        if parent.get_role() != UserRole::Reseller {
+            return Err(AppError::BadRequest("Parent user must be a reseller".to_string()));
+        }
+    }
+
+    password::validate_password(&payload.password, &payload.email, "password", config)?;
+
     // Hash password
-    let password_hash = password::hash_password(&payload.password)?;
+    let password_hash = password::hash_password(&payload.password, config)?;
 
     // Create user
     let role = payload.role.unwrap_or(UserRole::User);
     let user = sqlx::query_as::<_, User>(
-        "INSERT INTO users (id, email, password_hash, role, company, created_at, updated_at, mfa_enabled)
-         VALUES ($1, $2, $3, $4, $5, $6, $7, $8)
+        "INSERT INTO users (id, email, password_hash, role, company, parent_id, created_at, updated_at, mfa_enabled)
+         VALUES ($1, $2, $3, $4, $5, $6, $7, $8, $9)
          RETURNING *"
     )
     .bind(Uuid::new_v4())
@@ -56,6 +97,7 @@ pub async fn create_user(db: &DbPool, payload: CreateUser) -> Result<User, AppEr
     .bind(&password_hash)
     .bind(role.as_str())
     .bind(&payload.company)
+    .bind(payload.parent_id)
     .bind(Utc::now())
     .bind(Utc::now())
     .bind(false)
@@ -67,14 +109,21 @@ pub async fn create_user(db: &DbPool, payload: CreateUser) -> Result<User, AppEr
 
 pub async fn update_user(
     db: &DbPool,
+    scope: &Scope,
     id: Uuid,
     payload: UpdateUser,
 ) -> Result<User, AppError> {
-    // Get existing user
-    let mut user = get_user(db, id).await?;
+    // Get existing user (also enforces tenant scope)
+    let mut user = get_user(db, scope, id).await?;
 
     // Update fields
+    let mut email_changed = false;
     if let Some(email) = payload.email {
+        // A new address has to be verified again
+        if email != user.email {
+            user.email_verified_at = None;
+            email_changed = true;
+        }
         user.email = email;
     }
     if let Some(company) = payload.company {
@@ -88,29 +137,45 @@ pub async fn update_user(
     }
 
     // Save to database
+    let mut tx = db.begin().await?;
+
     let user = sqlx::query_as::<_, User>(
         "UPDATE users
-         SET email = $1, company = $2, timezone = $3, avatar_url = $4, updated_at = $5
-         WHERE id = $6
+         SET email = $1, company = $2, timezone = $3, avatar_url = $4, email_verified_at = $5,
+             updated_at = $6
+         WHERE id = $7
          RETURNING *"
     )
     .bind(&user.email)
     .bind(&user.company)
     .bind(&user.timezone)
     .bind(&user.avatar_url)
+    .bind(user.email_verified_at)
     .bind(Utc::now())
     .bind(id)
-    .fetch_one(db)
+    .fetch_one(&mut *tx)
     .await?;
 
+    // Links mailed to the old address must not verify the new one
+    if email_changed {
+        verification_service::invalidate_tokens(&mut *tx, id, TokenPurpose::EmailVerification).await?;
+    }
+
+    tx.commit().await?;
+
     Ok(user)
 }
 
-pub async fn delete_user(db: &DbPool, id: Uuid) -> Result<(), AppError> {
-    let result = sqlx::query("DELETE FROM users WHERE id = $1")
-        .bind(id)
-        .execute(db)
-        .await?;
+pub async fn delete_user(db: &DbPool, scope: &Scope, id: Uuid) -> Result<(), AppError> {
+    let result = sqlx::query(
+        "DELETE FROM users
+         WHERE id = $1 AND ($2::uuid IS NULL OR id = $2 OR ($3 AND parent_id = $2))"
+    )
+    .bind(id)
+    .bind(scope.owner_id())
+    .bind(scope.includes_customers())
+    .execute(db)
+    .await?;
 
     if result.rows_affected() == 0 {
         return Err(AppError::NotFound("User not found".to_string()));
@@ -118,3 +183,72 @@ pub async fn delete_user(db: &DbPool, id: Uuid) -> Result<(), AppError> {
 
     Ok(())
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_support::{self, Tenants};
+
+    fn rename() -> UpdateUser {
+        UpdateUser { email: None, company: Some("Renamed".to_string()), timezone: None, avatar_url: None }
+    }
+
+    async fn listed(db: &DbPool, scope: &Scope, id: Uuid) -> bool {
+        let query: ListQuery = serde_json::from_value(serde_json::json!({ "limit": 500 })).unwrap();
+        list_users(db, scope, &query).await.unwrap().items.iter().any(|user| user.id == id)
+    }
+
+    async fn hidden(db: &DbPool, scope: &Scope, id: Uuid) -> bool {
+        matches!(get_user(db, scope, id).await, Err(AppError::NotFound(_)))
+            && matches!(update_user(db, scope, id, rename()).await, Err(AppError::NotFound(_)))
+            && matches!(delete_user(db, scope, id).await, Err(AppError::NotFound(_)))
+            && !listed(db, scope, id).await
+    }
+
+    #[tokio::test]
+    async fn users_outside_the_scope_are_not_found() {
+        let Some(db) = test_support::database().await else { return };
+        let tenants = Tenants::create(&db).await;
+
+        let cases = [
+            ("other reseller -> customer", Scope::Reseller(tenants.other_reseller), tenants.customer),
+            ("reseller -> other reseller", Scope::Reseller(tenants.reseller), tenants.other_reseller),
+            ("reseller -> unrelated user", Scope::Reseller(tenants.reseller), tenants.user),
+            ("customer -> own reseller", Scope::Owner(tenants.customer), tenants.reseller),
+            ("customer -> other customer", Scope::Owner(tenants.customer), tenants.other_customer),
+            ("user -> customer", Scope::Owner(tenants.user), tenants.customer),
+        ];
+        let mut leaks = Vec::new();
+        for (case, scope, id) in &cases {
+            if !hidden(&db, scope, *id).await {
+                leaks.push(*case);
+            }
+        }
+        let survivors = sqlx::query_scalar::<_, i64>("SELECT COUNT(*) FROM users WHERE id = ANY($1)")
+            .bind([tenants.customer, tenants.other_reseller, tenants.user, tenants.reseller, tenants.other_customer])
+            .fetch_one(&db)
+            .await
+            .unwrap();
+
+        tenants.delete(&db).await;
+        assert!(leaks.is_empty(), "visible: {:?}", leaks);
+        assert_eq!(survivors, 5);
+    }
+
+    #[tokio::test]
+    async fn resellers_manage_their_own_customers() {
+        let Some(db) = test_support::database().await else { return };
+        let tenants = Tenants::create(&db).await;
+        let reseller = Scope::Reseller(tenants.reseller);
+
+        let seen = get_user(&db, &reseller, tenants.customer).await.is_ok() && listed(&db, &reseller, tenants.customer).await;
+        let sees_self = get_user(&db, &reseller, tenants.reseller).await.is_ok();
+        let updated = update_user(&db, &reseller, tenants.customer, rename()).await.unwrap();
+        let deleted = delete_user(&db, &reseller, tenants.customer).await;
+
+        tenants.delete(&db).await;
+        assert!(seen && sees_self);
+        assert_eq!(updated.company.as_deref(), Some("Renamed"));
+        assert!(deleted.is_ok());
+    }
+}
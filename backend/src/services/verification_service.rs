@@ -0,0 +1,311 @@
+use crate::{
+    config::Config,
+    database::DbPool,
+    models::{auth::TokenPurpose, user::User},
+    services::{
+        auth_service, lockout_service,
+        mail_service::{self, MailMessage, MailSender},
+    },
+    utils::{crypto, errors::AppError, password},
+};
+use chrono::{Duration, Utc};
+use std::sync::Arc;
+use uuid::Uuid;
+
+const PASSWORD_RESET_TTL_MINUTES: i64 = 60;
+const EMAIL_VERIFICATION_TTL_HOURS: i64 = 48;
+
+/// Mark every unused token of `purpose` as used, e.g. verification links sent to an old address
+pub async fn invalidate_tokens<'e, E>(executor: E, user_id: Uuid, purpose: TokenPurpose) -> Result<(), AppError>
+where
+    E: sqlx::PgExecutor<'e>,
+{
+    sqlx::query(
+        "UPDATE user_tokens SET used_at = $1 WHERE user_id = $2 AND purpose = $3 AND used_at IS NULL"
+    )
+    .bind(Utc::now())
+    .bind(user_id)
+    .bind(purpose.as_str())
+    .execute(executor)
+    .await?;
+
+    Ok(())
+}
+
+/// Issue a new one-time token, invalidating any unused ones for the same purpose
+async fn create_token(
+    db: &DbPool,
+    user_id: Uuid,
+    purpose: TokenPurpose,
+    ttl: Duration,
+) -> Result<String, AppError> {
+    let token = crypto::random_token(32);
+
+    invalidate_tokens(db, user_id, purpose).await?;
+
+    sqlx::query(
+        "INSERT INTO user_tokens (user_id, purpose, token_hash, expires_at, created_at)
+         VALUES ($1, $2, $3, $4, $5)"
+    )
+    .bind(user_id)
+    .bind(purpose.as_str())
+    .bind(crypto::sha256_hex(&token))
+    .bind(Utc::now() + ttl)
+    .bind(Utc::now())
+    .execute(db)
+    .await?;
+
+    Ok(token)
+}
+
+/// Redeem a token atomically, returning its user
+async fn consume_token(db: &DbPool, token: &str, purpose: TokenPurpose) -> Result<Uuid, AppError> {
+    sqlx::query_scalar::<_, Uuid>(
+        "UPDATE user_tokens SET used_at = $1
+         WHERE token_hash = $2 AND purpose = $3 AND used_at IS NULL AND expires_at > $1
+         RETURNING user_id"
+    )
+    .bind(Utc::now())
+    .bind(crypto::sha256_hex(token))
+    .bind(purpose.as_str())
+    .fetch_optional(db)
+    .await?
+    .ok_or(AppError::BadRequest("Invalid or expired token".to_string()))
+}
+
+async fn find_user_by_email(db: &DbPool, email: &str) -> Result<Option<User>, AppError> {
+    let user = sqlx::query_as::<_, User>("SELECT * FROM users WHERE email = $1")
+        .bind(email)
+        .fetch_optional(db)
+        .await?;
+
+    Ok(user)
+}
+
+/// Email a reset link if the account exists; callers always report success to avoid enumeration
+pub async fn request_password_reset(
+    db: &DbPool,
+    config: &Config,
+    mailer: Arc<dyn MailSender>,
+    email: &str,
+) -> Result<(), AppError> {
+    let Some(user) = find_user_by_email(db, email).await? else {
+        return Ok(());
+    };
+
+    let token = create_token(
+        db,
+        user.id,
+        TokenPurpose::PasswordReset,
+        Duration::minutes(PASSWORD_RESET_TTL_MINUTES),
+    )
+    .await?;
+
+    mail_service::send_in_background(mailer, MailMessage {
+        to: user.email,
+        subject: "Reset your Unified Panel password".to_string(),
+        body: format!(
+            "A password reset was requested for your account.\n\n\
+             Reset your password: {}/reset-password?token={}\n\n\
+             This link expires in {} minutes. If you did not request it, ignore this email.",
+            config.public_url, token, PASSWORD_RESET_TTL_MINUTES
+        ),
+    });
+
+    Ok(())
+}
+
+/// Set a new password from a reset token and sign out every existing session
+pub async fn reset_password(
+    db: &DbPool,
+    config: &Config,
+    token: &str,
+    new_password: &str,
+) -> Result<(), AppError> {
+    // Validate before redeeming so a rejected password does not burn the token
+    let email = sqlx::query_scalar::<_, String>(
+        "SELECT u.email FROM user_tokens t
+         JOIN users u ON u.id = t.user_id
+         WHERE t.token_hash = $1 AND t.purpose = $2 AND t.used_at IS NULL AND t.expires_at > $3"
+    )
+    .bind(crypto::sha256_hex(token))
+    .bind(TokenPurpose::PasswordReset.as_str())
+    .bind(Utc::now())
+    .fetch_optional(db)
+    .await?
+    .ok_or(AppError::BadRequest("Invalid or expired token".to_string()))?;
+
+    password::validate_password(new_password, &email, "new_password", config)?;
+
+    let user_id = consume_token(db, token, TokenPurpose::PasswordReset).await?;
+    let password_hash = password::hash_password(new_password, config)?;
+
+    let email = sqlx::query_scalar::<_, String>(
+        "UPDATE users SET password_hash = $1, updated_at = $2 WHERE id = $3 RETURNING email"
+    )
+    .bind(&password_hash)
+    .bind(Utc::now())
+    .bind(user_id)
+    .fetch_one(db)
+    .await?;
+
+    lockout_service::clear(db, &email).await?;
+    auth_service::revoke_all_sessions(db, user_id).await
+}
+
+pub async fn send_verification_email(
+    db: &DbPool,
+    config: &Config,
+    mailer: Arc<dyn MailSender>,
+    user: &User,
+) -> Result<(), AppError> {
+    if user.email_verified_at.is_some() {
+        return Ok(());
+    }
+
+    let token = create_token(
+        db,
+        user.id,
+        TokenPurpose::EmailVerification,
+        Duration::hours(EMAIL_VERIFICATION_TTL_HOURS),
+    )
+    .await?;
+
+    mail_service::send_in_background(mailer, MailMessage {
+        to: user.email.clone(),
+        subject: "Verify your Unified Panel email address".to_string(),
+        body: format!(
+            "Confirm your email address: {}/verify-email?token={}\n\n\
+             This link expires in {} hours.",
+            config.public_url, token, EMAIL_VERIFICATION_TTL_HOURS
+        ),
+    });
+
+    Ok(())
+}
+
+/// Resend a verification link if the account exists and is unverified
+pub async fn request_verification(
+    db: &DbPool,
+    config: &Config,
+    mailer: Arc<dyn MailSender>,
+    email: &str,
+) -> Result<(), AppError> {
+    match find_user_by_email(db, email).await? {
+        Some(user) => send_verification_email(db, config, mailer, &user).await,
+        None => Ok(()),
+    }
+}
+
+pub async fn verify_email(db: &DbPool, token: &str) -> Result<(), AppError> {
+    let user_id = consume_token(db, token, TokenPurpose::EmailVerification).await?;
+
+    sqlx::query("UPDATE users SET email_verified_at = $1, updated_at = $1 WHERE id = $2")
+        .bind(Utc::now())
+        .bind(user_id)
+        .execute(db)
+        .await?;
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        models::{auth::Scope, user::UpdateUser},
+        services::user_service,
+        test_support::{self, RecordingMailer},
+    };
+
+    async fn unverified_user(db: &DbPool) -> User {
+        sqlx::query_as::<_, User>(
+            "INSERT INTO users (id, email, password_hash, role, created_at, updated_at, mfa_enabled)
+             VALUES ($1, $2, 'unused', 'user', NOW(), NOW(), FALSE)
+             RETURNING *"
+        )
+        .bind(Uuid::new_v4())
+        .bind(format!("verify-{}@example.com", Uuid::new_v4()))
+        .fetch_one(db)
+        .await
+        .unwrap()
+    }
+
+    fn link_token(message: &MailMessage) -> String {
+        let start = message.body.find("token=").unwrap() + "token=".len();
+        message.body[start..].split_whitespace().next().unwrap().to_string()
+    }
+
+    async fn is_verified(db: &DbPool, id: Uuid) -> bool {
+        user_service::get_user(db, &Scope::All, id).await.unwrap().email_verified_at.is_some()
+    }
+
+    #[tokio::test]
+    async fn resending_replaces_the_previous_link() {
+        let Some(db) = test_support::database().await else { return };
+        let config = test_support::config();
+        let mailer = Arc::new(RecordingMailer::default());
+        let user = unverified_user(&db).await;
+
+        send_verification_email(&db, &config, mailer.clone(), &user).await.unwrap();
+        let first = link_token(&mailer.nth(1).await);
+        request_verification(&db, &config, mailer.clone(), &user.email).await.unwrap();
+        let second = mailer.nth(2).await;
+
+        assert_eq!(second.to, user.email);
+        assert!(matches!(verify_email(&db, &first).await, Err(AppError::BadRequest(_))));
+        verify_email(&db, &link_token(&second)).await.unwrap();
+        assert!(is_verified(&db, user.id).await);
+        assert!(matches!(verify_email(&db, &link_token(&second)).await, Err(AppError::BadRequest(_))));
+
+        sqlx::query("DELETE FROM users WHERE id = $1").bind(user.id).execute(&db).await.unwrap();
+    }
+
+    #[tokio::test]
+    async fn changing_the_email_invalidates_old_links() {
+        let Some(db) = test_support::database().await else { return };
+        let config = test_support::config();
+        let mailer = Arc::new(RecordingMailer::default());
+        let user = unverified_user(&db).await;
+
+        send_verification_email(&db, &config, mailer.clone(), &user).await.unwrap();
+        let old_link = link_token(&mailer.nth(1).await);
+
+        let update = UpdateUser {
+            email: Some(format!("changed-{}", user.email)),
+            company: None,
+            timezone: None,
+            avatar_url: None,
+        };
+        let user = user_service::update_user(&db, &Scope::All, user.id, update).await.unwrap();
+
+        assert!(matches!(verify_email(&db, &old_link).await, Err(AppError::BadRequest(_))));
+        assert!(!is_verified(&db, user.id).await);
+
+        send_verification_email(&db, &config, mailer.clone(), &user).await.unwrap();
+        let new_link = mailer.nth(2).await;
+        assert_eq!(new_link.to, user.email);
+        verify_email(&db, &link_token(&new_link)).await.unwrap();
+        assert!(is_verified(&db, user.id).await);
+
+        sqlx::query("DELETE FROM users WHERE id = $1").bind(user.id).execute(&db).await.unwrap();
+    }
+
+    #[tokio::test]
+    async fn resend_ignores_unknown_and_verified_accounts() {
+        let Some(db) = test_support::database().await else { return };
+        let config = test_support::config();
+        let mailer = Arc::new(RecordingMailer::default());
+
+        request_verification(&db, &config, mailer.clone(), "nobody@example.invalid").await.unwrap();
+
+        let mut user = unverified_user(&db).await;
+        user.email_verified_at = Some(Utc::now());
+        send_verification_email(&db, &config, mailer.clone(), &user).await.unwrap();
+
+        tokio::time::sleep(std::time::Duration::from_millis(50)).await;
+        assert!(mailer.is_empty());
+
+        sqlx::query("DELETE FROM users WHERE id = $1").bind(user.id).execute(&db).await.unwrap();
+    }
+}
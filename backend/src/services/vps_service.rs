@@ -1,159 +1,87 @@
 use crate::{
     database::DbPool,
-    models::vps::*,
+    models::{
+        alert::AlertSeverity,
+        auth::Scope,
+        event::{Event, EventKind},
+        list::{ListQuery, Page},
+        notification::{Notification, NotificationEvent},
+        provider::{ActionStatus, CloudServer, CloudServerRequest, CloudSnapshot, ProviderKind},
+        vps::*,
+    },
+    services::{
+        cloud_service::{CloudProvider, CloudProviders},
+        event_service::EventBus,
+        notification_service,
+        quota_service::{self, QuotaResource},
+        user_service,
+    },
     utils::errors::AppError,
 };
-use chrono::Utc;
-use reqwest;
+use chrono::{DateTime, Duration, Utc};
+use std::collections::{HashMap, HashSet};
 use uuid::Uuid;
 
-const HETZNER_API_URL: &str = "https://api.hetzner.cloud/v1";
+/// Actions still running after this long are given up on
+const ACTION_TIMEOUT_MINUTES: i64 = 30;
 
-#[derive(Clone)]
-pub struct HetznerClient {
-    api_token: String,
-    client: reqwest::Client,
-}
-
-impl HetznerClient {
-    pub fn new(api_token: String) -> Self {
-        Self {
-            api_token,
-            client: reqwest::Client::new(),
-        }
-    }
-
-    async fn request<T, B>(
-        &self,
-        method: reqwest::Method,
-        endpoint: &str,
-        body: Option<B>,
-    ) -> Result<T, AppError>
-    where
-        T: serde::de::DeserializeOwned,
-        B: serde::Serialize,
-    {
-        let url = format!("{}{}", HETZNER_API_URL, endpoint);
-
-        let mut req = self.client
-            .request(method, &url)
-            .header("Authorization", format!("Bearer {}", self.api_token));
-
-        if let Some(body) = body {
-            req = req.json(&body);
-        }
+/// Running actions checked per poll
+const ACTION_POLL_BATCH: i64 = 100;
 
-        let response = req.send().await
-            .map_err(|e| AppError::InternalError(format!("Hetzner API request failed: {}", e)))?;
+const VPS_SORT_FIELDS: [&str; 6] = ["name", "status", "server_type", "location", "monthly_cost", "created_at"];
 
-        if !response.status().is_success() {
-            let status = response.status();
-            let error_text = response.text().await.unwrap_or_default();
-            return Err(AppError::InternalError(
-                format!("Hetzner API error {}: {}", status, error_text)
-            ));
-        }
-
-        response.json().await
-            .map_err(|e| AppError::InternalError(format!("Failed to parse Hetzner response: {}", e)))
-    }
-
-    pub async fn create_server(&self, request: HetznerCreateServerRequest) -> Result<HetznerServer, AppError> {
-        let response: HetznerServerResponse = self.request::<HetznerServerResponse, HetznerCreateServerRequest>(
-            reqwest::Method::POST,
-            "/servers",
-            Some(request),
-        ).await?;
-
-        Ok(response.server)
-    }
+/// $1/$2 tenant scope, then status, server type, location and search pattern
+const VPS_FILTERS: &str = "
+    WHERE ($1::uuid IS NULL OR user_id = $1
+           OR ($2 AND user_id IN (SELECT id FROM users WHERE parent_id = $1)))
+      AND ($3::text IS NULL OR status = $3)
+      AND ($4::text IS NULL OR server_type = $4)
+      AND ($5::text IS NULL OR location = $5)
+      AND ($6::text IS NULL OR name ILIKE $6 OR ipv4 ILIKE $6 OR ipv6 ILIKE $6)";
 
-    pub async fn get_server(&self, server_id: i64) -> Result<HetznerServer, AppError> {
-        let response: HetznerServerResponse = self.request(
-            reqwest::Method::GET,
-            &format!("/servers/{}", server_id),
-            None::<()>,
-        ).await?;
-
-        Ok(response.server)
-    }
-
-    pub async fn list_servers(&self) -> Result<Vec<HetznerServer>, AppError> {
-        let response: HetznerServersResponse = self.request::<HetznerServersResponse, ()>(
-            reqwest::Method::GET,
-            "/servers",
-            None::<()>,
-        ).await?;
-
-        Ok(response.servers)
-    }
-
-    pub async fn delete_server(&self, server_id: i64) -> Result<(), AppError> {
-        self.request::<serde_json::Value, ()>(
-            reqwest::Method::DELETE,
-            &format!("/servers/{}", server_id),
-            None::<()>,
-        ).await?;
-
-        Ok(())
-    }
-
-    pub async fn power_on(&self, server_id: i64) -> Result<(), AppError> {
-        self.request::<serde_json::Value, ()>(
-            reqwest::Method::POST,
-            &format!("/servers/{}/actions/poweron", server_id),
-            None::<()>,
-        ).await?;
-
-        Ok(())
-    }
-
-    pub async fn power_off(&self, server_id: i64) -> Result<(), AppError> {
-        self.request::<serde_json::Value, ()>(
-            reqwest::Method::POST,
-            &format!("/servers/{}/actions/poweroff", server_id),
-            None::<()>,
-        ).await?;
-
-        Ok(())
-    }
-
-    pub async fn reboot(&self, server_id: i64) -> Result<(), AppError> {
-        self.request::<serde_json::Value, ()>(
-            reqwest::Method::POST,
-            &format!("/servers/{}/actions/reboot", server_id),
-            None::<()>,
-        ).await?;
+// Database operations
+pub async fn list_vps(db: &DbPool, scope: &Scope, query: &ListQuery) -> Result<Page<Vps>, AppError> {
+    let order_by = query.order_by(&VPS_SORT_FIELDS, "created_at")?;
+    let search = query.search_pattern();
 
-        Ok(())
-    }
-}
+    let items = sqlx::query_as::<_, Vps>(&format!(
+        "SELECT * FROM vps {} ORDER BY {} LIMIT $7 OFFSET $8",
+        VPS_FILTERS, order_by
+    ))
+    .bind(scope.owner_id())
+    .bind(scope.includes_customers())
+    .bind(query.status())
+    .bind(query.server_type())
+    .bind(query.location())
+    .bind(&search)
+    .bind(query.limit())
+    .bind(query.offset())
+    .fetch_all(db)
+    .await?;
 
-// Database operations
-pub async fn list_vps(db: &DbPool, user_id: Option<Uuid>) -> Result<Vec<Vps>, AppError> {
-    let vps = if let Some(uid) = user_id {
-        sqlx::query_as::<_, Vps>(
-            "SELECT * FROM vps WHERE user_id = $1 ORDER BY created_at DESC"
-        )
-        .bind(uid)
-        .fetch_all(db)
-        .await?
-    } else {
-        sqlx::query_as::<_, Vps>(
-            "SELECT * FROM vps ORDER BY created_at DESC"
-        )
-        .fetch_all(db)
-        .await?
-    };
+    let total = sqlx::query_scalar::<_, i64>(&format!("SELECT COUNT(*) FROM vps {}", VPS_FILTERS))
+        .bind(scope.owner_id())
+        .bind(scope.includes_customers())
+        .bind(query.status())
+        .bind(query.server_type())
+        .bind(query.location())
+        .bind(&search)
+        .fetch_one(db)
+        .await?;
 
-    Ok(vps)
+    Ok(Page { items, total })
 }
 
-pub async fn get_vps(db: &DbPool, id: Uuid) -> Result<Vps, AppError> {
+pub async fn get_vps(db: &DbPool, scope: &Scope, id: Uuid) -> Result<Vps, AppError> {
     let vps = sqlx::query_as::<_, Vps>(
-        "SELECT * FROM vps WHERE id = $1"
+        "SELECT * FROM vps
+         WHERE id = $1
+           AND ($2::uuid IS NULL OR user_id = $2
+                OR ($3 AND user_id IN (SELECT id FROM users WHERE parent_id = $2)))"
     )
     .bind(id)
+    .bind(scope.owner_id())
+    .bind(scope.includes_customers())
     .fetch_optional(db)
     .await?
     .ok_or(AppError::NotFound("VPS not found".to_string()))?;
@@ -163,81 +91,123 @@ pub async fn get_vps(db: &DbPool, id: Uuid) -> Result<Vps, AppError> {
 
 pub async fn create_vps(
     db: &DbPool,
-    hetzner_client: &HetznerClient,
+    providers: &CloudProviders,
+    events: &EventBus,
     user_id: Uuid,
     payload: CreateVps,
 ) -> Result<Vps, AppError> {
-    // Create server on Hetzner
-    let hetzner_request = HetznerCreateServerRequest {
-        name: payload.name.clone(),
-        server_type: payload.server_type.clone(),
-        location: payload.location.clone(),
-        image: payload.image.clone(),
+    // Fail early before creating anything at the provider; `insert_vps` checks again under lock
+    quota_service::ensure_capacity(&mut *db.acquire().await?, user_id, QuotaResource::Vps).await?;
+
+    let catalog = providers.catalog(db, user_id, payload.provider).await?;
+    catalog
+        .validate(&payload.server_type, &payload.location, &payload.image)
+        .map_err(AppError::ValidationFailed)?;
+
+    let provider = providers.for_user(db, user_id, payload.provider).await?;
+
+    // Create the server with the provider
+    let request = CloudServerRequest {
+        name: payload.name,
+        server_type: payload.server_type,
+        location: payload.location,
+        image: payload.image,
         ssh_keys: payload.ssh_keys,
         user_data: payload.user_data,
-        start_after_create: true,
     };
 
-    let hetzner_server = hetzner_client.create_server(hetzner_request).await?;
-
-    // Extract pricing
-    let monthly_cost = hetzner_server.server_type.prices
-        .first()
-        .and_then(|p| p.price_monthly.gross.parse::<f64>().ok());
+    let mut server = provider.create_server(request).await?;
+    if server.monthly_cost.is_none() {
+        server.monthly_cost = catalog.server_types
+            .iter()
+            .find(|option| option.name == server.server_type)
+            .and_then(|option| option.monthly_price(&server.location));
+    }
 
     // Save to database
-    let vps_result = sqlx::query_as::<_, Vps>(
-        "INSERT INTO vps (
-            id, user_id, name, hetzner_id, status, server_type, location, image,
-            ipv4, ipv6, cpu_cores, ram_gb, disk_gb, monthly_cost, created_at, updated_at
-         )
-         VALUES ($1, $2, $3, $4, $5, $6, $7, $8, $9, $10, $11, $12, $13, $14, $15, $16)
-         RETURNING *"
-    )
-    .bind(Uuid::new_v4())
-    .bind(user_id)
-    .bind(&hetzner_server.name)
-    .bind(hetzner_server.id)
-    .bind(&hetzner_server.status)
-    .bind(&hetzner_server.server_type.name)
-    .bind(&hetzner_server.datacenter.location.name)
-    .bind(&hetzner_server.image.name)
-    .bind(hetzner_server.public_net.ipv4.as_ref().map(|ip| ip.ip.clone()))
-    .bind(hetzner_server.public_net.ipv6.as_ref().map(|ip| ip.ip.clone()))
-    .bind(hetzner_server.server_type.cores)
-    .bind((hetzner_server.server_type.memory / 1024.0) as i32)
-    .bind(hetzner_server.server_type.disk)
-    .bind(monthly_cost)
-    .bind(Utc::now())
-    .bind(Utc::now())
-    .fetch_one(db)
-    .await;
+    let vps_result = insert_vps(db, user_id, payload.provider.as_str(), &server).await;
 
-    // If database insertion fails, rollback by deleting the Hetzner server
+    // If database insertion fails, rollback by deleting the cloud server
     match vps_result {
-        Ok(vps) => Ok(vps),
+        Ok(vps) => {
+            let notification = Notification {
+                event: NotificationEvent::VpsCreated,
+                severity: AlertSeverity::Info,
+                title: "VPS created".to_string(),
+                message: format!("VPS {} ({} in {}) was created", vps.name, vps.server_type, vps.location),
+                data: serde_json::json!({ "vps_id": vps.id, "provider": vps.provider, "external_id": vps.external_id }),
+                occurred_at: vps.created_at,
+            };
+            if let Err(e) = notification_service::notify(db, vps.user_id, notification).await {
+                tracing::error!("Failed to queue VPS notification: {}", e);
+            }
+            publish_status(events, &vps, &vps.status, None).await;
+            Ok(vps)
+        }
         Err(e) => {
-            // Attempt to delete the orphaned Hetzner server
-            if let Err(delete_err) = hetzner_client.delete_server(hetzner_server.id).await {
+            // Attempt to delete the orphaned cloud server
+            if let Err(delete_err) = provider.delete_server(&server.external_id).await {
                 // Log the deletion error but return the original database error
                 tracing::error!(
-                    "Failed to rollback Hetzner server {} after database error: {:?}. Original DB error: {:?}",
-                    hetzner_server.id,
+                    "Failed to rollback {} server {} after database error: {:?}. Original DB error: {:?}",
+                    payload.provider.as_str(),
+                    server.external_id,
                     delete_err,
                     e
                 );
             }
-            Err(AppError::from(e))
+            Err(e)
         }
     }
 }
 
+/// Store a provider server as a VPS of `user_id`, within their quota
+async fn insert_vps(db: &DbPool, user_id: Uuid, provider: &str, server: &CloudServer) -> Result<Vps, AppError> {
+    let mut tx = db.begin().await?;
+    quota_service::ensure_capacity(&mut tx, user_id, QuotaResource::Vps).await?;
+
+    let now = Utc::now();
+    let vps = sqlx::query_as::<_, Vps>(
+        "INSERT INTO vps (
+            id, user_id, name, provider, external_id, status, server_type, location, image,
+            ipv4, ipv6, cpu_cores, ram_gb, disk_gb, monthly_cost, created_at, updated_at
+         )
+         VALUES ($1, $2, $3, $4, $5, $6, $7, $8, $9, $10, $11, $12, $13, $14, $15, $16, $16)
+         RETURNING *"
+    )
+    .bind(Uuid::new_v4())
+    .bind(user_id)
+    .bind(&server.name)
+    .bind(provider)
+    .bind(&server.external_id)
+    .bind(server.status.as_str())
+    .bind(&server.server_type)
+    .bind(&server.location)
+    .bind(&server.image)
+    .bind(&server.ipv4)
+    .bind(&server.ipv6)
+    .bind(server.cpu_cores)
+    .bind(server.ram_gb)
+    .bind(server.disk_gb)
+    .bind(server.monthly_cost)
+    .bind(now)
+    .fetch_one(&mut *tx)
+    .await?;
+
+    tx.commit().await?;
+
+    Ok(vps)
+}
+
 pub async fn update_vps(
     db: &DbPool,
+    events: &EventBus,
+    scope: &Scope,
     id: Uuid,
     payload: UpdateVps,
 ) -> Result<Vps, AppError> {
-    let mut vps = get_vps(db, id).await?;
+    let mut vps = get_vps(db, scope, id).await?;
+    let previous_status = vps.status.clone();
 
     if let Some(name) = payload.name {
         vps.name = name;
@@ -259,19 +229,25 @@ pub async fn update_vps(
     .fetch_one(db)
     .await?;
 
+    if vps.status != previous_status {
+        publish_status(events, &vps, &vps.status, Some(&previous_status)).await;
+    }
+
     Ok(vps)
 }
 
 pub async fn delete_vps(
     db: &DbPool,
-    hetzner_client: &HetznerClient,
+    providers: &CloudProviders,
+    events: &EventBus,
+    scope: &Scope,
     id: Uuid,
 ) -> Result<(), AppError> {
-    let vps = get_vps(db, id).await?;
+    let vps = get_vps(db, scope, id).await?;
 
-    // Delete from Hetzner if hetzner_id exists
-    if let Some(hetzner_id) = vps.hetzner_id {
-        hetzner_client.delete_server(hetzner_id).await?;
+    // Delete from the provider if the row is linked to a cloud server
+    if let Some(external_id) = &vps.external_id {
+        providers.for_vps(db, &vps).await?.delete_server(external_id).await?;
     }
 
     // Delete from database
@@ -284,35 +260,1008 @@ pub async fn delete_vps(
         return Err(AppError::NotFound("VPS not found".to_string()));
     }
 
+    publish_status(events, &vps, "deleted", Some(&vps.status)).await;
+
     Ok(())
 }
 
 pub async fn sync_vps_status(
     db: &DbPool,
-    hetzner_client: &HetznerClient,
+    providers: &CloudProviders,
+    events: &EventBus,
+    scope: &Scope,
+    id: Uuid,
+) -> Result<Vps, AppError> {
+    let vps = get_vps(db, scope, id).await?;
+
+    if vps.external_id.is_none() {
+        return Ok(vps);
+    }
+
+    let provider = providers.for_vps(db, &vps).await?;
+    refresh_vps(db, provider.as_ref(), events, vps).await
+}
+
+/// Write the provider's view of status and addresses onto the row, which it evidently still lists
+async fn store_server_state(db: &DbPool, events: &EventBus, vps: Vps, server: &CloudServer) -> Result<Vps, AppError> {
+    let previous_status = vps.status;
+
+    let vps = sqlx::query_as::<_, Vps>(
+        "UPDATE vps
+         SET status = $1, ipv4 = $2, ipv6 = $3, missing_since = NULL, updated_at = $4
+         WHERE id = $5
+         RETURNING *"
+    )
+    .bind(server.status.as_str())
+    .bind(&server.ipv4)
+    .bind(&server.ipv6)
+    .bind(Utc::now())
+    .bind(vps.id)
+    .fetch_one(db)
+    .await?;
+
+    if vps.status != previous_status {
+        publish_status(events, &vps, &vps.status, Some(&previous_status)).await;
+    }
+
+    Ok(vps)
+}
+
+/// Start a power action; the VPS shows its pending status until `poll_actions` sees the action finish
+pub async fn power_action(
+    db: &DbPool,
+    providers: &CloudProviders,
+    events: &EventBus,
+    scope: &Scope,
+    id: Uuid,
+    action: PowerAction,
+) -> Result<VpsAction, AppError> {
+    let vps = get_vps(db, scope, id).await?;
+    let external_id = vps.external_id.as_deref()
+        .ok_or(AppError::BadRequest("VPS not linked to a cloud server".to_string()))?;
+    let provider = providers.for_vps(db, &vps).await?;
+
+    // Recorded first so a request that dies mid-call still leaves a trace for the poller to time out
+    let vps_action = sqlx::query_as::<_, VpsAction>(
+        "INSERT INTO vps_actions (id, vps_id, user_id, command, status, created_at, updated_at)
+         VALUES ($1, $2, $3, $4, $5, $6, $6)
+         RETURNING *"
+    )
+    .bind(Uuid::new_v4())
+    .bind(vps.id)
+    .bind(vps.user_id)
+    .bind(action.as_str())
+    .bind(ActionStatus::Running.as_str())
+    .bind(Utc::now())
+    .fetch_one(db)
+    .await?;
+
+    let result = match action {
+        PowerAction::PowerOn => provider.power_on(external_id).await,
+        PowerAction::PowerOff => provider.power_off(external_id).await,
+        PowerAction::Reboot => provider.reboot(external_id).await,
+    };
+    let cloud_action = match result {
+        Ok(cloud_action) => cloud_action,
+        Err(e) => {
+            record_action(db, vps_action.id, None, &ActionStatus::Error, vps_action.progress, Some(e.to_string())).await?;
+            return Err(e);
+        }
+    };
+
+    let vps_action = record_action(
+        db,
+        vps_action.id,
+        Some(&cloud_action.external_id),
+        &cloud_action.status,
+        cloud_action.progress,
+        cloud_action.error,
+    )
+    .await?;
+
+    let now = Utc::now();
+
+    let previous_status = vps.status;
+    let vps = sqlx::query_as::<_, Vps>(
+        "UPDATE vps SET status = $1, updated_at = $2 WHERE id = $3 RETURNING *"
+    )
+    .bind(action.pending_status().as_str())
+    .bind(now)
+    .bind(id)
+    .fetch_one(db)
+    .await?;
+
+    if vps.status != previous_status {
+        publish_status(events, &vps, &vps.status, Some(&previous_status)).await;
+    }
+
+    Ok(vps_action)
+}
+
+pub async fn list_actions(db: &DbPool, scope: &Scope, vps_id: Uuid) -> Result<Vec<VpsAction>, AppError> {
+    let vps = get_vps(db, scope, vps_id).await?;
+
+    let actions = sqlx::query_as::<_, VpsAction>(
+        "SELECT * FROM vps_actions WHERE vps_id = $1 ORDER BY created_at DESC LIMIT 50"
+    )
+    .bind(vps.id)
+    .fetch_all(db)
+    .await?;
+
+    Ok(actions)
+}
+
+pub async fn get_action(db: &DbPool, scope: &Scope, vps_id: Uuid, action_id: Uuid) -> Result<VpsAction, AppError> {
+    let vps = get_vps(db, scope, vps_id).await?;
+
+    let action = sqlx::query_as::<_, VpsAction>(
+        "SELECT * FROM vps_actions WHERE id = $1 AND vps_id = $2"
+    )
+    .bind(action_id)
+    .bind(vps.id)
+    .fetch_optional(db)
+    .await?
+    .ok_or(AppError::NotFound("VPS action not found".to_string()))?;
+
+    Ok(action)
+}
+
+/// Follow running actions; finished ones are recorded, announced and their VPS refreshed
+pub async fn poll_actions(db: &DbPool, providers: &CloudProviders, events: &EventBus) -> Result<(), AppError> {
+    let actions = sqlx::query_as::<_, VpsAction>(
+        "SELECT * FROM vps_actions WHERE status = 'running' ORDER BY created_at LIMIT $1"
+    )
+    .bind(ACTION_POLL_BATCH)
+    .fetch_all(db)
+    .await?;
+
+    for action in actions {
+        if let Err(e) = poll_action(db, providers, events, &action).await {
+            tracing::warn!("Failed to poll VPS action {}: {}", action.id, e);
+        }
+    }
+
+    Ok(())
+}
+
+/// Store an action's latest state; `external_id` is kept when `None`
+async fn record_action(
+    db: &DbPool,
+    id: Uuid,
+    external_id: Option<&str>,
+    status: &ActionStatus,
+    progress: i32,
+    error: Option<String>,
+) -> Result<VpsAction, AppError> {
+    let now = Utc::now();
+    let action = sqlx::query_as::<_, VpsAction>(
+        "UPDATE vps_actions
+         SET external_id = COALESCE($1, external_id), status = $2, progress = $3, error = $4,
+             finished_at = $5, updated_at = $6
+         WHERE id = $7
+         RETURNING *"
+    )
+    .bind(external_id)
+    .bind(status.as_str())
+    .bind(progress)
+    .bind(error)
+    .bind((*status != ActionStatus::Running).then_some(now))
+    .bind(now)
+    .bind(id)
+    .fetch_one(db)
+    .await?;
+
+    Ok(action)
+}
+
+async fn poll_action(db: &DbPool, providers: &CloudProviders, events: &EventBus, action: &VpsAction) -> Result<(), AppError> {
+    let vps = get_vps(db, &Scope::All, action.vps_id).await?;
+    let failed = |error: String| (ActionStatus::Error, action.progress, Some(error));
+
+    // Checked before asking the provider so an action it keeps failing on still ends
+    let (status, progress, error) = if Utc::now() - action.created_at > Duration::minutes(ACTION_TIMEOUT_MINUTES) {
+        failed("Timed out waiting for the provider to finish the action".to_string())
+    } else if let Some(external_id) = &action.external_id {
+        match providers.for_vps(db, &vps).await?.get_action(external_id).await {
+            Ok(cloud_action) => (cloud_action.status, cloud_action.progress, cloud_action.error),
+            Err(AppError::NotFound(message)) => failed(message),
+            Err(e) => return Err(e),
+        }
+    } else {
+        // The provider call that starts it has not returned yet
+        return Ok(());
+    };
+
+    let action = record_action(db, action.id, None, &status, progress, error).await?;
+
+    if status != ActionStatus::Running {
+        events.publish(Event::new(EventKind::VpsAction, action.user_id, serde_json::json!(action))).await;
+        let provider = providers.for_vps(db, &vps).await?;
+        refresh_vps(db, provider.as_ref(), events, vps).await?;
+    }
+
+    Ok(())
+}
+
+async fn refresh_vps(db: &DbPool, provider: &dyn CloudProvider, events: &EventBus, vps: Vps) -> Result<Vps, AppError> {
+    let Some(external_id) = vps.external_id.clone() else {
+        return Ok(vps);
+    };
+
+    let server = provider.get_server(&external_id).await?;
+    store_server_state(db, events, vps, &server).await
+}
+
+pub async fn create_snapshot(
+    db: &DbPool,
+    providers: &CloudProviders,
+    scope: &Scope,
+    id: Uuid,
+    description: Option<String>,
+) -> Result<CloudSnapshot, AppError> {
+    let vps = get_vps(db, scope, id).await?;
+    let external_id = vps.external_id.as_deref()
+        .ok_or(AppError::BadRequest("VPS not linked to a cloud server".to_string()))?;
+
+    let description = description.unwrap_or_else(|| format!("{} {}", vps.name, Utc::now().format("%Y-%m-%d %H:%M")));
+    providers.for_vps(db, &vps).await?.create_snapshot(external_id, &description).await
+}
+
+/// A linked VPS and the owner of the credentials its provider calls use (`None` for the platform token)
+#[derive(sqlx::FromRow)]
+struct LinkedVps {
+    #[sqlx(flatten)]
+    vps: Vps,
+    credential_user_id: Option<Uuid>,
+}
+
+/// Compare every provider account's server list with the `vps` table: refresh known VPS,
+/// flag those whose server is gone and record servers no VPS tracks
+pub async fn reconcile(db: &DbPool, providers: &CloudProviders, events: &EventBus) -> Result<(), AppError> {
+    for kind in ProviderKind::ALL {
+        if let Err(e) = reconcile_provider(db, providers, events, kind).await {
+            tracing::error!("{} reconciliation failed: {}", kind.label(), e);
+        }
+    }
+
+    Ok(())
+}
+
+async fn reconcile_provider(
+    db: &DbPool,
+    providers: &CloudProviders,
+    events: &EventBus,
+    kind: ProviderKind,
+) -> Result<(), AppError> {
+    let accounts = providers.accounts(db, kind).await?;
+    if accounts.is_empty() {
+        return Ok(());
+    }
+
+    // A failed listing must not make its servers look deleted, so rows on that account are left alone
+    let mut listings: HashMap<Option<Uuid>, Vec<CloudServer>> = HashMap::new();
+    let mut complete = true;
+    for (credential_user_id, provider) in accounts {
+        match provider.list_servers().await {
+            Ok(servers) => {
+                listings.insert(credential_user_id, servers);
+            }
+            Err(e) => {
+                complete = false;
+                tracing::warn!("Failed to list {} servers for account {:?}: {}", kind.label(), credential_user_id, e);
+            }
+        }
+    }
+
+    // Same credential resolution as `CloudProviders::for_user`
+    let linked = sqlx::query_as::<_, LinkedVps>(
+        "SELECT v.*, (
+             SELECT c.user_id
+             FROM provider_credentials c
+             JOIN users u ON u.id = v.user_id
+             WHERE c.provider = v.provider AND (c.user_id = u.id OR c.user_id = u.parent_id)
+             ORDER BY (c.user_id = u.id) DESC
+             LIMIT 1
+         ) AS credential_user_id
+         FROM vps v
+         WHERE v.provider = $1 AND v.external_id IS NOT NULL"
+    )
+    .bind(kind.as_str())
+    .fetch_all(db)
+    .await?;
+
+    let managed: HashSet<String> = linked.iter().filter_map(|row| row.vps.external_id.clone()).collect();
+
+    for LinkedVps { vps, credential_user_id } in linked {
+        let Some(servers) = listings.get(&credential_user_id) else {
+            continue;
+        };
+
+        match servers.iter().find(|server| vps.external_id.as_deref() == Some(server.external_id.as_str())) {
+            Some(server) => {
+                let changed = vps.status != server.status.as_str()
+                    || vps.ipv4 != server.ipv4
+                    || vps.ipv6 != server.ipv6
+                    || vps.missing_since.is_some();
+                if changed {
+                    store_server_state(db, events, vps, server).await?;
+                }
+            }
+            None if vps.missing_since.is_none() => {
+                tracing::warn!("{} no longer lists the server of VPS {} ({})", kind.label(), vps.id, vps.name);
+                sqlx::query("UPDATE vps SET missing_since = $1 WHERE id = $2")
+                    .bind(Utc::now())
+                    .bind(vps.id)
+                    .execute(db)
+                    .await?;
+            }
+            None => {}
+        }
+    }
+
+    let now = Utc::now();
+    let mut seen = Vec::new();
+    for (credential_user_id, servers) in &listings {
+        for server in servers.iter().filter(|server| !managed.contains(&server.external_id)) {
+            record_unmanaged(db, kind, *credential_user_id, server, now).await?;
+            seen.push(server.external_id.clone());
+        }
+    }
+
+    // Forget unmanaged servers that were deleted or adopted elsewhere, once every account was listed
+    if complete {
+        sqlx::query("DELETE FROM unmanaged_servers WHERE provider = $1 AND NOT (external_id = ANY($2))")
+            .bind(kind.as_str())
+            .bind(&seen)
+            .execute(db)
+            .await?;
+    }
+
+    Ok(())
+}
+
+async fn record_unmanaged(
+    db: &DbPool,
+    kind: ProviderKind,
+    credential_user_id: Option<Uuid>,
+    server: &CloudServer,
+    now: DateTime<Utc>,
+) -> Result<(), AppError> {
+    sqlx::query(
+        "INSERT INTO unmanaged_servers (
+            id, provider, external_id, credential_user_id, name, status, server_type, location, image,
+            ipv4, ipv6, cpu_cores, ram_gb, disk_gb, monthly_cost, first_seen_at, last_seen_at
+         )
+         VALUES ($1, $2, $3, $4, $5, $6, $7, $8, $9, $10, $11, $12, $13, $14, $15, $16, $16)
+         ON CONFLICT (provider, external_id) DO UPDATE SET
+            credential_user_id = EXCLUDED.credential_user_id, name = EXCLUDED.name, status = EXCLUDED.status,
+            server_type = EXCLUDED.server_type, location = EXCLUDED.location, image = EXCLUDED.image,
+            ipv4 = EXCLUDED.ipv4, ipv6 = EXCLUDED.ipv6, cpu_cores = EXCLUDED.cpu_cores, ram_gb = EXCLUDED.ram_gb,
+            disk_gb = EXCLUDED.disk_gb, monthly_cost = EXCLUDED.monthly_cost, last_seen_at = EXCLUDED.last_seen_at"
+    )
+    .bind(Uuid::new_v4())
+    .bind(kind.as_str())
+    .bind(&server.external_id)
+    .bind(credential_user_id)
+    .bind(&server.name)
+    .bind(server.status.as_str())
+    .bind(&server.server_type)
+    .bind(&server.location)
+    .bind(&server.image)
+    .bind(&server.ipv4)
+    .bind(&server.ipv6)
+    .bind(server.cpu_cores)
+    .bind(server.ram_gb)
+    .bind(server.disk_gb)
+    .bind(server.monthly_cost)
+    .bind(now)
+    .execute(db)
+    .await?;
+
+    Ok(())
+}
+
+/// Unmanaged servers listed with the scope's credentials; those on the platform token are admin-only
+pub async fn list_unmanaged(db: &DbPool, scope: &Scope) -> Result<Vec<UnmanagedServer>, AppError> {
+    let servers = sqlx::query_as::<_, UnmanagedServer>(
+        "SELECT * FROM unmanaged_servers
+         WHERE ($1::uuid IS NULL OR credential_user_id = $1
+                OR ($2 AND credential_user_id IN (SELECT id FROM users WHERE parent_id = $1)))
+         ORDER BY provider, name"
+    )
+    .bind(scope.owner_id())
+    .bind(scope.includes_customers())
+    .fetch_all(db)
+    .await?;
+
+    Ok(servers)
+}
+
+pub async fn get_unmanaged(db: &DbPool, scope: &Scope, id: Uuid) -> Result<UnmanagedServer, AppError> {
+    let server = sqlx::query_as::<_, UnmanagedServer>(
+        "SELECT * FROM unmanaged_servers
+         WHERE id = $1
+           AND ($2::uuid IS NULL OR credential_user_id = $2
+                OR ($3 AND credential_user_id IN (SELECT id FROM users WHERE parent_id = $2)))"
+    )
+    .bind(id)
+    .bind(scope.owner_id())
+    .bind(scope.includes_customers())
+    .fetch_optional(db)
+    .await?
+    .ok_or(AppError::NotFound("Unmanaged server not found".to_string()))?;
+
+    Ok(server)
+}
+
+/// Turn an unmanaged server into a VPS of `user_id`, whose credentials must be able to see it
+pub async fn adopt_server(
+    db: &DbPool,
+    providers: &CloudProviders,
+    events: &EventBus,
+    scope: &Scope,
     id: Uuid,
+    user_id: Uuid,
 ) -> Result<Vps, AppError> {
-    let vps = get_vps(db, id).await?;
+    let unmanaged = get_unmanaged(db, scope, id).await?;
+    user_service::get_user(db, scope, user_id).await?;
+
+    let kind: ProviderKind = unmanaged.provider.parse().map_err(AppError::InternalError)?;
+    let server = providers.for_user(db, user_id, kind).await?.get_server(&unmanaged.external_id).await?;
+
+    let vps = insert_vps(db, user_id, kind.as_str(), &server).await?;
+
+    sqlx::query("DELETE FROM unmanaged_servers WHERE id = $1")
+        .bind(unmanaged.id)
+        .execute(db)
+        .await?;
+
+    publish_status(events, &vps, &vps.status, None).await;
+
+    Ok(vps)
+}
+
+/// Push a VPS status transition to `/api/events` subscribers
+async fn publish_status(events: &EventBus, vps: &Vps, status: &str, previous_status: Option<&str>) {
+    let data = serde_json::json!({
+        "vps_id": vps.id,
+        "name": vps.name,
+        "status": status,
+        "previous_status": previous_status,
+        "ipv4": vps.ipv4,
+        "ipv6": vps.ipv6,
+    });
+    events.publish(Event::new(EventKind::VpsStatus, vps.user_id, data)).await;
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        models::provider::SetProviderCredential,
+        services::cloud_service,
+        test_support::{self, vps, Tenants},
+    };
+    use axum::{
+        extract::{Path, State},
+        http::{HeaderMap, StatusCode},
+        response::{IntoResponse, Response},
+        routing::{get, post},
+        Json, Router,
+    };
+    use std::sync::{Arc, Mutex};
+
+    /// Hetzner account whose servers and action outcomes each test sets up
+    #[derive(Default)]
+    struct MockCloud {
+        /// Server id → Hetzner status
+        servers: Mutex<HashMap<i64, &'static str>>,
+        /// Action id → Hetzner status; a server's power action gets the server's id
+        actions: Mutex<HashMap<i64, &'static str>>,
+    }
+
+    impl MockCloud {
+        fn set_action(&self, id: i64, status: &'static str) {
+            self.actions.lock().unwrap().insert(id, status);
+        }
+    }
+
+    fn authorized(headers: &HeaderMap) -> bool {
+        headers.get("authorization").and_then(|v| v.to_str().ok()) == Some("Bearer test-token")
+    }
+
+    async fn list_servers(State(cloud): State<Arc<MockCloud>>, headers: HeaderMap) -> Response {
+        if !authorized(&headers) {
+            return StatusCode::UNAUTHORIZED.into_response();
+        }
+
+        let servers: Vec<_> = cloud.servers.lock().unwrap()
+            .iter()
+            .map(|(id, status)| test_support::hetzner_server(*id, &format!("server-{}", id), status))
+            .collect();
+        Json(serde_json::json!({ "servers": servers })).into_response()
+    }
+
+    async fn get_server(State(cloud): State<Arc<MockCloud>>, Path(id): Path<i64>, headers: HeaderMap) -> Response {
+        match cloud.servers.lock().unwrap().get(&id) {
+            Some(status) if authorized(&headers) => {
+                let server = test_support::hetzner_server(id, &format!("server-{}", id), status);
+                Json(serde_json::json!({ "server": server })).into_response()
+            }
+            _ => StatusCode::NOT_FOUND.into_response(),
+        }
+    }
+
+    /// Applies the action to the server at once; the action itself stays running until the test finishes it
+    async fn server_action(State(cloud): State<Arc<MockCloud>>, Path((id, action)): Path<(i64, String)>) -> Response {
+        let mut servers = cloud.servers.lock().unwrap();
+        let Some(status) = servers.get_mut(&id) else {
+            return StatusCode::NOT_FOUND.into_response();
+        };
+        *status = if action == "poweroff" { "off" } else { "running" };
+        cloud.set_action(id, "running");
+
+        Json(serde_json::json!({
+            "action": { "id": id, "status": "running", "progress": 0, "error": null }
+        }))
+        .into_response()
+    }
 
-    if let Some(hetzner_id) = vps.hetzner_id {
-        let hetzner_server = hetzner_client.get_server(hetzner_id).await?;
+    async fn get_action(State(cloud): State<Arc<MockCloud>>, Path(id): Path<i64>) -> Response {
+        let action = match cloud.actions.lock().unwrap().get(&id) {
+            Some(&"error") => serde_json::json!({
+                "id": id,
+                "status": "error",
+                "progress": 30,
+                "error": { "code": "action_failed", "message": "Action failed" }
+            }),
+            Some(&"success") => serde_json::json!({ "id": id, "status": "success", "progress": 100, "error": null }),
+            Some(_) => serde_json::json!({ "id": id, "status": "running", "progress": 50, "error": null }),
+            None => return StatusCode::NOT_FOUND.into_response(),
+        };
+        Json(serde_json::json!({ "action": action })).into_response()
+    }
+
+    /// Creates the server in the requested location, without the per-location prices Hetzner embeds
+    async fn create_server(State(cloud): State<Arc<MockCloud>>, Json(body): Json<serde_json::Value>) -> Json<serde_json::Value> {
+        let id = server_id();
+        cloud.servers.lock().unwrap().insert(id, "initializing");
+
+        let mut server = test_support::hetzner_server(id, body["name"].as_str().unwrap(), "initializing");
+        server["datacenter"]["location"]["name"] = body["location"].clone();
+        server["server_type"]["name"] = body["server_type"].clone();
+        server["server_type"]["prices"] = serde_json::json!([]);
+        Json(serde_json::json!({ "server": server }))
+    }
+
+    /// cx22 is offered in fsn1 and nbg1, ccx13 only in fsn1
+    async fn list_server_types() -> Json<serde_json::Value> {
+        let price = |location: &str, gross: &str| {
+            serde_json::json!({ "location": location, "price_monthly": { "gross": gross, "net": gross } })
+        };
+        Json(serde_json::json!({
+            "server_types": [
+                { "name": "cx22", "cores": 2, "memory": 4.0, "disk": 40, "prices": [price("fsn1", "4.5100"), price("nbg1", "5.0500")] },
+                { "name": "ccx13", "cores": 2, "memory": 8.0, "disk": 80, "prices": [price("fsn1", "14.8600")] }
+            ]
+        }))
+    }
+
+    async fn list_locations() -> Json<serde_json::Value> {
+        Json(serde_json::json!({
+            "locations": [
+                { "name": "fsn1", "city": "Falkenstein", "country": "DE" },
+                { "name": "nbg1", "city": "Nuremberg", "country": "DE" }
+            ]
+        }))
+    }
+
+    async fn list_images() -> Json<serde_json::Value> {
+        Json(serde_json::json!({
+            "images": [{ "id": 1, "name": "ubuntu-24.04", "description": "Ubuntu 24.04", "created": null }]
+        }))
+    }
+
+    /// Providers whose Hetzner API is `cloud`; `default_token` is the platform-wide token
+    async fn mock_providers(cloud: &Arc<MockCloud>, default_token: Option<&str>) -> CloudProviders {
+        let router = Router::new()
+            .route("/servers", get(list_servers).post(create_server))
+            .route("/servers/:id", get(get_server))
+            .route("/servers/:id/actions/:action", post(server_action))
+            .route("/actions/:id", get(get_action))
+            .route("/server_types", get(list_server_types))
+            .route("/locations", get(list_locations))
+            .route("/images", get(list_images))
+            .with_state(cloud.clone());
+
+        let mut config = test_support::config();
+        config.hetzner_api_url = test_support::serve(router).await;
+        config.hetzner_api_token = default_token.map(str::to_string);
+        CloudProviders::new(&config)
+    }
+
+    /// A provider id no other test uses, as VPS rows are unique per provider id
+    fn server_id() -> i64 {
+        rand::random::<u32>() as i64
+    }
+
+    /// Link `vps_id` to Hetzner server `server_id`
+    async fn link(db: &DbPool, vps_id: Uuid, server_id: i64, status: VpsStatus) {
+        sqlx::query("UPDATE vps SET provider = 'hetzner', external_id = $2, status = $3 WHERE id = $1")
+            .bind(vps_id)
+            .bind(server_id.to_string())
+            .bind(status.as_str())
+            .execute(db)
+            .await
+            .unwrap();
+    }
+
+    /// Reconciliation forgets every unmanaged server it did not just see, so runs must not interleave
+    static RECONCILIATION: tokio::sync::Mutex<()> = tokio::sync::Mutex::const_new(());
+
+    /// Tenants whose reseller's Hetzner token (the only one accepted by `cloud`) covers their customer
+    async fn reseller_account(db: &DbPool, cloud: &Arc<MockCloud>) -> (Tenants, CloudProviders) {
+        let tenants = Tenants::create(db).await;
+        let providers = mock_providers(cloud, None).await;
+        let credential = SetProviderCredential { api_token: "test-token".to_string() };
+        cloud_service::set_credential(db, &providers, tenants.reseller, ProviderKind::Hetzner, credential)
+            .await
+            .unwrap();
+        (tenants, providers)
+    }
+
+    async fn unmanaged(db: &DbPool, server_id: i64) -> Option<UnmanagedServer> {
+        sqlx::query_as("SELECT * FROM unmanaged_servers WHERE provider = 'hetzner' AND external_id = $1")
+            .bind(server_id.to_string())
+            .fetch_optional(db)
+            .await
+            .unwrap()
+    }
+
+    async fn action_row(db: &DbPool, id: Uuid) -> VpsAction {
+        sqlx::query_as("SELECT * FROM vps_actions WHERE id = $1").bind(id).fetch_one(db).await.unwrap()
+    }
+
+    fn rename() -> UpdateVps {
+        UpdateVps { name: Some("renamed".to_string()), status: None }
+    }
+
+    async fn listed(db: &DbPool, scope: &Scope, id: Uuid) -> bool {
+        let query: ListQuery = serde_json::from_value(serde_json::json!({ "limit": 500 })).unwrap();
+        list_vps(db, scope, &query).await.unwrap().items.iter().any(|vps| vps.id == id)
+    }
+
+    #[tokio::test]
+    async fn other_tenants_vps_are_not_found() {
+        let Some(db) = test_support::database().await else { return };
+        let providers = CloudProviders::new(&test_support::config());
+        let events = EventBus::new(None);
+        let tenants = Tenants::create(&db).await;
+        let id = vps(&db, tenants.customer).await;
+
+        let outsiders = [
+            Scope::Owner(tenants.user),
+            Scope::Owner(tenants.other_customer),
+            Scope::Reseller(tenants.other_reseller),
+            Scope::Owner(tenants.reseller),
+        ];
+        let mut leaks = Vec::new();
+        for scope in &outsiders {
+            if get_vps(&db, scope, id).await.is_ok()
+                || !matches!(update_vps(&db, &events, scope, id, rename()).await, Err(AppError::NotFound(_)))
+                || !matches!(delete_vps(&db, &providers, &events, scope, id).await, Err(AppError::NotFound(_)))
+                || listed(&db, scope, id).await
+            {
+                leaks.push(format!("{:?}", scope));
+            }
+        }
+        let untouched = get_vps(&db, &Scope::All, id).await.unwrap();
+
+        tenants.delete(&db).await;
+        assert!(leaks.is_empty(), "visible to {:?}", leaks);
+        assert_eq!(untouched.name, "test");
+    }
+
+    #[tokio::test]
+    async fn resellers_manage_their_customers_vps() {
+        let Some(db) = test_support::database().await else { return };
+        let providers = CloudProviders::new(&test_support::config());
+        let events = EventBus::new(None);
+        let tenants = Tenants::create(&db).await;
+        let id = vps(&db, tenants.customer).await;
+        let reseller = Scope::Reseller(tenants.reseller);
+
+        let seen = get_vps(&db, &reseller, id).await.is_ok() && listed(&db, &reseller, id).await;
+        let renamed = update_vps(&db, &events, &reseller, id, rename()).await.unwrap();
+        let deleted = delete_vps(&db, &providers, &events, &reseller, id).await;
+
+        tenants.delete(&db).await;
+        assert!(seen);
+        assert_eq!(renamed.name, "renamed");
+        assert!(deleted.is_ok());
+    }
+
+    #[tokio::test]
+    async fn power_actions_show_the_pending_status_until_they_finish() {
+        let Some(db) = test_support::database().await else { return };
+        let cloud = Arc::new(MockCloud::default());
+        let providers = mock_providers(&cloud, Some("test-token")).await;
+        let events = EventBus::new(None);
+        let user = test_support::user(&db, crate::models::user::UserRole::User, None).await;
+        let id = vps(&db, user).await;
+        let server = server_id();
+        cloud.servers.lock().unwrap().insert(server, "off");
+        link(&db, id, server, VpsStatus::Stopped).await;
+
+        let started = power_action(&db, &providers, &events, &Scope::Owner(user), id, PowerAction::PowerOn).await.unwrap();
+        let pending = get_vps(&db, &Scope::All, id).await.unwrap();
+
+        poll_action(&db, &providers, &events, &started).await.unwrap();
+        let still_running = action_row(&db, started.id).await;
+        let still_pending = get_vps(&db, &Scope::All, id).await.unwrap();
+
+        cloud.set_action(server, "success");
+        poll_action(&db, &providers, &events, &still_running).await.unwrap();
+        let finished = action_row(&db, started.id).await;
+        let running = get_vps(&db, &Scope::All, id).await.unwrap();
+
+        test_support::delete_users(&db, &[user]).await;
+
+        assert_eq!(started.external_id, Some(server.to_string()));
+        assert_eq!(started.command, "power_on");
+        assert_eq!(started.status, "running");
+        assert_eq!(pending.status, VpsStatus::Starting.as_str());
+
+        assert_eq!(still_running.status, "running");
+        assert_eq!(still_running.progress, 50);
+        assert_eq!(still_running.finished_at, None);
+        assert_eq!(still_pending.status, VpsStatus::Starting.as_str());
 
-        let vps = sqlx::query_as::<_, Vps>(
-            "UPDATE vps
-             SET status = $1, ipv4 = $2, ipv6 = $3, updated_at = $4
-             WHERE id = $5
+        assert_eq!(finished.status, "success");
+        assert_eq!(finished.progress, 100);
+        assert!(finished.finished_at.is_some());
+        assert_eq!(running.status, VpsStatus::Running.as_str());
+        assert_eq!(running.ipv4.as_deref(), Some("203.0.113.20"));
+    }
+
+    #[tokio::test]
+    async fn failed_actions_are_recorded_and_the_vps_refreshed() {
+        let Some(db) = test_support::database().await else { return };
+        let cloud = Arc::new(MockCloud::default());
+        let providers = mock_providers(&cloud, Some("test-token")).await;
+        let events = EventBus::new(None);
+        let user = test_support::user(&db, crate::models::user::UserRole::User, None).await;
+        let id = vps(&db, user).await;
+        let server = server_id();
+        cloud.servers.lock().unwrap().insert(server, "running");
+        link(&db, id, server, VpsStatus::Running).await;
+
+        let started = power_action(&db, &providers, &events, &Scope::All, id, PowerAction::PowerOff).await.unwrap();
+        let stopping = get_vps(&db, &Scope::All, id).await.unwrap();
+        // The provider failed the action and the server kept running
+        cloud.set_action(server, "error");
+        cloud.servers.lock().unwrap().insert(server, "running");
+        poll_action(&db, &providers, &events, &started).await.unwrap();
+        let failed = action_row(&db, started.id).await;
+        let refreshed = get_vps(&db, &Scope::All, id).await.unwrap();
+
+        test_support::delete_users(&db, &[user]).await;
+
+        assert_eq!(stopping.status, VpsStatus::Stopping.as_str());
+        assert_eq!(failed.status, "error");
+        assert_eq!(failed.error.as_deref(), Some("action_failed: Action failed"));
+        assert!(failed.finished_at.is_some());
+        assert_eq!(refreshed.status, VpsStatus::Running.as_str());
+    }
+
+    #[tokio::test]
+    async fn rejected_power_actions_leave_the_vps_alone() {
+        let Some(db) = test_support::database().await else { return };
+        let cloud = Arc::new(MockCloud::default());
+        let providers = mock_providers(&cloud, Some("test-token")).await;
+        let events = EventBus::new(None);
+        let user = test_support::user(&db, crate::models::user::UserRole::User, None).await;
+        let id = vps(&db, user).await;
+        // Linked to a server the provider does not know
+        link(&db, id, server_id(), VpsStatus::Stopped).await;
+
+        let result = power_action(&db, &providers, &events, &Scope::All, id, PowerAction::PowerOn).await;
+        let actions = list_actions(&db, &Scope::All, id).await.unwrap();
+        let unchanged = get_vps(&db, &Scope::All, id).await.unwrap();
+
+        test_support::delete_users(&db, &[user]).await;
+
+        assert!(matches!(result, Err(AppError::NotFound(_))));
+        assert_eq!(actions.len(), 1);
+        assert_eq!(actions[0].status, "error");
+        assert_eq!(actions[0].external_id, None);
+        assert!(actions[0].finished_at.is_some());
+        assert_eq!(unchanged.status, VpsStatus::Stopped.as_str());
+    }
+
+    #[tokio::test]
+    async fn actions_time_out_or_fail_once_the_provider_forgets_them() {
+        let Some(db) = test_support::database().await else { return };
+        let cloud = Arc::new(MockCloud::default());
+        let providers = mock_providers(&cloud, Some("test-token")).await;
+        let events = EventBus::new(None);
+        let user = test_support::user(&db, crate::models::user::UserRole::User, None).await;
+        let id = vps(&db, user).await;
+        let server = server_id();
+        cloud.servers.lock().unwrap().insert(server, "running");
+        link(&db, id, server, VpsStatus::Starting).await;
+
+        // Started long ago; the provider would report it finished, but the deadline comes first
+        let stale: VpsAction = sqlx::query_as(
+            "INSERT INTO vps_actions (id, vps_id, user_id, external_id, command, status, created_at, updated_at)
+             VALUES ($1, $2, $3, $4, 'power_on', 'running', $5, $5)
              RETURNING *"
         )
-        .bind(&hetzner_server.status)
-        .bind(hetzner_server.public_net.ipv4.as_ref().map(|ip| ip.ip.clone()))
-        .bind(hetzner_server.public_net.ipv6.as_ref().map(|ip| ip.ip.clone()))
-        .bind(Utc::now())
+        .bind(Uuid::new_v4())
         .bind(id)
-        .fetch_one(db)
-        .await?;
+        .bind(user)
+        .bind(server.to_string())
+        .bind(Utc::now() - Duration::minutes(ACTION_TIMEOUT_MINUTES + 1))
+        .fetch_one(&db)
+        .await
+        .unwrap();
+        cloud.set_action(server, "success");
+        poll_action(&db, &providers, &events, &stale).await.unwrap();
+        let timed_out = action_row(&db, stale.id).await;
+        let refreshed = get_vps(&db, &Scope::All, id).await.unwrap();
 
-        Ok(vps)
-    } else {
-        Ok(vps)
+        // Recent, but unknown to the provider
+        let forgotten: VpsAction = sqlx::query_as(
+            "INSERT INTO vps_actions (id, vps_id, user_id, external_id, command, status, created_at, updated_at)
+             VALUES ($1, $2, $3, '999999999999', 'reboot', 'running', NOW(), NOW())
+             RETURNING *"
+        )
+        .bind(Uuid::new_v4())
+        .bind(id)
+        .bind(user)
+        .fetch_one(&db)
+        .await
+        .unwrap();
+        poll_action(&db, &providers, &events, &forgotten).await.unwrap();
+        let not_found = action_row(&db, forgotten.id).await;
+
+        test_support::delete_users(&db, &[user]).await;
+
+        assert_eq!(timed_out.status, "error");
+        assert!(timed_out.error.unwrap().contains("Timed out"));
+        assert!(timed_out.finished_at.is_some());
+        assert_eq!(refreshed.status, VpsStatus::Running.as_str());
+        assert_eq!(not_found.status, "error");
+        assert!(not_found.finished_at.is_some());
+    }
+
+    #[tokio::test]
+    async fn reconciliation_tracks_vanished_and_unknown_servers() {
+        let Some(db) = test_support::database().await else { return };
+        let _serial = RECONCILIATION.lock().await;
+        let cloud = Arc::new(MockCloud::default());
+        let (tenants, providers) = reseller_account(&db, &cloud).await;
+        let events = EventBus::new(None);
+        let (listed, vanished, unknown) = (server_id(), server_id(), server_id());
+        cloud.servers.lock().unwrap().extend([(listed, "running"), (unknown, "off")]);
+        let listed_vps = vps(&db, tenants.customer).await;
+        let vanished_vps = vps(&db, tenants.customer).await;
+        link(&db, listed_vps, listed, VpsStatus::Stopped).await;
+        link(&db, vanished_vps, vanished, VpsStatus::Running).await;
+
+        reconcile_provider(&db, &providers, &events, ProviderKind::Hetzner).await.unwrap();
+        let updated = get_vps(&db, &Scope::All, listed_vps).await.unwrap();
+        let missing = get_vps(&db, &Scope::All, vanished_vps).await.unwrap();
+        let recorded = unmanaged(&db, unknown).await;
+        let reseller_sees = list_unmanaged(&db, &Scope::Reseller(tenants.reseller)).await.unwrap();
+        let customer_sees = list_unmanaged(&db, &Scope::Owner(tenants.customer)).await.unwrap();
+        let other_reseller_sees = list_unmanaged(&db, &Scope::Reseller(tenants.other_reseller)).await.unwrap();
+
+        cloud.servers.lock().unwrap().insert(vanished, "running");
+        reconcile_provider(&db, &providers, &events, ProviderKind::Hetzner).await.unwrap();
+        let returned = get_vps(&db, &Scope::All, vanished_vps).await.unwrap();
+
+        tenants.delete(&db).await;
+
+        assert_eq!(updated.status, VpsStatus::Running.as_str());
+        assert_eq!(updated.ipv4.as_deref(), Some("203.0.113.20"));
+        assert_eq!(updated.missing_since, None);
+        assert!(missing.missing_since.is_some());
+        assert_eq!(missing.status, VpsStatus::Running.as_str());
+
+        let recorded = recorded.expect("unknown server was not recorded");
+        assert_eq!(recorded.credential_user_id, Some(tenants.reseller));
+        assert_eq!(recorded.status, VpsStatus::Stopped.as_str());
+        assert_eq!(recorded.location, "nbg1");
+        assert_eq!(recorded.monthly_cost, Some(5.05));
+        assert!(reseller_sees.iter().any(|server| server.id == recorded.id));
+        assert!(customer_sees.is_empty());
+        assert!(other_reseller_sees.iter().all(|server| server.id != recorded.id));
+        // Linked servers are never offered for adoption
+        assert!(reseller_sees.iter().all(|server| server.external_id != listed.to_string()));
+
+        assert_eq!(returned.missing_since, None);
+    }
+
+    #[tokio::test]
+    async fn servers_are_only_adopted_into_the_callers_tenants() {
+        let Some(db) = test_support::database().await else { return };
+        let _serial = RECONCILIATION.lock().await;
+        let cloud = Arc::new(MockCloud::default());
+        let (tenants, providers) = reseller_account(&db, &cloud).await;
+        let events = EventBus::new(None);
+        let server = server_id();
+        cloud.servers.lock().unwrap().insert(server, "running");
+
+        reconcile_provider(&db, &providers, &events, ProviderKind::Hetzner).await.unwrap();
+        let id = unmanaged(&db, server).await.expect("server was not recorded").id;
+        let reseller = Scope::Reseller(tenants.reseller);
+
+        let refused = [
+            adopt_server(&db, &providers, &events, &reseller, id, tenants.other_customer).await,
+            adopt_server(&db, &providers, &events, &reseller, id, tenants.user).await,
+            adopt_server(&db, &providers, &events, &Scope::Reseller(tenants.other_reseller), id, tenants.other_customer).await,
+            adopt_server(&db, &providers, &events, &Scope::Owner(tenants.customer), id, tenants.customer).await,
+        ];
+        let still_unmanaged = unmanaged(&db, server).await.is_some();
+        let adopted = adopt_server(&db, &providers, &events, &reseller, id, tenants.customer).await;
+        let remaining = unmanaged(&db, server).await;
+
+        tenants.delete(&db).await;
+
+        for result in refused {
+            assert!(matches!(result, Err(AppError::NotFound(_))), "{:?}", result.map(|vps| vps.id));
+        }
+        assert!(still_unmanaged);
+        let adopted = adopted.unwrap();
+        assert_eq!(adopted.user_id, tenants.customer);
+        assert_eq!(adopted.external_id, Some(server.to_string()));
+        assert_eq!(adopted.status, VpsStatus::Running.as_str());
+        assert!(remaining.is_none());
+    }
+
+    #[tokio::test]
+    async fn new_vps_are_checked_against_the_catalog_and_priced_by_location() {
+        let Some(db) = test_support::database().await else { return };
+        let cloud = Arc::new(MockCloud::default());
+        let providers = mock_providers(&cloud, Some("test-token")).await;
+        let events = EventBus::new(None);
+        let user = test_support::user(&db, crate::models::user::UserRole::User, None).await;
+        let request = |server_type: &str, location: &str, image: &str| CreateVps {
+            provider: ProviderKind::Hetzner,
+            name: "web-1".to_string(),
+            server_type: server_type.to_string(),
+            location: location.to_string(),
+            image: image.to_string(),
+            ssh_keys: None,
+            user_data: None,
+        };
+
+        let mut rejected = Vec::new();
+        for (server_type, location, image) in [
+            ("cx99", "fsn1", "ubuntu-24.04"),
+            ("cx22", "mars1", "ubuntu-24.04"),
+            ("cx22", "fsn1", "windows-95"),
+            ("ccx13", "nbg1", "ubuntu-24.04"),
+        ] {
+            match create_vps(&db, &providers, &events, user, request(server_type, location, image)).await {
+                Err(AppError::ValidationFailed(errors)) => {
+                    rejected.push(errors.field_errors().into_keys().collect::<Vec<_>>());
+                }
+                other => panic!("{} in {} with {} was not rejected: {:?}", server_type, location, image, other.map(|vps| vps.id)),
+            }
+        }
+        let created_at_provider = cloud.servers.lock().unwrap().len();
+
+        let fsn1 = create_vps(&db, &providers, &events, user, request("cx22", "fsn1", "ubuntu-24.04")).await;
+        let nbg1 = create_vps(&db, &providers, &events, user, request("cx22", "nbg1", "ubuntu-24.04")).await;
+
+        test_support::delete_users(&db, &[user]).await;
+
+        assert_eq!(rejected, [["server_type"], ["location"], ["image"], ["server_type"]]);
+        assert_eq!(created_at_provider, 0);
+        let (fsn1, nbg1) = (fsn1.unwrap(), nbg1.unwrap());
+        assert_eq!((fsn1.location.as_str(), fsn1.monthly_cost), ("fsn1", Some(4.51)));
+        assert_eq!((nbg1.location.as_str(), nbg1.monthly_cost), ("nbg1", Some(5.05)));
     }
 }
@@ -0,0 +1,84 @@
+use crate::{
+    database::DbPool,
+    models::{auth::Scope, list::{ListQuery, Page}, website::*},
+    services::{quota_service::{self, QuotaResource}, server_service},
+    utils::errors::AppError,
+};
+use chrono::Utc;
+use uuid::Uuid;
+
+const WEBSITE_SORT_FIELDS: [&str; 4] = ["domain", "status", "application_type", "created_at"];
+
+/// $1/$2 tenant scope, then status and search pattern
+const WEBSITE_FILTERS: &str = "
+    WHERE ($1::uuid IS NULL OR user_id = $1
+           OR ($2 AND user_id IN (SELECT id FROM users WHERE parent_id = $1)))
+      AND ($3::text IS NULL OR status = $3)
+      AND ($4::text IS NULL OR domain ILIKE $4)";
+
+pub async fn list_websites(db: &DbPool, scope: &Scope, query: &ListQuery) -> Result<Page<Website>, AppError> {
+    let order_by = query.order_by(&WEBSITE_SORT_FIELDS, "created_at")?;
+    let search = query.search_pattern();
+
+    let items = sqlx::query_as::<_, Website>(&format!(
+        "SELECT * FROM websites {} ORDER BY {} LIMIT $5 OFFSET $6",
+        WEBSITE_FILTERS, order_by
+    ))
+    .bind(scope.owner_id())
+    .bind(scope.includes_customers())
+    .bind(query.status())
+    .bind(&search)
+    .bind(query.limit())
+    .bind(query.offset())
+    .fetch_all(db)
+    .await?;
+
+    let total = sqlx::query_scalar::<_, i64>(&format!("SELECT COUNT(*) FROM websites {}", WEBSITE_FILTERS))
+        .bind(scope.owner_id())
+        .bind(scope.includes_customers())
+        .bind(query.status())
+        .bind(&search)
+        .fetch_one(db)
+        .await?;
+
+    Ok(Page { items, total })
+}
+
+/// Record a website for deployment, within the owner's website quota
+pub async fn create_website(
+    db: &DbPool,
+    scope: &Scope,
+    user_id: Uuid,
+    payload: CreateWebsite,
+) -> Result<Website, AppError> {
+    if let Some(server_id) = payload.server_id {
+        server_service::get_server(db, scope, server_id).await?;
+    }
+
+    let mut tx = db.begin().await?;
+    quota_service::ensure_capacity(&mut tx, user_id, QuotaResource::Websites).await?;
+
+    let website = sqlx::query_as::<_, Website>(
+        "INSERT INTO websites (
+            id, user_id, server_id, domain, status, application_type,
+            php_version, document_root, created_at, updated_at
+         )
+         VALUES ($1, $2, $3, $4, 'pending', $5, $6, $7, $8, $9)
+         RETURNING *"
+    )
+    .bind(Uuid::new_v4())
+    .bind(user_id)
+    .bind(payload.server_id)
+    .bind(payload.domain.trim_end_matches('.').to_lowercase())
+    .bind(&payload.application_type)
+    .bind(&payload.php_version)
+    .bind(&payload.document_root)
+    .bind(Utc::now())
+    .bind(Utc::now())
+    .fetch_one(&mut *tx)
+    .await?;
+
+    tx.commit().await?;
+
+    Ok(website)
+}
@@ -0,0 +1,200 @@
+//! Helpers shared by unit tests
+
+use crate::{
+    config::Config,
+    database::{self, DbPool},
+    models::user::UserRole,
+    services::mail_service::{MailMessage, MailSender},
+    utils::errors::AppError,
+};
+use async_trait::async_trait;
+use axum::Router;
+use std::sync::{Mutex, Once};
+use uuid::Uuid;
+
+/// Serve `router` on an ephemeral loopback port and return its base URL
+pub async fn serve(router: Router) -> String {
+    let listener = tokio::net::TcpListener::bind("127.0.0.1:0")
+        .await
+        .expect("Failed to bind test listener");
+    let addr = listener.local_addr().expect("Test listener has no address");
+
+    tokio::spawn(async move {
+        axum::serve(listener, router).await.ok();
+    });
+
+    format!("http://{}", addr)
+}
+
+/// Configuration from the environment, with the required variables filled in when unset
+pub fn config() -> Config {
+    static DEFAULTS: Once = Once::new();
+    DEFAULTS.call_once(|| {
+        for (name, value) in [
+            ("DATABASE_URL", "postgresql://localhost/unified_panel_test"),
+            ("JWT_SECRET", "test-jwt-secret"),
+            ("SESSION_SECRET", "test-session-secret"),
+            ("PROVIDER_TOKEN_KEY", "000102030405060708090a0b0c0d0e0f101112131415161718191a1b1c1d1e1f"),
+        ] {
+            if std::env::var(name).is_err() {
+                std::env::set_var(name, value);
+            }
+        }
+    });
+
+    Config::from_env().expect("Invalid test configuration")
+}
+
+/// Migrated pool on `TEST_DATABASE_URL`; tests needing Postgres skip themselves when it is unset
+pub async fn database() -> Option<DbPool> {
+    let Ok(url) = std::env::var("TEST_DATABASE_URL") else {
+        eprintln!("TEST_DATABASE_URL is not set, skipping");
+        return None;
+    };
+
+    let db = database::create_pool(&url).await.expect("Failed to connect to the test database");
+    sqlx::migrate!("./migrations").run(&db).await.expect("Failed to migrate the test database");
+    Some(db)
+}
+
+/// Insert a user with an unusable password, optionally managed by a reseller
+pub async fn user(db: &DbPool, role: UserRole, parent_id: Option<Uuid>) -> Uuid {
+    sqlx::query_scalar(
+        "INSERT INTO users (id, email, password_hash, role, parent_id, created_at, updated_at, mfa_enabled)
+         VALUES ($1, $2, 'unused', $3, $4, NOW(), NOW(), FALSE)
+         RETURNING id"
+    )
+    .bind(Uuid::new_v4())
+    .bind(format!("test-{}@example.com", Uuid::new_v4()))
+    .bind(role.as_str())
+    .bind(parent_id)
+    .fetch_one(db)
+    .await
+    .expect("Failed to insert test user")
+}
+
+/// Insert a server owned by `user_id`
+pub async fn server(db: &DbPool, user_id: Uuid) -> Uuid {
+    sqlx::query_scalar(
+        "INSERT INTO servers (id, user_id, name, hostname, ip_address, status, server_type, created_at, updated_at)
+         VALUES ($1, $2, 'test', 'test.example.com', '203.0.113.10', 'online', 'dedicated', NOW(), NOW())
+         RETURNING id"
+    )
+    .bind(Uuid::new_v4())
+    .bind(user_id)
+    .fetch_one(db)
+    .await
+    .expect("Failed to insert test server")
+}
+
+/// Insert a VPS owned by `user_id` that is not linked to a provider server
+pub async fn vps(db: &DbPool, user_id: Uuid) -> Uuid {
+    sqlx::query_scalar(
+        "INSERT INTO vps (id, user_id, name, status, server_type, location, image, cpu_cores, ram_gb, disk_gb)
+         VALUES ($1, $2, 'test', 'running', 'cx22', 'fsn1', 'ubuntu-24.04', 2, 4, 40)
+         RETURNING id"
+    )
+    .bind(Uuid::new_v4())
+    .bind(user_id)
+    .fetch_one(db)
+    .await
+    .expect("Failed to insert test VPS")
+}
+
+/// A server as the Hetzner API returns it, placed in nbg1
+pub fn hetzner_server(id: i64, name: &str, status: &str) -> serde_json::Value {
+    serde_json::json!({
+        "id": id,
+        "name": name,
+        "status": status,
+        "public_net": {
+            "ipv4": { "ip": "203.0.113.20" },
+            "ipv6": { "ip": "2001:db8::/64" }
+        },
+        "server_type": {
+            "name": "cx22",
+            "description": "CX22",
+            "cores": 2,
+            "memory": 4.0,
+            "disk": 40,
+            "prices": [
+                { "location": "fsn1", "price_monthly": { "gross": "4.5100", "net": "3.7900" } },
+                { "location": "nbg1", "price_monthly": { "gross": "5.0500", "net": "4.2400" } }
+            ]
+        },
+        "datacenter": {
+            "name": "nbg1-dc3",
+            "location": { "name": "nbg1", "city": "Nuremberg", "country": "DE" }
+        },
+        "image": { "id": 114690387, "name": "ubuntu-24.04", "description": "Ubuntu 24.04", "created": null },
+        "created": "2026-10-17T10:00:00+00:00"
+    })
+}
+
+/// Two resellers with one customer each, and a user outside both
+pub struct Tenants {
+    pub reseller: Uuid,
+    pub customer: Uuid,
+    pub other_reseller: Uuid,
+    pub other_customer: Uuid,
+    pub user: Uuid,
+}
+
+impl Tenants {
+    pub async fn create(db: &DbPool) -> Self {
+        let reseller = user(db, UserRole::Reseller, None).await;
+        let other_reseller = user(db, UserRole::Reseller, None).await;
+
+        Self {
+            reseller,
+            customer: user(db, UserRole::User, Some(reseller)).await,
+            other_reseller,
+            other_customer: user(db, UserRole::User, Some(other_reseller)).await,
+            user: user(db, UserRole::User, None).await,
+        }
+    }
+
+    pub async fn delete(&self, db: &DbPool) {
+        delete_users(db, &[self.customer, self.other_customer, self.reseller, self.other_reseller, self.user]).await;
+    }
+}
+
+/// Remove test users; everything they own goes with them
+pub async fn delete_users(db: &DbPool, ids: &[Uuid]) {
+    sqlx::query("DELETE FROM users WHERE id = ANY($1)")
+        .bind(ids)
+        .execute(db)
+        .await
+        .expect("Failed to delete test users");
+}
+
+/// Keeps sent messages for inspection
+#[derive(Default)]
+pub struct RecordingMailer {
+    messages: Mutex<Vec<MailMessage>>,
+}
+
+impl RecordingMailer {
+    pub fn is_empty(&self) -> bool {
+        self.messages.lock().unwrap().is_empty()
+    }
+
+    /// Wait for the `count`th message; background sends land shortly after the call returns
+    pub async fn nth(&self, count: usize) -> MailMessage {
+        for _ in 0..100 {
+            if let Some(message) = self.messages.lock().unwrap().get(count - 1) {
+                return message.clone();
+            }
+            tokio::time::sleep(std::time::Duration::from_millis(10)).await;
+        }
+        panic!("Mail {} was never sent", count);
+    }
+}
+
+#[async_trait]
+impl MailSender for RecordingMailer {
+    async fn send(&self, message: &MailMessage) -> Result<(), AppError> {
+        self.messages.lock().unwrap().push(message.clone());
+        Ok(())
+    }
+}
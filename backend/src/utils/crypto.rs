@@ -0,0 +1,122 @@
+use aes_gcm::{
+    aead::{Aead, AeadCore, KeyInit, OsRng},
+    Aes256Gcm, Key, Nonce,
+};
+use hmac::{Hmac, Mac};
+use rand::RngCore;
+use sha2::{Digest, Sha256};
+
+/// Random URL-safe secret with `bytes` bytes of entropy, hex encoded
+pub fn random_token(bytes: usize) -> String {
+    let mut buf = vec![0u8; bytes];
+    rand::thread_rng().fill_bytes(&mut buf);
+    hex::encode(buf)
+}
+
+/// SHA-256 digest used to store high-entropy secrets (refresh tokens, API keys) at rest
+pub fn sha256_hex(value: &str) -> String {
+    hex::encode(Sha256::digest(value.as_bytes()))
+}
+
+/// HMAC-SHA256 of `message` keyed with `secret`, hex encoded (webhook signatures)
+pub fn hmac_sha256_hex(secret: &str, message: &[u8]) -> String {
+    let mut mac = <Hmac<Sha256> as Mac>::new_from_slice(secret.as_bytes())
+        .expect("HMAC accepts keys of any length");
+    mac.update(message);
+    hex::encode(mac.finalize().into_bytes())
+}
+
+/// First few characters of a secret, enough to recognise it without revealing it
+pub fn mask_secret(secret: &str) -> String {
+    let visible: String = secret.chars().take(4).collect();
+    format!("{}…", visible)
+}
+
+/// Marks (and versions) values sealed by [`SecretKey::encrypt`]
+const SEALED_PREFIX: &str = "v1:";
+
+const NONCE_BYTES: usize = 12;
+
+/// AES-256-GCM key for secrets the panel must read back, such as cloud provider tokens
+#[derive(Clone)]
+pub struct SecretKey(Aes256Gcm);
+
+impl SecretKey {
+    /// Key given as 64 hex characters (`openssl rand -hex 32`)
+    pub fn from_hex(key: &str) -> Result<Self, String> {
+        let bytes = hex::decode(key.trim()).map_err(|_| "must be hex encoded".to_string())?;
+        if bytes.len() != 32 {
+            return Err("must be 32 bytes (64 hex characters)".to_string());
+        }
+
+        Ok(Self(Aes256Gcm::new(Key::<Aes256Gcm>::from_slice(&bytes))))
+    }
+
+    /// `v1:` followed by the hex encoded random nonce and ciphertext
+    pub fn encrypt(&self, plaintext: &str) -> String {
+        let nonce = Aes256Gcm::generate_nonce(&mut OsRng);
+        let ciphertext = self.0
+            .encrypt(&nonce, plaintext.as_bytes())
+            .expect("AES-GCM encryption of an in-memory buffer cannot fail");
+
+        format!("{}{}{}", SEALED_PREFIX, hex::encode(nonce), hex::encode(ciphertext))
+    }
+
+    /// Plaintext of a value from [`SecretKey::encrypt`]; `None` if it was sealed with another key or altered
+    pub fn decrypt(&self, sealed: &str) -> Option<String> {
+        let data = hex::decode(sealed.strip_prefix(SEALED_PREFIX)?).ok()?;
+        if data.len() < NONCE_BYTES {
+            return None;
+        }
+
+        let (nonce, ciphertext) = data.split_at(NONCE_BYTES);
+        let plaintext = self.0.decrypt(Nonce::from_slice(nonce), ciphertext).ok()?;
+        String::from_utf8(plaintext).ok()
+    }
+}
+
+/// Whether `value` was produced by [`SecretKey::encrypt`] rather than stored as plaintext
+pub fn is_sealed(value: &str) -> bool {
+    value.starts_with(SEALED_PREFIX)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const KEY: &str = "000102030405060708090a0b0c0d0e0f101112131415161718191a1b1c1d1e1f";
+
+    #[test]
+    fn sealed_secrets_round_trip() {
+        let key = SecretKey::from_hex(KEY).unwrap();
+        let sealed = key.encrypt("hcloud-token");
+
+        assert!(is_sealed(&sealed));
+        assert!(!sealed.contains("hcloud-token"));
+        assert_ne!(sealed, key.encrypt("hcloud-token"), "nonces must not repeat");
+        assert_eq!(key.decrypt(&sealed).as_deref(), Some("hcloud-token"));
+    }
+
+    #[test]
+    fn altered_or_foreign_ciphertexts_are_rejected() {
+        let key = SecretKey::from_hex(KEY).unwrap();
+        let sealed = key.encrypt("hcloud-token");
+
+        let mut tampered = sealed.clone();
+        let last = tampered.pop().unwrap();
+        tampered.push(if last == '0' { '1' } else { '0' });
+        assert_eq!(key.decrypt(&tampered), None);
+
+        let other = SecretKey::from_hex(&KEY.replace("00", "ff")).unwrap();
+        assert_eq!(other.decrypt(&sealed), None);
+
+        assert_eq!(key.decrypt("hcloud-token"), None);
+        assert_eq!(key.decrypt("v1:abcd"), None);
+    }
+
+    #[test]
+    fn keys_must_be_32_hex_bytes() {
+        assert!(SecretKey::from_hex(&KEY[..62]).is_err());
+        assert!(SecretKey::from_hex(&"zz".repeat(32)).is_err());
+    }
+}
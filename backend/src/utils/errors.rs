@@ -1,55 +1,320 @@
 use axum::{
-    http::StatusCode,
+    http::{header, HeaderValue, StatusCode},
     response::{IntoResponse, Response},
     Json,
 };
 use serde_json::json;
-use std::fmt;
+use std::{collections::BTreeMap, fmt};
+use validator::{ValidationErrors, ValidationErrorsKind};
+use crate::middleware::request_id;
 
 #[derive(Debug)]
 pub enum AppError {
     BadRequest(String),
     Unauthorized(String),
+    Forbidden(String),
     NotFound(String),
+    Conflict(String),
+    RateLimited(String),
+    ValidationFailed(ValidationErrors),
+    /// A dependency such as the Hetzner API failed or returned an error
+    UpstreamError(String),
+    Timeout(String),
     InternalError(String),
     DatabaseError(sqlx::Error),
 }
 
+impl AppError {
+    /// Stable machine-readable code; clients may rely on these not changing
+    pub fn code(&self) -> &'static str {
+        match self {
+            AppError::BadRequest(_) => "bad_request",
+            AppError::Unauthorized(_) => "unauthorized",
+            AppError::Forbidden(_) => "forbidden",
+            AppError::NotFound(_) => "not_found",
+            AppError::Conflict(_) => "conflict",
+            AppError::RateLimited(_) => "rate_limited",
+            AppError::ValidationFailed(_) => "validation_failed",
+            AppError::UpstreamError(_) => "upstream_error",
+            AppError::Timeout(_) => "timeout",
+            AppError::InternalError(_) => "internal_error",
+            AppError::DatabaseError(_) => "database_error",
+        }
+    }
+
+    pub fn status(&self) -> StatusCode {
+        match self {
+            AppError::BadRequest(_) => StatusCode::BAD_REQUEST,
+            AppError::Unauthorized(_) => StatusCode::UNAUTHORIZED,
+            AppError::Forbidden(_) => StatusCode::FORBIDDEN,
+            AppError::NotFound(_) => StatusCode::NOT_FOUND,
+            AppError::Conflict(_) => StatusCode::CONFLICT,
+            AppError::RateLimited(_) => StatusCode::TOO_MANY_REQUESTS,
+            AppError::ValidationFailed(_) => StatusCode::UNPROCESSABLE_ENTITY,
+            AppError::UpstreamError(_) => StatusCode::BAD_GATEWAY,
+            AppError::Timeout(_) => StatusCode::GATEWAY_TIMEOUT,
+            AppError::InternalError(_) | AppError::DatabaseError(_) => StatusCode::INTERNAL_SERVER_ERROR,
+        }
+    }
+}
+
 impl fmt::Display for AppError {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
         match self {
             AppError::BadRequest(msg) => write!(f, "Bad Request: {}", msg),
             AppError::Unauthorized(msg) => write!(f, "Unauthorized: {}", msg),
+            AppError::Forbidden(msg) => write!(f, "Forbidden: {}", msg),
             AppError::NotFound(msg) => write!(f, "Not Found: {}", msg),
+            AppError::Conflict(msg) => write!(f, "Conflict: {}", msg),
+            AppError::RateLimited(msg) => write!(f, "Rate Limited: {}", msg),
+            AppError::ValidationFailed(errors) => write!(f, "Validation Failed: {}", errors),
+            AppError::UpstreamError(msg) => write!(f, "Upstream Error: {}", msg),
+            AppError::Timeout(msg) => write!(f, "Timeout: {}", msg),
             AppError::InternalError(msg) => write!(f, "Internal Error: {}", msg),
             AppError::DatabaseError(err) => write!(f, "Database Error: {}", err),
         }
     }
 }
 
+impl std::error::Error for AppError {}
+
 impl From<sqlx::Error> for AppError {
     fn from(err: sqlx::Error) -> Self {
+        if let Some(db_err) = err.as_database_error() {
+            if db_err.is_unique_violation() {
+                let message = match db_err.constraint() {
+                    Some(constraint) => format!("Resource already exists ({})", constraint),
+                    None => "Resource already exists".to_string(),
+                };
+                return AppError::Conflict(message);
+            }
+        }
+
         AppError::DatabaseError(err)
     }
 }
 
+impl From<ValidationErrors> for AppError {
+    fn from(errors: ValidationErrors) -> Self {
+        AppError::ValidationFailed(errors)
+    }
+}
+
+/// Field path (`samples[2].cpu_usage`) to messages, falling back to the rule code when a rule has no message
+fn field_messages(errors: &ValidationErrors) -> BTreeMap<String, Vec<String>> {
+    let mut fields = BTreeMap::new();
+    collect_field_messages(errors, "", &mut fields);
+    fields
+}
+
+fn collect_field_messages(errors: &ValidationErrors, prefix: &str, fields: &mut BTreeMap<String, Vec<String>>) {
+    for (field, kind) in errors.errors() {
+        let path = if prefix.is_empty() {
+            field.to_string()
+        } else {
+            format!("{}.{}", prefix, field)
+        };
+
+        match kind {
+            ValidationErrorsKind::Field(errors) => {
+                let messages = errors
+                    .iter()
+                    .map(|e| e.message.as_ref().unwrap_or(&e.code).to_string())
+                    .collect();
+                fields.insert(path, messages);
+            }
+            ValidationErrorsKind::Struct(nested) => collect_field_messages(nested, &path, fields),
+            ValidationErrorsKind::List(items) => {
+                for (index, nested) in items {
+                    collect_field_messages(nested, &format!("{}[{}]", path, index), fields);
+                }
+            }
+        }
+    }
+}
+
+/// Renders an RFC 7807 `application/problem+json` body
 impl IntoResponse for AppError {
     fn into_response(self) -> Response {
-        let (status, message) = match self {
-            AppError::BadRequest(msg) => (StatusCode::BAD_REQUEST, msg),
-            AppError::Unauthorized(msg) => (StatusCode::UNAUTHORIZED, msg),
-            AppError::NotFound(msg) => (StatusCode::NOT_FOUND, msg),
-            AppError::InternalError(msg) => (StatusCode::INTERNAL_SERVER_ERROR, msg),
+        let status = self.status();
+        let code = self.code();
+        let request_id = request_id::current();
+
+        let detail = match &self {
+            AppError::BadRequest(msg)
+            | AppError::Unauthorized(msg)
+            | AppError::Forbidden(msg)
+            | AppError::NotFound(msg)
+            | AppError::Conflict(msg)
+            | AppError::RateLimited(msg)
+            | AppError::UpstreamError(msg)
+            | AppError::Timeout(msg) => msg.clone(),
+            AppError::ValidationFailed(_) => "One or more fields are invalid".to_string(),
+            AppError::InternalError(msg) => {
+                tracing::error!(request_id = ?request_id, "Internal error: {}", msg);
+                "Internal server error".to_string()
+            }
             AppError::DatabaseError(err) => {
-                tracing::error!("Database error: {:?}", err);
-                (StatusCode::INTERNAL_SERVER_ERROR, "Database error".to_string())
+                tracing::error!(request_id = ?request_id, "Database error: {:?}", err);
+                "Database error".to_string()
             }
         };
 
-        let body = Json(json!({
-            "error": message
+        let mut body = json!({
+            "type": format!("urn:unified-panel:error:{}", code),
+            "title": status.canonical_reason().unwrap_or("Error"),
+            "status": status.as_u16(),
+            "detail": detail,
+            "code": code,
+            "request_id": request_id,
+        });
+
+        if let AppError::ValidationFailed(errors) = &self {
+            body["errors"] = json!(field_messages(errors));
+        }
+
+        let mut response = (status, Json(body)).into_response();
+        response.headers_mut().insert(
+            header::CONTENT_TYPE,
+            HeaderValue::from_static("application/problem+json"),
+        );
+        response
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{middleware::request_id::assign_request_id, test_support};
+    use axum::{body::Body, http::Request, routing::get, Router};
+    use tower::ServiceExt;
+    use validator::Validate;
+
+    #[derive(Validate)]
+    struct Batch {
+        #[validate(length(min = 1, message = "Must not be empty"))]
+        server_id: String,
+        #[validate(nested)]
+        samples: Vec<Sample>,
+        #[validate(nested)]
+        host: Host,
+    }
+
+    #[derive(Validate)]
+    struct Sample {
+        #[validate(range(min = 0.0, max = 100.0, message = "Must be between 0 and 100"))]
+        cpu_usage: f64,
+        #[validate(range(min = 0.0))]
+        memory_usage: f64,
+    }
+
+    #[derive(Validate)]
+    struct Host {
+        #[validate(length(max = 5, message = "Too long"))]
+        hostname: String,
+    }
+
+    fn invalid_batch() -> ValidationErrors {
+        let sample = |cpu_usage, memory_usage| Sample { cpu_usage, memory_usage };
+        Batch {
+            server_id: String::new(),
+            samples: vec![sample(10.0, 1.0), sample(20.0, 1.0), sample(140.0, -1.0)],
+            host: Host { hostname: "web-1.example.com".to_string() },
+        }
+        .validate()
+        .unwrap_err()
+    }
+
+    /// Status, content type and JSON body of `error` rendered inside a request carrying `x-request-id: req-1`
+    async fn render(error: fn() -> AppError) -> (StatusCode, String, serde_json::Value) {
+        let app = Router::new()
+            .route("/", get(move || async move { Err::<(), _>(error()) }))
+            .layer(axum::middleware::from_fn(assign_request_id));
+        let request = Request::get("/").header("x-request-id", "req-1").body(Body::empty()).unwrap();
+        let response = app.oneshot(request).await.unwrap();
+
+        let status = response.status();
+        let content_type = response.headers()[header::CONTENT_TYPE].to_str().unwrap().to_string();
+        let body = axum::body::to_bytes(response.into_body(), usize::MAX).await.unwrap();
+        (status, content_type, serde_json::from_slice(&body).unwrap())
+    }
+
+    #[test]
+    fn field_messages_name_nested_paths() {
+        let fields = field_messages(&invalid_batch());
+
+        let expected: BTreeMap<String, Vec<String>> = [
+            ("host.hostname", "Too long"),
+            ("samples[2].cpu_usage", "Must be between 0 and 100"),
+            // No message on the rule, so its code stands in
+            ("samples[2].memory_usage", "range"),
+            ("server_id", "Must not be empty"),
+        ]
+        .into_iter()
+        .map(|(path, message)| (path.to_string(), vec![message.to_string()]))
+        .collect();
+        assert_eq!(fields, expected);
+    }
+
+    #[tokio::test]
+    async fn errors_render_as_problem_details() {
+        let (status, content_type, body) = render(|| AppError::NotFound("Server not found".to_string())).await;
+
+        assert_eq!(status, StatusCode::NOT_FOUND);
+        assert_eq!(content_type, "application/problem+json");
+        assert_eq!(body, json!({
+            "type": "urn:unified-panel:error:not_found",
+            "title": "Not Found",
+            "status": 404,
+            "detail": "Server not found",
+            "code": "not_found",
+            "request_id": "req-1",
         }));
+    }
+
+    #[tokio::test]
+    async fn validation_problems_list_field_errors() {
+        let (status, content_type, body) = render(|| AppError::ValidationFailed(invalid_batch())).await;
+
+        assert_eq!(status, StatusCode::UNPROCESSABLE_ENTITY);
+        assert_eq!(content_type, "application/problem+json");
+        assert_eq!(body["code"], "validation_failed");
+        assert_eq!(body["detail"], "One or more fields are invalid");
+        assert_eq!(body["errors"]["samples[2].cpu_usage"], json!(["Must be between 0 and 100"]));
+        assert_eq!(body["errors"].as_object().unwrap().len(), 4);
+    }
+
+    #[tokio::test]
+    async fn internal_details_are_not_exposed() {
+        let (status, _, body) = render(|| AppError::InternalError("SMTP password rejected".to_string())).await;
+
+        assert_eq!(status, StatusCode::INTERNAL_SERVER_ERROR);
+        assert_eq!(body["detail"], "Internal server error");
+        assert_eq!(body["code"], "internal_error");
+        assert_eq!(body["request_id"], "req-1");
+    }
+
+    #[tokio::test]
+    async fn unique_violations_become_conflicts() {
+        let Some(db) = test_support::database().await else { return };
+        // Temporary tables live on one connection, so keep using the same one
+        let mut conn = db.acquire().await.unwrap();
+        sqlx::query("CREATE TEMPORARY TABLE error_test (name TEXT CONSTRAINT error_test_name_key UNIQUE)")
+            .execute(&mut *conn)
+            .await
+            .unwrap();
+
+        let insert = "INSERT INTO error_test (name) VALUES ('web-1')";
+        sqlx::query(insert).execute(&mut *conn).await.unwrap();
+        let duplicate = AppError::from(sqlx::query(insert).execute(&mut *conn).await.unwrap_err());
+        let other = AppError::from(sqlx::query("SELECT missing_column FROM error_test").execute(&mut *conn).await.unwrap_err());
 
-        (status, body).into_response()
+        assert!(
+            matches!(&duplicate, AppError::Conflict(message) if message == "Resource already exists (error_test_name_key)"),
+            "{:?}",
+            duplicate
+        );
+        assert_eq!(duplicate.status(), StatusCode::CONFLICT);
+        assert!(matches!(other, AppError::DatabaseError(_)));
     }
 }
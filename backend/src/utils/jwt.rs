@@ -5,15 +5,23 @@ use crate::{
 };
 use chrono::Utc;
 use jsonwebtoken::{decode, encode, DecodingKey, EncodingKey, Header, Validation};
+use uuid::Uuid;
 
-pub fn generate_token(user: &User, config: &Config) -> Result<String, AppError> {
+pub fn generate_token(
+    user: &User,
+    session_id: Uuid,
+    mfa_enrollment_required: bool,
+    config: &Config,
+) -> Result<String, AppError> {
     let now = Utc::now().timestamp();
     let exp = now + config.jwt_expiration;
 
     let claims = Claims {
         sub: user.id,
         email: user.email.clone(),
-        role: user.role.clone(),
+        role: user.get_role(),
+        sid: session_id,
+        mfa_enrollment_required,
         exp,
         iat: now,
     };
@@ -1,3 +1,5 @@
+pub mod crypto;
 pub mod errors;
 pub mod jwt;
 pub mod password;
+pub mod validation;
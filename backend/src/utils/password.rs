@@ -1,14 +1,89 @@
 use argon2::{
     password_hash::{rand_core::OsRng, PasswordHash, PasswordHasher, PasswordVerifier, SaltString},
-    Argon2,
+    Algorithm, Argon2, Params, Version,
 };
-use crate::utils::errors::AppError;
+use crate::{config::Config, utils::errors::AppError};
+use std::{borrow::Cow, collections::HashSet, sync::OnceLock};
+use validator::{ValidationError, ValidationErrors};
 
-pub fn hash_password(password: &str) -> Result<String, AppError> {
+/// Bundled so the check works offline; compared case-insensitively
+const COMMON_PASSWORDS: &str = include_str!("../../data/common-passwords.txt");
+
+fn common_passwords() -> &'static HashSet<&'static str> {
+    static SET: OnceLock<HashSet<&'static str>> = OnceLock::new();
+    SET.get_or_init(|| {
+        COMMON_PASSWORDS
+            .lines()
+            .map(str::trim)
+            .filter(|line| !line.is_empty())
+            .collect()
+    })
+}
+
+fn argon2(config: &Config) -> Result<Argon2<'static>, AppError> {
+    let params = Params::new(
+        config.argon2_memory_kib,
+        config.argon2_iterations,
+        config.argon2_parallelism,
+        None,
+    )
+    .map_err(|e| AppError::InternalError(format!("Invalid argon2 parameters: {}", e)))?;
+
+    Ok(Argon2::new(Algorithm::Argon2id, Version::V0x13, params))
+}
+
+/// Policy violations are reported like any other invalid field (422)
+fn policy_error(field: &'static str, code: &'static str, message: String) -> AppError {
+    let mut errors = ValidationErrors::new();
+    errors.add(field, ValidationError::new(code).with_message(Cow::Owned(message)));
+    AppError::ValidationFailed(errors)
+}
+
+/// Enforce the configured password policy before a password is hashed; `field` names the
+/// request field carrying it
+pub fn validate_password(password: &str, email: &str, field: &'static str, config: &Config) -> Result<(), AppError> {
+    if password.chars().count() < config.password_min_length {
+        return Err(policy_error(field, "length", format!(
+            "Password must be at least {} characters long",
+            config.password_min_length
+        )));
+    }
+
+    let classes = [
+        password.chars().any(|c| c.is_lowercase()),
+        password.chars().any(|c| c.is_uppercase()),
+        password.chars().any(|c| c.is_ascii_digit()),
+        password.chars().any(|c| !c.is_alphanumeric()),
+    ]
+    .into_iter()
+    .filter(|present| *present)
+    .count();
+
+    if classes < config.password_min_classes {
+        return Err(policy_error(field, "classes", format!(
+            "Password must contain at least {} of: lowercase letters, uppercase letters, digits, symbols",
+            config.password_min_classes
+        )));
+    }
+
+    let lowered = password.to_lowercase();
+    let email = email.trim().to_lowercase();
+    let local_part = email.split('@').next().unwrap_or_default();
+    if lowered == email || (!local_part.is_empty() && lowered == local_part) {
+        return Err(policy_error(field, "email", "Password must not be your email address".to_string()));
+    }
+
+    if common_passwords().contains(lowered.as_str()) {
+        return Err(policy_error(field, "common", "Password is too common".to_string()));
+    }
+
+    Ok(())
+}
+
+pub fn hash_password(password: &str, config: &Config) -> Result<String, AppError> {
     let salt = SaltString::generate(&mut OsRng);
-    let argon2 = Argon2::default();
 
-    argon2
+    argon2(config)?
         .hash_password(password.as_bytes(), &salt)
         .map(|hash| hash.to_string())
         .map_err(|e| AppError::InternalError(format!("Failed to hash password: {}", e)))
@@ -22,3 +97,67 @@ pub fn verify_password(password: &str, hash: &str) -> Result<bool, AppError> {
         .verify_password(password.as_bytes(), &parsed_hash)
         .is_ok())
 }
+
+/// Whether a stored hash uses a different algorithm or weaker parameters than the current config
+pub fn needs_rehash(hash: &str, config: &Config) -> bool {
+    let Ok(parsed_hash) = PasswordHash::new(hash) else {
+        return true;
+    };
+
+    if parsed_hash.algorithm != Algorithm::Argon2id.ident() {
+        return true;
+    }
+
+    match Params::try_from(&parsed_hash) {
+        Ok(params) => {
+            params.m_cost() < config.argon2_memory_kib
+                || params.t_cost() < config.argon2_iterations
+                || params.p_cost() < config.argon2_parallelism
+        }
+        Err(_) => true,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_support;
+
+    fn rejected_field(result: Result<(), AppError>) -> Vec<String> {
+        match result {
+            Err(AppError::ValidationFailed(errors)) => errors.field_errors().keys().map(|field| field.to_string()).collect(),
+            other => panic!("Expected a validation error, got {:?}", other),
+        }
+    }
+
+    #[test]
+    fn policy_violations_are_field_errors() {
+        let mut config = test_support::config();
+        config.password_min_length = 12;
+        config.password_min_classes = 3;
+
+        assert_eq!(rejected_field(validate_password("Sh0rt!", "a@example.com", "password", &config)), ["password"]);
+        assert_eq!(rejected_field(validate_password("alllowercaseletters", "a@example.com", "password", &config)), ["password"]);
+        assert_eq!(
+            rejected_field(validate_password("Alice.Example1@x.io", "alice.example1@x.io", "new_password", &config)),
+            ["new_password"]
+        );
+    }
+
+    #[test]
+    fn common_passwords_are_rejected() {
+        let mut config = test_support::config();
+        config.password_min_length = 1;
+        config.password_min_classes = 1;
+        let common = common_passwords().iter().next().unwrap();
+
+        assert_eq!(rejected_field(validate_password(common, "a@example.com", "password", &config)), ["password"]);
+    }
+
+    #[test]
+    fn strong_passwords_pass() {
+        let config = test_support::config();
+
+        assert!(validate_password("Correct-Horse-Battery-9", "a@example.com", "password", &config).is_ok());
+    }
+}
@@ -0,0 +1,73 @@
+//! Custom field validators for `#[validate(custom(function = ...))]`
+
+use chrono::{DateTime, Duration, Utc};
+use std::{borrow::Cow, net::IpAddr};
+use validator::ValidationError;
+
+pub fn invalid(code: &'static str, message: &'static str) -> ValidationError {
+    ValidationError::new(code).with_message(Cow::Borrowed(message))
+}
+
+pub fn ip_address(value: &str) -> Result<(), ValidationError> {
+    value.parse::<IpAddr>()
+        .map(|_| ())
+        .map_err(|_| invalid("ip", "Must be a valid IPv4 or IPv6 address"))
+}
+
+/// RFC 1123 hostname: dot-separated labels of letters, digits and inner hyphens
+pub fn hostname(value: &str) -> Result<(), ValidationError> {
+    let value = value.strip_suffix('.').unwrap_or(value);
+
+    let valid = !value.is_empty()
+        && value.len() <= 253
+        && value.split('.').all(|label| {
+            !label.is_empty()
+                && label.len() <= 63
+                && !label.starts_with('-')
+                && !label.ends_with('-')
+                && label.chars().all(|c| c.is_ascii_alphanumeric() || c == '-')
+        });
+
+    if valid {
+        Ok(())
+    } else {
+        Err(invalid("hostname", "Must be a valid RFC 1123 hostname"))
+    }
+}
+
+/// Cloud server names (Hetzner servers, DigitalOcean droplets) must be valid hostnames of at most 63 characters
+pub fn cloud_name(value: &str) -> Result<(), ValidationError> {
+    if value.len() > 63 {
+        return Err(invalid("cloud_name", "Must be at most 63 characters"));
+    }
+
+    hostname(value).map_err(|_| invalid("cloud_name", "Must be a valid hostname (letters, digits, hyphens and dots)"))
+}
+
+/// Provider catalogue identifiers such as `cx22`, `nbg1`, `s-1vcpu-1gb` or `ubuntu-24.04`
+pub fn cloud_identifier(value: &str) -> Result<(), ValidationError> {
+    let valid = !value.is_empty()
+        && value.len() <= 64
+        && value.chars().all(|c| c.is_ascii_lowercase() || c.is_ascii_digit() || matches!(c, '-' | '.' | '_'));
+
+    if valid {
+        Ok(())
+    } else {
+        Err(invalid("cloud_identifier", "Must be a lowercase provider identifier"))
+    }
+}
+
+/// Metrics samples may be at most a week old and a few minutes ahead to allow for clock skew
+pub fn sample_timestamp(value: &DateTime<Utc>) -> Result<(), ValidationError> {
+    let now = Utc::now();
+
+    if *value > now + Duration::minutes(5) {
+        return Err(invalid("timestamp", "Must not be in the future"));
+    }
+
+    if *value < now - Duration::days(7) {
+        return Err(invalid("timestamp", "Must be within the last 7 days"));
+    }
+
+    Ok(())
+}